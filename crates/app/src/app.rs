@@ -1,27 +1,37 @@
+use crate::apu_viewer::ApuViewer;
 use crate::audio::AppAudio;
 use crate::battery::BatterySave;
+use crate::cheats::{CheatsSave, RamSearchState};
+use crate::cmd::{MovieCmd, NetplayCmd, RamSearchCmd};
 use crate::config::{AppConfig, VideoBackendType};
 
 use crate::input::handler::InputHandler;
+use crate::memory_viewer::MemoryViewer;
 use crate::menu::AppMenu;
 use crate::notification::Notifications;
 use crate::palette::LcdPalette;
 use crate::roms::RomsState;
 use crate::video::shader::{next_shader_by_name, prev_shader_by_name};
 use crate::video::AppVideo;
+use crate::vram_viewer::VramViewer;
 use crate::{AppConfigFile, AppPlatform, PlatformFileDialog, PlatformFileSystem};
 use arrayvec::ArrayString;
 use core::cart::Cart;
+use core::cheats::Cheat;
 use core::emu::config::GbModel;
+use core::emu::movie::{MovieEvent, MovieMode, MovieSession};
+use core::emu::netplay::{NetplayEvent, Transport};
 use core::emu::runtime::EmuRuntime;
 use core::emu::runtime::RunMode;
-use core::emu::state::SaveStateCmd;
+use core::emu::state::{EmuSaveState, SaveStateCmd};
+use core::emu::state_file::SaveStateInfo;
 use core::emu::Emu;
 use core::emu::EmuAudioCallback;
 use core::ppu::framebuffer::FrameBuffer;
 use core::ppu::tile::PixelColor;
 use sdl2::Sdl;
 use std::fmt::Write;
+use std::mem;
 use std::path::Path;
 use std::thread;
 use std::time::Duration;
@@ -48,9 +58,22 @@ where
     pub state: AppState,
     pub config: AppConfig,
     pub menu: AppMenu,
+    /// The menu draws here, so the game's last frame stays for save-state
+    /// thumbnails.
+    menu_fb: FrameBuffer,
     pub notifications: Notifications,
     pub platform: AppPlatform<FS, FD>,
     pub roms: RomsState,
+    /// Cheats of the loaded game.
+    pub cheats: Vec<Cheat>,
+    pub ram_search: RamSearchState,
+    pub memory_viewer: MemoryViewer,
+    pub vram_viewer: VramViewer,
+    pub apu_viewer: ApuViewer,
+    #[cfg(feature = "debug")]
+    pub console: crate::debugger::DebugConsole,
+    #[cfg(feature = "debug")]
+    dap: Option<core::debugger::DapServer>,
 }
 
 impl<FS, FD> EmuAudioCallback for App<FS, FD>
@@ -111,6 +134,7 @@ where
         Ok(Self {
             audio: AppAudio::new(sdl, &config.audio),
             menu: AppMenu::new(&roms),
+            menu_fb: FrameBuffer::default(),
             state: AppState::Paused,
             fps_str: ArrayString::<10>::new(),
             video,
@@ -119,6 +143,15 @@ where
             notifications,
             platform,
             roms,
+            cheats: Vec::new(),
+            ram_search: Default::default(),
+            memory_viewer: Default::default(),
+            vram_viewer: Default::default(),
+            apu_viewer: Default::default(),
+            #[cfg(feature = "debug")]
+            console: Default::default(),
+            #[cfg(feature = "debug")]
+            dap: crate::debugger::bind_dap_server(),
         })
     }
 
@@ -132,6 +165,8 @@ where
 
         loop {
             input.handle_events(self, emu);
+            #[cfg(feature = "debug")]
+            self.poll_dap(emu);
 
             match self.state {
                 AppState::Quitting => break,
@@ -145,6 +180,11 @@ where
     #[inline(always)]
     pub fn render_frame(&mut self, emu: &mut Emu) {
         let on_time = emu.run_frame(self);
+        let broke = self.handle_break(emu);
+        self.handle_movie_events(emu);
+        self.handle_netplay_events(emu);
+        #[cfg(feature = "script")]
+        self.handle_script(emu);
 
         // The audio callback only gets a shared runtime borrow, so the rate
         // computed by the queue's rate control is applied here instead.
@@ -156,7 +196,7 @@ where
             .apu
             .set_sample_rate(self.audio.sample_rate());
 
-        if on_time || self.video.must_render() {
+        if on_time || self.video.must_render() || broke {
             self.render_framebuffer(emu);
         }
     }
@@ -164,12 +204,148 @@ where
     #[inline(always)]
     pub fn render_scanline(&mut self, emu: &mut Emu) {
         emu.runtime.run_scanline(self);
+        self.handle_break(emu);
         self.render_framebuffer(emu);
     }
 
+    /// Lets an attached editor inspect and drive the emulator. Its run
+    /// requests only apply on a break, not while a menu is open.
+    #[cfg(feature = "debug")]
+    fn poll_dap(&mut self, emu: &mut Emu) {
+        let Some(dap) = self.dap.as_mut() else {
+            return;
+        };
+
+        let Some((cpu, debugger)) = emu.runtime.debug_parts() else {
+            return;
+        };
+
+        let stopped = self.state == AppState::Stepping;
+
+        if dap.poll(cpu, debugger, stopped) && stopped {
+            self.state = AppState::Running;
+        }
+    }
+
+    /// Picks up the `.sym` file next to the ROM, dropping the previous ROM's.
+    #[cfg(feature = "debug")]
+    fn load_symbols(&mut self, emu: &mut Emu, rom_path: &Path) {
+        use core::debugger::Symbols;
+
+        let Some(debugger) = emu.runtime.debugger_mut() else {
+            return;
+        };
+
+        let sym_path = Symbols::path_for_rom(rom_path);
+        debugger.symbols = match self.platform.fs.read_file_bytes(&sym_path) {
+            Some(bytes) => {
+                let symbols = Symbols::parse(&String::from_utf8_lossy(&bytes));
+                log::info!("Loaded {} symbols from {sym_path:?}", symbols.len());
+                symbols
+            }
+            None => Symbols::default(),
+        };
+    }
+
+    /// Runs the `.rhai` script next to the ROM, if there is one.
+    #[cfg(feature = "script")]
+    fn load_script(&mut self, emu: &mut Emu, rom_path: &Path) {
+        use core::script::ScriptHost;
+
+        let path = crate::script::script_path(rom_path);
+        let Some(bytes) = self.platform.fs.read_file_bytes(&path) else {
+            emu.runtime.set_script(None);
+            return;
+        };
+
+        let source = String::from_utf8_lossy(&bytes);
+        let script = ScriptHost::new(&source).and_then(|mut script| {
+            script.start(&mut emu.runtime.cpu)?;
+            Ok(script)
+        });
+
+        let script = match script {
+            Ok(script) => {
+                log::info!("Loaded script {path:?}");
+                self.notifications.add("Script loaded");
+                Some(script)
+            }
+            Err(err) => {
+                log::error!("Failed to load script {path:?}: {err}");
+                self.notifications.add("Script failed to load");
+                None
+            }
+        };
+
+        emu.runtime.set_script(script);
+    }
+
+    /// Carries out what the script asked for during the frame, and unloads
+    /// it once it stops or fails.
+    #[cfg(feature = "script")]
+    fn handle_script(&mut self, emu: &mut Emu) {
+        use core::script::ScriptRequest;
+
+        let Some(script) = emu.runtime.script_mut() else {
+            return;
+        };
+
+        if script.error().is_some() {
+            emu.runtime.set_script(None);
+            self.notifications.add("Script stopped on error");
+            return;
+        }
+
+        for request in script.take_requests() {
+            match request {
+                ScriptRequest::SaveState(cmd, slot) => self.handle_save_state(emu, cmd, Some(slot)),
+                ScriptRequest::Stop => {
+                    emu.runtime.set_script(None);
+                    self.notifications.add("Script stopped");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Stops on a debugger break and shows why. Returns whether it did.
+    #[inline(always)]
+    fn handle_break(&mut self, emu: &mut Emu) -> bool {
+        #[cfg(feature = "debug")]
+        if let Some(reason) = emu.runtime.take_break() {
+            self.state = AppState::Stepping;
+            let label = emu.runtime.debug_parts().and_then(|(cpu, debugger)| {
+                debugger.symbols.describe(cpu, cpu.registers.pc)
+            });
+            self.console.on_break(&reason, label.as_deref());
+
+            if let Some(dap) = self.dap.as_mut() {
+                dap.on_break(&reason);
+            }
+
+            if !self.console.visible {
+                self.notifications.add(format!("Break: {reason}"));
+            }
+
+            return true;
+        }
+
+        #[cfg(not(feature = "debug"))]
+        let _ = emu;
+
+        false
+    }
+
     #[inline(always)]
     pub fn render_framebuffer(&mut self, emu: &mut Emu) {
         let fps = emu.get_fps();
+
+        #[cfg(feature = "script")]
+        if let Some((cpu, script)) = emu.runtime.script_parts() {
+            let fb = &mut cpu.clock.bus.io.ppu.lcd.buffer;
+            crate::script::draw_script(fb, script.draws(), self.video.ui.text_color);
+        }
+
         let fb = emu.get_framebuffer();
         self.update_notif(fb);
 
@@ -179,7 +355,27 @@ where
             self.video.ui.fill_fps(fb, &self.fps_str);
         }
 
-        self.video.draw_buffer(fb);
+        if self.memory_viewer.visible {
+            let fb = self.memory_viewer.draw(emu, &self.video.ui);
+            self.video.draw_buffer(fb);
+        } else if self.vram_viewer.visible {
+            let fb = self.vram_viewer.draw(emu, &self.video.ui);
+            self.video.draw_buffer(fb);
+        } else if self.apu_viewer.visible {
+            let fb = self.apu_viewer.draw(emu, &self.video.ui);
+            self.video.draw_buffer(fb);
+        } else {
+            #[cfg(feature = "debug")]
+            if self.console.visible {
+                let fb = self.console.draw(emu, &self.video.ui);
+                self.video.draw_buffer(fb);
+            } else {
+                self.video.draw_buffer(emu.get_framebuffer());
+            }
+
+            #[cfg(not(feature = "debug"))]
+            self.video.draw_buffer(emu.get_framebuffer());
+        }
 
         if self.config.video.interface.show_tiles {
             let tiles = emu.runtime.cpu.clock.bus.io.ppu.video_ram.iter_tiles();
@@ -192,9 +388,10 @@ where
     #[inline(always)]
     pub fn render_menu(&mut self, emu: &mut Emu) {
         emu.runtime.cpu.clock.reset();
-        let fb = emu.get_framebuffer();
-        self.draw_menu(fb);
-        self.update_notif(fb);
+        let mut fb = mem::replace(&mut self.menu_fb, FrameBuffer::new(Box::default()));
+        self.draw_menu(&mut fb);
+        self.update_notif(&mut fb);
+        self.menu_fb = fb;
         self.video.render();
 
         thread::sleep(Duration::from_millis(30));
@@ -232,10 +429,13 @@ where
 
     #[inline(always)]
     fn draw_menu(&mut self, fb: &mut FrameBuffer) -> bool {
-        let (items, updated) = self.menu.get_items(&self.config, &self.roms);
+        let (items, thumbnail, updated) = self.menu.get_items(&self.config, &self.roms);
 
         if updated {
-            self.video.ui.fill_menu(fb, items, true, true);
+            match thumbnail {
+                Some(thumbnail) => self.video.ui.fill_thumbnail_menu(fb, items, thumbnail),
+                None => self.video.ui.fill_menu(fb, items, true, true),
+            }
         }
 
         self.video.draw_menu(fb);
@@ -347,38 +547,281 @@ where
         match event {
             SaveStateCmd::Create => {
                 let save_state = emu.create_save_state();
-                let index = index.unwrap_or(self.config.current_save_slot).to_string();
+                let info = SaveStateInfo::new(&emu.runtime.cpu);
+                let slot = index.unwrap_or(self.config.current_save_slot);
+                let index = slot.to_string();
 
-                if let Err(err) = AppConfigFile::write_save_state_file(&save_state, &name, &index) {
+                if let Err(err) =
+                    AppConfigFile::write_save_state_file(&save_state, &info, &name, &index)
+                {
                     log::error!("Failed save_state: {err}");
                     return;
                 }
 
                 let msg = format!("Saved save state: {index}");
                 self.notifications.add(msg);
+                self.update_save_states(&name, Some(slot));
+            }
+            SaveStateCmd::Load if emu.netplay.is_some() => {
+                self.notifications.add("Can't load during netplay");
             }
             SaveStateCmd::Load => {
                 let index = index.unwrap_or(self.config.current_load_slot).to_string();
-                let save_state = AppConfigFile::read_save_state_file(&name, &index);
+                let save_state =
+                    AppConfigFile::read_save_state_file(&name, &index, &emu.runtime.cpu);
+
+                let save_state = match save_state {
+                    Ok(x) => x,
+                    Err(err) => {
+                        log::error!("Failed load save_state: {err}");
+                        self.notifications.add(format!("Load {index}: {err}"));
+                        return;
+                    }
+                };
+
+                self.load_state(emu, save_state);
+
+                let msg = format!("Loaded save state: {index}");
+                self.notifications.add(msg);
+                self.state = AppState::Running;
+            }
+        }
+    }
 
-                let Ok(save_state) = save_state else {
-                    log::error!("Failed load save_state: {}", save_state.unwrap_err());
+    /// Loads `state` with the palette and audio settings of the app rather
+    /// than the ones it was made with.
+    fn load_state(&mut self, emu: &mut Emu, state: EmuSaveState) {
+        emu.load_save_state(state);
+        let colors = self
+            .config
+            .video
+            .interface
+            .get_palette_colors(&self.palettes);
+        self.apply_dmg_palette(emu, colors);
+        emu.runtime.cpu.clock.bus.io.apu.config = self.config.audio.get_apu_config();
+    }
+
+    pub fn handle_movie(&mut self, emu: &mut Emu, cmd: MovieCmd) {
+        if emu.netplay.is_some() {
+            self.notifications.add("No movies during netplay");
+            return;
+        }
+
+        match cmd {
+            MovieCmd::Record | MovieCmd::RecordFromReset => {
+                self.stop_movie(emu);
+                let power_on = cmd == MovieCmd::RecordFromReset;
+
+                if power_on {
+                    self.restart_rom(emu);
+                }
+
+                let state = emu.create_save_state();
+                let info = SaveStateInfo::new(&emu.runtime.cpu);
+
+                match MovieSession::record(&state, &info, &self.cheats, power_on) {
+                    Ok(movie) => {
+                        emu.start_movie(movie);
+                        self.notifications.add("Recording movie");
+                        self.state = AppState::Running;
+                    }
+                    Err(err) => log::error!("Failed record movie: {err}"),
+                }
+            }
+            MovieCmd::Play | MovieCmd::PlayReadOnly => {
+                self.stop_movie(emu);
+                let path = self.roms.get_last_path().unwrap();
+                let name = self.platform.fs.get_file_name(path).unwrap();
+                let movie = AppConfigFile::read_movie_file(&name).and_then(|movie| {
+                    let state = movie.start_state(&emu.runtime.cpu)?;
+                    Ok((movie, state))
+                });
+
+                let (movie, state) = match movie {
+                    Ok(x) => x,
+                    Err(err) => {
+                        log::error!("Failed load movie: {err}");
+                        self.notifications.add(format!("Movie: {err}"));
+                        return;
+                    }
+                };
+
+                self.load_state(emu, state);
+                emu.runtime.set_cheats(&movie.cheats);
+                let read_only = cmd == MovieCmd::PlayReadOnly;
+                emu.start_movie(MovieSession::play(movie, &emu.runtime.cpu, read_only));
+                self.notifications.add("Playing movie");
+                self.state = AppState::Running;
+            }
+            MovieCmd::ToggleReadOnly => {
+                let Some(movie) = emu.movie.as_mut() else {
                     return;
                 };
 
-                emu.load_save_state(save_state);
-                let colors = self
-                    .config
-                    .video
-                    .interface
-                    .get_palette_colors(&self.palettes);
-                self.apply_dmg_palette(emu, colors);
-                emu.runtime.cpu.clock.bus.io.apu.config = self.config.audio.get_apu_config();
+                movie.set_read_only(!movie.read_only());
+                self.notifications.add(if movie.read_only() {
+                    "Movie read-only"
+                } else {
+                    "Movie read-write"
+                });
+            }
+            MovieCmd::Stop => {
+                if emu.movie.is_some() {
+                    self.stop_movie(emu);
+                    self.notifications.add("Movie stopped");
+                }
+            }
+        }
+    }
 
-                let msg = format!("Loaded save state: {index}");
+    /// Ends the movie, saving it if input was recorded, and puts the game's
+    /// own cheats back.
+    fn stop_movie(&mut self, emu: &mut Emu) {
+        let Some(movie) = emu.stop_movie() else {
+            return;
+        };
+
+        emu.runtime.set_cheats(&self.cheats);
+
+        if movie.is_modified() {
+            self.save_movie(&movie);
+        }
+    }
+
+    fn save_movie(&mut self, movie: &MovieSession) {
+        let path = self.roms.get_last_path().unwrap();
+        let name = self.platform.fs.get_file_name(path).unwrap();
+
+        match AppConfigFile::write_movie_file(movie.movie(), &name) {
+            Ok(()) => {
+                let path = AppConfigFile::get_movie_path(&name);
+                log::info!("Saved movie {path:?}, {} frames", movie.len());
+                self.notifications.add("Saved movie");
+            }
+            Err(err) => log::error!("Failed save movie: {err}"),
+        }
+    }
+
+    fn handle_movie_events(&mut self, emu: &mut Emu) {
+        let Some(movie) = emu.movie.as_mut() else {
+            return;
+        };
+
+        for event in movie.take_events() {
+            match event {
+                MovieEvent::Desync { frame } => {
+                    log::warn!("Movie desync at frame {frame}");
+                    let msg = format!("Movie desync: frame {frame}");
+                    self.notifications.add(msg);
+                }
+                MovieEvent::Ended if movie.mode() == MovieMode::Record => {
+                    self.notifications.add("Movie ended, recording");
+                }
+                MovieEvent::Ended => self.notifications.add("Movie ended"),
+            }
+        }
+    }
+
+    pub fn handle_netplay(&mut self, emu: &mut Emu, cmd: NetplayCmd) {
+        match cmd {
+            NetplayCmd::Start => {
+                self.stop_movie(emu);
+
+                // the session starts without the battery save, keep it as is
+                if let Err(err) = self.save_files(emu) {
+                    log::warn!("Failed save_files: {err}");
+                }
+
+                let conf = &self.config.netplay;
+                let latency = Duration::from_millis(conf.latency_ms);
+                let result = Transport::bind(&conf.local, &conf.peer)
+                    .map(|x| x.with_conditions(latency, conf.loss))
+                    .and_then(|x| emu.start_netplay(conf.player, conf.delay, x));
+
+                if let Err(err) = result {
+                    log::error!("Failed start netplay: {err}");
+                    self.notifications.add(format!("Netplay: {err}"));
+                    return;
+                }
+
+                let msg = format!("Netplay: waiting for {}", conf.peer);
                 self.notifications.add(msg);
+                self.refresh_dmg_palette(emu);
+                let show_fps = self.config.video.interface.show_fps;
+                emu.runtime.cpu.clock.bus.io.ppu.toggle_fps(show_fps);
                 self.state = AppState::Running;
             }
+            NetplayCmd::Stop => {
+                if emu.netplay.is_some() {
+                    self.stop_netplay(emu);
+                    self.notifications.add("Netplay stopped");
+                }
+            }
+        }
+    }
+
+    /// Ends the session and goes back to the game with its battery save.
+    fn stop_netplay(&mut self, emu: &mut Emu) {
+        self.restart_rom(emu);
+        emu.netplay = None;
+    }
+
+    fn handle_netplay_events(&mut self, emu: &mut Emu) {
+        let Some(netplay) = emu.netplay.as_mut() else {
+            return;
+        };
+
+        let player = netplay.player() + 1;
+
+        for event in netplay.take_events() {
+            match event {
+                NetplayEvent::Connected => {
+                    let msg = format!("Netplay: connected as player {player}");
+                    self.notifications.add(msg);
+                }
+                NetplayEvent::Desync { frame } => {
+                    let msg = format!("Netplay desync: frame {frame}");
+                    self.notifications.add(msg);
+                }
+                NetplayEvent::Ended(reason) => {
+                    let msg = format!("Netplay ended: {reason}");
+                    self.notifications.add(msg);
+                    self.stop_netplay(emu);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Opens the slot browser of the running game.
+    pub fn browse_save_states(&mut self) {
+        let path = self.roms.get_last_path().unwrap();
+        let name = self.platform.fs.get_file_name(path).unwrap();
+
+        self.menu
+            .open_save_states(AppConfigFile::list_save_states(&name));
+    }
+
+    pub fn delete_save_state(&mut self, index: usize) {
+        let path = self.roms.get_last_path().unwrap();
+        let name = self.platform.fs.get_file_name(path).unwrap();
+
+        if let Err(err) = AppConfigFile::delete_save_state_file(&name, &index.to_string()) {
+            log::error!("Failed delete save_state: {err}");
+            return;
+        }
+
+        let msg = format!("Deleted save state: {index}");
+        self.notifications.add(msg);
+        self.update_save_states(&name, None);
+    }
+
+    /// Relists the slots if the browser may be open, which is only while
+    /// paused.
+    fn update_save_states(&mut self, name: &str, open: Option<usize>) {
+        if self.state == AppState::Paused {
+            let slots = AppConfigFile::list_save_states(name);
+            self.menu.update_save_states(slots, open);
         }
     }
 
@@ -404,6 +847,11 @@ where
             return Err("Failed filesystem.get_file_name: not found".to_string());
         };
 
+        // a session runs without the battery save
+        if emu.netplay.is_some() {
+            return Ok(());
+        }
+
         // save sram for battery emulation
         if let Some(bytes) = emu.runtime.cpu.clock.bus.cart.dump_ram() {
             let battery = BatterySave::from_bytes(bytes)
@@ -415,10 +863,16 @@ where
             };
         }
 
+        if let Some(movie) = emu.movie.as_ref().filter(|x| x.is_modified()) {
+            let movie = movie.clone();
+            self.save_movie(&movie);
+        }
+
         if self.config.auto_save_state {
             let state = emu.create_save_state();
+            let info = SaveStateInfo::new(&emu.runtime.cpu);
             if let Err(err) =
-                AppConfigFile::write_save_state_file(&state, &name, AUTO_SAVE_STATE_SUFFIX)
+                AppConfigFile::write_save_state_file(&state, &info, &name, AUTO_SAVE_STATE_SUFFIX)
             {
                 log::warn!("Failed save_state: {err}");
             }
@@ -457,7 +911,16 @@ where
         }
 
         emu.load_cart(cart);
+        self.cheats = CheatsSave::load_file(&file_name)
+            .map(|x| x.cheats)
+            .unwrap_or_default();
+        emu.runtime.set_cheats(&self.cheats);
+        self.ram_search.search = None;
         self.roms.insert_or_update(path.to_path_buf());
+        #[cfg(feature = "debug")]
+        self.load_symbols(emu, path);
+        #[cfg(feature = "script")]
+        self.load_script(emu, path);
 
         let colors = self
             .config
@@ -480,7 +943,11 @@ where
         if !is_reload && self.config.auto_save_state {
             let path = self.roms.get_last_path().unwrap();
             let name = self.platform.fs.get_file_name(path).unwrap();
-            let save_state = AppConfigFile::read_save_state_file(&name, AUTO_SAVE_STATE_SUFFIX);
+            let save_state = AppConfigFile::read_save_state_file(
+                &name,
+                AUTO_SAVE_STATE_SUFFIX,
+                &emu.runtime.cpu,
+            );
 
             if let Ok(save_state) = save_state {
                 emu.load_save_state(save_state);
@@ -492,6 +959,75 @@ where
         Ok(())
     }
 
+    /// Applies the cheat list of the loaded game and saves it.
+    pub fn set_cheats(&mut self, emu: &mut Emu, cheats: Vec<Cheat>) {
+        // the peer's console wouldn't have them, they apply once it ends
+        if emu.netplay.is_none() {
+            emu.runtime.set_cheats(&cheats);
+        }
+
+        self.cheats = cheats;
+
+        let Some(name) = self
+            .roms
+            .get_last_path()
+            .and_then(|x| self.platform.fs.get_file_name(x))
+        else {
+            return;
+        };
+
+        let save = CheatsSave {
+            cheats: self.cheats.clone(),
+        };
+
+        if let Err(err) = save.save_file(&name) {
+            log::warn!("Failed CheatsSave: {err}");
+        }
+    }
+
+    pub fn ram_search(&mut self, emu: &mut Emu, cmd: RamSearchCmd) {
+        let bus = &emu.runtime.cpu.clock.bus;
+
+        match cmd {
+            RamSearchCmd::Start(kind) => self.ram_search.start(bus, kind),
+            RamSearchCmd::Filter(filter) => self.ram_search.filter(bus, filter),
+            RamSearchCmd::Freeze(location, value) => {
+                let Some(search) = self.ram_search.search.as_ref() else {
+                    return;
+                };
+                let cheat = search.to_cheat(location, value);
+                self.notifications
+                    .add(format!("Cheat added: {}", cheat.name));
+
+                let mut cheats = self.cheats.clone();
+                cheats.push(cheat);
+                self.set_cheats(emu, cheats);
+            }
+            RamSearchCmd::Watch(location) => {
+                #[cfg(feature = "debug")]
+                {
+                    let Some(search) = self.ram_search.search.as_ref() else {
+                        return;
+                    };
+                    let watchpoint = search.to_watchpoint(location);
+
+                    match emu.runtime.debugger_mut() {
+                        Some(debugger) => {
+                            debugger.breakpoints.watch.push(watchpoint);
+                            self.notifications.add(format!("Watching {location}"));
+                        }
+                        None => self.notifications.add("Debugger is off"),
+                    }
+                }
+
+                #[cfg(not(feature = "debug"))]
+                let _ = location;
+            }
+        }
+
+        self.menu.update_ram_search(&self.ram_search);
+    }
+
     pub fn change_volume(&mut self, emu: &mut Emu, delta: f32) {
         emu.runtime.cpu.clock.bus.io.apu.config.change_volume(delta);
         self.config.audio.volume = emu.runtime.cpu.clock.bus.io.apu.config.volume;
@@ -0,0 +1,175 @@
+use crate::cmd::{AppCmd, ChangeConfigCmd};
+use crate::video::draw_color;
+use crate::video::overlay::Overlay;
+use core::emu::Emu;
+use core::ppu::framebuffer::FrameBuffer;
+use core::ppu::LCD_X_RES;
+use sdl2::keyboard::Scancode;
+use std::fmt::Write;
+
+/// Samples the scope keeps, about 23 ms of output.
+const SCOPE_SAMPLES: usize = 1024;
+/// Four label lines tall.
+const STRIP_HEIGHT: usize = 20;
+/// Left of a strip, for the channel's labels.
+const LABEL_WIDTH: usize = 17;
+/// Text line the register table starts on, under the strips.
+const FIRST_TABLE_LINE: usize = 17;
+const WAVE_LINE: usize = FIRST_TABLE_LINE + 6;
+const LINES: usize = 28;
+
+/// Oscilloscope of each channel's DAC output over the live APU registers,
+/// channel state and wave RAM.
+#[derive(Default)]
+pub struct ApuViewer {
+    pub visible: bool,
+    fb: FrameBuffer,
+    lines: Vec<String>,
+}
+
+impl ApuViewer {
+    /// Viewer keys, only while it's shown.
+    pub fn handle_key(&self, sc: Scancode, pressed: bool) -> Option<AppCmd> {
+        if !self.visible || !pressed {
+            return None;
+        }
+
+        let channel = match sc {
+            Scancode::Insert | Scancode::Escape => return Some(AppCmd::ToggleApuViewer),
+            Scancode::Num1 => 0,
+            Scancode::Num2 => 1,
+            Scancode::Num3 => 2,
+            Scancode::Num4 => 3,
+            _ => return None,
+        };
+
+        Some(AppCmd::ChangeConfig(ChangeConfigCmd::ToggleChannel(
+            channel,
+        )))
+    }
+
+    /// Shows or hides the view; the scope only runs while it's shown.
+    pub fn toggle(&mut self, emu: &mut Emu) {
+        self.visible = !self.visible;
+
+        if !self.visible {
+            emu.runtime.cpu.clock.bus.io.apu.stop_scope();
+        }
+    }
+
+    /// Redraws the view and returns its buffer.
+    pub fn draw(&mut self, emu: &mut Emu, overlay: &Overlay) -> &FrameBuffer {
+        let apu = &mut emu.runtime.cpu.clock.bus.io.apu;
+
+        // also after a game was loaded with the view open
+        if apu.scope().is_none() {
+            apu.start_scope(SCOPE_SAMPLES);
+        }
+
+        let states = apu.channel_states();
+        let registers = apu.peek_registers();
+        let wave_ram = apu.wave_ram();
+        let mask = apu.config.channel_mask;
+
+        self.lines.clear();
+
+        for (i, state) in states.iter().enumerate() {
+            self.lines.push(format!("CH{}", i + 1));
+            self.lines.push(format!("V{}", state.volume));
+            self.lines
+                .push(if state.active { "ON" } else { "OFF" }.to_string());
+            self.lines
+                .push(if mask & (1 << i) == 0 { "MUTE" } else { "" }.to_string());
+        }
+
+        self.lines.resize(FIRST_TABLE_LINE, String::new());
+        self.lines
+            .push("CH  X0 X1 X2 X3 X4 VOL LEN   FREQ".to_string());
+
+        for (i, state) in states.iter().enumerate() {
+            // NR41-NR44 sit one address later, with nothing at $FF1F
+            let regs = &registers[i * 5..i * 5 + 5];
+            let mut line = format!("NR{}", i + 1);
+
+            for (j, byte) in regs.iter().enumerate() {
+                if i == 3 && j == 0 {
+                    line.push_str(" --");
+                } else {
+                    write!(line, " {byte:02X}").ok();
+                }
+            }
+
+            write!(
+                line,
+                "  {:>2} {:>3} {:>6.0}",
+                state.volume, state.length, state.frequency
+            )
+            .ok();
+            self.lines.push(line);
+        }
+
+        let sweep = states[0].sweep_period.unwrap_or_default();
+        self.lines.push(format!(
+            "NR50 {:02X} NR51 {:02X} NR52 {:02X} SWEEP {sweep:03X}",
+            registers[20], registers[21], registers[22]
+        ));
+
+        let mut wave = "WAVE ".to_string();
+        for byte in wave_ram.bytes() {
+            write!(wave, "{byte:02X}").ok();
+        }
+        self.lines.push(wave);
+
+        self.lines.resize(LINES - 1, String::new());
+        self.lines.push("1-4:MUTE INSERT:CLOSE".to_string());
+
+        let lines: Vec<&str> = self.lines.iter().map(|x| x.as_str()).collect();
+        overlay.fill_debugger(&mut self.fb, &lines, &[], 0);
+
+        // the sample being played, high nibble first
+        let index = wave_ram.get_sample_index();
+        let byte = wave_ram.bytes()[index / 2];
+        let sample = if index.is_multiple_of(2) {
+            byte >> 4
+        } else {
+            byte & 0xF
+        };
+        overlay.invert_text(&mut self.fb, &format!("{sample:X}"), WAVE_LINE, 5 + index);
+
+        if let Some(scope) = apu.scope() {
+            let width = LCD_X_RES as usize - LABEL_WIDTH;
+
+            for channel in 0..4 {
+                let samples = scope.window(channel, width);
+                let top = channel * STRIP_HEIGHT;
+                draw_wave(&mut self.fb, &samples, top, overlay);
+            }
+        }
+
+        &self.fb
+    }
+}
+
+/// Plots samples in -1..1 across a strip, joining each to the last so
+/// steep edges stay visible.
+fn draw_wave(fb: &mut FrameBuffer, samples: &[f32], top: usize, overlay: &Overlay) {
+    let height = STRIP_HEIGHT - 3;
+    let to_y = |x: f32| top + 1 + ((1.0 - x.clamp(-1.0, 1.0)) / 2.0 * height as f32) as usize;
+    let mut last = None;
+
+    for (i, sample) in samples.iter().enumerate() {
+        let x = LABEL_WIDTH + i;
+        let y = to_y(*sample);
+        let (from, to) = match last {
+            Some(last) => (y.min(last), y.max(last)),
+            None => (y, y),
+        };
+
+        for y in from..=to {
+            let offset = y * FrameBuffer::PITCH + x * FrameBuffer::BYTES_PER_PIXEL;
+            draw_color(fb, offset, overlay.text_color);
+        }
+
+        last = Some(y);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::get_base_dir;
+use core::bus::Bus;
+use core::cheats::search::{Filter, RamSearch, ValueKind};
+use core::cheats::Cheat;
+use core::debugger::CmpOp;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A game's cheat list, kept with its battery save.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheatsSave {
+    pub cheats: Vec<Cheat>,
+}
+
+impl CheatsSave {
+    pub fn save_file(&self, name: &str) -> std::io::Result<()> {
+        let path = Self::generate_path(name);
+
+        if let Some(parent) = Path::new(&path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    pub fn load_file(name: &str) -> std::io::Result<Self> {
+        let data = fs::read_to_string(Self::generate_path(name))?;
+
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub fn generate_path(name: &str) -> PathBuf {
+        get_base_dir()
+            .join("saves")
+            .join(format!("{name}.cheats.json"))
+    }
+}
+
+/// The running RAM search and the settings its menu was left at.
+#[derive(Debug, Clone)]
+pub struct RamSearchState {
+    pub search: Option<RamSearch>,
+    pub kind: ValueKind,
+    pub filter: Filter,
+}
+
+impl Default for RamSearchState {
+    fn default() -> Self {
+        Self {
+            search: None,
+            kind: ValueKind::default(),
+            filter: Filter::new(CmpOp::Ne, None),
+        }
+    }
+}
+
+impl RamSearchState {
+    pub fn start(&mut self, bus: &Bus, kind: ValueKind) {
+        self.search = Some(RamSearch::new(bus, kind));
+        self.kind = kind;
+    }
+
+    pub fn filter(&mut self, bus: &Bus, filter: Filter) {
+        if let Some(search) = self.search.as_mut() {
+            search.filter(bus, filter);
+        }
+
+        self.filter = filter;
+    }
+}
@@ -4,6 +4,9 @@ use crate::{
 };
 use core::{
     auxiliary::joypad::JoypadButton,
+    cart::mbc3::RtcClock,
+    cheats::search::{Filter, Location, ValueKind},
+    cheats::Cheat,
     emu::{config::GbModel, runtime::RunMode, state::SaveStateCmd},
 };
 use serde::{Deserialize, Serialize};
@@ -17,6 +20,9 @@ pub enum AppCmd {
     RestartRom,
     ChangeMode(RunMode),
     SaveState(SaveStateCmd, Option<usize>),
+    /// Opens the slot browser of the running game.
+    BrowseSaveStates,
+    DeleteSaveState(usize),
     SelectRom,
     Quit,
     ChangeConfig(ChangeConfigCmd),
@@ -32,6 +38,15 @@ pub enum AppCmd {
     StepFrame,
     StepScanline,
     ClearScreen,
+    Debugger(DebugCmd),
+    /// Replaces the cheat list of the running game.
+    SetCheats(Vec<Cheat>),
+    RamSearch(RamSearchCmd),
+    MemoryViewer(MemoryCmd),
+    ToggleApuViewer,
+    VramViewer(VramCmd),
+    Movie(MovieCmd),
+    Netplay(NetplayCmd),
 }
 
 impl AppCmd {
@@ -43,6 +58,8 @@ impl AppCmd {
             AppCmd::RestartRom => "Restart ROM",
             AppCmd::ChangeMode(m) => m.name(),
             AppCmd::SaveState(m, _) => m.name(),
+            AppCmd::BrowseSaveStates => "Save States",
+            AppCmd::DeleteSaveState(_) => "Delete Save State",
             AppCmd::SelectRom => "Select ROM",
             AppCmd::Quit => "Quit",
             AppCmd::ChangeConfig(conf) => conf.name(),
@@ -58,6 +75,139 @@ impl AppCmd {
             AppCmd::ToggleStepping => "Toggle Stepping",
             AppCmd::StepScanline => "Step Scanline",
             AppCmd::ClearScreen => "Clear Screen",
+            AppCmd::Debugger(cmd) => cmd.name(),
+            AppCmd::SetCheats(_) => "Set Cheats",
+            AppCmd::RamSearch(cmd) => cmd.name(),
+            AppCmd::MemoryViewer(cmd) => cmd.name(),
+            AppCmd::ToggleApuViewer => "APU Viewer",
+            AppCmd::VramViewer(cmd) => cmd.name(),
+            AppCmd::Movie(cmd) => cmd.name(),
+            AppCmd::Netplay(cmd) => cmd.name(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum RamSearchCmd {
+    /// Snapshots RAM, dropping the previous search.
+    Start(ValueKind),
+    Filter(Filter),
+    /// Adds a cheat holding the value at the location.
+    Freeze(Location, u16),
+    /// Adds a write watchpoint over the value at the location.
+    Watch(Location),
+}
+
+impl RamSearchCmd {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            RamSearchCmd::Start(_) => "New Search",
+            RamSearchCmd::Filter(_) => "Filter Search",
+            RamSearchCmd::Freeze(..) => "Freeze Value",
+            RamSearchCmd::Watch(_) => "Watch Value",
+        }
+    }
+}
+
+/// Memory viewer commands.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum MemoryCmd {
+    Toggle,
+    /// Drops the pending edit or goto, or closes the viewer.
+    Cancel,
+    Confirm,
+    /// Moves the cursor by a number of bytes.
+    Move(i32),
+    Goto,
+    NextRegion,
+    ChangeBank(i32),
+    /// A typed hex digit, for the byte under the cursor or a goto address.
+    Digit(u8),
+}
+
+impl MemoryCmd {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            MemoryCmd::Toggle => "Memory Viewer",
+            MemoryCmd::Cancel => "Cancel",
+            MemoryCmd::Confirm => "Confirm",
+            MemoryCmd::Move(_) => "Move Cursor",
+            MemoryCmd::Goto => "Goto Address",
+            MemoryCmd::NextRegion => "Next Region",
+            MemoryCmd::ChangeBank(_) => "Change Bank",
+            MemoryCmd::Digit(_) => "Hex Digit",
+        }
+    }
+}
+
+/// VRAM viewer commands.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum VramCmd {
+    Toggle,
+    /// Drops the pending color edit, or closes the viewer.
+    Cancel,
+    Confirm,
+    /// Pans a picture, scrolls the sprite list or selects a color.
+    Move(i32, i32),
+    ChangePage(i32),
+    /// A typed hex digit of a new color.
+    Digit(u8),
+}
+
+impl VramCmd {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            VramCmd::Toggle => "VRAM Viewer",
+            VramCmd::Cancel => "Cancel",
+            VramCmd::Confirm => "Confirm",
+            VramCmd::Move(..) => "Move",
+            VramCmd::ChangePage(_) => "Change Page",
+            VramCmd::Digit(_) => "Hex Digit",
+        }
+    }
+}
+
+/// Input movie commands, on the running game's movie file.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum MovieCmd {
+    /// Records from the current state.
+    Record,
+    /// Restarts the game and records from power on.
+    RecordFromReset,
+    /// Plays back, switching to recording when a state is loaded.
+    Play,
+    PlayReadOnly,
+    ToggleReadOnly,
+    /// Stops the movie, saving it if it was recorded into.
+    Stop,
+}
+
+impl MovieCmd {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            MovieCmd::Record => "Record Movie",
+            MovieCmd::RecordFromReset => "Record From Reset",
+            MovieCmd::Play => "Play Movie",
+            MovieCmd::PlayReadOnly => "Play Read-Only",
+            MovieCmd::ToggleReadOnly => "Movie Read-Only",
+            MovieCmd::Stop => "Stop Movie",
+        }
+    }
+}
+
+/// Netplay commands, with the addresses and player from the config.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum NetplayCmd {
+    /// Restarts the game as a session and waits for the peer.
+    Start,
+    Stop,
+}
+
+impl NetplayCmd {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            NetplayCmd::Start => "Start Netplay",
+            NetplayCmd::Stop => "Stop Netplay",
         }
     }
 }
@@ -80,6 +230,59 @@ impl AppCmd {
     }
 }
 
+/// Debugger console commands; no-ops without the `debug` feature.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum DebugCmd {
+    Toggle,
+    Continue,
+    StepInto,
+    StepOver,
+    StepOut,
+    RunToCursor,
+    ToggleBreakpoint,
+    EnableBreakpoint,
+    ClearBreakpoints,
+    CursorUp,
+    CursorDown,
+    MemoryUp,
+    MemoryDown,
+    MemoryToCursor,
+    MemoryToHl,
+    ToggleProfiler,
+    ReverseStep,
+    ReverseContinue,
+    /// Goes back to the last write to the byte at HL.
+    LastWrite,
+    ToggleTimeline,
+}
+
+impl DebugCmd {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            DebugCmd::Toggle => "Debugger",
+            DebugCmd::Continue => "Continue",
+            DebugCmd::StepInto => "Step Into",
+            DebugCmd::StepOver => "Step Over",
+            DebugCmd::StepOut => "Step Out",
+            DebugCmd::RunToCursor => "Run To Cursor",
+            DebugCmd::ToggleBreakpoint => "Toggle Breakpoint",
+            DebugCmd::EnableBreakpoint => "Enable Breakpoint",
+            DebugCmd::ClearBreakpoints => "Clear Breakpoints",
+            DebugCmd::CursorUp => "Cursor Up",
+            DebugCmd::CursorDown => "Cursor Down",
+            DebugCmd::MemoryUp => "Memory Up",
+            DebugCmd::MemoryDown => "Memory Down",
+            DebugCmd::MemoryToCursor => "Memory To Cursor",
+            DebugCmd::MemoryToHl => "Memory To HL",
+            DebugCmd::ToggleProfiler => "Profiler",
+            DebugCmd::ReverseStep => "Reverse Step",
+            DebugCmd::ReverseContinue => "Reverse Continue",
+            DebugCmd::LastWrite => "Last Write",
+            DebugCmd::ToggleTimeline => "Timeline",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct BindInputCmd {
     pub input_index: InputIndex,
@@ -133,8 +336,11 @@ pub enum ChangeConfigCmd {
     NormalSpeed(f32),
     TurboSpeed(f32),
     SlowSpeed(f32),
-    RewindSize(i32),
+    RewindBudget(i32),
     RewindFrames(i32),
+    RewindAudio,
+    RunAhead(i32),
+    RunAheadSecondInstance,
     AutoSaveState,
     AudioBufferSize(i32),
     MuteTurbo,
@@ -152,6 +358,9 @@ pub enum ChangeConfigCmd {
     PrevShader,
     FrameSkip(usize),
     SetGbModel(Option<GbModel>),
+    SetRtcClock(RtcClock),
+    NetplayPlayer,
+    NetplayDelay(i32),
     TargetFps(f32),
 }
 
@@ -171,8 +380,11 @@ impl ChangeConfigCmd {
             ChangeConfigCmd::NormalSpeed(_) => "Normal Speed",
             ChangeConfigCmd::TurboSpeed(_) => "Turbo Speed",
             ChangeConfigCmd::SlowSpeed(_) => "Slow Speed",
-            ChangeConfigCmd::RewindSize(_) => "Rewind Size",
+            ChangeConfigCmd::RewindBudget(_) => "Rewind Budget",
             ChangeConfigCmd::RewindFrames(_) => "Rewind Frames",
+            ChangeConfigCmd::RewindAudio => "Rewind Audio",
+            ChangeConfigCmd::RunAhead(_) => "Run Ahead",
+            ChangeConfigCmd::RunAheadSecondInstance => "Run Ahead Instance",
             ChangeConfigCmd::AutoSaveState => "Auto Save State",
             ChangeConfigCmd::AudioBufferSize(_) => "Audio Buffer Size",
             ChangeConfigCmd::MuteTurbo => "Mute Turbo",
@@ -189,6 +401,9 @@ impl ChangeConfigCmd {
             ChangeConfigCmd::PrevShader => "Prev Shader",
             ChangeConfigCmd::FrameSkip(_) => "Frame Skip",
             ChangeConfigCmd::SetGbModel(_) => "Model",
+            ChangeConfigCmd::SetRtcClock(_) => "RTC Clock",
+            ChangeConfigCmd::NetplayPlayer => "Netplay Player",
+            ChangeConfigCmd::NetplayDelay(_) => "Netplay Delay",
             ChangeConfigCmd::TargetFps(_) => "Target Fps",
         }
     }
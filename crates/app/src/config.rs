@@ -26,6 +26,8 @@ pub struct AppConfig {
     pub audio: AudioConfig,
     pub video: VideoConfig,
     pub input: InputConfig,
+    #[serde(default)]
+    pub netplay: NetplayConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
@@ -159,6 +161,36 @@ impl AudioConfig {
     }
 }
 
+/// Where a netplay session listens and who it talks to. The addresses are
+/// only set in the config file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct NetplayConfig {
+    pub local: String,
+    pub peer: String,
+    /// 0 for player 1, 1 for player 2.
+    pub player: usize,
+    /// Frames of input delay.
+    pub delay: usize,
+    /// Artificial latency added to every packet sent, for trying it out.
+    pub latency_ms: u64,
+    /// Fraction of packets dropped on purpose, for trying it out.
+    pub loss: f32,
+}
+
+impl Default for NetplayConfig {
+    fn default() -> Self {
+        Self {
+            local: "0.0.0.0:7845".to_string(),
+            peer: "127.0.0.1:7846".to_string(),
+            player: 0,
+            delay: 1,
+            latency_ms: 0,
+            loss: 0.0,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub enum ScaleMode {
     Integer,
@@ -204,7 +236,15 @@ impl InterfaceConfig {
 impl AppConfig {
     pub fn from_file(path: &Path) -> io::Result<Self> {
         let data = fs::read_to_string(path)?;
-        let config: Self = serde_json::from_str(&data)?;
+        let value: serde_json::Value = serde_json::from_str(&data)?;
+        // configs from before the budget snapshot every 60 frames, which
+        // rewinds by 60 frames now
+        let is_old = value["emulation"].get("rewind_budget").is_none();
+        let mut config: Self = serde_json::from_value(value)?;
+
+        if is_old {
+            config.emulation.rewind_frames = EmuConfig::default().rewind_frames;
+        }
 
         Ok(config)
     }
@@ -279,6 +319,7 @@ impl Default for AppConfig {
                 },
             },
             auto_continue: false,
+            netplay: NetplayConfig::default(),
         }
     }
 }
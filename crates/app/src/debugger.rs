@@ -0,0 +1,498 @@
+use crate::cmd::{AppCmd, DebugCmd};
+use crate::get_base_dir;
+use crate::video::overlay::Overlay;
+use core::cpu::instructions::Instruction;
+use core::cpu::Cpu;
+use core::debugger::{
+    find_instructions_before, get_asm_string_at, get_length_at, BreakReason, Breakpoints,
+    DapServer, Debugger, StepMode,
+};
+use core::emu::Emu;
+use core::ppu::framebuffer::FrameBuffer;
+use sdl2::keyboard::Scancode;
+use std::fmt::Write;
+
+const DISASM_LINES: usize = 14;
+const DISASM_LINES_BEFORE: usize = 4;
+const STACK_LINES: usize = 14;
+const MEMORY_LINES: usize = 8;
+const MEMORY_ROW_BYTES: u16 = 8;
+const MEMORY_PAGE: u16 = MEMORY_ROW_BYTES * MEMORY_LINES as u16;
+/// Routines in the report logged when the profiler stops.
+const PROFILE_REPORT_LINES: usize = 32;
+/// Frames the timeline keeps: the one shown and the one being recorded.
+const TIMELINE_FRAMES: usize = 2;
+/// Column of the stack view, in characters.
+const STACK_COLUMN: usize = 30;
+
+/// Port of the debug adapter server, off unless set.
+pub const DAP_PORT_ENV: &str = "OXGBC_DAP_PORT";
+
+/// In-app debugger pane drawn over the screen: disassembly around PC with
+/// the cursor and breakpoints, registers, the stack and a memory hex view.
+pub struct DebugConsole {
+    pub visible: bool,
+    fb: FrameBuffer,
+    cursor: u16,
+    /// The cursor snaps back to PC after each break until it's moved.
+    follow_pc: bool,
+    mem_addr: u16,
+    /// First event shown of the timeline's last frame.
+    timeline_row: usize,
+    status: String,
+    left: Vec<String>,
+    right: Vec<String>,
+    /// Instruction addresses of the last drawn disassembly.
+    disasm_addrs: Vec<u16>,
+}
+
+impl Default for DebugConsole {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            fb: FrameBuffer::default(),
+            cursor: 0,
+            follow_pc: true,
+            mem_addr: 0xC000,
+            timeline_row: 0,
+            status: String::new(),
+            left: Vec::with_capacity(DISASM_LINES + MEMORY_LINES + 5),
+            right: Vec::with_capacity(STACK_LINES),
+            disasm_addrs: Vec::with_capacity(DISASM_LINES),
+        }
+    }
+}
+
+impl DebugConsole {
+    /// Console-local keys, only while it's shown.
+    pub fn handle_key(&self, sc: Scancode, pressed: bool) -> Option<AppCmd> {
+        if !self.visible || !pressed {
+            return None;
+        }
+
+        let cmd = match sc {
+            Scancode::Escape => DebugCmd::Toggle,
+            Scancode::R => DebugCmd::Continue,
+            Scancode::I => DebugCmd::StepInto,
+            Scancode::O => DebugCmd::StepOver,
+            Scancode::U => DebugCmd::StepOut,
+            Scancode::C => DebugCmd::RunToCursor,
+            Scancode::B => DebugCmd::ToggleBreakpoint,
+            Scancode::E => DebugCmd::EnableBreakpoint,
+            Scancode::X => DebugCmd::ClearBreakpoints,
+            Scancode::Up => DebugCmd::CursorUp,
+            Scancode::Down => DebugCmd::CursorDown,
+            Scancode::PageUp => DebugCmd::MemoryUp,
+            Scancode::PageDown => DebugCmd::MemoryDown,
+            Scancode::M => DebugCmd::MemoryToCursor,
+            Scancode::H => DebugCmd::MemoryToHl,
+            Scancode::P => DebugCmd::ToggleProfiler,
+            Scancode::J => DebugCmd::ReverseStep,
+            Scancode::K => DebugCmd::ReverseContinue,
+            Scancode::W => DebugCmd::LastWrite,
+            Scancode::T => DebugCmd::ToggleTimeline,
+            _ => return None,
+        };
+
+        Some(AppCmd::Debugger(cmd))
+    }
+
+    /// Applies a command. Returns whether emulation should run afterward:
+    /// until the armed step completes or a breakpoint hits.
+    pub fn handle_cmd(&mut self, emu: &mut Emu, cmd: DebugCmd) -> bool {
+        let cpu = &emu.runtime.cpu;
+        let step = match cmd {
+            DebugCmd::Toggle => {
+                self.visible = !self.visible;
+                self.follow_pc = true;
+                None
+            }
+            DebugCmd::Continue => {
+                self.status.clear();
+                return true;
+            }
+            DebugCmd::StepInto => Some(StepMode::Into),
+            DebugCmd::StepOver => Some(StepMode::over(cpu)),
+            DebugCmd::StepOut => Some(StepMode::out(cpu)),
+            DebugCmd::RunToCursor => Some(StepMode::run_to(self.cursor)),
+            DebugCmd::ToggleBreakpoint => {
+                if let Some(debugger) = emu.runtime.debugger_mut() {
+                    debugger.breakpoints.toggle(self.cursor);
+                }
+                None
+            }
+            DebugCmd::EnableBreakpoint => {
+                if let Some(debugger) = emu.runtime.debugger_mut() {
+                    let bps = debugger.breakpoints.pc.iter_mut();
+                    for bp in bps.filter(|x| x.addr == self.cursor) {
+                        bp.enabled = !bp.enabled;
+                    }
+                }
+                None
+            }
+            DebugCmd::ClearBreakpoints => {
+                if let Some(debugger) = emu.runtime.debugger_mut() {
+                    debugger.breakpoints = Breakpoints::default();
+                }
+                None
+            }
+            DebugCmd::CursorUp => {
+                self.move_cursor(cpu, false);
+                None
+            }
+            DebugCmd::CursorDown => {
+                self.move_cursor(cpu, true);
+                None
+            }
+            DebugCmd::MemoryUp if cpu.clock.bus.io.timeline.is_some() => {
+                self.timeline_row = self.timeline_row.saturating_sub(MEMORY_LINES);
+                None
+            }
+            DebugCmd::MemoryDown if cpu.clock.bus.io.timeline.is_some() => {
+                self.timeline_row += MEMORY_LINES;
+                None
+            }
+            DebugCmd::MemoryUp => {
+                self.mem_addr = self.mem_addr.wrapping_sub(MEMORY_PAGE);
+                None
+            }
+            DebugCmd::MemoryDown => {
+                self.mem_addr = self.mem_addr.wrapping_add(MEMORY_PAGE);
+                None
+            }
+            DebugCmd::MemoryToCursor => {
+                self.mem_addr = self.cursor & !(MEMORY_ROW_BYTES - 1);
+                None
+            }
+            DebugCmd::MemoryToHl => {
+                self.mem_addr = cpu.registers.get_hl() & !(MEMORY_ROW_BYTES - 1);
+                None
+            }
+            DebugCmd::ToggleProfiler => {
+                self.toggle_profiler(emu);
+                None
+            }
+            DebugCmd::ReverseStep | DebugCmd::ReverseContinue | DebugCmd::LastWrite => {
+                self.travel_back(emu, cmd);
+                None
+            }
+            DebugCmd::ToggleTimeline => {
+                self.toggle_timeline(emu);
+                None
+            }
+        };
+
+        let Some(step) = step else {
+            return false;
+        };
+
+        let Some(debugger) = emu.runtime.debugger_mut() else {
+            return false;
+        };
+
+        debugger.set_step_mode(step);
+        self.follow_pc = true;
+        self.status.clear();
+
+        true
+    }
+
+    /// `label` names where execution stopped, if the ROM has symbols.
+    pub fn on_break(&mut self, reason: &BreakReason, label: Option<&str>) {
+        self.follow_pc = true;
+        self.status.clear();
+
+        if !matches!(reason, BreakReason::Step { .. }) {
+            write!(self.status, "BREAK: {reason}").ok();
+        }
+
+        if let Some(label) = label {
+            write!(self.status, " {label}").ok();
+        }
+    }
+
+    /// Redraws the pane for the current state and returns its buffer.
+    pub fn draw(&mut self, emu: &mut Emu, overlay: &Overlay) -> &FrameBuffer {
+        let Some((cpu, debugger)) = emu.runtime.debug_parts() else {
+            return &self.fb;
+        };
+
+        if self.follow_pc {
+            self.cursor = cpu.registers.pc;
+        }
+
+        self.left.clear();
+        self.right.clear();
+        self.fill_registers(cpu);
+        self.fill_disasm(cpu, debugger);
+        if cpu.profiler.is_some() {
+            self.fill_profile(cpu, debugger);
+        } else if cpu.clock.bus.io.timeline.is_some() {
+            self.fill_timeline(cpu);
+        } else {
+            self.fill_memory(cpu);
+        }
+        self.fill_stack(cpu);
+
+        let left: Vec<&str> = self.left.iter().map(|x| x.as_str()).collect();
+        let right: Vec<&str> = self.right.iter().map(|x| x.as_str()).collect();
+        overlay.fill_debugger(&mut self.fb, &left, &right, STACK_COLUMN);
+
+        &self.fb
+    }
+
+    fn fill_registers(&mut self, cpu: &mut Cpu) {
+        let r = &mut cpu.registers;
+        let f = r.flags.get_byte();
+        let flag = |bit: u8, c: char| if f & (1 << bit) != 0 { c } else { '-' };
+
+        // kept short of the stack view
+        self.left.push(format!(
+            "AF {:04X} BC {:04X} DE {:04X}",
+            r.get_af(),
+            r.get_bc(),
+            r.get_de()
+        ));
+        self.left.push(format!(
+            "HL {:04X} SP {:04X} PC {:04X}",
+            r.get_hl(),
+            r.sp,
+            r.pc
+        ));
+        self.left.push(format!(
+            "{}{}{}{} IME {} LY {:02X}",
+            flag(7, 'Z'),
+            flag(6, 'N'),
+            flag(5, 'H'),
+            flag(4, 'C'),
+            cpu.clock.bus.io.interrupts.ime as u8,
+            cpu.clock.bus.io.ppu.lcd.ly,
+        ));
+        self.left.push(self.status.clone());
+    }
+
+    fn fill_disasm(&mut self, cpu: &Cpu, debugger: &Debugger) {
+        self.disasm_addrs.clear();
+        self.disasm_addrs.extend(find_instructions_before(
+            cpu,
+            self.cursor,
+            DISASM_LINES_BEFORE,
+        ));
+        let mut addr = self.cursor;
+
+        while self.disasm_addrs.len() < DISASM_LINES {
+            self.disasm_addrs.push(addr);
+            addr = addr.wrapping_add(get_length_at(cpu, addr));
+        }
+
+        for &addr in self.disasm_addrs.iter() {
+            let instruction = Instruction::get_by_opcode(cpu.clock.bus.read(addr));
+            let cursor = if addr == self.cursor { '▶' } else { ' ' };
+            let pc = if addr == cpu.registers.pc { '>' } else { ' ' };
+            let bp = match debugger.breakpoints.pc.iter().find(|x| x.addr == addr) {
+                Some(bp) if bp.enabled => '●',
+                Some(_) => '-',
+                None => ' ',
+            };
+
+            self.left.push(format!(
+                "{cursor}{pc}{bp}{addr:04X} {}",
+                get_asm_string_at(cpu, addr, instruction, &debugger.symbols)
+            ));
+        }
+    }
+
+    fn fill_memory(&mut self, cpu: &Cpu) {
+        self.left.push(String::new());
+
+        for row in 0..MEMORY_LINES as u16 {
+            let addr = self.mem_addr.wrapping_add(row * MEMORY_ROW_BYTES);
+            let mut line = format!("{addr:04X}");
+
+            for i in 0..MEMORY_ROW_BYTES {
+                write!(line, " {:02X}", cpu.clock.bus.read(addr.wrapping_add(i))).ok();
+            }
+
+            self.left.push(line);
+        }
+
+        self.left.push("I O U:STEP C:CURSOR B:BP R:RUN".to_string());
+    }
+
+    /// The last frame's hottest routines in place of the memory view.
+    fn fill_profile(&mut self, cpu: &Cpu, debugger: &Debugger) {
+        let Some(profiler) = &cpu.profiler else {
+            return;
+        };
+
+        let header = format!("{:<16} {:>6} {:>6}", "ROUTINE", "EXCL", "FRAME");
+        self.left.push(header);
+
+        let frame_cycles = profiler.last_frame_cycles().max(1);
+
+        for (routine, stats) in profiler.last_frame().iter().take(MEMORY_LINES) {
+            let mut name = routine.name(&debugger.symbols);
+            name.truncate(16);
+            let percent = stats.exclusive as f64 * 100.0 / frame_cycles as f64;
+            self.left
+                .push(format!("{name:<16} {:>6} {percent:>5.1}%", stats.exclusive));
+        }
+
+        for _ in profiler.last_frame().len()..MEMORY_LINES {
+            self.left.push(String::new());
+        }
+
+        self.left.push("P:STOP PROFILER R:RUN".to_string());
+    }
+
+    /// Starts profiling, or stops it and logs the report.
+    fn toggle_profiler(&mut self, emu: &mut Emu) {
+        let cpu = &mut emu.runtime.cpu;
+
+        let Some(profiler) = cpu.profiler.take() else {
+            cpu.start_profiler();
+            self.status = "PROFILING".to_string();
+            return;
+        };
+
+        self.status.clear();
+
+        if let Some(debugger) = emu.runtime.debugger_mut() {
+            log::info!(
+                "profile over {} frames (M-cycles):\n{}",
+                profiler.frames(),
+                profiler.report(&debugger.symbols, PROFILE_REPORT_LINES)
+            );
+        }
+    }
+
+    /// The last complete frame's hardware events in place of the memory view.
+    fn fill_timeline(&mut self, cpu: &Cpu) {
+        let Some(timeline) = &cpu.clock.bus.io.timeline else {
+            return;
+        };
+
+        let events = timeline
+            .last_complete_frame()
+            .map(|x| x.events.as_slice())
+            .unwrap_or_default();
+        self.timeline_row = self
+            .timeline_row
+            .min(events.len().saturating_sub(MEMORY_LINES));
+        self.left.push(format!("{:>3} {:>3} EVENT", "LY", "DOT"));
+
+        for i in self.timeline_row..self.timeline_row + MEMORY_LINES {
+            let line = match events.get(i) {
+                Some(event) => format!("{:>3} {:>3} {}", event.ly, event.dot, event.kind),
+                None => String::new(),
+            };
+            self.left.push(line);
+        }
+
+        self.left.push("PGUP PGDN:SCROLL T:STOP".to_string());
+    }
+
+    /// Starts recording the timeline, or stops it and saves the recording
+    /// as a Chrome trace.
+    fn toggle_timeline(&mut self, emu: &mut Emu) {
+        let io = &mut emu.runtime.cpu.clock.bus.io;
+
+        let Some(timeline) = io.timeline.take() else {
+            io.start_timeline(TIMELINE_FRAMES);
+            self.timeline_row = 0;
+            self.status = "TIMELINE".to_string();
+            return;
+        };
+
+        self.status.clear();
+        let path = get_base_dir().join("timeline.trace.json");
+
+        match std::fs::write(&path, timeline.to_chrome_trace()) {
+            Ok(()) => log::info!("timeline saved to {}", path.display()),
+            Err(e) => log::error!("failed to save {}: {e}", path.display()),
+        }
+    }
+
+    /// Replays from the rewind snapshots to an earlier step.
+    fn travel_back(&mut self, emu: &mut Emu, cmd: DebugCmd) {
+        self.follow_pc = true;
+        self.status.clear();
+
+        let result = match cmd {
+            DebugCmd::ReverseStep => emu.reverse_step(),
+            DebugCmd::ReverseContinue => emu.reverse_continue().map(|reason| match reason {
+                Some(reason) => self.on_break(&reason, None),
+                None => self.status.push_str("START OF HISTORY"),
+            }),
+            _ => {
+                let addr = emu.runtime.cpu.registers.get_hl();
+
+                emu.last_write(addr).and_then(|write| match write {
+                    Some(write) => {
+                        write!(
+                            self.status,
+                            "{addr:04X} WRITTEN {:02X} AT {:04X}",
+                            write.value, write.pc
+                        )
+                        .ok();
+                        emu.seek(write.step)
+                    }
+                    None => {
+                        write!(self.status, "NO WRITE TO {addr:04X}").ok();
+                        Ok(())
+                    }
+                })
+            }
+        };
+
+        if let Err(err) = result {
+            self.status = err.to_uppercase();
+        }
+    }
+
+    fn fill_stack(&mut self, cpu: &Cpu) {
+        self.right.push("STACK".to_string());
+        let sp = cpu.registers.sp;
+
+        for i in 0..STACK_LINES as u16 {
+            let addr = sp.wrapping_add(i * 2);
+            let lo = cpu.clock.bus.read(addr);
+            let hi = cpu.clock.bus.read(addr.wrapping_add(1));
+            self.right
+                .push(format!("{addr:04X} {:04X}", u16::from_le_bytes([lo, hi])));
+        }
+    }
+
+    fn move_cursor(&mut self, cpu: &Cpu, down: bool) {
+        self.follow_pc = false;
+
+        self.cursor = if down {
+            self.cursor.wrapping_add(get_length_at(cpu, self.cursor))
+        } else {
+            find_instructions_before(cpu, self.cursor, 1)
+                .first()
+                .copied()
+                .unwrap_or(self.cursor.wrapping_sub(1))
+        };
+    }
+}
+
+pub fn bind_dap_server() -> Option<DapServer> {
+    let port = std::env::var(DAP_PORT_ENV).ok()?;
+
+    let Ok(port) = port.parse() else {
+        log::error!("Invalid {DAP_PORT_ENV}: {port}");
+        return None;
+    };
+
+    match DapServer::bind(port) {
+        Ok(server) => {
+            log::info!("Debug adapter listening on 127.0.0.1:{port}");
+            Some(server)
+        }
+        Err(err) => {
+            log::error!("Failed to start debug adapter on port {port}: {err}");
+            None
+        }
+    }
+}
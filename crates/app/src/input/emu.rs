@@ -86,7 +86,13 @@ where
     FD: PlatformFileDialog,
 {
     if app.state == AppState::Paused && pressed {
-        return app.menu.select(&app.config, &app.platform.fs, &app.roms);
+        return app.menu.select(
+            &app.config,
+            &app.platform.fs,
+            &app.roms,
+            &app.cheats,
+            &app.ram_search,
+        );
     } else {
         emu.runtime.cpu.clock.bus.io.joypad.a = pressed;
     }
@@ -112,7 +118,13 @@ where
     FD: PlatformFileDialog,
 {
     if app.state == AppState::Paused && pressed {
-        return app.menu.select(&app.config, &app.platform.fs, &app.roms);
+        return app.menu.select(
+            &app.config,
+            &app.platform.fs,
+            &app.roms,
+            &app.cheats,
+            &app.ram_search,
+        );
     } else {
         emu.runtime.cpu.clock.bus.io.joypad.start = pressed;
     }
@@ -64,6 +64,27 @@ impl InputHandler {
                 Event::KeyDown {
                     scancode: Some(sc), ..
                 } => {
+                    if let Some(cmd) = app.memory_viewer.handle_key(sc, true) {
+                        self.handle_cmd(app, emu, cmd);
+                        continue;
+                    }
+
+                    if let Some(cmd) = app.vram_viewer.handle_key(sc, true) {
+                        self.handle_cmd(app, emu, cmd);
+                        continue;
+                    }
+
+                    if let Some(cmd) = app.apu_viewer.handle_key(sc, true) {
+                        self.handle_cmd(app, emu, cmd);
+                        continue;
+                    }
+
+                    #[cfg(feature = "debug")]
+                    if let Some(cmd) = app.console.handle_key(sc, true) {
+                        self.handle_cmd(app, emu, cmd);
+                        continue;
+                    }
+
                     if let Some(cmd) = app.menu.handle_input(sc, true) {
                         self.handle_cmd(app, emu, cmd);
                     } else {
@@ -156,6 +177,8 @@ impl InputHandler {
                 emu.runtime.set_mode(mode);
             }
             AppCmd::SaveState(event, index) => app.handle_save_state(emu, event, index),
+            AppCmd::BrowseSaveStates => app.browse_save_states(),
+            AppCmd::DeleteSaveState(index) => app.delete_save_state(index),
             AppCmd::SelectRom => {
                 if app.state == AppState::Paused {
                     if let Some(path) = app.platform.fd.select_file(
@@ -228,16 +251,29 @@ impl InputHandler {
                         core::change_f64_rounded(emu.config.slow_speed, x as f64).max(0.05);
                     app.config.emulation.slow_speed = emu.config.slow_speed;
                 }
-                ChangeConfigCmd::RewindSize(x) => {
-                    emu.config.rewind_size =
-                        core::change_usize(emu.config.rewind_size, x).clamp(0, 500);
-                    app.config.emulation.rewind_size = emu.config.rewind_size;
+                ChangeConfigCmd::RewindBudget(x) => {
+                    emu.config.rewind_budget =
+                        core::change_usize(emu.config.rewind_budget, x).clamp(0, 512);
+                    app.config.emulation.rewind_budget = emu.config.rewind_budget;
                 }
                 ChangeConfigCmd::RewindFrames(delta) => {
                     emu.config.rewind_frames =
-                        core::change_usize(emu.config.rewind_frames, delta).clamp(0, 600);
+                        core::change_usize(emu.config.rewind_frames, delta).clamp(1, 60);
                     app.config.emulation.rewind_frames = emu.config.rewind_frames;
                 }
+                ChangeConfigCmd::RewindAudio => {
+                    emu.config.rewind_audio = !emu.config.rewind_audio;
+                    app.config.emulation.rewind_audio = emu.config.rewind_audio;
+                }
+                ChangeConfigCmd::RunAhead(x) => {
+                    emu.config.run_ahead = core::change_usize(emu.config.run_ahead, x).clamp(0, 4);
+                    app.config.emulation.run_ahead = emu.config.run_ahead;
+                }
+                ChangeConfigCmd::RunAheadSecondInstance => {
+                    emu.config.run_ahead_second_instance = !emu.config.run_ahead_second_instance;
+                    app.config.emulation.run_ahead_second_instance =
+                        emu.config.run_ahead_second_instance;
+                }
                 ChangeConfigCmd::AutoSaveState => {
                     app.config.auto_save_state = !app.config.auto_save_state
                 }
@@ -314,6 +350,24 @@ impl InputHandler {
                     emu.runtime.cpu.clock.bus.update_model(model);
                     app.refresh_dmg_palette(emu);
                 }
+                ChangeConfigCmd::SetRtcClock(clock) => {
+                    app.config.emulation.rtc_clock = clock;
+                    emu.config.rtc_clock = clock;
+
+                    // a session keeps the clock both sides start with, and
+                    // a movie the one it plays back on
+                    if emu.netplay.is_none() {
+                        let clock = emu.rtc_clock();
+                        emu.runtime.cpu.clock.bus.cart.set_rtc_clock(clock);
+                    }
+                }
+                ChangeConfigCmd::NetplayPlayer => {
+                    app.config.netplay.player = 1 - app.config.netplay.player.min(1);
+                }
+                ChangeConfigCmd::NetplayDelay(x) => {
+                    let delay = core::change_usize(app.config.netplay.delay, x);
+                    app.config.netplay.delay = delay.clamp(0, 8);
+                }
                 ChangeConfigCmd::TargetFps(x) => {
                     app.config.video.render.target_fps = x;
                     app.video.update_config(&app.config.video);
@@ -398,6 +452,50 @@ impl InputHandler {
                 emu.get_framebuffer().clear();
                 app.render_framebuffer(emu);
             }
+            AppCmd::Debugger(cmd) => {
+                #[cfg(feature = "debug")]
+                if matches!(app.state, AppState::Running | AppState::Stepping) {
+                    if app.console.handle_cmd(emu, cmd) {
+                        app.state = AppState::Running;
+                    } else {
+                        app.render_framebuffer(emu);
+                    }
+                }
+
+                #[cfg(not(feature = "debug"))]
+                let _ = cmd;
+            }
+            AppCmd::SetCheats(cheats) => app.set_cheats(emu, cheats),
+            AppCmd::RamSearch(cmd) => app.ram_search(emu, cmd),
+            AppCmd::MemoryViewer(cmd) => {
+                if matches!(app.state, AppState::Running | AppState::Stepping) {
+                    app.memory_viewer.handle_cmd(emu, cmd);
+
+                    if app.state == AppState::Stepping {
+                        app.render_framebuffer(emu);
+                    }
+                }
+            }
+            AppCmd::ToggleApuViewer => {
+                if matches!(app.state, AppState::Running | AppState::Stepping) {
+                    app.apu_viewer.toggle(emu);
+
+                    if app.state == AppState::Stepping {
+                        app.render_framebuffer(emu);
+                    }
+                }
+            }
+            AppCmd::Movie(cmd) => app.handle_movie(emu, cmd),
+            AppCmd::Netplay(cmd) => app.handle_netplay(emu, cmd),
+            AppCmd::VramViewer(cmd) => {
+                if matches!(app.state, AppState::Running | AppState::Stepping) {
+                    app.vram_viewer.handle_cmd(emu, cmd);
+
+                    if app.state == AppState::Stepping {
+                        app.render_framebuffer(emu);
+                    }
+                }
+            }
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::cmd::{AppCmd, ChangeConfigCmd};
+use crate::cmd::{AppCmd, ChangeConfigCmd, DebugCmd, MemoryCmd, VramCmd};
 use crate::input::bindings::{BindableInput, InputBindings, InputKind};
 use crate::input::config::InputConfig;
 use core::auxiliary::joypad::JoypadButton;
@@ -52,6 +52,16 @@ pub fn default_keys() -> InputBindings<Scancode> {
     bindings.bind_cmd(Scancode::F6, true, AppCmd::StepFrame);
     bindings.bind_cmd(Scancode::F7, true, AppCmd::StepScanline);
     bindings.bind_cmd(Scancode::F12, true, AppCmd::ClearScreen);
+    bindings.bind_cmd(Scancode::F8, true, AppCmd::Debugger(DebugCmd::Toggle));
+    bindings.bind_cmd(Scancode::F9, true, AppCmd::Debugger(DebugCmd::StepInto));
+    bindings.bind_cmd(Scancode::F10, true, AppCmd::Debugger(DebugCmd::StepOver));
+    bindings.bind_cmd(
+        Scancode::Home,
+        true,
+        AppCmd::MemoryViewer(MemoryCmd::Toggle),
+    );
+    bindings.bind_cmd(Scancode::End, true, AppCmd::VramViewer(VramCmd::Toggle));
+    bindings.bind_cmd(Scancode::Insert, true, AppCmd::ToggleApuViewer);
 
     // vi-keys
     bindings.bind_btn(Scancode::K, JoypadButton::Up);
@@ -1,12 +1,16 @@
 use crate::app::{App, AppState};
 use crate::config::AppConfig;
 use crate::input::handler::InputHandler;
+use crate::menu::save_states::SaveStateSlot;
 use core::apu::Apu;
 use core::auxiliary::io::Io;
 use core::bus::Bus;
 use core::cart::Cart;
+use core::cpu::Cpu;
+use core::emu::movie::Movie;
 use core::emu::runtime::EmuRuntime;
 use core::emu::state::EmuSaveState;
+use core::emu::state_file::{self, SaveStateInfo};
 use core::emu::Emu;
 use core::ppu::lcd::Lcd;
 use core::ppu::Ppu;
@@ -19,16 +23,24 @@ use std::{env, fs};
 use zip::ZipArchive;
 
 pub mod app;
+pub mod apu_viewer;
 pub mod audio;
 pub mod battery;
+pub mod cheats;
 pub mod config;
+#[cfg(feature = "debug")]
+pub mod debugger;
 pub mod file_browser;
 pub mod input;
+pub mod memory_viewer;
 pub mod menu;
 pub mod notification;
 pub mod palette;
 pub mod roms;
+#[cfg(feature = "script")]
+pub mod script;
 pub mod video;
+pub mod vram_viewer;
 pub mod cmd;
 
 pub fn is_zip(path: &Path) -> bool {
@@ -196,6 +208,7 @@ pub struct AppConfigFile;
 impl AppConfigFile {
     pub fn write_save_state_file(
         state: &EmuSaveState,
+        info: &SaveStateInfo,
         name: &str,
         suffix: &str,
     ) -> Result<(), String> {
@@ -205,20 +218,64 @@ impl AppConfigFile {
             fs::create_dir_all(parent).map_err(|e| e.to_string())?;
         }
 
-        let mut file = File::create(path).map_err(|e| e.to_string())?;
-        postcard::to_io(state, &mut file).map_err(|e| e.to_string())?;
+        let bytes = state_file::encode(state, info)?;
+        fs::write(path, bytes).map_err(|e| e.to_string())?;
 
         Ok(())
     }
 
-    pub fn read_save_state_file(name: &str, suffix: &str) -> Result<EmuSaveState, String> {
+    /// Reads a state of the game `cpu` runs, refusing one of another ROM or
+    /// model.
+    pub fn read_save_state_file(
+        name: &str,
+        suffix: &str,
+        cpu: &Cpu,
+    ) -> Result<EmuSaveState, String> {
         let path = Self::get_save_state_path(name, suffix);
         let mut file = File::open(path).map_err(|e| e.to_string())?;
         let mut buffer = Vec::new();
         file.read_to_end(&mut buffer).map_err(|e| e.to_string())?;
-        let decoded: EmuSaveState = postcard::from_bytes(&buffer).map_err(|e| e.to_string())?;
 
-        Ok(decoded)
+        state_file::decode_for(&buffer, cpu)
+    }
+
+    /// Numbered slots of the game with a state in them, in order. A state
+    /// that can't be read is still listed, without info.
+    pub fn list_save_states(name: &str) -> Vec<SaveStateSlot> {
+        let prefix = format!("{name}_");
+        let Ok(entries) = fs::read_dir(get_base_dir().join("save_states")) else {
+            return Vec::new();
+        };
+
+        let mut slots: Vec<SaveStateSlot> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let file_name = path.file_name()?.to_str()?;
+                let index = file_name
+                    .strip_prefix(&prefix)?
+                    .strip_suffix(".state")?
+                    .parse()
+                    .ok()?;
+                let info = fs::read(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|bytes| state_file::decode_info(&bytes));
+
+                Some(SaveStateSlot {
+                    index,
+                    info: info.unwrap_or_else(|err| {
+                        log::warn!("Failed read save_state info: {err}");
+                        None
+                    }),
+                })
+            })
+            .collect();
+        slots.sort_by_key(|x| x.index);
+
+        slots
+    }
+
+    pub fn delete_save_state_file(name: &str, suffix: &str) -> Result<(), String> {
+        fs::remove_file(Self::get_save_state_path(name, suffix)).map_err(|e| e.to_string())
     }
 
     pub fn get_save_state_path(game_name: &str, suffix: &str) -> PathBuf {
@@ -226,6 +283,29 @@ impl AppConfigFile {
             .join("save_states")
             .join(format!("{game_name}_{suffix}.state"))
     }
+
+    pub fn write_movie_file(movie: &Movie, name: &str) -> Result<(), String> {
+        let path = Self::get_movie_path(name);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        fs::write(path, movie.encode()?).map_err(|e| e.to_string())
+    }
+
+    pub fn read_movie_file(name: &str) -> Result<Movie, String> {
+        let bytes = fs::read(Self::get_movie_path(name)).map_err(|e| e.to_string())?;
+
+        Movie::decode(&bytes)
+    }
+
+    /// One movie per game, re-recording overwrites it.
+    pub fn get_movie_path(game_name: &str) -> PathBuf {
+        get_base_dir()
+            .join("movies")
+            .join(format!("{game_name}.movie"))
+    }
 }
 
 pub trait PlatformFileDialog {
@@ -0,0 +1,309 @@
+use crate::cmd::{AppCmd, MemoryCmd};
+use crate::video::overlay::Overlay;
+use core::bus::Bus;
+use core::debugger::MemoryRegion;
+use core::emu::Emu;
+use core::ppu::framebuffer::FrameBuffer;
+use sdl2::keyboard::Scancode;
+use std::fmt::Write;
+
+const ROWS: usize = 16;
+const ROW_BYTES: usize = 8;
+const PAGE: usize = ROWS * ROW_BYTES;
+/// Line of the first row, after the header, status and column lines.
+const FIRST_ROW_LINE: usize = 3;
+/// Column of a row's first byte, after the address.
+const FIRST_BYTE_COL: usize = 5;
+
+/// What typed hex digits go to.
+#[derive(Default)]
+enum Input {
+    #[default]
+    None,
+    /// The high nibble of the byte under the cursor, waiting for the low one.
+    Edit(u8),
+    Goto(String),
+}
+
+/// Hex view over the CPU map or any bank of ROM, VRAM, SRAM and WRAM,
+/// drawn over the screen. Bytes can be edited while the game runs, and the
+/// ones that changed since the last frame are shown inverted.
+pub struct MemoryViewer {
+    pub visible: bool,
+    fb: FrameBuffer,
+    region: MemoryRegion,
+    bank: usize,
+    /// Offset of the cursor in the bank.
+    cursor: usize,
+    /// Offset of the first row shown.
+    top: usize,
+    input: Input,
+    status: String,
+    lines: Vec<String>,
+    /// Bytes of the last draw and where they were, to find what changed.
+    shown: Vec<u8>,
+    shown_at: (MemoryRegion, usize, usize),
+}
+
+impl Default for MemoryViewer {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            fb: FrameBuffer::default(),
+            region: MemoryRegion::Bus,
+            bank: 0,
+            cursor: 0xC000,
+            top: 0xC000,
+            input: Input::None,
+            status: String::new(),
+            lines: Vec::with_capacity(FIRST_ROW_LINE + ROWS + 3),
+            shown: Vec::with_capacity(PAGE),
+            shown_at: Default::default(),
+        }
+    }
+}
+
+impl MemoryViewer {
+    /// Viewer keys, only while it's shown.
+    pub fn handle_key(&self, sc: Scancode, pressed: bool) -> Option<AppCmd> {
+        if !self.visible || !pressed {
+            return None;
+        }
+
+        let cmd = match sc {
+            Scancode::Home => MemoryCmd::Toggle,
+            Scancode::Escape | Scancode::Backspace => MemoryCmd::Cancel,
+            Scancode::Return | Scancode::KpEnter => MemoryCmd::Confirm,
+            Scancode::Up => MemoryCmd::Move(-(ROW_BYTES as i32)),
+            Scancode::Down => MemoryCmd::Move(ROW_BYTES as i32),
+            Scancode::Left => MemoryCmd::Move(-1),
+            Scancode::Right => MemoryCmd::Move(1),
+            Scancode::PageUp => MemoryCmd::Move(-(PAGE as i32)),
+            Scancode::PageDown => MemoryCmd::Move(PAGE as i32),
+            Scancode::G => MemoryCmd::Goto,
+            Scancode::R => MemoryCmd::NextRegion,
+            Scancode::LeftBracket => MemoryCmd::ChangeBank(-1),
+            Scancode::RightBracket => MemoryCmd::ChangeBank(1),
+            sc => MemoryCmd::Digit(hex_digit(sc)?),
+        };
+
+        Some(AppCmd::MemoryViewer(cmd))
+    }
+
+    pub fn handle_cmd(&mut self, emu: &mut Emu, cmd: MemoryCmd) {
+        let bus = &mut emu.runtime.cpu.clock.bus;
+        self.status.clear();
+
+        match cmd {
+            MemoryCmd::Toggle => {
+                self.visible = !self.visible;
+                self.input = Input::None;
+            }
+            MemoryCmd::Cancel => match self.input {
+                Input::None => self.visible = false,
+                _ => self.input = Input::None,
+            },
+            MemoryCmd::Confirm => {
+                if let Input::Goto(text) = std::mem::take(&mut self.input) {
+                    self.goto(bus, &text);
+                }
+            }
+            MemoryCmd::Move(delta) => {
+                self.input = Input::None;
+                let (_, len) = self.region.range(self.bank);
+                let cursor = self.cursor as i64 + delta as i64;
+                self.set_cursor(cursor.clamp(0, len as i64 - 1) as usize);
+            }
+            MemoryCmd::Goto => self.input = Input::Goto(String::new()),
+            MemoryCmd::NextRegion => {
+                let i = MemoryRegion::ALL.iter().position(|x| *x == self.region);
+                let next = core::move_next_wrapped(i.unwrap_or(0), MemoryRegion::ALL.len() - 1);
+                self.region = MemoryRegion::ALL[next];
+                self.bank = 0;
+                self.input = Input::None;
+                self.set_cursor(0);
+            }
+            MemoryCmd::ChangeBank(delta) => {
+                let banks = self.region.banks(bus).max(1);
+                self.bank = (self.bank as i32 + delta).rem_euclid(banks as i32) as usize;
+                self.input = Input::None;
+                let (_, len) = self.region.range(self.bank);
+                self.set_cursor(self.cursor.min(len - 1));
+            }
+            MemoryCmd::Digit(digit) => self.type_digit(bus, digit),
+        }
+    }
+
+    /// Redraws the view for the current memory and returns its buffer.
+    pub fn draw(&mut self, emu: &Emu, overlay: &Overlay) -> &FrameBuffer {
+        let bus = &emu.runtime.cpu.clock.bus;
+        let banks = self.region.banks(bus);
+        let (start, len) = self.region.range(self.bank);
+        let rows = ROWS.min(len.div_ceil(ROW_BYTES));
+        let cursor_addr = start.wrapping_add(self.cursor as u16);
+
+        self.lines.clear();
+        self.lines.push(match (self.region, banks) {
+            (MemoryRegion::Bus, _) => format!("BUS {cursor_addr:04X}"),
+            (region, 0) => format!("{} NONE", region.name()),
+            (region, banks) => format!(
+                "{} BANK {:02X}/{:02X} {cursor_addr:04X}",
+                region.name(),
+                self.bank,
+                banks - 1
+            ),
+        });
+        self.lines.push(match &self.input {
+            Input::None => self.status.clone(),
+            Input::Edit(high) => format!("EDIT {cursor_addr:04X} {high:X}■"),
+            Input::Goto(text) => format!("GOTO {text}■"),
+        });
+
+        let mut columns = "    ".to_string();
+        for i in 0..ROW_BYTES {
+            write!(columns, " {i:02X}").ok();
+        }
+        self.lines.push(columns);
+
+        let bytes: Vec<u8> = if banks == 0 {
+            Vec::new()
+        } else {
+            let end = (self.top + rows * ROW_BYTES).min(len);
+            (self.top..end)
+                .map(|i| {
+                    self.region
+                        .read(bus, self.bank, start.wrapping_add(i as u16))
+                })
+                .collect()
+        };
+
+        for (row, chunk) in bytes.chunks(ROW_BYTES).enumerate() {
+            let offset = self.top + row * ROW_BYTES;
+            let mut line = format!("{:04X}", start.wrapping_add(offset as u16));
+
+            for (i, byte) in chunk.iter().enumerate() {
+                let mark = if offset + i == self.cursor {
+                    '▶'
+                } else {
+                    ' '
+                };
+                write!(line, "{mark}{byte:02X}").ok();
+            }
+
+            self.lines.push(line);
+        }
+
+        for _ in bytes.len().div_ceil(ROW_BYTES)..ROWS + 1 {
+            self.lines.push(String::new());
+        }
+
+        self.lines
+            .push("ARROWS PGUP PGDN:MOVE 0-F:EDIT G:GOTO".to_string());
+        self.lines.push("R:REGION [ ]:BANK HOME:CLOSE".to_string());
+
+        let lines: Vec<&str> = self.lines.iter().map(|x| x.as_str()).collect();
+        overlay.fill_debugger(&mut self.fb, &lines, &[], 0);
+
+        let at = (self.region, self.bank, self.top);
+        if self.shown_at == at {
+            for (i, (byte, _)) in bytes
+                .iter()
+                .zip(self.shown.iter())
+                .enumerate()
+                .filter(|(_, (new, old))| new != old)
+            {
+                let line = FIRST_ROW_LINE + i / ROW_BYTES;
+                let col = FIRST_BYTE_COL + (i % ROW_BYTES) * 3;
+                overlay.invert_text(&mut self.fb, &format!("{byte:02X}"), line, col);
+            }
+        }
+
+        self.shown = bytes;
+        self.shown_at = at;
+
+        &self.fb
+    }
+
+    fn type_digit(&mut self, bus: &mut Bus, digit: u8) {
+        match &mut self.input {
+            Input::Goto(text) => {
+                if text.len() < 4 {
+                    let c = char::from_digit(digit as u32, 16).unwrap_or('0');
+                    text.push(c.to_ascii_uppercase());
+                }
+            }
+            Input::Edit(high) => {
+                let value = *high << 4 | digit;
+                let (start, len) = self.region.range(self.bank);
+                let addr = start.wrapping_add(self.cursor as u16);
+                self.region.write(bus, self.bank, addr, value);
+                self.input = Input::None;
+                self.set_cursor((self.cursor + 1).min(len - 1));
+            }
+            Input::None if self.region.banks(bus) > 0 => self.input = Input::Edit(digit),
+            Input::None => {}
+        }
+    }
+
+    /// Moves the cursor to `text`, switching between a region's fixed and
+    /// switchable bank when the address is in the other one.
+    fn goto(&mut self, bus: &Bus, text: &str) {
+        let Ok(addr) = u16::from_str_radix(text, 16) else {
+            return;
+        };
+
+        let banks = self.region.banks(bus);
+        let bank = [self.bank, 0, self.bank.max(1)]
+            .into_iter()
+            .filter(|x| *x < banks.max(1))
+            .find(|x| {
+                let (start, len) = self.region.range(*x);
+                (start as usize..start as usize + len).contains(&(addr as usize))
+            });
+
+        let Some(bank) = bank else {
+            write!(self.status, "{addr:04X} NOT IN {}", self.region.name()).ok();
+            return;
+        };
+
+        let (start, _) = self.region.range(bank);
+        self.bank = bank;
+        self.set_cursor((addr - start) as usize);
+    }
+
+    /// Moves the cursor, scrolling to keep it in view.
+    fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+        let row = cursor - cursor % ROW_BYTES;
+
+        if row < self.top {
+            self.top = row;
+        } else if row >= self.top + PAGE {
+            self.top = row + ROW_BYTES - PAGE;
+        }
+    }
+}
+
+pub(crate) fn hex_digit(sc: Scancode) -> Option<u8> {
+    let digit = match sc {
+        Scancode::Num0 | Scancode::Kp0 => 0,
+        Scancode::Num1 | Scancode::Kp1 => 1,
+        Scancode::Num2 | Scancode::Kp2 => 2,
+        Scancode::Num3 | Scancode::Kp3 => 3,
+        Scancode::Num4 | Scancode::Kp4 => 4,
+        Scancode::Num5 | Scancode::Kp5 => 5,
+        Scancode::Num6 | Scancode::Kp6 => 6,
+        Scancode::Num7 | Scancode::Kp7 => 7,
+        Scancode::Num8 | Scancode::Kp8 => 8,
+        Scancode::Num9 | Scancode::Kp9 => 9,
+        Scancode::A => 0xA,
+        Scancode::B => 0xB,
+        Scancode::C => 0xC,
+        Scancode::D => 0xD,
+        Scancode::E => 0xE,
+        Scancode::F => 0xF,
+        _ => return None,
+    };
+
+    Some(digit)
+}
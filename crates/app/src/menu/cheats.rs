@@ -0,0 +1,397 @@
+use crate::cmd::AppCmd;
+use crate::config::AppConfig;
+use crate::menu::{get_menu_toggle, SubMenu, MAX_MENU_ITEMS_PER_PAGE, MAX_MENU_ITEM_CHARS};
+use crate::video::truncate_text;
+use core::cheats::Cheat;
+
+/// Blank is first so a cleared character reads as the end of the text.
+const CODE_CHARS: &str = " 0123456789ABCDEF-+";
+const NAME_CHARS: &str = " ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-+!.#";
+const CHEAT_ITEMS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Code,
+}
+
+#[derive(Debug, Clone)]
+enum Mode {
+    List,
+    /// Actions on one cheat.
+    Cheat(usize),
+    /// Entering the field of a cheat, or the code of a new one.
+    Edit {
+        index: Option<usize>,
+        field: Field,
+        text: TextInput,
+    },
+}
+
+/// Per-game cheat list: add codes, toggle them and name them. Every change
+/// is sent out whole as [`AppCmd::SetCheats`].
+#[derive(Debug, Clone)]
+pub struct CheatsMenu {
+    cheats: Vec<Cheat>,
+    mode: Mode,
+    selected_index: usize,
+    error: Option<String>,
+}
+
+impl CheatsMenu {
+    pub fn new(cheats: &[Cheat]) -> Self {
+        Self {
+            cheats: cheats.to_vec(),
+            mode: Mode::List,
+            selected_index: 0,
+            error: None,
+        }
+    }
+
+    fn items_len(&self) -> usize {
+        match self.mode {
+            Mode::List => self.cheats.len() + 2,
+            Mode::Cheat(_) => CHEAT_ITEMS,
+            Mode::Edit { .. } => 1,
+        }
+    }
+
+    fn set_mode(&mut self, mode: Mode, selected_index: usize) {
+        self.mode = mode;
+        self.selected_index = selected_index;
+        self.error = None;
+    }
+
+    fn toggle(&mut self, index: usize) -> Option<AppCmd> {
+        let cheat = self.cheats.get_mut(index)?;
+        cheat.enabled = !cheat.enabled;
+
+        Some(AppCmd::SetCheats(self.cheats.clone()))
+    }
+
+    fn edit(&mut self, index: Option<usize>, field: Field) {
+        let text = match (index.and_then(|i| self.cheats.get(i)), field) {
+            (Some(cheat), Field::Name) => TextInput::new(&cheat.name, NAME_CHARS),
+            (Some(cheat), Field::Code) => TextInput::new(&cheat.code, CODE_CHARS),
+            (None, _) => TextInput::new("", CODE_CHARS),
+        };
+
+        self.set_mode(Mode::Edit { index, field, text }, 0);
+    }
+
+    fn submit(&mut self, index: Option<usize>, field: Field, text: String) -> Option<AppCmd> {
+        let back = match index {
+            Some(i) => Mode::Cheat(i),
+            None => Mode::List,
+        };
+
+        if text.is_empty() {
+            self.set_mode(back, 0);
+            return None;
+        }
+
+        let text = match field {
+            Field::Name => text,
+            Field::Code => text.replace(' ', ""),
+        };
+
+        if field == Field::Code {
+            if let Err(err) = Cheat::new("", text.as_str()).parse() {
+                self.error = Some(err);
+                return None;
+            }
+        }
+
+        let index = match index {
+            Some(i) => {
+                let cheat = &mut self.cheats[i];
+                match field {
+                    Field::Name => cheat.name = text,
+                    Field::Code => cheat.code = text,
+                }
+                i
+            }
+            None => {
+                let name = format!("Cheat {}", self.cheats.len() + 1);
+                self.cheats.push(Cheat::new(name, text));
+                self.cheats.len() - 1
+            }
+        };
+
+        self.set_mode(Mode::Cheat(index), 0);
+
+        Some(AppCmd::SetCheats(self.cheats.clone()))
+    }
+
+    fn get_lines(&self) -> Vec<String> {
+        let mut lines = Vec::with_capacity(MAX_MENU_ITEMS_PER_PAGE);
+
+        match &self.mode {
+            Mode::List => {
+                for cheat in self.cheats.iter() {
+                    lines.push(format!("{} {}", get_menu_toggle(cheat.enabled), cheat.name));
+                }
+
+                lines.push("Add Cheat".to_string());
+                lines.push("Back".to_string());
+            }
+            Mode::Cheat(i) => {
+                let cheat = &self.cheats[*i];
+                lines.push(format!("Enabled: {}", get_menu_toggle(cheat.enabled)));
+                lines.push(format!("Name: {}", cheat.name));
+                lines.push(format!("Code: {}", cheat.code));
+                lines.push("Delete".to_string());
+                lines.push("Back".to_string());
+            }
+            Mode::Edit { text, .. } => lines.push(text.to_string()),
+        }
+
+        lines
+            .into_iter()
+            .map(|x| truncate_text(&x, MAX_MENU_ITEM_CHARS))
+            .collect()
+    }
+}
+
+impl SubMenu for CheatsMenu {
+    fn get_iterator<'a>(&'a self) -> Box<dyn Iterator<Item = String> + 'a> {
+        let lines = self.get_lines();
+        // keep the selection on screen once the list outgrows a page
+        let skip = (self.selected_index + 1).saturating_sub(MAX_MENU_ITEMS_PER_PAGE);
+        let mut header = Vec::with_capacity(2);
+
+        if let Mode::Edit { field, .. } = &self.mode {
+            header.push(match field {
+                Field::Name => "Enter Name".to_string(),
+                Field::Code => "Enter Code".to_string(),
+            });
+        }
+
+        let items = lines
+            .into_iter()
+            .enumerate()
+            .skip(skip)
+            .take(MAX_MENU_ITEMS_PER_PAGE)
+            .map(move |(i, line)| {
+                if i == self.selected_index && !matches!(self.mode, Mode::Edit { .. }) {
+                    format!("◀{line}▶")
+                } else {
+                    line
+                }
+            });
+
+        let footer = match (&self.mode, &self.error) {
+            (_, Some(err)) => Some(truncate_text(err, MAX_MENU_ITEM_CHARS)),
+            (Mode::Edit { .. }, None) => Some("▲▼ Char  A: OK".to_string()),
+            _ => None,
+        };
+
+        Box::new(header.into_iter().chain(items).chain(footer))
+    }
+
+    fn move_up(&mut self) {
+        if let Mode::Edit { text, .. } = &mut self.mode {
+            text.cycle(false);
+            self.error = None;
+            return;
+        }
+
+        self.selected_index = core::move_prev_wrapped(self.selected_index, self.items_len() - 1);
+    }
+
+    fn move_down(&mut self) {
+        if let Mode::Edit { text, .. } = &mut self.mode {
+            text.cycle(true);
+            self.error = None;
+            return;
+        }
+
+        self.selected_index = core::move_next_wrapped(self.selected_index, self.items_len() - 1);
+    }
+
+    fn move_left(&mut self) -> Option<AppCmd> {
+        match self.mode {
+            Mode::List => self.toggle(self.selected_index),
+            Mode::Cheat(i) if self.selected_index == 0 => self.toggle(i),
+            Mode::Cheat(_) => None,
+            Mode::Edit { ref mut text, .. } => {
+                text.move_cursor(false);
+                None
+            }
+        }
+    }
+
+    fn move_right(&mut self) -> Option<AppCmd> {
+        match self.mode {
+            Mode::List => self.toggle(self.selected_index),
+            Mode::Cheat(i) if self.selected_index == 0 => self.toggle(i),
+            Mode::Cheat(_) => None,
+            Mode::Edit { ref mut text, .. } => {
+                text.move_cursor(true);
+                None
+            }
+        }
+    }
+
+    fn select(&mut self, _config: &AppConfig) -> (Option<AppCmd>, bool) {
+        match self.mode.clone() {
+            Mode::List => match self.selected_index {
+                i if i < self.cheats.len() => {
+                    self.set_mode(Mode::Cheat(i), 0);
+                    (None, false)
+                }
+                i if i == self.cheats.len() => {
+                    self.edit(None, Field::Code);
+                    (None, false)
+                }
+                _ => (None, true),
+            },
+            Mode::Cheat(i) => match self.selected_index {
+                0 => (self.toggle(i), false),
+                1 => {
+                    self.edit(Some(i), Field::Name);
+                    (None, false)
+                }
+                2 => {
+                    self.edit(Some(i), Field::Code);
+                    (None, false)
+                }
+                3 => {
+                    self.cheats.remove(i);
+                    self.set_mode(Mode::List, i.min(self.cheats.len()));
+                    (Some(AppCmd::SetCheats(self.cheats.clone())), false)
+                }
+                _ => {
+                    self.set_mode(Mode::List, i);
+                    (None, false)
+                }
+            },
+            Mode::Edit { index, field, text } => (self.submit(index, field, text.get()), false),
+        }
+    }
+
+    fn next_page(&mut self) {}
+
+    fn prev_page(&mut self) {}
+}
+
+/// Text entered with a d-pad: up and down cycle the character under the
+/// cursor through `chars`, left and right move the cursor. Moving past the
+/// end adds a character; blank ones are dropped when it's read.
+#[derive(Debug, Clone)]
+pub struct TextInput {
+    chars: Vec<char>,
+    cursor: usize,
+    charset: &'static str,
+}
+
+impl TextInput {
+    pub fn new(text: &str, charset: &'static str) -> Self {
+        let mut chars: Vec<char> = text.chars().collect();
+        let cursor = chars.len().saturating_sub(1);
+
+        if chars.is_empty() {
+            chars.push(' ');
+        }
+
+        Self {
+            chars,
+            cursor,
+            charset,
+        }
+    }
+
+    pub fn cycle(&mut self, next: bool) {
+        let len = self.charset.chars().count();
+        let current = self.chars[self.cursor];
+        let index = self.charset.chars().position(|x| x == current).unwrap_or(0);
+        let index = if next {
+            core::move_next_wrapped(index, len - 1)
+        } else {
+            core::move_prev_wrapped(index, len - 1)
+        };
+
+        self.chars[self.cursor] = self.charset.chars().nth(index).unwrap_or(' ');
+    }
+
+    pub fn move_cursor(&mut self, right: bool) {
+        if !right {
+            self.cursor = self.cursor.saturating_sub(1);
+        } else if self.cursor + 1 < self.chars.len() {
+            self.cursor += 1;
+        } else if self.chars[self.cursor] != ' ' {
+            self.chars.push(' ');
+            self.cursor += 1;
+        }
+    }
+
+    /// The text with trailing blanks trimmed.
+    pub fn get(&self) -> String {
+        self.chars.iter().collect::<String>().trim().to_string()
+    }
+}
+
+impl std::fmt::Display for TextInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // a window around the cursor, leaving room for the brackets
+        let width = MAX_MENU_ITEM_CHARS - 2;
+        let start = (self.cursor + 1).saturating_sub(width);
+
+        for (i, c) in self.chars.iter().enumerate().skip(start).take(width) {
+            if i == self.cursor {
+                write!(f, "[{c}]")?;
+            } else {
+                write!(f, "{c}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_and_toggle() {
+        let mut menu = CheatsMenu::new(&[]);
+        let config = AppConfig::default();
+
+        // "Add Cheat", then enter "0" and confirm: not a valid code
+        menu.selected_index = 0;
+        assert_eq!(menu.select(&config), (None, false));
+        menu.move_down();
+        assert_eq!(menu.select(&config), (None, false));
+        assert!(menu.error.is_some());
+
+        let Mode::Edit { text, .. } = &mut menu.mode else {
+            panic!("not editing");
+        };
+        *text = TextInput::new("00A-17B", CODE_CHARS);
+
+        let (cmd, _) = menu.select(&config);
+        let cheat = Cheat::new("Cheat 1", "00A-17B");
+        assert_eq!(cmd, Some(AppCmd::SetCheats(vec![cheat.clone()])));
+
+        // "Enabled" of the new cheat
+        let cmd = menu.move_right();
+        let disabled = Cheat {
+            enabled: false,
+            ..cheat
+        };
+        assert_eq!(cmd, Some(AppCmd::SetCheats(vec![disabled])));
+    }
+
+    #[test]
+    fn text_input() {
+        let mut text = TextInput::new("", NAME_CHARS);
+        text.cycle(true);
+        text.move_cursor(true);
+        text.cycle(true);
+        text.cycle(true);
+        text.move_cursor(true);
+        assert_eq!(text.to_string(), "AB[ ]");
+        assert_eq!(text.get(), "AB");
+    }
+}
@@ -1,13 +1,18 @@
-use crate::cmd::{AppCmd, BindCmds, BindTarget, ChangeConfigCmd};
+use crate::cheats::RamSearchState;
+use crate::cmd::{AppCmd, BindCmds, BindTarget, ChangeConfigCmd, MovieCmd, NetplayCmd};
 use crate::config::{InterfaceConfig, VideoBackendType, VideoConfig};
+use crate::menu::cheats::CheatsMenu;
 use crate::menu::files::FilesMenu;
 use crate::menu::item::AppMenuItem;
+use crate::menu::ram_search::RamSearchMenu;
 use crate::menu::roms::RomsMenu;
+use crate::menu::save_states::{SaveStateSlot, SaveStatesMenu};
 use crate::menu::SubMenu;
 use crate::roms::RomsState;
 use crate::video::frame_blend::FrameBlendMode;
 use crate::PlatformFileSystem;
 use core::auxiliary::joypad::JoypadButton;
+use core::cheats::Cheat;
 use core::emu::runtime::RunMode;
 use core::emu::state::SaveStateCmd;
 use std::path::Path;
@@ -85,6 +90,48 @@ pub fn files_menu(
     vec![AppMenuItem::BrowseRomsSubMenu(files)].into_boxed_slice()
 }
 
+pub fn cheats_menu(cheats: &[Cheat]) -> Box<[AppMenuItem]> {
+    let cheats: Box<dyn SubMenu> = Box::new(CheatsMenu::new(cheats));
+
+    vec![AppMenuItem::CheatsSubMenu(cheats)].into_boxed_slice()
+}
+
+pub fn ram_search_menu(state: &RamSearchState) -> Box<[AppMenuItem]> {
+    let search: Box<dyn SubMenu> = Box::new(RamSearchMenu::new(state));
+
+    vec![AppMenuItem::RamSearchSubMenu(search)].into_boxed_slice()
+}
+
+pub fn save_states_menu(slots: Vec<SaveStateSlot>, open: Option<usize>) -> Box<[AppMenuItem]> {
+    let menu: Box<dyn SubMenu> = Box::new(SaveStatesMenu::new(slots, open));
+
+    vec![AppMenuItem::SaveStatesSubMenu(menu)].into_boxed_slice()
+}
+
+pub fn movie_menu() -> Box<[AppMenuItem]> {
+    vec![
+        AppMenuItem::Movie(MovieCmd::Record),
+        AppMenuItem::Movie(MovieCmd::RecordFromReset),
+        AppMenuItem::Movie(MovieCmd::Play),
+        AppMenuItem::Movie(MovieCmd::PlayReadOnly),
+        AppMenuItem::Movie(MovieCmd::ToggleReadOnly),
+        AppMenuItem::Movie(MovieCmd::Stop),
+        AppMenuItem::Back,
+    ]
+    .into_boxed_slice()
+}
+
+pub fn netplay_menu() -> Box<[AppMenuItem]> {
+    vec![
+        AppMenuItem::NetplayPlayer,
+        AppMenuItem::NetplayDelay,
+        AppMenuItem::Netplay(NetplayCmd::Start),
+        AppMenuItem::Netplay(NetplayCmd::Stop),
+        AppMenuItem::Back,
+    ]
+    .into_boxed_slice()
+}
+
 pub fn input_menu() -> Box<[AppMenuItem]> {
     vec![
         AppMenuItem::KeyboardInput,
@@ -101,12 +148,16 @@ pub fn confirm_menu(cmd: AppCmd) -> Box<[AppMenuItem]> {
 pub fn system_menu() -> Box<[AppMenuItem]> {
     vec![
         AppMenuItem::GbModel,
+        AppMenuItem::RtcClock,
         AppMenuItem::AutoSaveState,
         AppMenuItem::NormalSpeed,
         AppMenuItem::TurboSpeed,
         AppMenuItem::SlowSpeed,
-        AppMenuItem::RewindSize,
+        AppMenuItem::RewindBudget,
         AppMenuItem::RewindFrames,
+        AppMenuItem::RewindAudio,
+        AppMenuItem::RunAhead,
+        AppMenuItem::RunAheadSecondInstance,
         #[cfg(feature = "file-dialog")]
         AppMenuItem::RomsDir,
         AppMenuItem::Back,
@@ -133,7 +184,12 @@ pub fn start_menu(roms: &RomsState) -> Box<[AppMenuItem]> {
         items.push(AppMenuItem::Resume);
         items.push(AppMenuItem::SaveState);
         items.push(AppMenuItem::LoadState);
+        items.push(AppMenuItem::SaveStates);
+        items.push(AppMenuItem::Movies);
+        items.push(AppMenuItem::NetplayMenu);
         items.push(AppMenuItem::RestartGame);
+        items.push(AppMenuItem::Cheats);
+        items.push(AppMenuItem::RamSearch);
     }
 
     if roms.opened_count() != 0 {
@@ -268,6 +324,7 @@ pub fn keyboard_shortcuts_menu() -> Box<[AppMenuItem]> {
             AppCmd::ChangeConfig(ChangeConfigCmd::NextShader),
             None,
         )),
+        AppMenuItem::CmdsBinding(BindCmds::new(AppCmd::Movie(MovieCmd::ToggleReadOnly), None)),
         AppMenuItem::Back,
     ]
     .into_boxed_slice()
@@ -1,11 +1,14 @@
+use crate::cheats::RamSearchState;
 use crate::cmd::{AppCmd, BindTarget, ChangeConfigCmd};
 use crate::config::{update_frame_skip, AppConfig, ScaleMode, VideoBackendType};
 use crate::menu::factory::{
-    advanced_menu, audio_menu, confirm_menu, files_menu, input_menu, interface_menu, keyboard_menu,
-    keyboard_shortcuts_menu, loaded_roms_menu, opened_roms_menu, settings_menu, system_menu,
-    video_menu, wait_input_menu,
+    advanced_menu, audio_menu, cheats_menu, confirm_menu, files_menu, input_menu, interface_menu,
+    keyboard_menu, keyboard_shortcuts_menu, loaded_roms_menu, movie_menu, netplay_menu,
+    opened_roms_menu, ram_search_menu, save_states_menu, settings_menu, system_menu, video_menu,
+    wait_input_menu,
 };
 use crate::menu::item::AppMenuItem;
+use crate::menu::save_states::SaveStateSlot;
 use crate::roms::RomsState;
 use crate::video::frame_blend::{
     AdditiveFrameBlend, ExponentialFrameBlend, FrameBlendMode, GammaCorrectedFrameBlend,
@@ -13,6 +16,8 @@ use crate::video::frame_blend::{
 };
 use crate::video::shader::ShaderFrameBlendMode;
 use crate::PlatformFileSystem;
+use core::cart::mbc3::RtcClock;
+use core::cheats::Cheat;
 use core::emu::config::GbModel;
 
 impl super::AppMenu {
@@ -93,10 +98,17 @@ impl super::AppMenu {
             }
             AppMenuItem::TurboSpeed => Some(AppCmd::ChangeConfig(ChangeConfigCmd::TurboSpeed(0.1))),
             AppMenuItem::SlowSpeed => Some(AppCmd::ChangeConfig(ChangeConfigCmd::SlowSpeed(0.1))),
-            AppMenuItem::RewindSize => Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindSize(25))),
+            AppMenuItem::RewindBudget => {
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindBudget(16)))
+            }
             AppMenuItem::RewindFrames => {
-                Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindFrames(5)))
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindFrames(1)))
             }
+            AppMenuItem::RewindAudio => Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindAudio)),
+            AppMenuItem::RunAhead => Some(AppCmd::ChangeConfig(ChangeConfigCmd::RunAhead(1))),
+            AppMenuItem::RunAheadSecondInstance => Some(AppCmd::ChangeConfig(
+                ChangeConfigCmd::RunAheadSecondInstance,
+            )),
             AppMenuItem::AudioBufferSize => {
                 Some(AppCmd::ChangeConfig(ChangeConfigCmd::AudioBufferSize(2)))
             }
@@ -215,7 +227,19 @@ impl super::AppMenu {
             AppMenuItem::BrowseRoms | AppMenuItem::LoadedRoms | AppMenuItem::OpenedRoms => None,
             AppMenuItem::BrowseRomsSubMenu(x)
             | AppMenuItem::LoadedRomsSubMenu(x)
-            | AppMenuItem::OpenedRomsSubMenu(x) => x.move_right(),
+            | AppMenuItem::OpenedRomsSubMenu(x)
+            | AppMenuItem::CheatsSubMenu(x)
+            | AppMenuItem::RamSearchSubMenu(x)
+            | AppMenuItem::SaveStatesSubMenu(x) => x.move_right(),
+            AppMenuItem::Cheats | AppMenuItem::RamSearch | AppMenuItem::SaveStates => None,
+            AppMenuItem::Movies | AppMenuItem::Movie(_) => None,
+            AppMenuItem::NetplayMenu | AppMenuItem::Netplay(_) => None,
+            AppMenuItem::NetplayPlayer => {
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::NetplayPlayer))
+            }
+            AppMenuItem::NetplayDelay => {
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::NetplayDelay(1)))
+            }
             AppMenuItem::RomsDir => None,
             AppMenuItem::Confirm(_) => None,
             AppMenuItem::ScanlineFilter => None,
@@ -275,6 +299,15 @@ impl super::AppMenu {
 
                 Some(AppCmd::ChangeConfig(ChangeConfigCmd::SetGbModel(model)))
             }
+            AppMenuItem::RtcClock => {
+                let clock = match config.emulation.rtc_clock {
+                    RtcClock::WallClock => RtcClock::Emulated,
+                    RtcClock::Emulated => RtcClock::Epoch(0),
+                    RtcClock::Epoch(_) => RtcClock::WallClock,
+                };
+
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::SetRtcClock(clock)))
+            }
             AppMenuItem::TargetFps => Some(AppCmd::ChangeConfig(ChangeConfigCmd::TargetFps(
                 config.video.render.target_fps + 1.0,
             ))),
@@ -335,10 +368,17 @@ impl super::AppMenu {
                 Some(AppCmd::ChangeConfig(ChangeConfigCmd::TurboSpeed(-0.1)))
             }
             AppMenuItem::SlowSpeed => Some(AppCmd::ChangeConfig(ChangeConfigCmd::SlowSpeed(-0.1))),
-            AppMenuItem::RewindSize => Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindSize(-25))),
+            AppMenuItem::RewindBudget => {
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindBudget(-16)))
+            }
             AppMenuItem::RewindFrames => {
-                Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindFrames(-5)))
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindFrames(-1)))
             }
+            AppMenuItem::RewindAudio => Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindAudio)),
+            AppMenuItem::RunAhead => Some(AppCmd::ChangeConfig(ChangeConfigCmd::RunAhead(-1))),
+            AppMenuItem::RunAheadSecondInstance => Some(AppCmd::ChangeConfig(
+                ChangeConfigCmd::RunAheadSecondInstance,
+            )),
             AppMenuItem::AudioBufferSize => {
                 Some(AppCmd::ChangeConfig(ChangeConfigCmd::AudioBufferSize(-2)))
             }
@@ -460,7 +500,19 @@ impl super::AppMenu {
             AppMenuItem::BrowseRoms | AppMenuItem::LoadedRoms | AppMenuItem::OpenedRoms => None,
             AppMenuItem::BrowseRomsSubMenu(x)
             | AppMenuItem::LoadedRomsSubMenu(x)
-            | AppMenuItem::OpenedRomsSubMenu(x) => x.move_left(),
+            | AppMenuItem::OpenedRomsSubMenu(x)
+            | AppMenuItem::CheatsSubMenu(x)
+            | AppMenuItem::RamSearchSubMenu(x)
+            | AppMenuItem::SaveStatesSubMenu(x) => x.move_left(),
+            AppMenuItem::Cheats | AppMenuItem::RamSearch | AppMenuItem::SaveStates => None,
+            AppMenuItem::Movies | AppMenuItem::Movie(_) => None,
+            AppMenuItem::NetplayMenu | AppMenuItem::Netplay(_) => None,
+            AppMenuItem::NetplayPlayer => {
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::NetplayPlayer))
+            }
+            AppMenuItem::NetplayDelay => {
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::NetplayDelay(-1)))
+            }
             AppMenuItem::RomsDir => None,
             AppMenuItem::Confirm(_) => None,
             AppMenuItem::VignetteFilter => None,
@@ -515,12 +567,43 @@ impl super::AppMenu {
 
                 Some(AppCmd::ChangeConfig(ChangeConfigCmd::SetGbModel(model)))
             }
+            AppMenuItem::RtcClock => {
+                let clock = match config.emulation.rtc_clock {
+                    RtcClock::WallClock => RtcClock::Epoch(0),
+                    RtcClock::Emulated => RtcClock::WallClock,
+                    RtcClock::Epoch(_) => RtcClock::Emulated,
+                };
+
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::SetRtcClock(clock)))
+            }
             AppMenuItem::TargetFps => Some(AppCmd::ChangeConfig(ChangeConfigCmd::TargetFps(
                 (config.video.render.target_fps - 1.0).max(1.0),
             ))),
         }
     }
 
+    /// Shows the search the app just ran, if its menu is open.
+    pub fn update_ram_search(&mut self, state: &RamSearchState) {
+        if let Some(AppMenuItem::RamSearchSubMenu(_)) = self.items.first() {
+            self.items = ram_search_menu(state);
+            self.updated = true;
+        }
+    }
+
+    /// Opens the slot browser on `slots` of the running game.
+    pub fn open_save_states(&mut self, slots: Vec<SaveStateSlot>) {
+        self.next_items(save_states_menu(slots, None));
+    }
+
+    /// Shows the slots after one changed, on the actions of slot `open`, if
+    /// the browser is open.
+    pub fn update_save_states(&mut self, slots: Vec<SaveStateSlot>, open: Option<usize>) {
+        if let Some(AppMenuItem::SaveStatesSubMenu(_)) = self.items.first() {
+            self.items = save_states_menu(slots, open);
+            self.updated = true;
+        }
+    }
+
     pub fn back(&mut self) {
         self.updated = true;
 
@@ -536,12 +619,14 @@ impl super::AppMenu {
         config: &AppConfig,
         filesystem: &impl PlatformFileSystem,
         roms: &RomsState,
+        cheats: &[Cheat],
+        ram_search: &RamSearchState,
     ) -> Option<AppCmd> {
         self.updated = true;
         let item = self.items.get_mut(self.selected_index).unwrap();
 
         match item {
-            AppMenuItem::GbModel => None,
+            AppMenuItem::GbModel | AppMenuItem::RtcClock => None,
             AppMenuItem::Resume => Some(AppCmd::ToggleMenu),
             AppMenuItem::OpenRom => Some(AppCmd::SelectRom),
             AppMenuItem::Quit => Some(AppCmd::Quit),
@@ -594,8 +679,13 @@ impl super::AppMenu {
             AppMenuItem::NormalSpeed => None,
             AppMenuItem::TurboSpeed => None,
             AppMenuItem::SlowSpeed => None,
-            AppMenuItem::RewindSize => None,
+            AppMenuItem::RewindBudget => None,
             AppMenuItem::RewindFrames => None,
+            AppMenuItem::RewindAudio => Some(AppCmd::ChangeConfig(ChangeConfigCmd::RewindAudio)),
+            AppMenuItem::RunAhead => None,
+            AppMenuItem::RunAheadSecondInstance => Some(AppCmd::ChangeConfig(
+                ChangeConfigCmd::RunAheadSecondInstance,
+            )),
             AppMenuItem::AudioBufferSize => None,
             AppMenuItem::MuteTurbo => Some(AppCmd::ChangeConfig(ChangeConfigCmd::MuteTurbo)),
             AppMenuItem::MuteSlow => Some(AppCmd::ChangeConfig(ChangeConfigCmd::MuteSlow)),
@@ -653,9 +743,35 @@ impl super::AppMenu {
                 self.next_items(opened_roms_menu(filesystem, roms));
                 None
             }
+            AppMenuItem::Cheats => {
+                self.next_items(cheats_menu(cheats));
+                None
+            }
+            AppMenuItem::RamSearch => {
+                self.next_items(ram_search_menu(ram_search));
+                None
+            }
+            AppMenuItem::SaveStates => Some(AppCmd::BrowseSaveStates),
+            AppMenuItem::Movies => {
+                self.next_items(movie_menu());
+                None
+            }
+            AppMenuItem::Movie(cmd) => Some(AppCmd::Movie(*cmd)),
+            AppMenuItem::NetplayMenu => {
+                self.next_items(netplay_menu());
+                None
+            }
+            AppMenuItem::NetplayPlayer => {
+                Some(AppCmd::ChangeConfig(ChangeConfigCmd::NetplayPlayer))
+            }
+            AppMenuItem::NetplayDelay => None,
+            AppMenuItem::Netplay(cmd) => Some(AppCmd::Netplay(*cmd)),
             AppMenuItem::BrowseRomsSubMenu(x)
             | AppMenuItem::LoadedRomsSubMenu(x)
-            | AppMenuItem::OpenedRomsSubMenu(x) => {
+            | AppMenuItem::OpenedRomsSubMenu(x)
+            | AppMenuItem::CheatsSubMenu(x)
+            | AppMenuItem::RamSearchSubMenu(x)
+            | AppMenuItem::SaveStatesSubMenu(x) => {
                 let (cmd, is_back) = x.select(config);
 
                 if is_back {
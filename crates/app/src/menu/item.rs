@@ -1,9 +1,10 @@
-use crate::cmd::{AppCmd, BindCmds, BindTarget};
+use crate::cmd::{AppCmd, BindCmds, BindTarget, MovieCmd, NetplayCmd};
 use crate::config::AppConfig;
 use crate::menu::{get_menu_toggle, SubMenu, MAX_MENU_ITEM_CHARS};
 use crate::roms::RomsState;
 use crate::video::truncate_text;
 use core::auxiliary::joypad::JoypadButton;
+use core::cart::mbc3::RtcClock;
 
 pub enum AppMenuItem {
     Resume,
@@ -29,8 +30,11 @@ pub enum AppMenuItem {
     NormalSpeed,
     TurboSpeed,
     SlowSpeed,
-    RewindSize,
+    RewindBudget,
     RewindFrames,
+    RewindAudio,
+    RunAhead,
+    RunAheadSecondInstance,
     AudioBufferSize,
     MuteTurbo,
     MuteSlow,
@@ -75,7 +79,20 @@ pub enum AppMenuItem {
     OpenedRoms,
     OpenedRomsSubMenu(Box<dyn SubMenu>),
     GbModel,
+    RtcClock,
     TargetFps,
+    Cheats,
+    CheatsSubMenu(Box<dyn SubMenu>),
+    RamSearch,
+    RamSearchSubMenu(Box<dyn SubMenu>),
+    SaveStates,
+    SaveStatesSubMenu(Box<dyn SubMenu>),
+    Movies,
+    Movie(MovieCmd),
+    NetplayMenu,
+    NetplayPlayer,
+    NetplayDelay,
+    Netplay(NetplayCmd),
 }
 
 impl AppMenuItem {
@@ -110,8 +127,11 @@ impl AppMenuItem {
             | AppMenuItem::NormalSpeed
             | AppMenuItem::TurboSpeed
             | AppMenuItem::SlowSpeed
-            | AppMenuItem::RewindSize
+            | AppMenuItem::RewindBudget
             | AppMenuItem::RewindFrames
+            | AppMenuItem::RewindAudio
+            | AppMenuItem::RunAhead
+            | AppMenuItem::RunAheadSecondInstance
             | AppMenuItem::AudioBufferSize
             | AppMenuItem::MuteTurbo
             | AppMenuItem::MuteSlow
@@ -143,11 +163,24 @@ impl AppMenuItem {
             | AppMenuItem::KeyboardShortcuts
             | AppMenuItem::ScaleMode
             | AppMenuItem::GbModel
+            | AppMenuItem::RtcClock
             | AppMenuItem::TargetFps
+            | AppMenuItem::Cheats
+            | AppMenuItem::RamSearch
+            | AppMenuItem::SaveStates
+            | AppMenuItem::Movies
+            | AppMenuItem::Movie(_)
+            | AppMenuItem::NetplayMenu
+            | AppMenuItem::NetplayPlayer
+            | AppMenuItem::NetplayDelay
+            | AppMenuItem::Netplay(_)
             | AppMenuItem::ButtonsBinding(_) => None,
             AppMenuItem::LoadedRomsSubMenu(x)
             | AppMenuItem::OpenedRomsSubMenu(x)
-            | AppMenuItem::BrowseRomsSubMenu(x) => Some(x),
+            | AppMenuItem::BrowseRomsSubMenu(x)
+            | AppMenuItem::CheatsSubMenu(x)
+            | AppMenuItem::RamSearchSubMenu(x)
+            | AppMenuItem::SaveStatesSubMenu(x) => Some(x),
         }
     }
 
@@ -182,8 +215,11 @@ impl AppMenuItem {
             | AppMenuItem::NormalSpeed
             | AppMenuItem::TurboSpeed
             | AppMenuItem::SlowSpeed
-            | AppMenuItem::RewindSize
+            | AppMenuItem::RewindBudget
             | AppMenuItem::RewindFrames
+            | AppMenuItem::RewindAudio
+            | AppMenuItem::RunAhead
+            | AppMenuItem::RunAheadSecondInstance
             | AppMenuItem::AudioBufferSize
             | AppMenuItem::MuteTurbo
             | AppMenuItem::MuteSlow
@@ -215,11 +251,24 @@ impl AppMenuItem {
             | AppMenuItem::KeyboardShortcuts
             | AppMenuItem::ScaleMode
             | AppMenuItem::GbModel
+            | AppMenuItem::RtcClock
             | AppMenuItem::TargetFps
+            | AppMenuItem::Cheats
+            | AppMenuItem::RamSearch
+            | AppMenuItem::SaveStates
+            | AppMenuItem::Movies
+            | AppMenuItem::Movie(_)
+            | AppMenuItem::NetplayMenu
+            | AppMenuItem::NetplayPlayer
+            | AppMenuItem::NetplayDelay
+            | AppMenuItem::Netplay(_)
             | AppMenuItem::ButtonsBinding(_) => None,
             AppMenuItem::LoadedRomsSubMenu(x)
             | AppMenuItem::OpenedRomsSubMenu(x)
-            | AppMenuItem::BrowseRomsSubMenu(x) => Some(x),
+            | AppMenuItem::BrowseRomsSubMenu(x)
+            | AppMenuItem::CheatsSubMenu(x)
+            | AppMenuItem::RamSearchSubMenu(x)
+            | AppMenuItem::SaveStatesSubMenu(x) => Some(x),
         }
     }
 }
@@ -268,10 +317,18 @@ impl AppMenuItem {
             AppMenuItem::NormalSpeed => with_value("Normal Speed", config.emulation.normal_speed),
             AppMenuItem::TurboSpeed => with_value("Turbo Speed", config.emulation.turbo_speed),
             AppMenuItem::SlowSpeed => with_value("Slow Speed", config.emulation.slow_speed),
-            AppMenuItem::RewindSize => with_value("Rewind Size", config.emulation.rewind_size),
+            AppMenuItem::RewindBudget => with_value(
+                "Rewind Budget",
+                format!("{}MB", config.emulation.rewind_budget),
+            ),
             AppMenuItem::RewindFrames => {
                 with_value("Rewind Frames", config.emulation.rewind_frames)
             }
+            AppMenuItem::RewindAudio => with_toggle("Rewind Audio", config.emulation.rewind_audio),
+            AppMenuItem::RunAhead => with_value("Run Ahead", config.emulation.run_ahead),
+            AppMenuItem::RunAheadSecondInstance => {
+                with_toggle("2nd Instance", config.emulation.run_ahead_second_instance)
+            }
             AppMenuItem::AudioBufferSize => with_value("Buffer Size", config.audio.buffer_size),
             AppMenuItem::MuteTurbo => with_toggle("Mute Turbo", config.audio.mute_turbo),
             AppMenuItem::MuteSlow => with_toggle("Mute Slow", config.audio.mute_slow),
@@ -405,9 +462,30 @@ impl AppMenuItem {
 
                 with_value("Model", model_name)
             }
+            AppMenuItem::RtcClock => {
+                let clock_name = match config.emulation.rtc_clock {
+                    RtcClock::WallClock => "Wall",
+                    RtcClock::Emulated => "Emulated",
+                    RtcClock::Epoch(_) => "Epoch",
+                };
+
+                with_value("RTC", clock_name)
+            }
             AppMenuItem::TargetFps => {
                 with_value("Target FPS", config.video.render.target_fps as usize)
             }
+            AppMenuItem::Cheats => "Cheats".to_string(),
+            AppMenuItem::CheatsSubMenu(_) => "Cheats Sub".to_string(),
+            AppMenuItem::RamSearch => "RAM Search".to_string(),
+            AppMenuItem::RamSearchSubMenu(_) => "RAM Search Sub".to_string(),
+            AppMenuItem::SaveStates => "Save States".to_string(),
+            AppMenuItem::SaveStatesSubMenu(_) => "Save States Sub".to_string(),
+            AppMenuItem::Movies => "Movie".to_string(),
+            AppMenuItem::Movie(cmd) => cmd.name().to_string(),
+            AppMenuItem::NetplayMenu => "Netplay".to_string(),
+            AppMenuItem::NetplayPlayer => with_value("Player", config.netplay.player + 1),
+            AppMenuItem::NetplayDelay => with_value("Input Delay", config.netplay.delay),
+            AppMenuItem::Netplay(cmd) => cmd.name().to_string(),
         };
 
         truncate_text(&item_str, MAX_MENU_ITEM_CHARS)
@@ -1,9 +1,12 @@
 pub mod buffer;
+pub mod cheats;
 pub mod factory;
 pub mod files;
 pub mod handler;
 pub mod item;
+pub mod ram_search;
 pub mod roms;
+pub mod save_states;
 
 use crate::cmd::{AppCmd, BindInputCmd};
 use crate::config::AppConfig;
@@ -58,7 +61,13 @@ impl AppMenu {
         self.updated = true;
     }
 
-    pub fn get_items(&mut self, config: &AppConfig, roms: &RomsState) -> (&[&str], bool) {
+    /// Menu lines, the thumbnail of the open sub menu's selection if it has
+    /// one, and whether they changed.
+    pub fn get_items(
+        &mut self,
+        config: &AppConfig,
+        roms: &RomsState,
+    ) -> (&[&str], Option<&[u8]>, bool) {
         let updated = self.updated;
         self.updated = false;
 
@@ -72,7 +81,7 @@ impl AppMenu {
                         self.sub_buffer.add(sub_item);
                     }
 
-                    return (self.sub_buffer.get(), updated);
+                    return (self.sub_buffer.get(), sub_items.thumbnail(), updated);
                 } else {
                     let line = item.to_string(config, roms);
                     if i == self.selected_index {
@@ -83,10 +92,16 @@ impl AppMenu {
                 }
             }
         } else if !self.sub_buffer.is_empty() {
-            return (self.sub_buffer.get(), updated);
+            let thumbnail = self
+                .items
+                .get(self.selected_index)
+                .and_then(|x| x.get_items())
+                .and_then(|x| x.thumbnail());
+
+            return (self.sub_buffer.get(), thumbnail, updated);
         }
 
-        (self.buffer.get(), updated)
+        (self.buffer.get(), None, updated)
     }
 
     fn next_items(&mut self, items: Box<[AppMenuItem]>) {
@@ -114,4 +129,10 @@ pub trait SubMenu {
     fn select(&mut self, _config: &AppConfig) -> (Option<AppCmd>, bool);
     fn next_page(&mut self);
     fn prev_page(&mut self);
+
+    /// Save-state thumbnail of what's selected, drawn above the lines; empty
+    /// for a blank frame.
+    fn thumbnail(&self) -> Option<&[u8]> {
+        None
+    }
 }
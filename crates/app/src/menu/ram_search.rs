@@ -0,0 +1,416 @@
+use crate::cheats::RamSearchState;
+use crate::cmd::{AppCmd, RamSearchCmd};
+use crate::config::AppConfig;
+use crate::menu::cheats::TextInput;
+use crate::menu::{SubMenu, MAX_MENU_ITEMS_PER_PAGE, MAX_MENU_ITEM_CHARS};
+use crate::video::truncate_text;
+use core::cheats::search::{Filter, SearchResult, ValueKind};
+use core::debugger::{parse_number, CmpOp};
+
+/// Results past this are only counted; filter more to see them.
+const MAX_RESULTS: usize = 100;
+const NUMBER_CHARS: &str = " 0123456789";
+const OPS: [CmpOp; 6] = [
+    CmpOp::Ne,
+    CmpOp::Eq,
+    CmpOp::Gt,
+    CmpOp::Lt,
+    CmpOp::Ge,
+    CmpOp::Le,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Row {
+    Kind,
+    NewSearch,
+    Op,
+    Value,
+    Apply,
+    Result(usize),
+    Back,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ResultRow {
+    Freeze,
+    #[cfg(feature = "debug")]
+    Watch,
+    Back,
+}
+
+const RESULT_ROWS: &[ResultRow] = &[
+    ResultRow::Freeze,
+    #[cfg(feature = "debug")]
+    ResultRow::Watch,
+    ResultRow::Back,
+];
+
+#[derive(Debug, Clone)]
+enum Mode {
+    List,
+    /// Actions on one result, with the value to freeze it at.
+    Result(usize, u16),
+    /// Entering the value to compare to.
+    Value(TextInput),
+}
+
+/// Cheat finder: start a search, then filter it between stretches of play
+/// until the address of a value is left, and freeze or watch it. The search
+/// runs in the app, as it reads the bus; this shows what it last found.
+#[derive(Debug, Clone)]
+pub struct RamSearchMenu {
+    kind: ValueKind,
+    filter: Filter,
+    /// Candidates left, `None` before a search is started.
+    len: Option<usize>,
+    results: Vec<SearchResult>,
+    mode: Mode,
+    selected_index: usize,
+    error: Option<String>,
+}
+
+impl RamSearchMenu {
+    pub fn new(state: &RamSearchState) -> Self {
+        let mut menu = Self {
+            kind: state.kind,
+            filter: state.filter,
+            len: state.search.as_ref().map(|x| x.len()),
+            results: state
+                .search
+                .as_ref()
+                .map(|x| x.results().take(MAX_RESULTS).collect())
+                .unwrap_or_default(),
+            mode: Mode::List,
+            selected_index: 0,
+            error: None,
+        };
+        // straight to filtering once there's a search, results just below
+        let row = match menu.len {
+            Some(_) => Row::Apply,
+            None => Row::NewSearch,
+        };
+        menu.selected_index = menu.rows().iter().position(|x| *x == row).unwrap_or(0);
+
+        menu
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = vec![Row::Kind, Row::NewSearch];
+
+        if self.len.is_some() {
+            rows.extend([Row::Op, Row::Value, Row::Apply]);
+            rows.extend((0..self.results.len()).map(Row::Result));
+        }
+
+        rows.push(Row::Back);
+
+        rows
+    }
+
+    fn items_len(&self) -> usize {
+        match self.mode {
+            Mode::List => self.rows().len(),
+            Mode::Result(..) => RESULT_ROWS.len(),
+            Mode::Value(_) => 1,
+        }
+    }
+
+    fn set_mode(&mut self, mode: Mode, selected_index: usize) {
+        self.mode = mode;
+        self.selected_index = selected_index;
+        self.error = None;
+    }
+
+    fn selected_row(&self) -> Row {
+        self.rows()
+            .get(self.selected_index)
+            .copied()
+            .unwrap_or(Row::Back)
+    }
+
+    /// Left and right on a list row.
+    fn change(&mut self, next: bool) {
+        match self.selected_row() {
+            Row::Kind => {
+                let i = ValueKind::ALL.iter().position(|x| *x == self.kind);
+                let i = cycle(i.unwrap_or(0), ValueKind::ALL.len(), next);
+                self.kind = ValueKind::ALL[i];
+            }
+            Row::Op => {
+                let i = OPS.iter().position(|x| *x == self.filter.op);
+                let i = cycle(i.unwrap_or(0), OPS.len(), next);
+                self.filter.op = OPS[i];
+            }
+            Row::Value => {
+                self.filter.value = match (self.filter.value, next) {
+                    (None, true) => Some(0),
+                    (None, false) | (Some(0), false) => None,
+                    (Some(x), true) => Some(x.saturating_add(1)),
+                    (Some(x), false) => Some(x - 1),
+                };
+            }
+            Row::NewSearch | Row::Apply | Row::Result(_) | Row::Back => {}
+        }
+    }
+
+    fn submit_value(&mut self, text: String) {
+        self.filter.value = match text.as_str() {
+            "" => None,
+            text => match parse_number(text) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    self.error = Some("Value too big".to_string());
+                    return;
+                }
+            },
+        };
+
+        let row = self.rows().iter().position(|x| *x == Row::Value);
+        self.set_mode(Mode::List, row.unwrap_or(0));
+    }
+
+    fn get_lines(&self) -> Vec<String> {
+        let mut lines = Vec::with_capacity(MAX_MENU_ITEMS_PER_PAGE);
+
+        match &self.mode {
+            Mode::List => {
+                for row in self.rows() {
+                    lines.push(match row {
+                        Row::Kind => format!("Type: {}", self.kind.name()),
+                        Row::NewSearch => "New Search".to_string(),
+                        Row::Op => format!("Filter: {}", op_name(&self.filter)),
+                        Row::Value => match self.filter.value {
+                            Some(x) => format!("Value: {x}"),
+                            None => "Value: previous".to_string(),
+                        },
+                        Row::Apply => "Apply Filter".to_string(),
+                        Row::Result(i) => {
+                            let x = &self.results[i];
+                            format!("{} {}", x.location, x.value)
+                        }
+                        Row::Back => "Back".to_string(),
+                    });
+                }
+            }
+            Mode::Result(i, value) => {
+                for row in RESULT_ROWS {
+                    lines.push(match row {
+                        ResultRow::Freeze => format!("Freeze at: {value}"),
+                        #[cfg(feature = "debug")]
+                        ResultRow::Watch => "Watch Writes".to_string(),
+                        ResultRow::Back => "Back".to_string(),
+                    });
+                }
+
+                let x = &self.results[*i];
+                lines.insert(0, format!("{} was {}", x.location, x.prev));
+            }
+            Mode::Value(text) => lines.push(text.to_string()),
+        }
+
+        lines
+            .into_iter()
+            .map(|x| truncate_text(&x, MAX_MENU_ITEM_CHARS))
+            .collect()
+    }
+}
+
+/// Words rather than symbols, which not every menu font has.
+fn op_name(filter: &Filter) -> &'static str {
+    match (filter.op, filter.value) {
+        (CmpOp::Ne, None) => "Changed",
+        (CmpOp::Eq, None) => "Unchanged",
+        (CmpOp::Gt, None) => "Increased",
+        (CmpOp::Lt, None) => "Decreased",
+        (CmpOp::Ge, None) => "Not decreased",
+        (CmpOp::Le, None) => "Not increased",
+        (CmpOp::Ne, Some(_)) => "Not",
+        (CmpOp::Eq, Some(_)) => "Equal to",
+        (CmpOp::Gt, Some(_)) => "More than",
+        (CmpOp::Lt, Some(_)) => "Less than",
+        (CmpOp::Ge, Some(_)) => "At least",
+        (CmpOp::Le, Some(_)) => "At most",
+    }
+}
+
+fn cycle(i: usize, len: usize, next: bool) -> usize {
+    if next {
+        core::move_next_wrapped(i, len - 1)
+    } else {
+        core::move_prev_wrapped(i, len - 1)
+    }
+}
+
+impl SubMenu for RamSearchMenu {
+    fn get_iterator<'a>(&'a self) -> Box<dyn Iterator<Item = String> + 'a> {
+        let mut lines = self.get_lines();
+        let header = match (&self.mode, self.len) {
+            (Mode::List, Some(len)) => Some(format!("{len} found")),
+            (Mode::List, None) => Some("No search".to_string()),
+            (Mode::Result(..), _) => Some(lines.remove(0)),
+            (Mode::Value(_), _) => Some("Enter Value".to_string()),
+        };
+        // keep the selection on screen once the list outgrows a page
+        let skip = (self.selected_index + 1).saturating_sub(MAX_MENU_ITEMS_PER_PAGE);
+
+        let items = lines
+            .into_iter()
+            .enumerate()
+            .skip(skip)
+            .take(MAX_MENU_ITEMS_PER_PAGE)
+            .map(move |(i, line)| {
+                if i == self.selected_index && !matches!(self.mode, Mode::Value(_)) {
+                    format!("◀{line}▶")
+                } else {
+                    line
+                }
+            });
+
+        let footer = match (&self.mode, &self.error) {
+            (_, Some(err)) => Some(truncate_text(err, MAX_MENU_ITEM_CHARS)),
+            (Mode::Value(_), None) => Some("Blank: previous".to_string()),
+            _ => None,
+        };
+
+        Box::new(header.into_iter().chain(items).chain(footer))
+    }
+
+    fn move_up(&mut self) {
+        if let Mode::Value(text) = &mut self.mode {
+            text.cycle(false);
+            self.error = None;
+            return;
+        }
+
+        self.selected_index = core::move_prev_wrapped(self.selected_index, self.items_len() - 1);
+    }
+
+    fn move_down(&mut self) {
+        if let Mode::Value(text) = &mut self.mode {
+            text.cycle(true);
+            self.error = None;
+            return;
+        }
+
+        self.selected_index = core::move_next_wrapped(self.selected_index, self.items_len() - 1);
+    }
+
+    fn move_left(&mut self) -> Option<AppCmd> {
+        match self.mode {
+            Mode::List => self.change(false),
+            Mode::Result(_, ref mut value) if self.selected_index == 0 => {
+                *value = value.saturating_sub(1)
+            }
+            Mode::Result(..) => {}
+            Mode::Value(ref mut text) => text.move_cursor(false),
+        }
+
+        None
+    }
+
+    fn move_right(&mut self) -> Option<AppCmd> {
+        match self.mode {
+            Mode::List => self.change(true),
+            Mode::Result(_, ref mut value) if self.selected_index == 0 => {
+                *value = value.saturating_add(1)
+            }
+            Mode::Result(..) => {}
+            Mode::Value(ref mut text) => text.move_cursor(true),
+        }
+
+        None
+    }
+
+    fn select(&mut self, _config: &AppConfig) -> (Option<AppCmd>, bool) {
+        let cmd = match self.mode.clone() {
+            Mode::List => match self.selected_row() {
+                Row::Kind | Row::Op => {
+                    self.change(true);
+                    None
+                }
+                Row::NewSearch => Some(RamSearchCmd::Start(self.kind)),
+                Row::Value => {
+                    let text = self.filter.value.map(|x| x.to_string());
+                    let text = TextInput::new(&text.unwrap_or_default(), NUMBER_CHARS);
+                    self.set_mode(Mode::Value(text), 0);
+                    None
+                }
+                Row::Apply => Some(RamSearchCmd::Filter(self.filter)),
+                Row::Result(i) => {
+                    self.set_mode(Mode::Result(i, self.results[i].value), 0);
+                    None
+                }
+                Row::Back => return (None, true),
+            },
+            Mode::Result(i, value) => {
+                let location = self.results[i].location;
+                let cmd = match RESULT_ROWS[self.selected_index] {
+                    ResultRow::Freeze => Some(RamSearchCmd::Freeze(location, value)),
+                    #[cfg(feature = "debug")]
+                    ResultRow::Watch => Some(RamSearchCmd::Watch(location)),
+                    ResultRow::Back => None,
+                };
+                let row = self.rows().iter().position(|x| *x == Row::Result(i));
+                self.set_mode(Mode::List, row.unwrap_or(0));
+                cmd
+            }
+            Mode::Value(text) => {
+                self.submit_value(text.get());
+                None
+            }
+        };
+
+        (cmd.map(AppCmd::RamSearch), false)
+    }
+
+    fn next_page(&mut self) {}
+
+    fn prev_page(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cheats::search::{Location, Region};
+
+    #[test]
+    fn filter_and_freeze() {
+        let mut menu = RamSearchMenu::new(&RamSearchState::default());
+        let config = AppConfig::default();
+
+        // "Type", then "New Search"
+        menu.move_up();
+        menu.move_right();
+        menu.move_down();
+        let cmd = AppCmd::RamSearch(RamSearchCmd::Start(ValueKind::U16));
+        assert_eq!(menu.select(&config), (Some(cmd), false));
+
+        let location = Location {
+            region: Region::Wram,
+            bank: 0,
+            addr: 0xC123,
+        };
+        menu.len = Some(1);
+        menu.results = vec![SearchResult {
+            location,
+            value: 5,
+            prev: 4,
+        }];
+
+        // "Value" up to 1, then "Apply Filter"
+        menu.selected_index = 3;
+        menu.move_right();
+        menu.move_right();
+        menu.move_down();
+        let filter = Filter::new(CmpOp::Ne, Some(1));
+        let cmd = AppCmd::RamSearch(RamSearchCmd::Filter(filter));
+        assert_eq!(menu.select(&config), (Some(cmd), false));
+
+        // the result, then "Freeze at" one higher
+        menu.move_down();
+        assert_eq!(menu.select(&config), (None, false));
+        menu.move_right();
+        let cmd = AppCmd::RamSearch(RamSearchCmd::Freeze(location, 6));
+        assert_eq!(menu.select(&config), (Some(cmd), false));
+    }
+}
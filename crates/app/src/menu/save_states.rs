@@ -0,0 +1,286 @@
+use crate::cmd::AppCmd;
+use crate::config::AppConfig;
+use crate::menu::SubMenu;
+use core::emu::state::SaveStateCmd;
+use core::emu::state_file::SaveStateInfo;
+use core::ppu::FRAME_DURATION;
+use std::time::SystemTime;
+
+/// Lines that fit under the thumbnail.
+const MAX_LINES: usize = 7;
+const SLOT_ITEMS: usize = 4;
+
+/// A numbered slot with a state in it, `info` missing for states from
+/// before it was stored.
+#[derive(Debug, Clone)]
+pub struct SaveStateSlot {
+    pub index: usize,
+    pub info: Option<SaveStateInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    List,
+    /// Actions on one slot, by position in the list.
+    Slot(usize),
+}
+
+/// Save-state slots of the running game with their thumbnail, age and play
+/// time; each can be loaded, overwritten or deleted.
+#[derive(Debug, Clone)]
+pub struct SaveStatesMenu {
+    slots: Vec<SaveStateSlot>,
+    mode: Mode,
+    selected_index: usize,
+    /// Delete was picked once, the next pick deletes.
+    confirm_delete: bool,
+    now: u64,
+}
+
+impl SaveStatesMenu {
+    /// Opens on the actions of slot `open` if it's there.
+    pub fn new(slots: Vec<SaveStateSlot>, open: Option<usize>) -> Self {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default();
+        let mode = match open.and_then(|i| slots.iter().position(|x| x.index == i)) {
+            Some(i) => Mode::Slot(i),
+            None => Mode::List,
+        };
+
+        Self {
+            slots,
+            mode,
+            selected_index: 0,
+            confirm_delete: false,
+            now,
+        }
+    }
+
+    fn items_len(&self) -> usize {
+        match self.mode {
+            Mode::List => self.slots.len() + 1,
+            Mode::Slot(_) => SLOT_ITEMS,
+        }
+    }
+
+    fn set_mode(&mut self, mode: Mode, selected_index: usize) {
+        self.mode = mode;
+        self.selected_index = selected_index;
+        self.confirm_delete = false;
+    }
+
+    fn slot_line(&self, slot: &SaveStateSlot) -> String {
+        match &slot.info {
+            Some(info) => format!(
+                "{:02} {:>3} {}",
+                slot.index,
+                format_age(self.now, info.created),
+                format_play_time(info.frame)
+            ),
+            None => format!("{:02} ?", slot.index),
+        }
+    }
+
+    fn get_lines(&self) -> Vec<String> {
+        let mut lines = Vec::with_capacity(MAX_LINES);
+
+        match self.mode {
+            Mode::List => {
+                for slot in self.slots.iter() {
+                    lines.push(self.slot_line(slot));
+                }
+
+                lines.push("Back".to_string());
+            }
+            Mode::Slot(_) => {
+                lines.push("Load".to_string());
+                lines.push("Overwrite".to_string());
+                lines.push(
+                    if self.confirm_delete {
+                        "Delete: Sure?"
+                    } else {
+                        "Delete"
+                    }
+                    .to_string(),
+                );
+                lines.push("Back".to_string());
+            }
+        }
+
+        lines
+    }
+
+    fn selected_slot(&self) -> Option<&SaveStateSlot> {
+        match self.mode {
+            Mode::List => self.slots.get(self.selected_index),
+            Mode::Slot(i) => self.slots.get(i),
+        }
+    }
+}
+
+impl SubMenu for SaveStatesMenu {
+    fn get_iterator<'a>(&'a self) -> Box<dyn Iterator<Item = String> + 'a> {
+        let header = match self.mode {
+            Mode::List if self.slots.is_empty() => Some("No Save States".to_string()),
+            Mode::List => None,
+            Mode::Slot(i) => Some(self.slot_line(&self.slots[i])),
+        };
+        let page = MAX_LINES - header.is_some() as usize;
+        // keep the selection on screen once the list outgrows a page
+        let skip = (self.selected_index + 1).saturating_sub(page);
+
+        let items = self
+            .get_lines()
+            .into_iter()
+            .enumerate()
+            .skip(skip)
+            .take(page)
+            .map(move |(i, line)| {
+                if i == self.selected_index {
+                    format!("◀{line}▶")
+                } else {
+                    line
+                }
+            });
+
+        Box::new(header.into_iter().chain(items))
+    }
+
+    fn move_up(&mut self) {
+        self.confirm_delete = false;
+        self.selected_index = core::move_prev_wrapped(self.selected_index, self.items_len() - 1);
+    }
+
+    fn move_down(&mut self) {
+        self.confirm_delete = false;
+        self.selected_index = core::move_next_wrapped(self.selected_index, self.items_len() - 1);
+    }
+
+    fn move_left(&mut self) -> Option<AppCmd> {
+        None
+    }
+
+    fn move_right(&mut self) -> Option<AppCmd> {
+        None
+    }
+
+    fn select(&mut self, _config: &AppConfig) -> (Option<AppCmd>, bool) {
+        match self.mode {
+            Mode::List => match self.selected_index {
+                i if i < self.slots.len() => {
+                    self.set_mode(Mode::Slot(i), 0);
+                    (None, false)
+                }
+                _ => (None, true),
+            },
+            Mode::Slot(i) => {
+                let index = self.slots[i].index;
+
+                match self.selected_index {
+                    0 => (
+                        Some(AppCmd::SaveState(SaveStateCmd::Load, Some(index))),
+                        false,
+                    ),
+                    1 => (
+                        Some(AppCmd::SaveState(SaveStateCmd::Create, Some(index))),
+                        false,
+                    ),
+                    2 if !self.confirm_delete => {
+                        self.confirm_delete = true;
+                        (None, false)
+                    }
+                    2 => (Some(AppCmd::DeleteSaveState(index)), false),
+                    _ => {
+                        self.set_mode(Mode::List, i);
+                        (None, false)
+                    }
+                }
+            }
+        }
+    }
+
+    fn next_page(&mut self) {}
+
+    fn prev_page(&mut self) {}
+
+    fn thumbnail(&self) -> Option<&[u8]> {
+        let info = self.selected_slot().and_then(|x| x.info.as_ref());
+
+        // a blank frame keeps the lines in place
+        Some(info.map(|x| x.thumbnail.as_slice()).unwrap_or_default())
+    }
+}
+
+/// How long ago `created` was, in its largest unit: `now`, `5m`, `3h`, `2d`.
+fn format_age(now: u64, created: u64) -> String {
+    if created == 0 {
+        return "?".to_string();
+    }
+
+    match now.saturating_sub(created) {
+        x if x < 60 => "now".to_string(),
+        x if x < 60 * 60 => format!("{}m", x / 60),
+        x if x < 60 * 60 * 24 => format!("{}h", x / (60 * 60)),
+        x => format!("{}d", x / (60 * 60 * 24)),
+    }
+}
+
+fn format_play_time(frame: usize) -> String {
+    let secs = (FRAME_DURATION.as_secs_f64() * frame as f64) as u64;
+
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::emu::config::GbModel;
+
+    fn slot(index: usize, frame: usize) -> SaveStateSlot {
+        SaveStateSlot {
+            index,
+            info: Some(SaveStateInfo {
+                emu_version: String::new(),
+                rom_checksum: 0,
+                rom_crc: None,
+                model: GbModel::Dmg,
+                created: 1_000,
+                frame,
+                thumbnail: vec![1, 2],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_slot_actions() {
+        let config = AppConfig::default();
+        let mut menu = SaveStatesMenu::new(vec![slot(2, 0), slot(5, 3600 * 60)], None);
+        menu.now = 1_000 + 7200;
+
+        let lines: Vec<_> = menu.get_iterator().collect();
+        assert_eq!(lines, ["◀02  2h 0:00:00▶", "05  2h 1:00:16", "Back"]);
+        assert_eq!(menu.thumbnail(), Some([1, 2].as_slice()));
+
+        menu.move_down();
+        assert_eq!(menu.select(&config), (None, false));
+        assert_eq!(
+            menu.select(&config),
+            (Some(AppCmd::SaveState(SaveStateCmd::Load, Some(5))), false)
+        );
+
+        // deleting takes a second pick
+        menu.move_down();
+        menu.move_down();
+        assert_eq!(menu.select(&config), (None, false));
+        assert_eq!(
+            menu.select(&config),
+            (Some(AppCmd::DeleteSaveState(5)), false)
+        );
+
+        // reopened on a slot that's gone
+        let menu = SaveStatesMenu::new(vec![slot(2, 0)], Some(5));
+        assert_eq!(menu.mode, Mode::List);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::video::draw_color;
+use crate::video::text::{fill_line, fill_rect, FontSize};
+use core::ppu::framebuffer::FrameBuffer;
+use core::ppu::tile::PixelColor;
+use core::ppu::{LCD_X_RES, LCD_Y_RES};
+use core::script::ScriptDraw;
+use std::path::{Path, PathBuf};
+
+/// Overrides the script picked up next to the ROM.
+const SCRIPT_ENV: &str = "OXGBC_SCRIPT";
+const FONT: FontSize = FontSize::Font3x4;
+
+/// `OXGBC_SCRIPT` if set, else the `.rhai` file next to the ROM.
+pub fn script_path(rom_path: &Path) -> PathBuf {
+    match std::env::var_os(SCRIPT_ENV) {
+        Some(path) => PathBuf::from(path),
+        None => rom_path.with_extension("rhai"),
+    }
+}
+
+/// Puts what a script drew over the picture, clipped to the screen.
+pub fn draw_script(fb: &mut FrameBuffer, draws: &[ScriptDraw], text_color: PixelColor) {
+    for draw in draws {
+        match draw {
+            ScriptDraw::Text { x, y, text, color } => {
+                // text keeps its left edge, so it's dropped rather than cut there
+                if *x < 0 || *y < 0 || *y as usize + FONT.height() > LCD_Y_RES as usize {
+                    continue;
+                }
+
+                let x = *x as usize;
+                let room = (LCD_X_RES as usize).saturating_sub(x) + FONT.spacing();
+                let text: String = text
+                    .chars()
+                    .take(room / (FONT.width() + FONT.spacing()))
+                    .collect();
+
+                if !text.is_empty() {
+                    fill_line(fb, &text, color.unwrap_or(text_color), x, *y as usize, FONT);
+                }
+            }
+            ScriptDraw::Pixel { x, y, color } => {
+                if let Some((x, y, _, _)) = clip(*x, *y, 1, 1) {
+                    let offset = y * FrameBuffer::PITCH + x * FrameBuffer::BYTES_PER_PIXEL;
+                    draw_color(fb, offset, *color);
+                }
+            }
+            ScriptDraw::Rect { x, y, w, h, color } => {
+                if let Some((x, y, x_end, y_end)) = clip(*x, *y, *w, *h) {
+                    fill_rect(fb, x_end, y_end, *color, x, y);
+                }
+            }
+        }
+    }
+}
+
+/// The part of a box on the screen, as `(x, y, x_end, y_end)`.
+fn clip(x: i32, y: i32, w: i32, h: i32) -> Option<(usize, usize, usize, usize)> {
+    if w <= 0 || h <= 0 {
+        return None;
+    }
+
+    let x_end = x.saturating_add(w).min(LCD_X_RES as i32);
+    let y_end = y.saturating_add(h).min(LCD_Y_RES as i32);
+    let (x, y) = (x.max(0), y.max(0));
+
+    if x >= x_end || y >= y_end {
+        return None;
+    }
+
+    Some((x as usize, y as usize, x_end as usize, y_end as usize))
+}
@@ -67,7 +67,12 @@ pub const fn char_bitmap_3x4(c: char) -> &'static [u8; 4] {
         '▼' => &[0b000, 0b101, 0b111, 0b010],
         '#' => &[0b101, 0b111, 0b101, 0b111],
         ':' => &[0b010, 0b000, 0b000, 0b010],
+        '=' => &[0b111, 0b000, 0b111, 0b000],
+        '$' => &[0b011, 0b110, 0b011, 0b110],
+        '[' => &[0b110, 0b100, 0b100, 0b110],
+        ']' => &[0b011, 0b001, 0b001, 0b011],
         '/' => &[0b001, 0b010, 0b010, 0b100],
+        '%' => &[0b101, 0b001, 0b010, 0b101],
         '\\' => &[0b100, 0b010, 0b010, 0b001],
         '…' => &[0b000, 0b000, 0b000, 0b101],
         _ => &[0b000; 4],
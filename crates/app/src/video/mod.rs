@@ -17,7 +17,7 @@ mod video;
 pub use video::*;
 
 mod gl_backend;
-mod overlay;
+pub mod overlay;
 mod sdl2_backend;
 pub mod sdl2_tiles;
 pub mod shader;
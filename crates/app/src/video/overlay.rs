@@ -1,7 +1,9 @@
 use crate::video::fill_buffer;
 use crate::video::text::{
-    fill_line_outlined, fill_lines, CenterAlignedText, FontSize, TextLinesStyle, TextStyle,
+    fill_line, fill_line_outlined, fill_lines, fill_rect, CenterAlignedText, FontSize,
+    TextLinesStyle, TextStyle,
 };
+use core::emu::state_file::{THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH};
 use core::ppu::framebuffer::FrameBuffer;
 use core::ppu::tile::PixelColor;
 use core::ppu::LCD_X_RES;
@@ -58,6 +60,62 @@ impl Overlay {
         fill_lines(fb, lines, style, x, y);
     }
 
+    /// Menu lines under a save-state thumbnail in a border, or an empty
+    /// border if it's not one.
+    pub fn fill_thumbnail_menu(&self, fb: &mut FrameBuffer, lines: &[&str], thumbnail: &[u8]) {
+        let size = FontSize::Font5x6;
+        let menu_width = LCD_X_RES as usize;
+        let row_len = THUMBNAIL_WIDTH * FrameBuffer::BYTES_PER_PIXEL;
+        let x = (menu_width - THUMBNAIL_WIDTH) / 2;
+        let y = size.padding();
+
+        fill_buffer(fb, self.bg_color);
+        fill_rect(
+            fb,
+            x + THUMBNAIL_WIDTH + 1,
+            y + THUMBNAIL_HEIGHT + 1,
+            self.text_color,
+            x - 1,
+            y - 1,
+        );
+        fill_rect(
+            fb,
+            x + THUMBNAIL_WIDTH,
+            y + THUMBNAIL_HEIGHT,
+            self.bg_color,
+            x,
+            y,
+        );
+
+        if thumbnail.len() == row_len * THUMBNAIL_HEIGHT {
+            for (row, pixels) in thumbnail.chunks_exact(row_len).enumerate() {
+                let offset = (y + row) * FrameBuffer::PITCH + x * FrameBuffer::BYTES_PER_PIXEL;
+                fb[offset..offset + row_len].copy_from_slice(pixels);
+            }
+        }
+
+        if lines.is_empty() {
+            return;
+        }
+
+        let center = CenterAlignedText::new(lines, size, menu_width);
+        let style = TextLinesStyle {
+            text_color: self.text_color,
+            bg_color: None,
+            size,
+            align_center: Some(center),
+        };
+        let lines_x = (menu_width - center.max_text_width) / 2;
+
+        fill_lines(
+            fb,
+            lines,
+            style,
+            lines_x,
+            y + THUMBNAIL_HEIGHT + size.padding(),
+        );
+    }
+
     #[inline(always)]
     pub fn fill_fps(&mut self, fb: &mut FrameBuffer, text: &str) {
         let style = TextStyle {
@@ -72,6 +130,44 @@ impl Overlay {
         fill_line_outlined(fb, text, style, x, y);
     }
 
+    /// Two text columns over a plain background; `right_col` is the right
+    /// column's start, in characters.
+    pub fn fill_debugger(
+        &self,
+        fb: &mut FrameBuffer,
+        left: &[&str],
+        right: &[&str],
+        right_col: usize,
+    ) {
+        let size = FontSize::Font3x4;
+        let style = || TextLinesStyle {
+            text_color: self.text_color,
+            bg_color: None,
+            size,
+            align_center: None,
+        };
+        let padding = size.padding();
+        let right_x = padding + right_col * (size.width() + size.spacing());
+
+        fill_buffer(fb, self.bg_color);
+        fill_lines(fb, left, style(), padding, padding);
+        fill_lines(fb, right, style(), right_x, padding);
+    }
+
+    /// Redraws `text` at a [`Self::fill_debugger`] line and column with the
+    /// colors swapped.
+    pub fn invert_text(&self, fb: &mut FrameBuffer, text: &str, line: usize, col: usize) {
+        let size = FontSize::Font3x4;
+        let padding = size.padding();
+        let x = padding + col * (size.width() + size.spacing());
+        let y = padding + line * (size.height() + size.line_spacing());
+        let w = x + size.calc_text_width(text) + 1;
+        let h = y + size.height() + 1;
+
+        fill_rect(fb, w, h, self.text_color, x - 1, y - 1);
+        fill_line(fb, text, self.bg_color, x, y, size);
+    }
+
     #[inline(always)]
     pub fn fill_notif(&mut self, fb: &mut FrameBuffer, lines: &[&str]) {
         let style = TextLinesStyle {
@@ -0,0 +1,463 @@
+use crate::cmd::{AppCmd, VramCmd};
+use crate::video::draw_color;
+use crate::video::overlay::Overlay;
+use crate::video::text::{fill_line, fill_rect, FontSize};
+use core::emu::Emu;
+use core::ppu::framebuffer::FrameBuffer;
+use core::ppu::inspect::{PixelImage, TILEMAP_SIZE};
+use core::ppu::oam::OAM_ENTRIES_COUNT;
+use core::ppu::tile::{PixelColor, BG_TILE_MAP_1_ADDR_START, BG_TILE_MAP_2_ADDR_START};
+use core::ppu::{Ppu, LCD_X_RES, LCD_Y_RES};
+use sdl2::keyboard::Scancode;
+use std::fmt::Write;
+
+/// Text lines that fit the screen, the last one for key hints.
+const LINES: usize = 28;
+/// Where pictures go: between the header line and the hint line.
+const IMAGE_Y: usize = 7;
+const IMAGE_HEIGHT: usize = 128;
+const PAN_STEP: i32 = 8;
+const PAN_PAGE: i32 = 64;
+/// Sprites listed at once, after the header and column lines.
+const OAM_ROWS: usize = LINES - 3;
+const SWATCH_SIZE: usize = 12;
+const SWATCH_STEP: usize = 14;
+const SWATCHES_Y: usize = 14;
+/// Left edge of the BG and OBJ palette columns.
+const PALETTE_COLUMNS_X: [usize; 2] = [1, 82];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Page {
+    #[default]
+    Map9800,
+    Map9C00,
+    Tiles,
+    Oam,
+    Palettes,
+}
+
+impl Page {
+    const ALL: [Page; 5] = [
+        Page::Map9800,
+        Page::Map9C00,
+        Page::Tiles,
+        Page::Oam,
+        Page::Palettes,
+    ];
+
+    fn map_addr(&self) -> Option<u16> {
+        match self {
+            Page::Map9800 => Some(BG_TILE_MAP_1_ADDR_START),
+            Page::Map9C00 => Some(BG_TILE_MAP_2_ADDR_START),
+            _ => None,
+        }
+    }
+}
+
+/// Pages over what the PPU draws from: both tile maps with the BG viewport
+/// and window outlined, the tile data, the sprite table and the palettes.
+/// Drawn over the screen like the other overlays, so it works with any
+/// video backend.
+pub struct VramViewer {
+    pub visible: bool,
+    fb: FrameBuffer,
+    page: Page,
+    /// Top-left pixel of the shown part of a map or the tile sheet.
+    pan: (usize, usize),
+    /// First sprite of the OAM list.
+    oam_top: usize,
+    /// Selected palette and color; colors 4-7 are the OBJ palette's.
+    palette_cursor: (usize, usize),
+    /// Hex digits of a new color for the selected one.
+    input: Option<String>,
+    lines: Vec<String>,
+}
+
+impl Default for VramViewer {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            fb: FrameBuffer::default(),
+            page: Page::Map9800,
+            pan: (0, 0),
+            oam_top: 0,
+            palette_cursor: (0, 0),
+            input: None,
+            lines: Vec::with_capacity(LINES),
+        }
+    }
+}
+
+impl VramViewer {
+    /// Viewer keys, only while it's shown.
+    pub fn handle_key(&self, sc: Scancode, pressed: bool) -> Option<AppCmd> {
+        if !self.visible || !pressed {
+            return None;
+        }
+
+        let cmd = match sc {
+            Scancode::End => VramCmd::Toggle,
+            Scancode::Escape | Scancode::Backspace => VramCmd::Cancel,
+            Scancode::Return | Scancode::KpEnter => VramCmd::Confirm,
+            Scancode::Up => VramCmd::Move(0, -1),
+            Scancode::Down => VramCmd::Move(0, 1),
+            Scancode::Left => VramCmd::Move(-1, 0),
+            Scancode::Right => VramCmd::Move(1, 0),
+            Scancode::PageUp => VramCmd::Move(0, -PAN_PAGE / PAN_STEP),
+            Scancode::PageDown => VramCmd::Move(0, PAN_PAGE / PAN_STEP),
+            Scancode::LeftBracket => VramCmd::ChangePage(-1),
+            Scancode::RightBracket => VramCmd::ChangePage(1),
+            sc => VramCmd::Digit(crate::memory_viewer::hex_digit(sc)?),
+        };
+
+        Some(AppCmd::VramViewer(cmd))
+    }
+
+    pub fn handle_cmd(&mut self, emu: &mut Emu, cmd: VramCmd) {
+        let ppu = &mut emu.runtime.cpu.clock.bus.io.ppu;
+
+        match cmd {
+            VramCmd::Toggle => {
+                self.visible = !self.visible;
+                self.input = None;
+
+                if self.visible {
+                    self.show_viewport(ppu);
+                }
+            }
+            VramCmd::Cancel => match self.input {
+                Some(_) => self.input = None,
+                None => self.visible = false,
+            },
+            VramCmd::Confirm => {
+                if let Some(text) = self.input.take() {
+                    let Ok(value) = u16::from_str_radix(&text, 16) else {
+                        return;
+                    };
+                    let (palette, color) = self.palette_cursor;
+                    ppu.lcd
+                        .cgb_palette
+                        .write_color(palette as u8, color % 4, color >= 4, value);
+                }
+            }
+            VramCmd::ChangePage(delta) => {
+                let i = Page::ALL.iter().position(|x| *x == self.page).unwrap_or(0);
+                let len = Page::ALL.len() as i32;
+                self.page = Page::ALL[(i as i32 + delta).rem_euclid(len) as usize];
+                self.input = None;
+                self.show_viewport(ppu);
+            }
+            VramCmd::Move(dx, dy) => {
+                self.input = None;
+                self.move_by(ppu, dx, dy);
+            }
+            VramCmd::Digit(digit) => {
+                if self.page != Page::Palettes || !ppu.lcd.is_cgb_mode() {
+                    return;
+                }
+
+                let text = self.input.get_or_insert_with(String::new);
+
+                if text.len() < 4 {
+                    let c = char::from_digit(digit as u32, 16).unwrap_or('0');
+                    text.push(c.to_ascii_uppercase());
+                }
+            }
+        }
+    }
+
+    /// Redraws the current page and returns its buffer.
+    pub fn draw(&mut self, emu: &Emu, overlay: &Overlay) -> &FrameBuffer {
+        let ppu = &emu.runtime.cpu.clock.bus.io.ppu;
+        self.lines.clear();
+
+        match self.page {
+            Page::Map9800 | Page::Map9C00 | Page::Tiles => self.draw_image(ppu, overlay),
+            Page::Oam => self.draw_oam(ppu, overlay),
+            Page::Palettes => self.draw_palettes(ppu, overlay),
+        }
+
+        &self.fb
+    }
+
+    fn draw_image(&mut self, ppu: &Ppu, overlay: &Overlay) {
+        let lcd = &ppu.lcd;
+        let (image, header) = match self.page.map_addr() {
+            Some(addr) => {
+                let mut header = format!("MAP {addr:04X}");
+
+                if lcd.control.get_bg_map_area() == addr {
+                    header.push_str(" BG");
+                }
+
+                if lcd.control.get_win_map_area() == addr && lcd.control.is_win_enabled() {
+                    header.push_str(" WIN");
+                }
+
+                write!(header, " SCX {:02X} SCY {:02X}", lcd.scroll_x, lcd.scroll_y).ok();
+                (ppu.render_tilemap(addr), header)
+            }
+            None => {
+                let area = lcd.control.get_bgw_data_area();
+                (ppu.render_tiles(), format!("TILES BG DATA {area:04X}"))
+            }
+        };
+
+        self.pan = clamp_pan(&image, self.pan);
+        let hint = "ARROWS PGUP PGDN:PAN [ ]:PAGE END:CLOSE";
+        self.fill_text(overlay, header, String::new(), Vec::new(), hint);
+        blit(&mut self.fb, &image, self.pan);
+
+        let Some(addr) = self.page.map_addr() else {
+            return;
+        };
+
+        if lcd.control.get_bg_map_area() == addr {
+            let viewport = (
+                lcd.scroll_x as usize,
+                lcd.scroll_y as usize,
+                LCD_X_RES as usize,
+                LCD_Y_RES as usize,
+            );
+            outline(&mut self.fb, self.pan, viewport, PixelColor::new(255, 0, 0));
+        }
+
+        if lcd.control.get_win_map_area() == addr && lcd.control.is_win_enabled() {
+            // the window starts at the map's corner, WX-7 and WY on screen,
+            // and is cut off by the right and bottom edges
+            let x = lcd.window.x as i32 - 7;
+            let y = lcd.window.y as usize;
+
+            if x < LCD_X_RES as i32 && y < LCD_Y_RES as usize {
+                let area = (
+                    (-x).max(0) as usize,
+                    0,
+                    LCD_X_RES as usize - x.max(0) as usize,
+                    LCD_Y_RES as usize - y,
+                );
+                outline(&mut self.fb, self.pan, area, PixelColor::new(0, 255, 0));
+            }
+        }
+    }
+
+    fn draw_oam(&mut self, ppu: &Ppu, overlay: &Overlay) {
+        let cgb = ppu.lcd.is_cgb_mode();
+        let height = ppu.lcd.control.get_obj_height();
+        let columns = "  NO   X   Y TL AT PAL BANK FLIP PRIO".to_string();
+        let mut rows = Vec::with_capacity(OAM_ROWS);
+
+        for (i, entry) in ppu
+            .oam_ram
+            .entries
+            .iter()
+            .enumerate()
+            .skip(self.oam_top)
+            .take(OAM_ROWS)
+        {
+            let flags = entry.flags;
+            // on screen when any of its pixels can be
+            let shown = (1..LCD_X_RES + 8).contains(&entry.x)
+                && (17 - height..LCD_Y_RES + 16).contains(&entry.y);
+            let (palette, bank) = if cgb {
+                let bank = flags.read_cgb_vram_bank();
+                (flags.read_cgb_palette(), char::from(b'0' + bank))
+            } else {
+                (flags.is_second_dmg_palette() as u8, '-')
+            };
+
+            rows.push(format!(
+                "{}{i:>3} {:>3} {:>3} {:02X} {:02X}  {palette}    {bank}   {}{}  {}",
+                if shown { '●' } else { '○' },
+                entry.x,
+                entry.y,
+                entry.tile_index,
+                flags.bits(),
+                if flags.is_x_flip() { 'X' } else { '-' },
+                if flags.is_y_flip() { 'Y' } else { '-' },
+                if flags.is_bgw_priority() { "BG" } else { "OBJ" },
+            ));
+        }
+
+        let header = format!("OAM 8X{height}");
+        let hint = "UP DOWN PGUP PGDN:SCROLL [ ]:PAGE";
+        self.fill_text(overlay, header, columns, rows, hint);
+    }
+
+    fn draw_palettes(&mut self, ppu: &Ppu, overlay: &Overlay) {
+        let lcd = &ppu.lcd;
+        let cgb = lcd.is_cgb_mode();
+        let (palette, color) = self.palette_cursor;
+        let is_obj = color >= 4;
+        let name = if is_obj { "OBJ" } else { "BG" };
+
+        let (header, status) = if cgb {
+            let value = lcd.cgb_palette.read_color(palette as u8, color % 4, is_obj);
+            let rgb = (value & 0x1F, value >> 5 & 0x1F, value >> 10 & 0x1F);
+            let status = match &self.input {
+                Some(text) => format!("{name}{palette}.{} EDIT {text}■", color % 4),
+                None => format!(
+                    "{name}{palette}.{} {value:04X} R{} G{} B{}",
+                    color % 4,
+                    rgb.0,
+                    rgb.1,
+                    rgb.2
+                ),
+            };
+            ("CGB PALETTES".to_string(), status)
+        } else {
+            let registers = [
+                ("BGP", lcd.dmg_palette.bg_palette),
+                ("OBP0", lcd.dmg_palette.obj_palette[0]),
+                ("OBP1", lcd.dmg_palette.obj_palette[1]),
+            ];
+            let status = registers
+                .iter()
+                .map(|(name, value)| format!("{name} {value:02X}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            ("DMG PALETTES".to_string(), status)
+        };
+
+        let hint = if cgb {
+            "ARROWS:SELECT 0-F ENTER:EDIT [ ]:PAGE"
+        } else {
+            "[ ]:PAGE END:CLOSE"
+        };
+        self.fill_text(overlay, header, status, Vec::new(), hint);
+
+        let size = FontSize::Font3x4;
+
+        for row in 0..8 {
+            for (side, x) in PALETTE_COLUMNS_X.iter().enumerate() {
+                let y = SWATCHES_Y + row * SWATCH_STEP;
+                let is_obj = side == 1;
+                let label = match (cgb, is_obj, row) {
+                    (true, false, _) => format!("BG{row}"),
+                    (true, true, _) => format!("OB{row}"),
+                    (false, false, 0) => "BGP".to_string(),
+                    (false, true, 0 | 1) => format!("OBP{row}"),
+                    _ => continue,
+                };
+                fill_line(&mut self.fb, &label, overlay.text_color, *x, y + 4, size);
+
+                for i in 0..4 {
+                    let color = if cgb {
+                        PixelColor::from_bgr555(lcd.cgb_palette.read_color(row as u8, i, is_obj))
+                    } else if is_obj && row == 1 {
+                        lcd.dmg_palette.sp2_colors[i]
+                    } else if is_obj {
+                        lcd.dmg_palette.sp1_colors[i]
+                    } else {
+                        lcd.dmg_palette.bg_colors[i]
+                    };
+                    let sx = x + 16 + i * SWATCH_STEP;
+
+                    if cgb && self.palette_cursor == (row, side * 4 + i) {
+                        let (w, h) = (sx + SWATCH_SIZE + 1, y + SWATCH_SIZE + 1);
+                        fill_rect(&mut self.fb, w, h, overlay.text_color, sx - 1, y - 1);
+                    }
+
+                    let (w, h) = (sx + SWATCH_SIZE, y + SWATCH_SIZE);
+                    fill_rect(&mut self.fb, w, h, color, sx, y);
+                }
+            }
+        }
+    }
+
+    /// Clears the buffer to a header, a status line, `body` and a key hint.
+    fn fill_text(
+        &mut self,
+        overlay: &Overlay,
+        header: String,
+        status: String,
+        body: Vec<String>,
+        hint: &str,
+    ) {
+        self.lines.push(header);
+        self.lines.push(status);
+        self.lines.extend(body);
+        self.lines.resize(LINES - 1, String::new());
+        self.lines.push(hint.to_string());
+
+        let lines: Vec<&str> = self.lines.iter().map(|x| x.as_str()).collect();
+        overlay.fill_debugger(&mut self.fb, &lines, &[], 0);
+    }
+
+    fn move_by(&mut self, ppu: &Ppu, dx: i32, dy: i32) {
+        match self.page {
+            Page::Map9800 | Page::Map9C00 | Page::Tiles => {
+                let x = self.pan.0 as i32 + dx * PAN_STEP;
+                let y = self.pan.1 as i32 + dy * PAN_STEP;
+                // clamped to the picture when drawn
+                self.pan = (x.max(0) as usize, y.max(0) as usize);
+            }
+            Page::Oam => {
+                let max = (OAM_ENTRIES_COUNT - OAM_ROWS) as i32;
+                self.oam_top = (self.oam_top as i32 + dy).clamp(0, max) as usize;
+            }
+            Page::Palettes if ppu.lcd.is_cgb_mode() => {
+                let (palette, color) = self.palette_cursor;
+                self.palette_cursor = (
+                    (palette as i32 + dy.signum()).rem_euclid(8) as usize,
+                    (color as i32 + dx).rem_euclid(8) as usize,
+                );
+            }
+            Page::Palettes => {}
+        }
+    }
+
+    /// Pans a map page to where the BG or window is drawn from.
+    fn show_viewport(&mut self, ppu: &Ppu) {
+        let lcd = &ppu.lcd;
+
+        self.pan = match self.page.map_addr() {
+            Some(addr) if lcd.control.get_bg_map_area() == addr => {
+                (lcd.scroll_x as usize, lcd.scroll_y as usize)
+            }
+            _ => (0, 0),
+        };
+    }
+}
+
+fn clamp_pan(image: &PixelImage, (x, y): (usize, usize)) -> (usize, usize) {
+    (
+        x.min(image.width.saturating_sub(LCD_X_RES as usize)),
+        y.min(image.height.saturating_sub(IMAGE_HEIGHT)),
+    )
+}
+
+/// Copies the part of `image` at `pan` to the picture area.
+fn blit(fb: &mut FrameBuffer, image: &PixelImage, pan: (usize, usize)) {
+    let width = image.width.min(LCD_X_RES as usize);
+    let height = image.height.min(IMAGE_HEIGHT);
+
+    for y in 0..height {
+        for x in 0..width {
+            let offset = (IMAGE_Y + y) * FrameBuffer::PITCH + x * FrameBuffer::BYTES_PER_PIXEL;
+            draw_color(fb, offset, image.get(pan.0 + x, pan.1 + y));
+        }
+    }
+}
+
+/// Draws the edges of a tile map area, wrapping around the map the way
+/// scrolling does, where they fall in the picture area.
+fn outline(
+    fb: &mut FrameBuffer,
+    pan: (usize, usize),
+    (x, y, w, h): (usize, usize, usize, usize),
+    color: PixelColor,
+) {
+    let edges = (0..w)
+        .flat_map(|i| [(x + i, y), (x + i, y + h - 1)])
+        .chain((0..h).flat_map(|i| [(x, y + i), (x + w - 1, y + i)]));
+
+    for (mx, my) in edges {
+        let sx = (mx % TILEMAP_SIZE).wrapping_sub(pan.0);
+        let sy = (my % TILEMAP_SIZE).wrapping_sub(pan.1);
+
+        if sx < LCD_X_RES as usize && sy < IMAGE_HEIGHT {
+            let offset = (IMAGE_Y + sy) * FrameBuffer::PITCH + sx * FrameBuffer::BYTES_PER_PIXEL;
+            draw_color(fb, offset, color);
+        }
+    }
+}
@@ -140,6 +140,25 @@ pub fn parse_vram(s: &str) -> Result<(u8, u16, u16), String> {
     Ok((bank, addr, len))
 }
 
+/// Parse a `--range` argument `A:B`: both hex (optional `0x`/`$` prefix),
+/// B inclusive. Returns `(start, end)`.
+pub fn parse_range(s: &str) -> Result<(u16, u16), String> {
+    let (start_s, end_s) = s
+        .split_once(':')
+        .ok_or_else(|| format!("invalid range '{s}' (use A:B)"))?;
+
+    let start = parse_u16_hex(start_s).ok_or_else(|| format!("invalid range start '{start_s}'"))?;
+    let end = parse_u16_hex(end_s).ok_or_else(|| format!("invalid range end '{end_s}'"))?;
+
+    if end < start {
+        return Err(format!(
+            "range end ${end:04X} is before its start ${start:04X}"
+        ));
+    }
+
+    Ok((start, end))
+}
+
 fn parse_u16_hex(s: &str) -> Option<u16> {
     let s = s
         .trim_start_matches("0x")
@@ -170,6 +189,14 @@ mod tests {
         assert!(parse_vram("9C00").is_err());
     }
 
+    #[test]
+    fn range_specs() {
+        assert_eq!(parse_range("4000:7FFF"), Ok((0x4000, 0x7FFF)));
+        assert_eq!(parse_range("$150:0x15F"), Ok((0x150, 0x15F)));
+        assert!(parse_range("200:100").is_err());
+        assert!(parse_range("200").is_err());
+    }
+
     #[test]
     fn timeouts() {
         assert_eq!(parse_timeout("1.5"), Ok(Duration::from_secs_f64(1.5)));
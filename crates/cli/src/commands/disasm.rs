@@ -0,0 +1,143 @@
+//! `oxgbc-cli disasm <ROM>` — print an RGBDS listing of a ROM that assembles
+//! back to the same bytes.
+//!
+//! Code is told from data by following control flow from the entry points
+//! (`core::debugger::disasm`), so banked code only reached through a bank
+//! switch the tracer can't see comes out as `db` lines, unless a code/data
+//! log from `run --cdl` shows it ran. Labels come from the ROM's `.sym` file
+//! when there is one.
+
+use crate::args::{next_val, parse_args, parse_range, print_common_usage, CommonOpts};
+use crate::rom::load_symbols;
+use core::debugger::disasm::Disassembly;
+use core::debugger::{parse_number, CodeDataLog};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+struct DisasmOpts {
+    rom: PathBuf,
+    bank: Option<u16>,
+    /// `--range`, end inclusive.
+    range: Option<(u16, u16)>,
+    sym: Option<PathBuf>,
+    /// `--cdl`: a code/data log of a run to take code from.
+    cdl: Option<PathBuf>,
+}
+
+pub fn cmd_disasm(args: &[String]) -> Result<ExitCode, String> {
+    let Some(opts) = parse(args)? else {
+        print_usage();
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let rom = std::fs::read(&opts.rom)
+        .map_err(|e| format!("failed to read '{}': {e}", opts.rom.display()))?;
+    let symbols = load_symbols(&opts.rom, opts.sym.as_deref())?;
+    let cdl = match &opts.cdl {
+        Some(path) => {
+            let bytes = std::fs::read(path)
+                .map_err(|e| format!("failed to read '{}': {e}", path.display()))?;
+            CodeDataLog::from_bytes(&bytes, &rom)?
+        }
+        None => CodeDataLog::default(),
+    };
+    let disasm = Disassembly::with_cdl(&rom, cdl.flags());
+    let sections = get_sections(&disasm, &opts)?;
+
+    println!("; {}", opts.rom.display());
+    print!("{}", disasm.listing(&sections, &symbols));
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Parse `disasm`'s arguments; `None` means help was requested.
+fn parse(args: &[String]) -> Result<Option<DisasmOpts>, String> {
+    let mut common = CommonOpts::default();
+    let mut rom: Option<PathBuf> = None;
+    let mut bank: Option<u16> = None;
+    let mut range: Option<(u16, u16)> = None;
+    let mut sym: Option<PathBuf> = None;
+    let mut cdl: Option<PathBuf> = None;
+
+    let help = parse_args(args, &mut common, |arg, it| {
+        match arg {
+            "--bank" => {
+                let v = next_val(it, "--bank")?;
+                bank = Some(parse_number(&v).map_err(|_| format!("invalid bank '{v}'"))?);
+            }
+            "--range" => range = Some(parse_range(&next_val(it, "--range")?)?),
+            "--sym" => sym = Some(PathBuf::from(next_val(it, "--sym")?)),
+            "--cdl" => cdl = Some(PathBuf::from(next_val(it, "--cdl")?)),
+            other if other.starts_with('-') => return Err(format!("unknown flag '{other}'")),
+            other if rom.is_none() => rom = Some(PathBuf::from(other)),
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+        Ok(())
+    })?;
+    if help {
+        return Ok(None);
+    }
+
+    Ok(Some(DisasmOpts {
+        rom: rom.ok_or("missing <ROM> path")?,
+        bank,
+        range,
+        sym,
+        cdl,
+    }))
+}
+
+/// Every bank by default; `--range` without `--bank` takes the bank from
+/// the address (bank 1 for $4000-$7FFF).
+fn get_sections(disasm: &Disassembly, opts: &DisasmOpts) -> Result<Vec<(u16, Range<u16>)>, String> {
+    let bank = match (opts.bank, opts.range) {
+        (None, None) => {
+            return Ok((0..disasm.bank_count())
+                .map(|x| (x, disasm.bank_range(x)))
+                .collect())
+        }
+        (Some(bank), _) => bank,
+        (None, Some((start, _))) => u16::from(start >= 0x4000),
+    };
+
+    if bank >= disasm.bank_count() {
+        return Err(format!(
+            "bank {bank} is out of range (the ROM has {})",
+            disasm.bank_count()
+        ));
+    }
+
+    let bank_range = disasm.bank_range(bank);
+    let range = match opts.range {
+        Some((start, end)) if bank_range.contains(&start) && bank_range.contains(&end) => {
+            start..end + 1
+        }
+        Some((start, end)) => {
+            return Err(format!(
+                "range ${start:04X}:${end:04X} is outside bank {bank} (${:04X}-${:04X})",
+                bank_range.start,
+                bank_range.end - 1
+            ))
+        }
+        None => bank_range,
+    };
+
+    Ok(vec![(bank, range)])
+}
+
+/// `disasm`'s full help: synopsis, common options, own flags.
+pub fn print_usage() {
+    eprintln!("USAGE:  oxgbc-cli disasm <ROM> [options]\n");
+    print_common_usage();
+    print_options();
+}
+
+/// Only `disasm`'s option block (also part of the global usage).
+pub fn print_options() {
+    eprintln!("disasm OPTIONS:  (RGBDS listing on stdout)");
+    eprintln!("  --bank <N>               only this ROM bank (default: all)");
+    eprintln!("  --range <A:B>            only addresses A to B, hex, within one bank");
+    eprintln!("  --sym <FILE>             labels (default: <ROM>.sym if present)");
+    eprintln!("  --cdl <FILE>             take code from a `run --cdl` log\n");
+}
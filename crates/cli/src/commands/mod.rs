@@ -2,9 +2,15 @@
 //! sibling files and re-export their entry point below.
 
 pub mod check;
+pub mod disasm;
+pub mod netplay;
 pub mod run;
 pub mod score;
+pub mod search;
 
 pub use check::cmd_check;
+pub use disasm::cmd_disasm;
+pub use netplay::cmd_netplay;
 pub use run::cmd_run;
 pub use score::cmd_score;
+pub use search::cmd_search;
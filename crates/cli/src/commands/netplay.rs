@@ -0,0 +1,259 @@
+//! `oxgbc-cli netplay <ROM>` — play a rollback netplay session headless with
+//! scripted input and report whether both sides stayed in sync. Without
+//! `--peer` both players run here, talking over localhost; with it only
+//! `--player` does and the other is a second `netplay` run, possibly on
+//! another machine. `--latency` and `--loss` make a local session behave
+//! like one over a poor network.
+
+use crate::args::{next_val, parse_args, print_common_usage, CommonOpts};
+use crate::exit_code;
+use core::cpu::Cpu;
+use core::emu::netplay::{Netplay, NetplayEvent, NetplayStatus, Transport};
+use core::harness::build_cpu;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_PORT: u16 = 7845;
+const DEFAULT_DELAY: usize = 1;
+const DEFAULT_FRAMES: usize = 600;
+/// How long a side keeps going once done, so the peer gets its last input.
+const LINGER: Duration = Duration::from_secs(1);
+/// Frames each scripted input is held.
+const INPUT_FRAMES: usize = 8;
+
+struct NetplayOpts {
+    common: CommonOpts,
+    rom: PathBuf,
+    player: usize,
+    port: u16,
+    peer: Option<String>,
+    delay: usize,
+    latency: Duration,
+    loss: f32,
+    frames: usize,
+}
+
+struct Side {
+    netplay: Netplay,
+    cpu: Cpu,
+}
+
+pub fn cmd_netplay(args: &[String]) -> Result<ExitCode, String> {
+    let Some(opts) = parse(args)? else {
+        print_usage();
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let rom = core::read_bytes(&opts.rom)?;
+    let mut sides = match &opts.peer {
+        Some(peer) => {
+            let local = format!("0.0.0.0:{}", opts.port);
+            vec![new_side(&opts, &rom, opts.player, &local, peer)?]
+        }
+        None => (0..2)
+            .map(|player| {
+                let local = format!("127.0.0.1:{}", opts.port + player as u16);
+                let peer = format!("127.0.0.1:{}", opts.port + 1 - player as u16);
+                new_side(&opts, &rom, player, &local, &peer)
+            })
+            .collect::<Result<_, _>>()?,
+    };
+
+    let start = Instant::now();
+    let mut done: Option<Instant> = None;
+
+    while done.is_none_or(|x| x.elapsed() < LINGER) {
+        if start.elapsed() > opts.common.timeout {
+            println!("timed out");
+            print_summary(&sides);
+            return Ok(exit_code(false));
+        }
+
+        let mut ran = false;
+
+        for side in sides.iter_mut() {
+            let frame = side.netplay.frame();
+            let pressed = scripted_input(side.netplay.player(), frame);
+            let status = side.netplay.advance(&mut side.cpu, pressed, None);
+            ran |= status == NetplayStatus::Ran;
+
+            for event in side.netplay.take_events() {
+                let player = side.netplay.player() + 1;
+
+                match event {
+                    NetplayEvent::Connected => println!("player {player}: connected"),
+                    NetplayEvent::Desync { frame } => {
+                        println!("player {player}: desync at frame {frame}")
+                    }
+                    NetplayEvent::Ended(reason) => println!("player {player}: ended, {reason}"),
+                }
+            }
+
+            if status == NetplayStatus::Ended {
+                print_summary(&sides);
+                return Ok(exit_code(false));
+            }
+        }
+
+        if done.is_none() && sides.iter().all(|x| x.netplay.frame() >= opts.frames) {
+            done = Some(Instant::now());
+        }
+
+        if !ran {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    let ok = print_summary(&sides);
+    println!("{}", if ok { "PASS" } else { "FAIL" });
+
+    Ok(exit_code(ok))
+}
+
+fn new_side(
+    opts: &NetplayOpts,
+    rom: &[u8],
+    player: usize,
+    local: &str,
+    peer: &str,
+) -> Result<Side, String> {
+    let transport = Transport::bind(local, peer)?.with_conditions(opts.latency, opts.loss);
+    let mut cpu = build_cpu(rom.into(), opts.common.model)?;
+    let netplay = Netplay::new(&mut cpu, player, opts.delay, transport)?;
+
+    Ok(Side { netplay, cpu })
+}
+
+/// Buttons that change every few frames, different for each player.
+fn scripted_input(player: usize, frame: usize) -> u8 {
+    let seed = (frame / INPUT_FRAMES) as u32 ^ ((player as u32 + 1) << 16);
+
+    (seed.wrapping_mul(0x9E37_79B1) >> 24) as u8
+}
+
+/// Prints how each side did, returning whether none desynced.
+fn print_summary(sides: &[Side]) -> bool {
+    for side in sides {
+        let netplay = &side.netplay;
+        let desync = match netplay.desync() {
+            Some(frame) => format!("desync at frame {frame}"),
+            None => "in sync".to_string(),
+        };
+        println!(
+            "player {}: {} frames, {} rollbacks, {desync}",
+            netplay.player() + 1,
+            netplay.frame(),
+            netplay.rollbacks()
+        );
+    }
+
+    sides.iter().all(|x| x.netplay.desync().is_none())
+}
+
+/// Parse `netplay`'s arguments; `None` means help was requested.
+fn parse(args: &[String]) -> Result<Option<NetplayOpts>, String> {
+    let mut common = CommonOpts::default();
+    let mut rom: Option<PathBuf> = None;
+    let mut player = 0;
+    let mut port = DEFAULT_PORT;
+    let mut peer = None;
+    let mut delay = DEFAULT_DELAY;
+    let mut latency = Duration::ZERO;
+    let mut loss = 0.0;
+    let mut frames = DEFAULT_FRAMES;
+
+    let help = parse_args(args, &mut common, |arg, it| {
+        match arg {
+            "--player" => player = parse_player(&next_val(it, "--player")?)?,
+            "--port" => port = parse_count(&next_val(it, "--port")?, "--port")?,
+            "--peer" => peer = Some(next_val(it, "--peer")?),
+            "--delay" => delay = parse_count(&next_val(it, "--delay")?, "--delay")?,
+            "--latency" => {
+                let ms = parse_count(&next_val(it, "--latency")?, "--latency")?;
+                latency = Duration::from_millis(ms);
+            }
+            "--loss" => loss = parse_loss(&next_val(it, "--loss")?)?,
+            "--frames" => frames = parse_count(&next_val(it, "--frames")?, "--frames")?,
+            other if other.starts_with('-') => return Err(format!("unknown flag '{other}'")),
+            other if rom.is_none() => rom = Some(PathBuf::from(other)),
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+        Ok(())
+    })?;
+    if help {
+        return Ok(None);
+    }
+
+    Ok(Some(NetplayOpts {
+        common,
+        rom: rom.ok_or("missing <ROM> path")?,
+        player,
+        port,
+        peer,
+        delay,
+        latency,
+        loss,
+        frames,
+    }))
+}
+
+fn parse_count<T: FromStr>(s: &str, flag: &str) -> Result<T, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("invalid {flag} '{s}'"))
+}
+
+fn parse_player(s: &str) -> Result<usize, String> {
+    match s {
+        "1" => Ok(0),
+        "2" => Ok(1),
+        other => Err(format!("invalid player '{other}' (use 1 or 2)")),
+    }
+}
+
+/// A percentage of packets to drop.
+fn parse_loss(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(pct) if (0.0..=100.0).contains(&pct) => Ok(pct / 100.0),
+        _ => Err(format!("invalid loss '{s}' (a percentage)")),
+    }
+}
+
+/// `netplay`'s full help: synopsis, common options, own flags.
+pub fn print_usage() {
+    eprintln!("USAGE:  oxgbc-cli netplay <ROM> [options]\n");
+    print_common_usage();
+    print_options();
+}
+
+/// Only `netplay`'s option block (also part of the global usage).
+pub fn print_options() {
+    eprintln!("netplay OPTIONS:  (rollback session with scripted input, --timeout caps it)");
+    eprintln!("  --peer <ADDR>            the other side, e.g. 10.0.0.2:7845; without it both");
+    eprintln!("                           players run here over localhost");
+    eprintln!("  --player <1|2>           the player run here with --peer (default: 1)");
+    eprintln!("  --port <N>               local UDP port, N and N+1 without --peer (default: {DEFAULT_PORT})");
+    eprintln!("  --delay <F>              frames of input delay (default: {DEFAULT_DELAY})");
+    eprintln!("  --latency <MS>           added to every packet sent (default: 0)");
+    eprintln!("  --loss <PCT>             packets dropped on purpose (default: 0)");
+    eprintln!("  --frames <N>             frames to play (default: {DEFAULT_FRAMES})\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn option_values() {
+        assert_eq!(parse_player("2"), Ok(1));
+        assert!(parse_player("0").is_err());
+        assert_eq!(parse_loss("25"), Ok(0.25));
+        assert!(parse_loss("101").is_err());
+        assert!(parse_loss("x").is_err());
+        assert_eq!(parse_count::<usize>("100000", "--frames"), Ok(100_000));
+        assert!(parse_count::<u16>("70000", "--port").is_err());
+    }
+}
@@ -4,13 +4,21 @@
 use crate::args::{next_val, parse_args, parse_dump, parse_vram, print_common_usage, CommonOpts};
 use crate::inspect::{dump_memory, dump_ppu, dump_regs, dump_vram, trace};
 use crate::report::{print_result_line, RomResult};
-use crate::rom::{compare_to_reference, save_screenshot};
+use crate::rom::{compare_to_reference, load_symbols, save_screenshot};
+use core::cheats;
 use core::cpu::Cpu;
+#[cfg(feature = "debug")]
+use core::debugger::CodeDataLog;
+use core::debugger::Symbols;
+use core::emu::movie::{self, Movie, MovieMode, MovieSession};
 use core::harness;
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
-/// How the ROM is run and how its pass/fail is decided. The three non-default
+/// How many frames `--timeline` and `--chrome-trace` keep: the last second.
+const TIMELINE_FRAMES: usize = 60;
+
+/// How the ROM is run and how its pass/fail is decided. The non-default
 /// modes are mutually exclusive.
 enum Mode {
     /// Run under the pass/fail protocol detector (the default).
@@ -22,6 +30,12 @@ enum Mode {
     /// `--compare`: run the full timeout, then diff the framebuffer against a
     /// reference PNG.
     Compare { reference: PathBuf, tolerance: u8 },
+    /// `--script`: run the full timeout with a script's hooks, or until the
+    /// script calls `stop()`; a script error fails the run.
+    Script(PathBuf),
+    /// `--movie`: play an input movie from its start state to the end; a
+    /// desync against its state hashes fails the run.
+    Movie(PathBuf),
 }
 
 /// Everything `run` accepts, parsed and validated.
@@ -35,6 +49,18 @@ struct RunOpts {
     ppu: bool,
     dumps: Vec<(u16, u16)>,
     vram_dumps: Vec<(u8, u16, u16)>,
+    /// `--sym`: labels for `--trace`, else the `.sym` next to the ROM if any.
+    sym: Option<PathBuf>,
+    /// `--cdl`: where to save the code/data log of the run.
+    cdl: Option<PathBuf>,
+    /// `--lcov`: where to save the run's coverage of the `.sym`/`.map` labels.
+    lcov: Option<PathBuf>,
+    /// `--profile N`: how many of the hottest routines to report.
+    profile: Option<usize>,
+    /// `--timeline`: where to save the last frames' hardware events as JSON.
+    timeline: Option<PathBuf>,
+    /// `--chrome-trace`: the same events in Chrome's trace format.
+    chrome_trace: Option<PathBuf>,
 }
 
 pub fn cmd_run(args: &[String]) -> Result<ExitCode, String> {
@@ -44,21 +70,30 @@ pub fn cmd_run(args: &[String]) -> Result<ExitCode, String> {
     };
 
     let mut cpu = harness::build_cpu_from_path(&opts.rom, opts.common.model)?;
+    start_coverage(&mut cpu, &opts)?;
+    start_profiler(&mut cpu, &opts)?;
+    start_timeline(&mut cpu, &opts)?;
 
     let passed = match &opts.mode {
         Mode::Detect => run_detect(&mut cpu, &opts),
         Mode::NoDetect => run_no_detect(&mut cpu, &opts),
         Mode::Trace(len) => {
-            trace(&mut cpu, opts.common.timeout, *len);
+            let symbols = load_symbols(&opts.rom, opts.sym.as_deref())?;
+            trace(&mut cpu, opts.common.timeout, *len, &symbols);
             false
         }
         Mode::Compare {
             reference,
             tolerance,
         } => run_compare(&mut cpu, &opts, reference, *tolerance),
+        Mode::Script(path) => run_script(&mut cpu, &opts, path)?,
+        Mode::Movie(path) => run_movie(&mut cpu, &opts, path)?,
     };
 
     inspect_after(&mut cpu, &opts)?;
+    save_coverage(&cpu, &opts)?;
+    print_profile(&cpu, &opts)?;
+    save_timeline(&cpu, &opts)?;
 
     Ok(crate::exit_code(passed))
 }
@@ -77,6 +112,14 @@ fn parse(args: &[String]) -> Result<Option<RunOpts>, String> {
     let mut tolerance: u8 = 0;
     let mut trace_len: Option<usize> = None;
     let mut no_detect = false;
+    let mut sym: Option<PathBuf> = None;
+    let mut cdl: Option<PathBuf> = None;
+    let mut lcov: Option<PathBuf> = None;
+    let mut profile: Option<usize> = None;
+    let mut timeline: Option<PathBuf> = None;
+    let mut chrome_trace: Option<PathBuf> = None;
+    let mut script: Option<PathBuf> = None;
+    let mut movie: Option<PathBuf> = None;
 
     let help = parse_args(args, &mut common, |arg, it| {
         match arg {
@@ -104,6 +147,20 @@ fn parse(args: &[String]) -> Result<Option<RunOpts>, String> {
                 trace_len = Some(n);
             }
             "--no-detect" => no_detect = true,
+            "--sym" => sym = Some(PathBuf::from(next_val(it, "--sym")?)),
+            "--cdl" => cdl = Some(PathBuf::from(next_val(it, "--cdl")?)),
+            "--lcov" => lcov = Some(PathBuf::from(next_val(it, "--lcov")?)),
+            "--profile" => {
+                let v = next_val(it, "--profile")?;
+                let n = v
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid routine count '{v}'"))?;
+                profile = Some(n);
+            }
+            "--timeline" => timeline = Some(PathBuf::from(next_val(it, "--timeline")?)),
+            "--chrome-trace" => chrome_trace = Some(PathBuf::from(next_val(it, "--chrome-trace")?)),
+            "--script" => script = Some(PathBuf::from(next_val(it, "--script")?)),
+            "--movie" => movie = Some(PathBuf::from(next_val(it, "--movie")?)),
             other if other.starts_with('-') => return Err(format!("unknown flag '{other}'")),
             other if rom.is_none() => rom = Some(PathBuf::from(other)),
             other => return Err(format!("unexpected argument '{other}'")),
@@ -114,15 +171,22 @@ fn parse(args: &[String]) -> Result<Option<RunOpts>, String> {
         return Ok(None);
     }
 
-    let mode = match (compare, trace_len, no_detect) {
-        (Some(reference), None, false) => Mode::Compare {
+    let mode = match (compare, trace_len, no_detect, script, movie) {
+        (Some(reference), None, false, None, None) => Mode::Compare {
             reference,
             tolerance,
         },
-        (None, Some(len), false) => Mode::Trace(len),
-        (None, None, true) => Mode::NoDetect,
-        (None, None, false) => Mode::Detect,
-        _ => return Err("--compare, --trace and --no-detect are mutually exclusive".to_string()),
+        (None, Some(len), false, None, None) => Mode::Trace(len),
+        (None, None, true, None, None) => Mode::NoDetect,
+        (None, None, false, Some(path), None) => Mode::Script(path),
+        (None, None, false, None, Some(path)) => Mode::Movie(path),
+        (None, None, false, None, None) => Mode::Detect,
+        _ => {
+            return Err(
+                "--compare, --trace, --no-detect, --script and --movie are mutually exclusive"
+                    .to_string(),
+            )
+        }
     };
 
     Ok(Some(RunOpts {
@@ -135,9 +199,172 @@ fn parse(args: &[String]) -> Result<Option<RunOpts>, String> {
         ppu,
         dumps,
         vram_dumps,
+        sym,
+        cdl,
+        lcov,
+        profile,
+        timeline,
+        chrome_trace,
     }))
 }
 
+/// `--cdl`/`--lcov`: record which ROM bytes the run executes and reads.
+/// The CPU only has the hooks for it in `debug` builds.
+fn start_coverage(cpu: &mut Cpu, opts: &RunOpts) -> Result<(), String> {
+    if opts.cdl.is_none() && opts.lcov.is_none() {
+        return Ok(());
+    }
+
+    if opts.lcov.is_some() && find_symbol_file(opts).is_none() {
+        return Err("--lcov needs labels: a <ROM>.sym or <ROM>.map, or --sym".to_string());
+    }
+
+    #[cfg(feature = "debug")]
+    {
+        let rom = cpu.clock.bus.cart.data.rom();
+        cpu.cdl = Some(Box::new(CodeDataLog::new(rom)));
+        Ok(())
+    }
+
+    #[cfg(not(feature = "debug"))]
+    {
+        let _ = cpu;
+        Err("--cdl and --lcov need a build with `--features debug`".to_string())
+    }
+}
+
+#[cfg(feature = "debug")]
+fn save_coverage(cpu: &Cpu, opts: &RunOpts) -> Result<(), String> {
+    let Some(cdl) = &cpu.cdl else {
+        return Ok(());
+    };
+
+    println!(
+        "coverage: {} code, {} data of {} ROM bytes",
+        cdl.count(CodeDataLog::CODE),
+        cdl.count(CodeDataLog::DATA),
+        cdl.flags().len()
+    );
+
+    if let Some(path) = &opts.cdl {
+        std::fs::write(path, cdl.to_bytes())
+            .map_err(|e| format!("failed to write '{}': {e}", path.display()))?;
+        println!("cdl -> {}", path.display());
+    }
+
+    if let (Some(path), Some(sym)) = (&opts.lcov, find_symbol_file(opts)) {
+        let text = std::fs::read_to_string(&sym)
+            .map_err(|e| format!("failed to read '{}': {e}", sym.display()))?;
+        std::fs::write(path, cdl.to_lcov(&sym.display().to_string(), &text))
+            .map_err(|e| format!("failed to write '{}': {e}", path.display()))?;
+        println!("lcov -> {}", path.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "debug"))]
+fn save_coverage(_cpu: &Cpu, _opts: &RunOpts) -> Result<(), String> {
+    Ok(())
+}
+
+/// `--profile`: attribute the run's cycles to the routines that spent them.
+fn start_profiler(cpu: &mut Cpu, opts: &RunOpts) -> Result<(), String> {
+    if opts.profile.is_none() {
+        return Ok(());
+    }
+
+    #[cfg(feature = "debug")]
+    {
+        cpu.start_profiler();
+        Ok(())
+    }
+
+    #[cfg(not(feature = "debug"))]
+    {
+        let _ = cpu;
+        Err("--profile needs a build with `--features debug`".to_string())
+    }
+}
+
+#[cfg(feature = "debug")]
+fn print_profile(cpu: &Cpu, opts: &RunOpts) -> Result<(), String> {
+    let (Some(profiler), Some(limit)) = (&cpu.profiler, opts.profile) else {
+        return Ok(());
+    };
+
+    let symbols = load_symbols(&opts.rom, opts.sym.as_deref())?;
+    println!("--- profile: {} frames, M-cycles ---", profiler.frames());
+    print!("{}", profiler.report(&symbols, limit));
+
+    Ok(())
+}
+
+#[cfg(not(feature = "debug"))]
+fn print_profile(_cpu: &Cpu, _opts: &RunOpts) -> Result<(), String> {
+    Ok(())
+}
+
+/// `--timeline`/`--chrome-trace`: record what the hardware does, dot by dot.
+fn start_timeline(cpu: &mut Cpu, opts: &RunOpts) -> Result<(), String> {
+    if opts.timeline.is_none() && opts.chrome_trace.is_none() {
+        return Ok(());
+    }
+
+    #[cfg(feature = "debug")]
+    {
+        cpu.clock.bus.io.start_timeline(TIMELINE_FRAMES);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "debug"))]
+    {
+        let _ = cpu;
+        Err("--timeline and --chrome-trace need a build with `--features debug`".to_string())
+    }
+}
+
+#[cfg(feature = "debug")]
+fn save_timeline(cpu: &Cpu, opts: &RunOpts) -> Result<(), String> {
+    let Some(timeline) = &cpu.clock.bus.io.timeline else {
+        return Ok(());
+    };
+
+    let outputs = [
+        (&opts.timeline, timeline.to_json()),
+        (&opts.chrome_trace, timeline.to_chrome_trace()),
+    ];
+
+    for (path, text) in outputs {
+        if let Some(path) = path {
+            std::fs::write(path, text)
+                .map_err(|e| format!("failed to write '{}': {e}", path.display()))?;
+            println!("timeline -> {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "debug"))]
+fn save_timeline(_cpu: &Cpu, _opts: &RunOpts) -> Result<(), String> {
+    Ok(())
+}
+
+/// `--sym`, else the `.sym` or `.map` next to the ROM.
+fn find_symbol_file(opts: &RunOpts) -> Option<PathBuf> {
+    if let Some(path) = &opts.sym {
+        return Some(path.clone());
+    }
+
+    [
+        Symbols::path_for_rom(&opts.rom),
+        opts.rom.with_extension("map"),
+    ]
+    .into_iter()
+    .find(|x| x.exists())
+}
+
 /// Default mode: run under the pass/fail detector, print the result line and
 /// (with `--serial`) the captured serial log.
 fn run_detect(cpu: &mut Cpu, opts: &RunOpts) -> bool {
@@ -185,6 +412,116 @@ fn run_compare(cpu: &mut Cpu, opts: &RunOpts, reference: &Path, tolerance: u8) -
     }
 }
 
+/// `--script`: step with the script's hooks until the timeout or `stop()`.
+/// Its save-state slots live in memory for the run; drawing has no screen
+/// to go to here.
+#[cfg(feature = "script")]
+fn run_script(cpu: &mut Cpu, opts: &RunOpts, path: &Path) -> Result<bool, String> {
+    use core::emu::state::SaveStateCmd;
+    use core::script::{ScriptHost, ScriptRequest};
+    use std::collections::HashMap;
+
+    let mut script = ScriptHost::from_file(path)?;
+    script.on_print(|x| println!("{x}"));
+    script.start(cpu)?;
+    let mut slots: HashMap<usize, Cpu> = HashMap::new();
+    let start = std::time::Instant::now();
+    let mut since_poll: u32 = 0;
+    let mut stopped = false;
+
+    while !stopped && script.error().is_none() {
+        script.step(cpu);
+
+        if script.has_requests() {
+            for request in script.take_requests() {
+                match request {
+                    ScriptRequest::SaveState(SaveStateCmd::Create, slot) => {
+                        slots.insert(slot, cpu.clone());
+                    }
+                    ScriptRequest::SaveState(SaveStateCmd::Load, slot) => match slots.get(&slot) {
+                        Some(state) => *cpu = state.clone(),
+                        None => eprintln!("script: no state in slot {slot}"),
+                    },
+                    ScriptRequest::Stop => stopped = true,
+                }
+            }
+        }
+
+        since_poll += 1;
+        if since_poll >= 1 << 12 {
+            since_poll = 0;
+
+            if start.elapsed() > opts.common.timeout {
+                break;
+            }
+        }
+    }
+
+    if let Some(err) = script.error() {
+        println!("FAIL    {}  (script)  {err}", opts.rom.display());
+        return Ok(false);
+    }
+
+    println!(
+        "RAN     {}  ({:.2}s, script{})",
+        opts.rom.display(),
+        start.elapsed().as_secs_f64(),
+        if stopped { ", stopped" } else { "" }
+    );
+
+    Ok(true)
+}
+
+#[cfg(not(feature = "script"))]
+fn run_script(_cpu: &mut Cpu, _opts: &RunOpts, _path: &Path) -> Result<bool, String> {
+    Err("--script needs a build with `--features script`".to_string())
+}
+
+/// `--movie`: load the movie's start state and cheats, then play its input
+/// to the end, or until a state hash differs or the timeout.
+fn run_movie(cpu: &mut Cpu, opts: &RunOpts, path: &Path) -> Result<bool, String> {
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("failed to read '{}': {e}", path.display()))?;
+    let movie = Movie::decode(&bytes)?;
+    *cpu = movie.start_state(cpu)?.into_cpu(cpu);
+    cpu.clock.bus.cart.set_rtc_clock(movie::RTC_CLOCK);
+    let (patches, writes) = cheats::compile(&movie.cheats);
+    cpu.clock.bus.cart.data.set_patches(patches);
+
+    let frames = movie.len();
+    let mut session = MovieSession::play(movie, cpu, true);
+    let start = std::time::Instant::now();
+
+    while session.mode() == MovieMode::Play && session.desync().is_none() {
+        if start.elapsed() > opts.common.timeout {
+            println!(
+                "FAIL    {}  (movie)  timed out at frame {} of {frames}",
+                opts.rom.display(),
+                session.frame(cpu).unwrap_or_default()
+            );
+            return Ok(false);
+        }
+
+        session.play_frame(cpu, &writes);
+    }
+
+    if let Some(frame) = session.desync() {
+        println!(
+            "FAIL    {}  (movie)  desync at frame {frame} of {frames}",
+            opts.rom.display()
+        );
+        return Ok(false);
+    }
+
+    println!(
+        "PASS    {}  ({:.2}s, movie, {frames} frames)",
+        opts.rom.display(),
+        start.elapsed().as_secs_f64()
+    );
+
+    Ok(true)
+}
+
 /// Post-run inspection: screenshot, memory/VRAM hex dumps, PPU and CPU state.
 fn inspect_after(cpu: &mut Cpu, opts: &RunOpts) -> Result<(), String> {
     if let Some(path) = &opts.screenshot {
@@ -230,8 +567,25 @@ pub fn print_options() {
     eprintln!("                           repeatable, e.g. --dump C000:8)");
     eprintln!("  --vram <B:ADDR[:LEN]>    hex-dump VRAM bank B directly (no mode-3 blocking;");
     eprintln!("                           repeatable, e.g. --vram 1:9C00:32)");
-    eprintln!("  --ppu                    print PPU registers, window state, and OAM after the run");
+    eprintln!(
+        "  --ppu                    print PPU registers, window state, and OAM after the run"
+    );
     eprintln!("  --trace <N>              record the last N instructions (freezes on a hang)");
+    eprintln!("  --sym <FILE>             labels for --trace and --profile (default: <ROM>.sym");
+    eprintln!("                           if present)");
+    eprintln!("  --cdl <FILE>             save a code/data log of the ROM bytes the run used");
+    eprintln!("                           (needs a `--features debug` build)");
+    eprintln!("  --lcov <FILE>            save lcov coverage of the --sym/.sym/.map labels");
+    eprintln!("  --profile <N>            report the N routines that took the most cycles,");
+    eprintln!("                           per frame and in total (needs a debug build)");
+    eprintln!("  --timeline <FILE>        save the last {TIMELINE_FRAMES} frames' hardware events (PPU modes,");
+    eprintln!("                           interrupts, DMA, LCD register writes) as JSON");
+    eprintln!("  --chrome-trace <FILE>    the same in Chrome's trace format, for Perfetto");
+    eprintln!("  --script <FILE>          run with a Rhai script's frame, exec and write hooks");
+    eprintln!("                           until the timeout or its stop() (needs a build with");
+    eprintln!("                           `--features script`)");
+    eprintln!("  --movie <FILE>           play an input movie of the ROM to its end; fails on a");
+    eprintln!("                           desync with the recorded state hashes");
     eprintln!("  --compare <PNG>          diff the final framebuffer against a reference PNG");
     eprintln!("  --tolerance <N>          per-channel diff allowed by --compare (default 0)\n");
 }
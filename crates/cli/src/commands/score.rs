@@ -112,7 +112,7 @@ pub fn print_usage() {
 pub fn print_options() {
     eprintln!("score OPTIONS:  (regenerate the project scoreboards; run from repo root)");
     eprintln!("  [SUITE...]               suites to score (default all): blargg mooneye same-suite");
-    eprintln!("  --out <DIR>              output dir (default: {DEFAULT_OUT})");
+    eprintln!("  --out <DIR>              output dir (default: {DEFAULT_OUT})\n");
 }
 
 /// Resolve the requested suite names (empty = all), erroring on an unknown one.
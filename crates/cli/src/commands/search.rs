@@ -0,0 +1,207 @@
+//! `oxgbc-cli search <ROM>` — find the RAM holding a value, the way a cheat
+//! finder does: snapshot WRAM, HRAM and cartridge RAM, then run some frames
+//! (optionally holding buttons) and keep only the addresses that pass a
+//! filter, step after step. What's left is printed with a GameShark code
+//! that freezes it.
+
+use crate::args::{next_val, parse_args, print_common_usage, CommonOpts};
+use core::auxiliary::joypad::{Joypad, JoypadButton};
+use core::cheats::search::{Filter, RamSearch, ValueKind};
+use core::harness::{build_cpu_from_path, run_frames};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const DEFAULT_BOOT_FRAMES: usize = 60;
+const DEFAULT_LIMIT: usize = 20;
+
+/// `--step FRAMES:FILTER[:BUTTONS]`
+#[derive(Debug, PartialEq)]
+struct Step {
+    frames: usize,
+    filter: Filter,
+    buttons: Vec<JoypadButton>,
+}
+
+struct SearchOpts {
+    common: CommonOpts,
+    rom: PathBuf,
+    kind: ValueKind,
+    /// Frames to run before the first snapshot.
+    boot_frames: usize,
+    steps: Vec<Step>,
+    limit: usize,
+}
+
+pub fn cmd_search(args: &[String]) -> Result<ExitCode, String> {
+    let Some(opts) = parse(args)? else {
+        print_usage();
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let mut cpu = build_cpu_from_path(&opts.rom, opts.common.model)?;
+    run_frames(&mut cpu, opts.boot_frames);
+
+    let mut search = RamSearch::new(&cpu.clock.bus, opts.kind);
+    println!("{} {} candidates", search.len(), opts.kind.name());
+
+    for step in opts.steps.iter() {
+        set_buttons(&mut cpu.clock.bus.io.joypad, &step.buttons, true);
+        run_frames(&mut cpu, step.frames);
+        set_buttons(&mut cpu.clock.bus.io.joypad, &step.buttons, false);
+
+        search.filter(&cpu.clock.bus, step.filter);
+        println!(
+            "+{} frames, {}: {} candidates",
+            step.frames,
+            step.filter,
+            search.len()
+        );
+    }
+
+    for result in search.results().take(opts.limit) {
+        let cheat = search.to_cheat(result.location, result.value);
+        println!(
+            "{}  {:>5} (was {:>5})  {}",
+            result.location, result.value, result.prev, cheat.code
+        );
+    }
+
+    if search.len() > opts.limit {
+        println!("... {} more", search.len() - opts.limit);
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn set_buttons(joypad: &mut Joypad, buttons: &[JoypadButton], pressed: bool) {
+    for button in buttons {
+        joypad.handle(*button, pressed);
+    }
+}
+
+/// Parse `search`'s arguments; `None` means help was requested.
+fn parse(args: &[String]) -> Result<Option<SearchOpts>, String> {
+    let mut common = CommonOpts::default();
+    let mut rom: Option<PathBuf> = None;
+    let mut kind = ValueKind::default();
+    let mut boot_frames = DEFAULT_BOOT_FRAMES;
+    let mut steps = Vec::new();
+    let mut limit = DEFAULT_LIMIT;
+
+    let help = parse_args(args, &mut common, |arg, it| {
+        match arg {
+            "--type" => kind = ValueKind::parse(&next_val(it, "--type")?)?,
+            "--frames" => boot_frames = parse_count(&next_val(it, "--frames")?, "--frames")?,
+            "--step" => steps.push(parse_step(&next_val(it, "--step")?)?),
+            "--limit" => limit = parse_count(&next_val(it, "--limit")?, "--limit")?,
+            other if other.starts_with('-') => return Err(format!("unknown flag '{other}'")),
+            other if rom.is_none() => rom = Some(PathBuf::from(other)),
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+        Ok(())
+    })?;
+    if help {
+        return Ok(None);
+    }
+
+    Ok(Some(SearchOpts {
+        common,
+        rom: rom.ok_or("missing <ROM> path")?,
+        kind,
+        boot_frames,
+        steps,
+        limit,
+    }))
+}
+
+fn parse_count(s: &str, flag: &str) -> Result<usize, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("invalid {flag} '{s}'"))
+}
+
+fn parse_step(s: &str) -> Result<Step, String> {
+    let mut parts = s.splitn(3, ':');
+    let (Some(frames), Some(filter)) = (parts.next(), parts.next()) else {
+        return Err(format!("invalid step '{s}' (use FRAMES:FILTER[:BUTTONS])"));
+    };
+    let buttons = match parts.next() {
+        Some(buttons) => buttons
+            .split(',')
+            .map(parse_button)
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(Step {
+        frames: parse_count(frames, "step frames")?,
+        filter: Filter::parse(filter)?,
+        buttons,
+    })
+}
+
+fn parse_button(s: &str) -> Result<JoypadButton, String> {
+    match s.trim().to_lowercase().as_str() {
+        "start" => Ok(JoypadButton::Start),
+        "select" => Ok(JoypadButton::Select),
+        "a" => Ok(JoypadButton::A),
+        "b" => Ok(JoypadButton::B),
+        "up" => Ok(JoypadButton::Up),
+        "down" => Ok(JoypadButton::Down),
+        "left" => Ok(JoypadButton::Left),
+        "right" => Ok(JoypadButton::Right),
+        other => Err(format!("unknown button '{other}'")),
+    }
+}
+
+/// `search`'s full help: synopsis, common options, own flags.
+pub fn print_usage() {
+    eprintln!("USAGE:  oxgbc-cli search <ROM> [options]\n");
+    print_common_usage();
+    print_options();
+}
+
+/// Only `search`'s option block (also part of the global usage).
+pub fn print_options() {
+    eprintln!("search OPTIONS:  (RAM search over WRAM, HRAM and cart RAM)");
+    eprintln!("  --type <t>               u8|u16|bcd8|bcd16 (default: u8)");
+    eprintln!("  --frames <N>             frames before the first snapshot (default: {DEFAULT_BOOT_FRAMES})");
+    eprintln!("  --step <F:FILTER[:BTNS]> run F frames holding BTNS (e.g. a,start), then filter;");
+    eprintln!("                           FILTER is an op vs the last snapshot (`>` increased,");
+    eprintln!("                           `!=` changed) or vs a value (`==5`, `<$10`); repeatable");
+    eprintln!("  --limit <N>              results to print (default: {DEFAULT_LIMIT})\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::debugger::CmpOp;
+
+    #[test]
+    fn step_specs() {
+        assert_eq!(
+            parse_step("30:>:a,up"),
+            Ok(Step {
+                frames: 30,
+                filter: Filter::new(CmpOp::Gt, None),
+                buttons: vec![JoypadButton::A, JoypadButton::Up],
+            })
+        );
+        assert_eq!(
+            parse_step("1:==$10"),
+            Ok(Step {
+                frames: 1,
+                filter: Filter::new(CmpOp::Eq, Some(0x10)),
+                buttons: Vec::new(),
+            })
+        );
+        assert!(parse_step("30").is_err());
+        assert!(parse_step("30:>:jump").is_err());
+    }
+
+    #[test]
+    fn counts() {
+        assert_eq!(parse_count("100000", "--frames"), Ok(100_000));
+        assert!(parse_count("-1", "--limit").is_err());
+    }
+}
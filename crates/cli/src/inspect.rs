@@ -2,6 +2,7 @@
 //! memory hex dump, and an instruction tracer.
 
 use core::cpu::Cpu;
+use core::debugger::{get_bank_at, Symbols};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
@@ -128,8 +129,9 @@ const SELF_LOOP_HANG: u32 = 4;
 /// then print it. The buffer freezes on a detected self-loop so it captures the
 /// path *into* a hang instead of `len` copies of the loop; otherwise it prints
 /// the last `len` instructions at `timeout`. A debugging aid, not a pass/fail run.
-pub fn trace(cpu: &mut Cpu, timeout: Duration, len: usize) {
-    let mut ring: VecDeque<(u16, [u8; 3])> = VecDeque::with_capacity(len);
+/// Each line is labelled from `symbols` by the bank mapped when it ran.
+pub fn trace(cpu: &mut Cpu, timeout: Duration, len: usize, symbols: &Symbols) {
+    let mut ring: VecDeque<(u16, Option<u16>, [u8; 3])> = VecDeque::with_capacity(len);
     let start = Instant::now();
     let mut steps: u64 = 0;
     let mut self_loops: u32 = 0;
@@ -145,7 +147,7 @@ pub fn trace(cpu: &mut Cpu, timeout: Duration, len: usize) {
         if ring.len() == len {
             ring.pop_front();
         }
-        ring.push_back((pc, ops));
+        ring.push_back((pc, get_bank_at(cpu, pc), ops));
 
         cpu.step();
         steps += 1;
@@ -179,7 +181,14 @@ pub fn trace(cpu: &mut Cpu, timeout: Duration, len: usize) {
         "--- trace: {} instr, {steps} steps, stopped on {reason} ---",
         ring.len()
     );
-    for (pc, ops) in &ring {
-        println!("{pc:04X}: {:02X} {:02X} {:02X}", ops[0], ops[1], ops[2]);
+    for (pc, bank, ops) in &ring {
+        let label = symbols
+            .describe_in(*bank, *pc)
+            .map(|x| format!("  {x}"))
+            .unwrap_or_default();
+        println!(
+            "{pc:04X}: {:02X} {:02X} {:02X}{label}",
+            ops[0], ops[1], ops[2]
+        );
     }
 }
@@ -2,12 +2,15 @@
 //!
 //! It drives `core::harness` (the same boot + pass/fail detection the
 //! integration tests use) so an arbitrary ROM can be run outside `cargo test`,
-//! screenshotted, and batch-scored. Three commands:
+//! screenshotted, and batch-scored. Six commands:
 //!
 //! ```text
-//! oxgbc-cli run   <ROM> [--model ..] [--timeout ..] [--protocol ..] [--no-detect] [--screenshot P] [--serial]
+//! oxgbc-cli run   <ROM> [--model ..] [--timeout ..] [--protocol ..] [--no-detect] [--movie F] [--screenshot P] [--serial]
 //! oxgbc-cli check <DIR> [--model ..] [--timeout ..] [--protocol ..] [-r] [--exclude G] [--json] [--screenshot-dir D]
 //! oxgbc-cli score [SUITE...] [--out DIR] [--model ..] [--timeout ..]
+//! oxgbc-cli disasm <ROM> [--bank N] [--range A:B] [--sym FILE] [--cdl FILE]
+//! oxgbc-cli search <ROM> [--type ..] [--frames N] [--step F:FILTER[:BTNS]]... [--limit N]
+//! oxgbc-cli netplay <ROM> [--peer ADDR] [--player N] [--port P] [--delay F] [--latency MS] [--loss PCT] [--frames N]
 //! ```
 
 mod args;
@@ -16,7 +19,7 @@ mod inspect;
 mod report;
 mod rom;
 
-use crate::commands::{cmd_check, cmd_run, cmd_score};
+use crate::commands::{cmd_check, cmd_disasm, cmd_netplay, cmd_run, cmd_score, cmd_search};
 use std::process::ExitCode;
 
 fn main() -> ExitCode {
@@ -37,6 +40,9 @@ fn dispatch(argv: &[String]) -> Result<ExitCode, String> {
         Some("run") => cmd_run(&argv[2..]),
         Some("check") => cmd_check(&argv[2..]),
         Some("score") => cmd_score(&argv[2..]),
+        Some("disasm") => cmd_disasm(&argv[2..]),
+        Some("search") => cmd_search(&argv[2..]),
+        Some("netplay") => cmd_netplay(&argv[2..]),
         Some("-h") | Some("--help") | Some("help") => {
             print_usage();
             Ok(ExitCode::SUCCESS)
@@ -65,9 +71,15 @@ pub fn print_usage() {
     eprintln!("USAGE:");
     eprintln!("  oxgbc-cli run   <ROM> [options]");
     eprintln!("  oxgbc-cli check <DIR> [options]");
-    eprintln!("  oxgbc-cli score [SUITE...] [options]\n");
+    eprintln!("  oxgbc-cli score [SUITE...] [options]");
+    eprintln!("  oxgbc-cli disasm <ROM> [options]");
+    eprintln!("  oxgbc-cli search <ROM> [options]");
+    eprintln!("  oxgbc-cli netplay <ROM> [options]\n");
     args::print_common_usage();
     commands::run::print_options();
     commands::check::print_options();
     commands::score::print_options();
+    commands::disasm::print_options();
+    commands::search::print_options();
+    commands::netplay::print_options();
 }
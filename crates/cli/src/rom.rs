@@ -1,6 +1,7 @@
 //! ROM discovery on disk and framebuffer screenshots.
 
 use core::cpu::Cpu;
+use core::debugger::Symbols;
 use core::ppu::{LCD_X_RES, LCD_Y_RES};
 use std::path::{Path, PathBuf};
 
@@ -131,6 +132,26 @@ pub fn compare_to_reference(cpu: &Cpu, ref_path: &Path, tolerance: u8) -> Result
     }
 }
 
+/// Labels for `rom`: an explicit `sym` file has to load, the `.sym` next to
+/// the ROM is optional.
+pub fn load_symbols(rom: &Path, sym: Option<&Path>) -> Result<Symbols, String> {
+    let path = match sym {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let path = Symbols::path_for_rom(rom);
+            if !path.exists() {
+                return Ok(Symbols::default());
+            }
+            path
+        }
+    };
+
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("failed to read '{}': {e}", path.display()))?;
+
+    Ok(Symbols::parse(&text))
+}
+
 #[cfg(test)]
 mod tests {
     use super::glob_match;
@@ -63,7 +63,7 @@ pub fn new_cpu(cart: Option<Cart>) -> Cpu {
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("create_save_state_1mb_rom", |b| {
         let bus = Bus::new(get_cart_1mb(), Default::default(), None);
-        let mut emu = Emu::new(EmuConfig::default(), EmuRuntime::new(bus)).unwrap();
+        let mut emu = Emu::new(EmuConfig::default(), EmuRuntime::without_debugger(bus)).unwrap();
 
         b.iter(|| black_box(emu.create_save_state()));
     });
@@ -1,4 +1,4 @@
-use crate::apu::channels::channel::ChannelType;
+use crate::apu::channels::channel::{ChannelState, ChannelType};
 use crate::apu::channels::noise_channel::NR41_CH4_LENGTH_TIMER_ADDRESS;
 use crate::apu::channels::noise_channel::{NoiseChannel, CH4_END_ADDRESS, CH4_START_ADDRESS};
 use crate::apu::channels::square_channel::{
@@ -8,14 +8,16 @@ use crate::apu::channels::square_channel::{
     NR11_CH1_LEN_TIMER_DUTY_CYCLE_ADDRESS, NR21_CH2_LEN_TIMER_DUTY_CYCLE_ADDRESS,
 };
 use crate::apu::channels::wave_channel::{
-    WaveChannel, CH3_END_ADDRESS, CH3_START_ADDRESS, CH3_WAVE_RAM_END, CH3_WAVE_RAM_START,
+    WaveChannel, WaveRam, CH3_END_ADDRESS, CH3_START_ADDRESS, CH3_WAVE_RAM_END, CH3_WAVE_RAM_START,
 };
 use crate::apu::dac::{apply_dac, DacEnable, DigitalSampleProducer};
 use crate::apu::hpf::Hpf;
 use crate::apu::mixer::Mixer;
+use crate::apu::ApuScope;
 use crate::cpu::CPU_CLOCK_SPEED;
 use crate::{change_f32_rounded, get_bit_flag, set_bit};
 use serde::{Deserialize, Serialize};
+use std::mem;
 
 pub const AUDIO_START_ADDRESS: u16 = 0xFF10;
 pub const AUDIO_END_ADDRESS: u16 = 0xFF26;
@@ -170,6 +172,9 @@ pub struct Apu {
     buffer_idx: usize,
     buffer: Box<[f32]>,
     hpf: Hpf,
+    /// Opt-in per-channel oscilloscope tap, filled while it's set.
+    #[serde(skip)]
+    scope: Option<Box<ApuScope>>,
     pub config: ApuConfig,
 }
 
@@ -211,6 +216,7 @@ impl Apu {
             buffer: vec![0.0; config.buffer_size].into_boxed_slice(),
             buffer_idx: 0,
             hpf: Hpf::new(SAMPLING_FREQUENCY),
+            scope: None,
             config,
         }
     }
@@ -326,6 +332,10 @@ impl Apu {
             self.window_right = 0.0;
             self.window_ticks = 0;
 
+            if let Some(scope) = self.scope.as_mut() {
+                scope.push(self.mixer.samples());
+            }
+
             self.push_buffer(output_left, output_right);
         }
     }
@@ -572,6 +582,52 @@ impl Apu {
         self.write(0xFF12, 0xF3, false); // NR12: initial volume 15, decreasing, pace 3
     }
 
+    /// Starts keeping the last `capacity` DAC outputs of each channel.
+    pub fn start_scope(&mut self, capacity: usize) {
+        self.scope = Some(Box::new(ApuScope::new(capacity)));
+    }
+
+    pub fn stop_scope(&mut self) {
+        self.scope = None;
+    }
+
+    pub fn scope(&self) -> Option<&ApuScope> {
+        self.scope.as_deref()
+    }
+
+    /// Hands the scope over to a restored APU, which comes without one.
+    pub(crate) fn swap_scope(&mut self, other: &mut Apu) {
+        mem::swap(&mut self.scope, &mut other.scope);
+    }
+
+    pub fn channel_states(&self) -> [ChannelState; 4] {
+        [
+            self.ch1.state(self.nr52),
+            self.ch2.state(self.nr52),
+            self.ch3.state(self.nr52),
+            self.ch4.state(self.nr52),
+        ]
+    }
+
+    /// NR10-NR52 ($FF10-$FF26) as written, write-only bits included and the
+    /// unused addresses as $FF, unlike [`Self::read`].
+    pub fn peek_registers(&self) -> [u8; 23] {
+        let mut registers = [0xFF; 23];
+        registers[0..5].copy_from_slice(&self.ch1.registers());
+        registers[5..10].copy_from_slice(&self.ch2.registers());
+        registers[10..15].copy_from_slice(&self.ch3.registers());
+        registers[16..20].copy_from_slice(&self.ch4.registers());
+        registers[20] = self.mixer.nr50_volume.byte;
+        registers[21] = self.mixer.nr51_panning.byte;
+        registers[22] = self.nr52.read();
+
+        registers
+    }
+
+    pub fn wave_ram(&self) -> &WaveRam {
+        &self.ch3.wave_ram
+    }
+
     /// PCM12 ($FF76, CGB): current digital output of channels 1 (low nibble)
     /// and 2 (high nibble).
     #[inline(always)]
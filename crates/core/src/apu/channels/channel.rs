@@ -15,6 +15,28 @@ pub enum ChannelType {
     CH4,
 }
 
+/// What a channel is doing, for debug views.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ChannelState {
+    /// Its NR52 status bit.
+    pub active: bool,
+    pub dac_enabled: bool,
+    /// Digital output, 0-15.
+    pub output: u8,
+    /// Envelope volume, or the wave channel's output level on the same
+    /// 0-15 scale.
+    pub volume: u8,
+    /// The 11-bit period from NRx3 and NRx4; 0 for the noise channel.
+    pub period: u16,
+    /// Tone frequency, or the noise channel's LFSR clock.
+    pub frequency: f32,
+    /// Length counter.
+    pub length: u16,
+    pub length_enabled: bool,
+    /// Channel 1's sweep shadow period.
+    pub sweep_period: Option<u16>,
+}
+
 impl ChannelType {
     #[inline]
     pub fn get_start_address(&self) -> u16 {
@@ -1,4 +1,4 @@
-use crate::apu::channels::channel::ChannelType;
+use crate::apu::channels::channel::{ChannelState, ChannelType};
 use crate::apu::dac::{DacEnable, DigitalSampleProducer};
 use crate::apu::registers::NRx1;
 use crate::apu::registers::{NRx2, NRx4};
@@ -115,6 +115,34 @@ impl NoiseChannel {
         }
     }
 
+    /// NR41-NR44 as written, write-only bits included.
+    pub fn registers(&self) -> [u8; 4] {
+        [
+            self.nrx1_len.byte,
+            self.nrx2_envelope_and_dac.byte,
+            self.nr43_freq_and_rnd.byte,
+            self.nrx4_ctrl.byte,
+        ]
+    }
+
+    pub fn state(&self, master_ctrl: NR52) -> ChannelState {
+        let nr43 = &self.nr43_freq_and_rnd;
+        // divider 0 counts as 0.5
+        let divider = (nr43.clock_divider() as f32).max(0.5);
+
+        ChannelState {
+            active: master_ctrl.is_ch4_on(),
+            dac_enabled: self.is_dac_enabled(),
+            output: self.get_sample(master_ctrl),
+            volume: self.envelope_timer.get_volume(),
+            period: 0,
+            frequency: 262_144.0 / divider / (1u32 << nr43.clock_shift()) as f32,
+            length: self.length_timer.get_counter(),
+            length_enabled: self.nrx4_ctrl.is_length_enabled(),
+            sweep_period: None,
+        }
+    }
+
     #[inline]
     pub fn write(
         &mut self,
@@ -405,7 +433,7 @@ impl NoiseChannel {
 /// This register allows controlling the way the amplitude is randomly switched.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NR43 {
-    byte: u8,
+    pub byte: u8,
 }
 
 impl NR43 {
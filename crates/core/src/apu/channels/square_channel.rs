@@ -1,4 +1,4 @@
-use crate::apu::channels::channel::ChannelType;
+use crate::apu::channels::channel::{ChannelState, ChannelType};
 use crate::apu::dac::{DacEnable, DigitalSampleProducer};
 use crate::apu::registers::{NRx1, NRx2, NRx3x4};
 use crate::apu::timers::envelope_timer::EnvelopeTimer;
@@ -137,6 +137,33 @@ impl SquareChannel {
         }
     }
 
+    /// NRx0-NRx4 as written, write-only bits included; $FF for NR20.
+    pub fn registers(&self) -> [u8; 5] {
+        [
+            self.sweep_timer.as_ref().map_or(0xFF, |x| x.nr10.byte),
+            self.nrx1_len_timer_duty_cycle.byte,
+            self.nrx2_volume_envelope_and_dac.byte,
+            self.nrx3x4_period_and_ctrl.period_low.byte,
+            self.nrx3x4_period_and_ctrl.nrx4.byte,
+        ]
+    }
+
+    pub fn state(&self, master_ctrl: NR52) -> ChannelState {
+        let period = self.nrx3x4_period_and_ctrl.get_period();
+
+        ChannelState {
+            active: master_ctrl.is_ch_on(self.ch_type),
+            dac_enabled: self.is_dac_enabled(),
+            output: self.get_sample(master_ctrl),
+            volume: self.envelope_timer.get_volume(),
+            period,
+            frequency: 131_072.0 / (2048 - period) as f32,
+            length: self.length_timer.get_counter(),
+            length_enabled: self.nrx3x4_period_and_ctrl.nrx4.is_length_enabled(),
+            sweep_period: self.sweep_timer.as_ref().map(|x| x.get_shadow_period()),
+        }
+    }
+
     #[inline]
     pub fn write(
         &mut self,
@@ -1,4 +1,4 @@
-use crate::apu::channels::channel::ChannelType;
+use crate::apu::channels::channel::{ChannelState, ChannelType};
 use crate::apu::dac::{DacEnable, DigitalSampleProducer};
 use crate::apu::registers::{NRx1, NRx3x4};
 use crate::apu::timers::length_timer::LengthTimer;
@@ -84,6 +84,33 @@ impl WaveChannel {
         }
     }
 
+    /// NR30-NR34 as written, write-only bits included.
+    pub fn registers(&self) -> [u8; 5] {
+        [
+            self.nrx0_dac_enable.byte,
+            self.nrx1_length_timer.byte,
+            self.nrx2_output_level.byte,
+            self.nrx3x4_period_and_ctrl.period_low.byte,
+            self.nrx3x4_period_and_ctrl.nrx4.byte,
+        ]
+    }
+
+    pub fn state(&self, master_ctrl: NR52) -> ChannelState {
+        let period = self.nrx3x4_period_and_ctrl.get_period();
+
+        ChannelState {
+            active: master_ctrl.is_ch3_on(),
+            dac_enabled: self.is_dac_enabled(),
+            output: self.get_sample(master_ctrl),
+            volume: 15 >> self.volume_shift,
+            period,
+            frequency: 65_536.0 / (2048 - period) as f32,
+            length: self.length_timer.get_counter(),
+            length_enabled: self.nrx3x4_period_and_ctrl.nrx4.is_length_enabled(),
+            sweep_period: None,
+        }
+    }
+
     #[inline]
     pub fn write(&mut self, address: u16, value: u8, master_ctrl: &mut NR52, len_first_half: bool) {
         match address {
@@ -192,6 +219,16 @@ impl WaveRam {
         self.bytes[addr as usize]
     }
 
+    /// All 32 samples, two per byte, regardless of what the channel plays.
+    pub fn bytes(&self) -> &[u8; 16] {
+        &self.bytes
+    }
+
+    /// Index of the sample being played.
+    pub fn get_sample_index(&self) -> usize {
+        self.sample_index
+    }
+
     #[inline]
     pub fn write(&mut self, addr: u16, value: u8, ch_active: bool) {
         if ch_active {
@@ -242,7 +279,7 @@ impl WaveRam {
 // DAC enable
 #[derive(Clone, Debug, Default, Copy, Serialize, Deserialize)]
 pub struct NR30 {
-    byte: u8,
+    pub byte: u8,
 }
 
 impl NR30 {
@@ -259,7 +296,7 @@ impl NR30 {
 /// Output level
 #[derive(Clone, Debug, Default, Copy, Serialize, Deserialize)]
 pub struct NR32 {
-    byte: u8,
+    pub byte: u8,
 }
 
 impl NR32 {
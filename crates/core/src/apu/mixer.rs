@@ -13,6 +13,12 @@ pub struct Mixer {
 }
 
 impl Mixer {
+    /// Each channel's DAC output, before panning and volume.
+    #[inline(always)]
+    pub fn samples(&self) -> [f32; 4] {
+        [self.sample1, self.sample2, self.sample3, self.sample4]
+    }
+
     /// Combines samples from all channels. `channel_mask` is the user-facing
     /// mute (bit N audible = channel N+1); it gates the hardware panning
     /// selection without touching any observable APU state.
@@ -4,6 +4,8 @@ pub mod dac;
 mod hpf;
 pub mod mixer;
 pub mod registers;
+mod scope;
 pub mod timers;
 
 pub use apu::*;
+pub use scope::ApuScope;
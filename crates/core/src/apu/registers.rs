@@ -103,7 +103,7 @@ impl NRx3x4 {
 ///  Period low, write-only
 #[derive(Clone, Debug, Default, Copy, Serialize, Deserialize)]
 pub struct NRx3 {
-    byte: u8,
+    pub byte: u8,
 }
 
 impl NRx3 {
@@ -116,7 +116,7 @@ impl NRx3 {
 /// Period high & length timer control
 #[derive(Clone, Debug, Default, Copy, Serialize, Deserialize)]
 pub struct NRx4 {
-    byte: u8,
+    pub byte: u8,
 }
 
 impl NRx4 {
@@ -0,0 +1,87 @@
+/// Ring buffers of each channel's DAC output, taken at the output sample
+/// rate, for oscilloscope views.
+#[derive(Debug, Clone)]
+pub struct ApuScope {
+    channels: [Box<[f32]>; 4],
+    /// Where the next sample goes.
+    pos: usize,
+    filled: usize,
+}
+
+impl ApuScope {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        Self {
+            channels: std::array::from_fn(|_| vec![0.0; capacity].into_boxed_slice()),
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, samples: [f32; 4]) {
+        let capacity = self.channels[0].len();
+
+        for (buffer, sample) in self.channels.iter_mut().zip(samples) {
+            buffer[self.pos] = sample;
+        }
+
+        self.pos = (self.pos + 1) % capacity;
+        self.filled = (self.filled + 1).min(capacity);
+    }
+
+    /// The last `len` samples of `channel`, oldest first. They start at the
+    /// latest rising edge that leaves room for `len` samples, so a steady
+    /// tone holds still from one frame to the next; without one they are
+    /// just the newest.
+    pub fn window(&self, channel: usize, len: usize) -> Vec<f32> {
+        let len = len.min(self.filled);
+        let last_start = self.filled - len;
+        let (min, max) = (0..self.filled)
+            .map(|i| self.get(channel, i))
+            .fold((f32::MAX, f32::MIN), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+        let mid = (min + max) / 2.0;
+
+        let start = (1..=last_start)
+            .rev()
+            .find(|i| self.get(channel, i - 1) < mid && self.get(channel, *i) >= mid)
+            .unwrap_or(last_start);
+
+        (start..start + len).map(|i| self.get(channel, i)).collect()
+    }
+
+    /// Sample `i` of `channel`, from the oldest kept.
+    fn get(&self, channel: usize, i: usize) -> f32 {
+        let buffer = &self.channels[channel];
+        let capacity = buffer.len();
+
+        buffer[(self.pos + capacity - self.filled + i) % capacity]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_starts_at_rising_edge() {
+        let mut scope = ApuScope::new(16);
+
+        // a square wave of period 4 on channel 1, starting mid-cycle
+        for i in 0..20 {
+            let high = (i + 1) % 4 >= 2;
+            scope.push([if high { 1.0 } else { -1.0 }, 0.0, 0.0, 0.0]);
+        }
+
+        // the latest rising edge that fits 6 samples
+        let window = scope.window(0, 6);
+        assert_eq!(window, [1.0, 1.0, -1.0, -1.0, 1.0, 1.0]);
+
+        // a flat channel gives its newest samples
+        assert_eq!(scope.window(1, 4), [0.0; 4]);
+        assert_eq!(scope.window(0, 100).len(), 16);
+    }
+}
@@ -39,6 +39,11 @@ impl LengthTimer {
         }
     }
 
+    #[inline(always)]
+    pub fn get_counter(&self) -> u16 {
+        self.counter
+    }
+
     #[inline(always)]
     pub fn is_expired(&self) -> bool {
         self.counter == 0
@@ -44,6 +44,11 @@ pub struct SweepTimer {
 }
 
 impl SweepTimer {
+    /// Period the next sweep step adds to or subtracts from.
+    pub fn get_shadow_period(&self) -> u16 {
+        self.shadow_sample_length
+    }
+
     /// 1 MHz pipeline tick.
     pub fn tick_1mhz(&mut self, nr52: &mut NR52, nrx3x4: &NRx3x4) {
         if self.reload_timer > 0 {
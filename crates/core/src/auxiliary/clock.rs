@@ -23,6 +23,19 @@ pub struct Clock {
     /// Parity of device (4 MHz) ticks, drives the 2 MHz VRAM-DMA cadence.
     #[serde(default)]
     device_phase: bool,
+    /// 4 MHz cycles into the current second of the emulated RTC. Not
+    /// cleared by [`Self::reset`], so it counts from power-on.
+    rtc_cycles: u32,
+}
+
+/// The clock's own counters, without the bus, for a save-state section.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ClockState {
+    pub(crate) cpu_halted: bool,
+    pub(crate) m_cycles: usize,
+    pub(crate) ds_phase: bool,
+    pub(crate) device_phase: bool,
+    pub(crate) rtc_cycles: u32,
 }
 
 impl Default for Clock {
@@ -40,9 +53,28 @@ impl Clock {
             m_cycles: 0,
             ds_phase: false,
             device_phase: false,
+            rtc_cycles: 0,
+        }
+    }
+
+    pub(crate) fn state(&self) -> ClockState {
+        ClockState {
+            cpu_halted: self.cpu_halted,
+            m_cycles: self.m_cycles,
+            ds_phase: self.ds_phase,
+            device_phase: self.device_phase,
+            rtc_cycles: self.rtc_cycles,
         }
     }
 
+    pub(crate) fn set_state(&mut self, state: ClockState) {
+        self.cpu_halted = state.cpu_halted;
+        self.m_cycles = state.m_cycles;
+        self.ds_phase = state.ds_phase;
+        self.device_phase = state.device_phase;
+        self.rtc_cycles = state.rtc_cycles;
+    }
+
     #[inline(always)]
     pub fn is_cpu_halted(&self) -> bool {
         self.cpu_halted || self.bus.vram_dma.is_transferring()
@@ -64,6 +96,7 @@ impl Clock {
         for _ in 0..m_cycles {
             self.m_cycles = self.m_cycles.wrapping_add(1);
             OamDma::tick(&mut self.bus);
+            self.tick_rtc();
 
             for _ in 0..T_CYCLES_PER_M_CYCLE {
                 self.bus.io.timer.tick(&mut self.bus.io.interrupts);
@@ -96,6 +129,8 @@ impl Clock {
                 }
 
                 self.bus.io.ppu.tick(&mut self.bus.io.interrupts);
+                #[cfg(feature = "debug")]
+                self.bus.sample_timeline();
                 let div_apu_bit = self
                     .bus
                     .io
@@ -106,6 +141,22 @@ impl Clock {
         }
     }
 
+    /// The RTC crystal is independent of CPU speed, so an M-cycle is half
+    /// as long in double speed.
+    #[inline(always)]
+    fn tick_rtc(&mut self) {
+        self.rtc_cycles += if self.bus.io.cgb_speed.double_speed {
+            T_CYCLES_PER_M_CYCLE as u32 / 2
+        } else {
+            T_CYCLES_PER_M_CYCLE as u32
+        };
+
+        if self.rtc_cycles >= CPU_CLOCK_SPEED {
+            self.rtc_cycles -= CPU_CLOCK_SPEED;
+            self.bus.cart.tick_rtc_second();
+        }
+    }
+
     #[inline(always)]
     pub fn get_m_cycles(&self) -> usize {
         self.m_cycles
@@ -33,6 +33,10 @@ pub struct Io {
     /// CGB undocumented registers FF72-FF75.
     #[serde(default)]
     pub undoc: CgbUndocumented,
+    /// Opt-in hardware event timeline, recorded while it's set.
+    #[cfg(feature = "debug")]
+    #[serde(skip)]
+    pub timeline: Option<Box<crate::debugger::Timeline>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -56,6 +60,22 @@ impl Io {
             apu,
             cgb_speed: CgbSpeed::default(),
             undoc: CgbUndocumented::default(),
+            #[cfg(feature = "debug")]
+            timeline: None,
+        }
+    }
+
+    #[cfg(feature = "debug")]
+    pub fn start_timeline(&mut self, frames: usize) {
+        self.interrupts.requested = 0;
+        self.timeline = Some(Box::new(crate::debugger::Timeline::new(frames)));
+    }
+
+    #[cfg(feature = "debug")]
+    #[inline(always)]
+    pub fn record_event(&mut self, kind: crate::debugger::EventKind) {
+        if let Some(timeline) = self.timeline.as_mut() {
+            timeline.record(kind);
         }
     }
 
@@ -133,6 +153,11 @@ impl Io {
 
     #[inline(always)]
     pub fn write(&mut self, addr: u16, value: u8) {
+        #[cfg(feature = "debug")]
+        if self.timeline.is_some() && crate::debugger::is_traced_register(addr) {
+            self.record_event(crate::debugger::EventKind::Write { addr, value });
+        }
+
         match addr {
             0xFF00 => self.joypad.set_byte(value),
             0xFF01 => self.serial.sb = value,
@@ -230,6 +255,14 @@ pub struct Serial {
     /// independent of the transfer timing so blargg's text output is captured
     /// even when the ROM fires bytes back-to-back without waiting.
     output: Option<u8>,
+    /// SB as the transfer started, what a link partner shifts in. Only a
+    /// link reads this and the next field, and a linked pair isn't saved,
+    /// so they stay out of states.
+    #[serde(skip)]
+    link_out: u8,
+    /// A transfer on the internal clock ended and the link hasn't taken it.
+    #[serde(skip)]
+    link_sent: bool,
 }
 
 const SERIAL_SC_UNUSED_MASK: u8 = 0b01111110;
@@ -244,6 +277,10 @@ impl Serial {
         self.prev_clock = clock_bit;
         self.sc = value;
 
+        if value & 0x80 != 0 {
+            self.link_out = self.sb;
+        }
+
         if value & 0x81 == 0x81 {
             self.output = Some(self.sb);
             self.bits_left = 8;
@@ -282,10 +319,46 @@ impl Serial {
 
         if self.bits_left == 0 {
             self.sc &= 0x7F;
+            self.link_sent = true;
             interrupts.request_interrupt(InterruptType::Serial);
         }
     }
 
+    /// The byte sent by a transfer this side clocked, once it ends.
+    #[inline(always)]
+    pub fn take_link_sent(&mut self) -> Option<u8> {
+        if !self.link_sent {
+            return None;
+        }
+
+        self.link_sent = false;
+        Some(self.link_out)
+    }
+
+    /// A transfer is waiting for the partner's clock (SC bit 7 with the
+    /// external clock).
+    #[inline(always)]
+    pub fn is_link_waiting(&self) -> bool {
+        self.sc & 0x81 == 0x80
+    }
+
+    /// Ends a waiting transfer the partner clocked, shifting in its `byte`;
+    /// returns the one shifted out.
+    pub fn link_clock_in(&mut self, byte: u8, interrupts: &mut Interrupts) -> u8 {
+        self.sb = byte;
+        self.sc &= 0x7F;
+        interrupts.request_interrupt(InterruptType::Serial);
+
+        self.link_out
+    }
+
+    /// Puts in the partner's byte after a transfer this side clocked, in
+    /// place of the 1s shifted in from an open line.
+    #[inline(always)]
+    pub fn set_link_received(&mut self, byte: u8) {
+        self.sb = byte;
+    }
+
     #[inline(always)]
     pub fn has_data(&self) -> bool {
         self.output.is_some()
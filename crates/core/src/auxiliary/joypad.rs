@@ -80,6 +80,35 @@ impl Joypad {
         self.actions_selected = (value & 0x20) == 0;
     }
 
+    /// The held buttons as bits, in [`JoypadButton`] order.
+    pub fn get_pressed(&self) -> u8 {
+        [
+            self.start,
+            self.select,
+            self.a,
+            self.b,
+            self.up,
+            self.down,
+            self.left,
+            self.right,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |acc, (i, x)| acc | ((*x as u8) << i))
+    }
+
+    pub fn set_pressed(&mut self, bits: u8) {
+        let pressed = |i: u8| bits & (1 << i) != 0;
+        self.start = pressed(0);
+        self.select = pressed(1);
+        self.a = pressed(2);
+        self.b = pressed(3);
+        self.up = pressed(4);
+        self.down = pressed(5);
+        self.left = pressed(6);
+        self.right = pressed(7);
+    }
+
     pub fn reset(&mut self) {
         self.start = false;
         self.select = false;
@@ -51,6 +51,27 @@ impl Ram {
         self.wram_bank_number = val & 0b0000_0111;
     }
 
+    /// Reads `addr` as if WRAM `bank` were selected.
+    pub fn read_wram_in(&self, bank: u8, addr: u16) -> u8 {
+        match addr {
+            WRAM_CGB_BANK_START_ADDR..=WRAM_CGB_BANK_END_ADDR => {
+                let bank = (bank & 0b111).max(1) as usize;
+                let index =
+                    (bank - 1) * WRAM_BANK_SIZE + (addr - WRAM_CGB_BANK_START_ADDR) as usize;
+                self.wram_cgb_banks[index]
+            }
+            _ => self.read_wram(addr),
+        }
+    }
+
+    /// Writes `addr` as if WRAM `bank` were selected, keeping the selection.
+    pub fn write_wram_in(&mut self, bank: u8, addr: u16, val: u8) {
+        let selected = self.wram_bank_number;
+        self.write_wram_bank(bank);
+        self.write_wram(addr, val);
+        self.wram_bank_number = selected;
+    }
+
     #[inline]
     pub fn read_wram(&self, addr: u16) -> u8 {
         match addr {
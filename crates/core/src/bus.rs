@@ -133,6 +133,30 @@ impl Bus {
         }
     }
 
+    /// Feeds the event timeline the state after a dot.
+    #[cfg(feature = "debug")]
+    #[inline(always)]
+    pub fn sample_timeline(&mut self) {
+        use crate::ppu::lcd::LcdStatSrc;
+
+        let Some(timeline) = self.io.timeline.as_mut() else {
+            return;
+        };
+
+        let ppu = &self.io.ppu;
+        let status = &ppu.lcd.status;
+        timeline.sample(crate::debugger::TimelineSample {
+            frame: ppu.current_frame,
+            ly: ppu.lcd.ly,
+            dot: ppu.get_dot() as u16,
+            mode: status.get_ppu_mode(),
+            oam_dma: self.oam_dma.is_active,
+            hdma: self.vram_dma.is_transferring(),
+            requested: std::mem::take(&mut self.io.interrupts.requested),
+            lyc: status.is_stat_interrupt(LcdStatSrc::Lyc) && status.get_lyc(),
+        });
+    }
+
     pub fn write(&mut self, addr: u16, value: u8) {
         #[cfg(any(debug_assertions, feature = "test-bus"))]
         if let Some(test_bytes) = self.flat_mem.as_mut() {
@@ -6,8 +6,9 @@ use crate::cart::mbc::{
 };
 use crate::cart::mbc1::Mbc1;
 use crate::cart::mbc2::Mbc2;
-use crate::cart::mbc3::Mbc3;
+use crate::cart::mbc3::{Mbc3, RtcClock};
 use crate::cart::mbc5::Mbc5;
+use crate::cheats::RomPatch;
 use serde::{Deserialize, Serialize};
 
 pub const RAM_ADDRESS_START: usize = 0xA000;
@@ -59,6 +60,11 @@ impl Cart {
     }
 
     pub fn new(rom_bytes: Box<[u8]>) -> Result<Cart, String> {
+        // ROM reads don't check bounds, every cart maps two banks
+        if rom_bytes.len() < 2 * ROM_BANK_SIZE {
+            return Err(format!("ROM of {} bytes is too short", rom_bytes.len()));
+        }
+
         let data = CartData::new(rom_bytes);
         let cart_type = data.get_cart_type()?;
         let ram_size = data.get_ram_size()?;
@@ -96,7 +102,9 @@ impl Cart {
             | CartType::PocketCamera
             | CartType::BandaiTama5
             | CartType::HuC3
-            | CartType::HuC1RamBattery => unimplemented!("Cart type {:?}", cart_type),
+            | CartType::HuC1RamBattery => {
+                return Err(format!("Unsupported cart type {cart_type:?}"))
+            }
         };
 
         Ok(Self {
@@ -117,7 +125,13 @@ impl Cart {
     pub fn read(&self, address: u16) -> u8 {
         match address {
             ROM_BANK_ZERO_START_ADDR..=ROM_BANK_NON_ZERO_END_ADDR => {
-                self.mbc.read_rom(&self.data, address)
+                let value = self.mbc.read_rom(&self.data, address);
+
+                if self.data.patches.is_empty() {
+                    value
+                } else {
+                    self.data.read_patched(address, value)
+                }
             }
             RAM_EXTERNAL_START_ADDR..=RAM_EXTERNAL_END_ADDR => self.mbc.read_ram(address),
             _ => 0xFF,
@@ -135,6 +149,53 @@ impl Cart {
         }
     }
 
+    pub fn rom_bank(&self, address: u16) -> u16 {
+        self.mbc.rom_bank(address)
+    }
+
+    pub fn write_ram_bank(&mut self, bank: u8, address: u16, value: u8) {
+        self.mbc.write_ram_bank(bank, address, value)
+    }
+
+    pub fn read_ram_bank(&self, bank: u8, address: u16) -> u8 {
+        self.mbc.read_ram_bank(bank, address)
+    }
+
+    pub fn ram_size(&self) -> usize {
+        self.mbc.ram_size()
+    }
+
+    /// Advances an emulated MBC3 clock by a second, see [`RtcClock`].
+    #[inline]
+    pub fn tick_rtc_second(&mut self) {
+        if let MbcVariant::Mbc3(mbc) = &mut self.mbc {
+            mbc.tick_rtc_second();
+        }
+    }
+
+    /// The MBC3 clock's time source, the default without one.
+    pub fn rtc_clock(&self) -> RtcClock {
+        match &self.mbc {
+            MbcVariant::Mbc3(mbc) => mbc.rtc_clock(),
+            _ => RtcClock::default(),
+        }
+    }
+
+    /// Switches the MBC3 clock's time source, if the cart has one.
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        if let MbcVariant::Mbc3(mbc) = &mut self.mbc {
+            mbc.set_rtc_clock(clock);
+        }
+    }
+
+    /// Sets the MBC3 clock's time source at power-on, after the battery
+    /// RAM is loaded.
+    pub fn power_on_rtc(&mut self, clock: RtcClock) {
+        if let MbcVariant::Mbc3(mbc) = &mut self.mbc {
+            mbc.power_on_rtc(clock);
+        }
+    }
+
     pub fn load_ram(&mut self, bytes: Box<[u8]>) {
         if self.has_battery {
             self.mbc.load_ram(bytes);
@@ -154,6 +215,10 @@ impl Cart {
 pub struct CartData {
     bytes: Box<[u8]>,
     pub cgb_flag: CgbFlag,
+    /// Game Genie patches over ROM reads. They live with the ROM image so
+    /// they stay applied across save-state loads and rewind.
+    #[serde(skip)]
+    patches: Vec<RomPatch>,
 }
 
 impl CartData {
@@ -161,9 +226,22 @@ impl CartData {
         Self {
             cgb_flag: CartHeader::parse_cgb_flag(&bytes),
             bytes,
+            patches: Vec::new(),
         }
     }
 
+    pub fn set_patches(&mut self, patches: Vec<RomPatch>) {
+        self.patches = patches;
+    }
+
+    #[inline]
+    fn read_patched(&self, address: u16, value: u8) -> u8 {
+        self.patches
+            .iter()
+            .find_map(|x| x.get(address, value))
+            .unwrap_or(value)
+    }
+
     /// Raw ROM image, including the cartridge header.
     #[inline(always)]
     pub fn rom(&self) -> &[u8] {
@@ -181,9 +259,9 @@ impl CartData {
     }
 
     #[inline(always)]
-    pub fn write(&mut self, addr: u16, value: u8) {
+    pub fn write(&mut self, addr: usize, value: u8) {
         unsafe {
-            *self.bytes.get_unchecked_mut(addr as usize) = value;
+            *self.bytes.get_unchecked_mut(addr) = value;
         }
     }
 
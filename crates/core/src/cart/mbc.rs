@@ -21,6 +21,15 @@ pub trait Mbc {
     fn write_ram(&mut self, address: u16, value: u8);
     fn load_ram(&mut self, bytes: Box<[u8]>);
     fn dump_ram(&self) -> Option<Box<[u8]>>;
+    /// ROM bank mapped at `address`, for tooling that needs `bank:addr`.
+    fn rom_bank(&self, address: u16) -> u16;
+    /// Writes RAM `bank` whatever bank is selected and even while RAM is
+    /// disabled, the way a cheat device does.
+    fn write_ram_bank(&mut self, bank: u8, address: u16, value: u8);
+    /// Reads RAM `bank` like [`Self::write_ram_bank`] writes it; `0xFF` past the end.
+    fn read_ram_bank(&self, bank: u8, address: u16) -> u8;
+    /// Bytes of cartridge RAM, not counting mirrors.
+    fn ram_size(&self) -> usize;
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -106,6 +115,58 @@ impl Mbc for MbcVariant {
             MbcVariant::Mbc5(c) => c.dump_ram(),
         }
     }
+
+    fn rom_bank(&self, address: u16) -> u16 {
+        match self {
+            MbcVariant::NoMbc | MbcVariant::NoMbcRam(_) => {
+                (address >= ROM_BANK_NON_ZERO_START_ADDR) as u16
+            }
+            MbcVariant::Mbc1(c) => c.rom_bank(address),
+            MbcVariant::Mbc2(c) => c.rom_bank(address),
+            MbcVariant::Mbc3(c) => c.rom_bank(address),
+            MbcVariant::Mbc5(c) => c.rom_bank(address),
+        }
+    }
+
+    fn write_ram_bank(&mut self, bank: u8, address: u16, value: u8) {
+        match self {
+            MbcVariant::NoMbc => {}
+            MbcVariant::NoMbcRam(c) => {
+                if let Some(x) = c.get_mut(address as usize - RAM_ADDRESS_START) {
+                    *x = value;
+                }
+            }
+            MbcVariant::Mbc1(c) => c.write_ram_bank(bank, address, value),
+            MbcVariant::Mbc2(c) => c.write_ram_bank(bank, address, value),
+            MbcVariant::Mbc3(c) => c.write_ram_bank(bank, address, value),
+            MbcVariant::Mbc5(c) => c.write_ram_bank(bank, address, value),
+        }
+    }
+
+    fn read_ram_bank(&self, bank: u8, address: u16) -> u8 {
+        match self {
+            MbcVariant::NoMbc => 0xFF,
+            MbcVariant::NoMbcRam(c) => c
+                .get(address as usize - RAM_ADDRESS_START)
+                .copied()
+                .unwrap_or(0xFF),
+            MbcVariant::Mbc1(c) => c.read_ram_bank(bank, address),
+            MbcVariant::Mbc2(c) => c.read_ram_bank(bank, address),
+            MbcVariant::Mbc3(c) => c.read_ram_bank(bank, address),
+            MbcVariant::Mbc5(c) => c.read_ram_bank(bank, address),
+        }
+    }
+
+    fn ram_size(&self) -> usize {
+        match self {
+            MbcVariant::NoMbc => 0,
+            MbcVariant::NoMbcRam(c) => c.len(),
+            MbcVariant::Mbc1(c) => c.ram_size(),
+            MbcVariant::Mbc2(c) => c.ram_size(),
+            MbcVariant::Mbc3(c) => c.ram_size(),
+            MbcVariant::Mbc5(c) => c.ram_size(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -145,6 +206,14 @@ impl MbcData {
         }
     }
 
+    pub fn rom_bank(&self, address: u16) -> u16 {
+        if address < ROM_BANK_NON_ZERO_START_ADDR {
+            0
+        } else {
+            self.rom_bank_number
+        }
+    }
+
     #[inline(always)]
     pub fn write_ram_enabled(&mut self, value: u8) {
         self.ram_enabled = value & 0xF == 0xA;
@@ -195,6 +264,20 @@ impl MbcData {
         }
     }
 
+    pub fn write_ram_bank(&mut self, bank: u8, address: u16, value: u8) {
+        let index = RAM_BANK_SIZE * bank as usize + (address as usize - RAM_ADDRESS_START);
+
+        if let Some(x) = self.ram_bytes.get_mut(index) {
+            *x = value;
+        }
+    }
+
+    pub fn read_ram_bank(&self, bank: u8, address: u16) -> u8 {
+        let index = RAM_BANK_SIZE * bank as usize + (address as usize - RAM_ADDRESS_START);
+
+        self.ram_bytes.get(index).copied().unwrap_or(0xFF)
+    }
+
     pub fn load_ram(&mut self, bytes: Box<[u8]>) {
         self.ram_bytes = bytes;
     }
@@ -1,7 +1,7 @@
 use crate::cart::header::RomSize;
 use crate::cart::mbc::{Mbc, MbcData};
 use crate::cart::mbc1::BankingMode;
-use crate::cart::CartData;
+use crate::cart::{CartData, RAM_ADDRESS_START};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,4 +61,22 @@ impl Mbc for Mbc2 {
     fn dump_ram(&self) -> Option<Box<[u8]>> {
         self.data.dump_ram()
     }
+
+    fn rom_bank(&self, address: u16) -> u16 {
+        self.data.rom_bank(address)
+    }
+
+    fn write_ram_bank(&mut self, _bank: u8, address: u16, value: u8) {
+        let address = RAM_ADDRESS_START as u16 | (address & 0x1FF);
+        self.data.write_ram_bank(0, address, value);
+    }
+
+    fn read_ram_bank(&self, _bank: u8, address: u16) -> u8 {
+        let address = RAM_ADDRESS_START as u16 | (address & 0x1FF);
+        self.data.read_ram_bank(0, address) | 0xF0
+    }
+
+    fn ram_size(&self) -> usize {
+        512
+    }
 }
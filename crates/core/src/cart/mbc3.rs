@@ -22,6 +22,26 @@ fn now_unix_secs() -> u64 {
         .as_secs()
 }
 
+/// Where the MBC3 real-time clock takes its time from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RtcClock {
+    /// Host time, so the clock keeps counting while the emulator is closed.
+    #[default]
+    WallClock,
+    /// Emulated time only, carrying on from the registers in the save.
+    Emulated,
+    /// Emulated time, starting at power-on this many seconds past day 0
+    /// whatever the save holds.
+    Epoch(u64),
+}
+
+impl RtcClock {
+    #[inline(always)]
+    pub fn is_emulated(self) -> bool {
+        self != RtcClock::WallClock
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Mbc3 {
     data: MbcData,
@@ -41,6 +61,10 @@ pub struct Mbc3 {
 /// the halt flag behave correctly, and — because the clock keeps ticking off
 /// real time even across sessions — matches how the physical, battery-backed
 /// RTC behaves while the console is powered off.
+///
+/// With an emulated [`RtcClock`] the wall clock is ignored and the clock
+/// instead gets [`Rtc::tick_second`] for each second of emulated time, so
+/// runs from the same state see the same time.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct Rtc {
     selected_register: u8,
@@ -51,6 +75,9 @@ struct Rtc {
     latched: RtcRegisters,
     /// Unix time (seconds) at which `live` was last advanced.
     last_unix_secs: u64,
+    /// A setting rather than state, reapplied after loading one.
+    #[serde(skip)]
+    clock: RtcClock,
 }
 
 impl Rtc {
@@ -73,6 +100,12 @@ impl Rtc {
 
     /// Advance the live clock by the real time elapsed since the last update.
     fn sync(&mut self) {
+        // the anchor stays put, so that it doesn't tell apart otherwise
+        // equal runs
+        if self.clock.is_emulated() {
+            return;
+        }
+
         let now = now_unix_secs();
         let elapsed = now.saturating_sub(self.last_unix_secs);
         self.last_unix_secs = now;
@@ -84,6 +117,37 @@ impl Rtc {
         self.live.advance(elapsed);
     }
 
+    fn tick_second(&mut self) {
+        if self.clock.is_emulated() && !self.live.halted {
+            self.live.advance(1);
+        }
+    }
+
+    fn set_clock(&mut self, clock: RtcClock) {
+        if clock == RtcClock::WallClock && self.clock.is_emulated() {
+            // the time spent on the emulated clock is already counted
+            self.last_unix_secs = now_unix_secs();
+        }
+
+        self.clock = clock;
+    }
+
+    fn power_on(&mut self, clock: RtcClock) {
+        self.set_clock(clock);
+
+        if clock.is_emulated() {
+            // unused until a switch to the wall clock, and the same on any
+            // host
+            self.last_unix_secs = 0;
+        }
+
+        if let RtcClock::Epoch(secs) = clock {
+            self.live = RtcRegisters::default();
+            self.live.advance(secs);
+            self.latched = self.live.clone();
+        }
+    }
+
     fn latch(&mut self) {
         self.sync();
         self.latched = self.live.clone();
@@ -113,7 +177,14 @@ impl Rtc {
         for (i, reg) in regs.iter().enumerate() {
             buf[i * 4..i * 4 + 4].copy_from_slice(&reg.to_le_bytes());
         }
-        buf[40..48].copy_from_slice(&self.last_unix_secs.to_le_bytes());
+        // registers on the emulated clock are current as of now, so a later
+        // wall-clock session only adds the time spent switched off
+        let ts = if self.clock.is_emulated() {
+            now_unix_secs()
+        } else {
+            self.last_unix_secs
+        };
+        buf[40..48].copy_from_slice(&ts.to_le_bytes());
         buf
     }
 
@@ -235,6 +306,36 @@ impl Mbc3 {
             has_timer,
         }
     }
+
+    /// Advances an emulated clock by a second of emulated time.
+    #[inline]
+    pub fn tick_rtc_second(&mut self) {
+        if self.has_timer {
+            self.rtc.tick_second();
+        }
+    }
+
+    pub fn rtc_clock(&self) -> RtcClock {
+        self.rtc.clock
+    }
+
+    /// Switches the clock's time source, keeping the time it shows.
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.rtc.set_clock(clock);
+    }
+
+    /// Like [`Self::set_rtc_clock`] at power-on, where an
+    /// [`RtcClock::Epoch`] also sets the time.
+    pub fn power_on_rtc(&mut self, clock: RtcClock) {
+        self.rtc.power_on(clock);
+    }
+
+    /// Zeroes the wall-clock anchor. It tells when the clock was last
+    /// brought up to date rather than what it shows, so copies cleared of
+    /// it compare equal whenever their runs were made.
+    pub fn clear_rtc_anchor(&mut self) {
+        self.rtc.last_unix_secs = 0;
+    }
 }
 
 impl Mbc for Mbc3 {
@@ -324,6 +425,22 @@ impl Mbc for Mbc3 {
         out.extend_from_slice(&self.rtc.to_save_bytes());
         Some(out.into_boxed_slice())
     }
+
+    fn rom_bank(&self, address: u16) -> u16 {
+        self.data.rom_bank(address)
+    }
+
+    fn write_ram_bank(&mut self, bank: u8, address: u16, value: u8) {
+        self.data.write_ram_bank(bank, address, value);
+    }
+
+    fn read_ram_bank(&self, bank: u8, address: u16) -> u8 {
+        self.data.read_ram_bank(bank, address)
+    }
+
+    fn ram_size(&self) -> usize {
+        self.data.ram_len()
+    }
 }
 
 #[cfg(test)]
@@ -390,6 +507,53 @@ mod tests {
         assert_eq!(rtc.live.minutes, 0);
     }
 
+    #[test]
+    fn emulated_clock_ignores_wall_time() {
+        let mut rtc = Rtc::new();
+        rtc.set_clock(RtcClock::Emulated);
+        rtc.last_unix_secs = rtc.last_unix_secs.saturating_sub(120);
+        rtc.latch();
+        assert_eq!(rtc.latched.minutes, 0);
+
+        rtc.tick_second();
+        rtc.latch();
+        assert_eq!(rtc.latched.seconds, 1);
+
+        rtc.live.halted = true;
+        rtc.tick_second();
+        assert_eq!(rtc.live.seconds, 1);
+
+        // back on the wall clock, the time spent emulated isn't added again
+        rtc.live.halted = false;
+        rtc.set_clock(RtcClock::WallClock);
+        rtc.latch();
+        assert_eq!(rtc.latched.seconds, 1);
+    }
+
+    #[test]
+    fn wall_clock_ignores_emulated_seconds() {
+        let mut rtc = Rtc::new();
+        rtc.tick_second();
+        assert_eq!(rtc.live.seconds, 0);
+    }
+
+    #[test]
+    fn epoch_clock_sets_time_at_power_on() {
+        let mut rtc = Rtc::new();
+        rtc.live = regs(12, 34, 5, 300);
+        rtc.power_on(RtcClock::Epoch(86_400 + 3_661));
+        assert_eq!(
+            (rtc.live.seconds, rtc.live.minutes, rtc.live.hours),
+            (1, 1, 1)
+        );
+        assert_eq!(rtc.live.days, 1);
+        assert_eq!(rtc.latched.days, 1);
+
+        // switching the source later keeps the time
+        rtc.power_on(RtcClock::Emulated);
+        assert_eq!(rtc.live.days, 1);
+    }
+
     #[test]
     fn save_round_trip_preserves_registers() {
         let mut rtc = Rtc::new();
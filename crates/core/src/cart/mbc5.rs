@@ -63,4 +63,20 @@ impl Mbc for Mbc5 {
     fn dump_ram(&self) -> Option<Box<[u8]>> {
         self.data.dump_ram()
     }
+
+    fn rom_bank(&self, address: u16) -> u16 {
+        self.data.rom_bank(address)
+    }
+
+    fn write_ram_bank(&mut self, bank: u8, address: u16, value: u8) {
+        self.data.write_ram_bank(bank, address, value);
+    }
+
+    fn read_ram_bank(&self, bank: u8, address: u16) -> u8 {
+        self.data.read_ram_bank(bank, address)
+    }
+
+    fn ram_size(&self) -> usize {
+        self.data.ram_len()
+    }
 }
@@ -0,0 +1,238 @@
+pub mod search;
+
+use crate::bus::Bus;
+use serde::{Deserialize, Serialize};
+
+/// A cheat as the user entered it. `code` holds one or more Game Genie or
+/// GameShark codes joined by `+`, for effects that need several patches.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Cheat {
+    pub name: String,
+    pub code: String,
+    pub enabled: bool,
+}
+
+impl Cheat {
+    pub fn new(name: impl Into<String>, code: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            code: code.into(),
+            enabled: true,
+        }
+    }
+
+    pub fn parse(&self) -> Result<Vec<CheatCode>, String> {
+        self.code.split('+').map(CheatCode::parse).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatCode {
+    GameGenie(RomPatch),
+    GameShark(RamWrite),
+}
+
+impl CheatCode {
+    /// `ABC-DEF` or `ABC-DEF-GHI` for the Game Genie, `TTVVAAAA` for the
+    /// GameShark. Dashes and spaces are optional.
+    pub fn parse(code: &str) -> Result<Self, String> {
+        let digits: Vec<u8> = code
+            .chars()
+            .filter(|x| !matches!(x, '-' | ' '))
+            .map(|x| x.to_digit(16).map(|x| x as u8))
+            .collect::<Option<_>>()
+            .ok_or_else(|| format!("Invalid cheat code: {}", code.trim()))?;
+
+        match digits.len() {
+            6 | 9 => Ok(Self::GameGenie(RomPatch::decode(&digits))),
+            8 => RamWrite::decode(&digits).map(Self::GameShark),
+            _ => Err(format!("Invalid cheat code: {}", code.trim())),
+        }
+    }
+}
+
+/// A Game Genie patch: reads of `addr` return `value` instead, only while
+/// the ROM byte there is `compare` when it's set, so one bank is targeted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RomPatch {
+    pub addr: u16,
+    pub value: u8,
+    pub compare: Option<u8>,
+}
+
+impl RomPatch {
+    fn decode(d: &[u8]) -> Self {
+        let addr = ((d[5] ^ 0xF) as u16) << 12 | (d[2] as u16) << 8 | (d[3] as u16) << 4;
+
+        Self {
+            addr: addr | d[4] as u16,
+            value: d[0] << 4 | d[1],
+            compare: (d.len() == 9).then(|| (d[6] << 4 | d[8]).rotate_right(2) ^ 0xBA),
+        }
+    }
+
+    #[inline(always)]
+    pub fn get(&self, addr: u16, value: u8) -> Option<u8> {
+        if self.addr == addr && self.compare.is_none_or(|x| x == value) {
+            return Some(self.value);
+        }
+
+        None
+    }
+}
+
+/// RAM a GameShark code writes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RamBank {
+    /// Whatever is mapped at the address.
+    Mapped,
+    /// A cartridge RAM bank, for `A000-BFFF`.
+    Sram(u8),
+    /// A CGB WRAM bank, for `D000-DFFF`.
+    Wram(u8),
+}
+
+/// A GameShark code: `value` is written to `addr` once per frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RamWrite {
+    pub bank: RamBank,
+    pub addr: u16,
+    pub value: u8,
+}
+
+impl RamWrite {
+    fn decode(d: &[u8]) -> Result<Self, String> {
+        let bank = match (d[0], d[1]) {
+            (0, 0 | 1) => RamBank::Mapped,
+            (8, x) => RamBank::Sram(x),
+            (9, x) => RamBank::Wram(x),
+            _ => {
+                return Err(format!(
+                    "Unsupported GameShark code type {:X}{:X}",
+                    d[0], d[1]
+                ))
+            }
+        };
+        // the address is little-endian
+        let addr = (d[6] as u16) << 12 | (d[7] as u16) << 8 | (d[4] as u16) << 4 | d[5] as u16;
+
+        if addr < 0x8000 {
+            return Err(format!("GameShark code writes ROM at ${addr:04X}"));
+        }
+
+        Ok(Self {
+            bank,
+            addr,
+            value: d[2] << 4 | d[3],
+        })
+    }
+
+    pub fn apply(&self, bus: &mut Bus) {
+        match (self.bank, self.addr) {
+            (RamBank::Sram(bank), 0xA000..=0xBFFF) => {
+                bus.cart.write_ram_bank(bank, self.addr, self.value)
+            }
+            (RamBank::Wram(bank), 0xD000..=0xDFFF) => {
+                bus.io.ram.write_wram_in(bank, self.addr, self.value)
+            }
+            _ => bus.write(self.addr, self.value),
+        }
+    }
+}
+
+/// The enabled codes of a cheat list, split by where they apply. Invalid
+/// codes are skipped; the menu checks them as they're entered.
+pub fn compile(cheats: &[Cheat]) -> (Vec<RomPatch>, Vec<RamWrite>) {
+    let mut patches = Vec::new();
+    let mut writes = Vec::new();
+
+    for cheat in cheats.iter().filter(|x| x.enabled) {
+        let codes = match cheat.parse() {
+            Ok(codes) => codes,
+            Err(err) => {
+                log::warn!("Skipped cheat {}: {err}", cheat.name);
+                continue;
+            }
+        };
+
+        for code in codes {
+            match code {
+                CheatCode::GameGenie(x) => patches.push(x),
+                CheatCode::GameShark(x) => writes.push(x),
+            }
+        }
+    }
+
+    (patches, writes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_codes() {
+        assert_eq!(
+            CheatCode::parse("00A-17B"),
+            Ok(CheatCode::GameGenie(RomPatch {
+                addr: 0x4A17,
+                value: 0x00,
+                compare: None
+            }))
+        );
+        assert_eq!(
+            CheatCode::parse("3EC-F4A-E6E"),
+            Ok(CheatCode::GameGenie(RomPatch {
+                addr: 0x5CF4,
+                value: 0x3E,
+                compare: Some(0xEE_u8.rotate_right(2) ^ 0xBA)
+            }))
+        );
+        assert_eq!(
+            CheatCode::parse("010F34C1"),
+            Ok(CheatCode::GameShark(RamWrite {
+                bank: RamBank::Mapped,
+                addr: 0xC134,
+                value: 0x0F
+            }))
+        );
+        assert_eq!(
+            CheatCode::parse("9263 1FD0"),
+            Ok(CheatCode::GameShark(RamWrite {
+                bank: RamBank::Wram(2),
+                addr: 0xD01F,
+                value: 0x63
+            }))
+        );
+        assert!(CheatCode::parse("01990040").is_err());
+        assert!(CheatCode::parse("5A0158C1").is_err());
+        assert!(CheatCode::parse("00A-17").is_err());
+        assert!(CheatCode::parse("ZZA-17B").is_err());
+
+        let cheat = Cheat::new("Lives", "010F34C1+00A-17B");
+        assert_eq!(cheat.parse().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_patch_compare() {
+        let patch = RomPatch {
+            addr: 0x4A17,
+            value: 0x00,
+            compare: Some(0x3D),
+        };
+        assert_eq!(patch.get(0x4A17, 0x3D), Some(0x00));
+        assert_eq!(patch.get(0x4A17, 0x3C), None);
+        assert_eq!(patch.get(0x4A18, 0x3D), None);
+
+        let (patches, writes) = compile(&[
+            Cheat::new("A", "00A-17B"),
+            Cheat {
+                enabled: false,
+                ..Cheat::new("B", "010F34C1")
+            },
+            Cheat::new("C", "bad"),
+        ]);
+        assert_eq!(patches.len(), 1);
+        assert!(writes.is_empty());
+    }
+}
@@ -0,0 +1,425 @@
+use crate::bus::Bus;
+use crate::cheats::Cheat;
+use crate::debugger::{parse_number, CmpOp, Watchpoint};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const WRAM_BANK_SIZE: usize = 0x1000;
+const SRAM_BANK_SIZE: usize = 0x2000;
+const HRAM_START: u16 = 0xFF80;
+const HRAM_SIZE: usize = 0x7F;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Region {
+    Wram,
+    Hram,
+    Sram,
+}
+
+/// Where a candidate lives: a bank of a region plus the CPU address it's
+/// mapped at when that bank is selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub region: Region,
+    pub bank: u8,
+    pub addr: u16,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02X}:{:04X}", self.bank, self.addr)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ValueKind {
+    #[default]
+    U8,
+    /// Little-endian, as the CPU stores them.
+    U16,
+    Bcd8,
+    /// Little-endian packed BCD, `0-9999`.
+    Bcd16,
+}
+
+impl ValueKind {
+    pub const ALL: [ValueKind; 4] = [
+        ValueKind::U8,
+        ValueKind::U16,
+        ValueKind::Bcd8,
+        ValueKind::Bcd16,
+    ];
+
+    /// Bytes a value takes in memory.
+    pub fn size(&self) -> usize {
+        match self {
+            ValueKind::U8 | ValueKind::Bcd8 => 1,
+            ValueKind::U16 | ValueKind::Bcd16 => 2,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValueKind::U8 => "u8",
+            ValueKind::U16 => "u16",
+            ValueKind::Bcd8 => "bcd8",
+            ValueKind::Bcd16 => "bcd16",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|x| x.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown value type: {s}"))
+    }
+
+    /// The value of `bytes`, `None` when they aren't valid BCD.
+    fn decode(&self, bytes: &[u8]) -> Option<u16> {
+        match self {
+            ValueKind::U8 => Some(bytes[0] as u16),
+            ValueKind::U16 => Some(u16::from_le_bytes([bytes[0], bytes[1]])),
+            ValueKind::Bcd8 => from_bcd(bytes[0]),
+            ValueKind::Bcd16 => Some(from_bcd(bytes[1])? * 100 + from_bcd(bytes[0])?),
+        }
+    }
+
+    fn encode(&self, value: u16) -> Vec<u8> {
+        match self {
+            ValueKind::U8 => vec![value as u8],
+            ValueKind::U16 => value.to_le_bytes().to_vec(),
+            ValueKind::Bcd8 => vec![to_bcd(value % 100)],
+            ValueKind::Bcd16 => vec![to_bcd(value % 100), to_bcd(value / 100 % 100)],
+        }
+    }
+}
+
+fn from_bcd(x: u8) -> Option<u16> {
+    let (hi, lo) = (x >> 4, x & 0xF);
+
+    (hi < 10 && lo < 10).then_some(hi as u16 * 10 + lo as u16)
+}
+
+fn to_bcd(x: u16) -> u8 {
+    (((x / 10) << 4) | (x % 10)) as u8
+}
+
+/// Keeps candidates whose value compares to the previous snapshot, or to a
+/// fixed value, with `op`. `>` alone means increased, `!=` changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Filter {
+    pub op: CmpOp,
+    pub value: Option<u16>,
+}
+
+impl Filter {
+    pub fn new(op: CmpOp, value: Option<u16>) -> Self {
+        Self { op, value }
+    }
+
+    /// `<op>` or `<op><value>`, e.g. `>`, `!=`, `==5` or `<$10`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        // two-char symbols first so `<=` isn't taken for `<`
+        let ops = [
+            CmpOp::Eq,
+            CmpOp::Ne,
+            CmpOp::Le,
+            CmpOp::Ge,
+            CmpOp::Lt,
+            CmpOp::Gt,
+        ];
+
+        for op in ops {
+            if let Some(value) = s.strip_prefix(op.symbol()) {
+                let value = match value.trim() {
+                    "" => None,
+                    x => Some(parse_number(x)?),
+                };
+
+                return Ok(Self::new(op, value));
+            }
+        }
+
+        Err(format!("no comparison in filter '{s}'"))
+    }
+
+    fn matches(&self, value: u16, prev: u16) -> bool {
+        self.op.apply(value, self.value.unwrap_or(prev))
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Some(value) => write!(f, "{} {value}", self.op.symbol()),
+            None => write!(f, "{} prev", self.op.symbol()),
+        }
+    }
+}
+
+/// A bank of RAM as one contiguous run of snapshot bytes.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    region: Region,
+    bank: u8,
+    start: u16,
+    len: usize,
+    /// Offset in the snapshot.
+    offset: usize,
+}
+
+impl Segment {
+    fn read(&self, bus: &Bus, i: usize) -> u8 {
+        let addr = self.start + i as u16;
+
+        match self.region {
+            Region::Wram => bus.io.ram.read_wram_in(self.bank, addr),
+            Region::Hram => bus.io.ram.read_hram(addr),
+            Region::Sram => bus.cart.read_ram_bank(self.bank, addr),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchResult {
+    pub location: Location,
+    pub value: u16,
+    pub prev: u16,
+}
+
+/// A cheat finder over WRAM, HRAM and cartridge RAM. Every bank is read
+/// directly, so values in banks that aren't mapped are still followed.
+/// Each [`Self::filter`] compares against the snapshot of the one before.
+#[derive(Debug, Clone)]
+pub struct RamSearch {
+    kind: ValueKind,
+    segments: Vec<Segment>,
+    snapshot: Vec<u8>,
+    prev: Vec<u8>,
+    /// Snapshot offsets of the remaining candidates.
+    candidates: Vec<usize>,
+}
+
+impl RamSearch {
+    pub fn new(bus: &Bus, kind: ValueKind) -> Self {
+        let wram_banks = if bus.io.ppu.lcd.is_cgb_mode() { 7 } else { 1 };
+        let sram_banks = bus.cart.ram_size().div_ceil(SRAM_BANK_SIZE);
+        let mut segments = Vec::with_capacity(wram_banks + sram_banks + 2);
+        let mut offset = 0;
+        let mut push = |region, bank, start, len| {
+            segments.push(Segment {
+                region,
+                bank,
+                start,
+                len,
+                offset,
+            });
+            offset += len;
+        };
+
+        push(Region::Wram, 0, 0xC000, WRAM_BANK_SIZE);
+        for bank in 1..=wram_banks {
+            push(Region::Wram, bank as u8, 0xD000, WRAM_BANK_SIZE);
+        }
+        push(Region::Hram, 0, HRAM_START, HRAM_SIZE);
+        for bank in 0..sram_banks {
+            let len = (bus.cart.ram_size() - bank * SRAM_BANK_SIZE).min(SRAM_BANK_SIZE);
+            push(Region::Sram, bank as u8, 0xA000, len);
+        }
+
+        // 16-bit values don't straddle banks
+        let candidates = segments
+            .iter()
+            .flat_map(|x| x.offset..x.offset + (x.len + 1).saturating_sub(kind.size()))
+            .collect();
+
+        let mut search = Self {
+            kind,
+            segments,
+            snapshot: Vec::new(),
+            prev: Vec::new(),
+            candidates,
+        };
+        search.snapshot = search.read_snapshot(bus);
+        search.prev = search.snapshot.clone();
+        let (kind, snapshot) = (search.kind, &search.snapshot);
+        search
+            .candidates
+            .retain(|&i| kind.decode(&snapshot[i..]).is_some());
+
+        search
+    }
+
+    pub fn kind(&self) -> ValueKind {
+        self.kind
+    }
+
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Takes a new snapshot and keeps the candidates that pass `filter`.
+    pub fn filter(&mut self, bus: &Bus, filter: Filter) {
+        let snapshot = self.read_snapshot(bus);
+        let old = std::mem::replace(&mut self.snapshot, snapshot);
+
+        self.candidates.retain(|&i| {
+            let value = self.kind.decode(&self.snapshot[i..]);
+            let prev = self.kind.decode(&old[i..]);
+
+            match (value, prev) {
+                (Some(value), Some(prev)) => filter.matches(value, prev),
+                _ => false,
+            }
+        });
+        self.prev = old;
+    }
+
+    pub fn results(&self) -> impl Iterator<Item = SearchResult> + '_ {
+        self.candidates.iter().map(|&i| SearchResult {
+            location: self.location(i),
+            value: self.value_at(&self.snapshot, i).unwrap_or_default(),
+            prev: self.value_at(&self.prev, i).unwrap_or_default(),
+        })
+    }
+
+    /// GameShark codes that hold `location` at `value` every frame.
+    pub fn to_cheat(&self, location: Location, value: u16) -> Cheat {
+        let bytes = self.kind.encode(value);
+        let code_type = match location.region {
+            Region::Sram => 0x80 | location.bank,
+            Region::Wram if location.addr >= 0xD000 && self.is_cgb_banked() => 0x90 | location.bank,
+            Region::Wram | Region::Hram => 0x01,
+        };
+        let code = bytes
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let [lo, hi] = (location.addr + i as u16).to_le_bytes();
+                format!("{code_type:02X}{value:02X}{lo:02X}{hi:02X}")
+            })
+            .collect::<Vec<_>>()
+            .join("+");
+
+        Cheat::new(format!("{location} -> {value}"), code)
+    }
+
+    /// A write watchpoint over the value at `location`.
+    pub fn to_watchpoint(&self, location: Location) -> Watchpoint {
+        let end = location.addr + self.kind.size() as u16 - 1;
+
+        Watchpoint::new(location.addr, end).write()
+    }
+
+    fn is_cgb_banked(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|x| x.region == Region::Wram && x.bank > 1)
+    }
+
+    fn read_snapshot(&self, bus: &Bus) -> Vec<u8> {
+        let mut snapshot = Vec::with_capacity(self.snapshot.len());
+
+        for segment in self.segments.iter() {
+            snapshot.extend((0..segment.len).map(|i| segment.read(bus, i)));
+        }
+
+        snapshot
+    }
+
+    fn value_at(&self, snapshot: &[u8], i: usize) -> Option<u16> {
+        self.kind.decode(&snapshot[i..])
+    }
+
+    fn location(&self, i: usize) -> Location {
+        let segment = self
+            .segments
+            .iter()
+            .rfind(|x| x.offset <= i)
+            .expect("offset in a segment");
+
+        Location {
+            region: segment.region,
+            bank: segment.bank,
+            addr: segment.start + (i - segment.offset) as u16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cart::Cart;
+
+    fn bus() -> Bus {
+        let mut rom = vec![0; 0x8000];
+        // MBC1+RAM+BATTERY with 8K of RAM
+        rom[0x147] = 0x03;
+        rom[0x149] = 0x02;
+        let cart = Cart::new(rom.into_boxed_slice()).unwrap();
+
+        Bus::new(cart, Default::default(), None)
+    }
+
+    #[test]
+    fn test_filter_parse() {
+        assert_eq!(Filter::parse(">"), Ok(Filter::new(CmpOp::Gt, None)));
+        assert_eq!(
+            Filter::parse("<= $10"),
+            Ok(Filter::new(CmpOp::Le, Some(16)))
+        );
+        assert_eq!(Filter::parse("==5"), Ok(Filter::new(CmpOp::Eq, Some(5))));
+        assert!(Filter::parse("5").is_err());
+    }
+
+    #[test]
+    fn test_search_narrows() {
+        let mut bus = bus();
+        bus.write(0xC123, 3);
+        bus.write(0xFF90, 3);
+        let mut search = RamSearch::new(&bus, ValueKind::U8);
+        let all = search.len();
+
+        bus.write(0xC123, 2);
+        bus.write(0xFF90, 4);
+        search.filter(&bus, Filter::parse("<").unwrap());
+        assert!(search.len() < all);
+
+        search.filter(&bus, Filter::parse("==2").unwrap());
+        let results: Vec<_> = search.results().collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].location.addr, 0xC123);
+        assert_eq!(results[0].value, 2);
+
+        let cheat = search.to_cheat(results[0].location, 9);
+        assert_eq!(cheat.code, "010923C1");
+        assert!(cheat.parse().is_ok());
+        assert_eq!(
+            search.to_watchpoint(results[0].location),
+            Watchpoint::new(0xC123, 0xC123).write()
+        );
+    }
+
+    #[test]
+    fn test_bcd16() {
+        let mut bus = bus();
+        bus.cart.write_ram_bank(0, 0xA000, 0x0A);
+        bus.cart.write_ram_bank(0, 0xA010, 0x34);
+        bus.cart.write_ram_bank(0, 0xA011, 0x12);
+        let mut search = RamSearch::new(&bus, ValueKind::Bcd16);
+        search.filter(&bus, Filter::parse("==1234").unwrap());
+
+        let results: Vec<_> = search.results().collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].location.region, Region::Sram);
+        assert_eq!(results[0].location.addr, 0xA010);
+
+        let cheat = search.to_cheat(results[0].location, 99);
+        assert_eq!(cheat.code, "809910A0+800011A0");
+    }
+}
@@ -1,5 +1,6 @@
 use crate::auxiliary::clock::Clock;
 use crate::cpu::Registers;
+use crate::debugger::{BreakReason, Debugger};
 use serde::{Deserialize, Serialize};
 
 pub const CPU_CLOCK_SPEED: u32 = 4194304;
@@ -16,6 +17,23 @@ pub struct Cpu {
     pub clock: Clock,
     pub step_ctx: StepCtx,
     pub stop_m_cycles: u32,
+    #[cfg(feature = "debug")]
+    #[serde(skip)]
+    pub probe: crate::debugger::Probe,
+    /// Opt-in ROM coverage, recorded while it's set.
+    #[cfg(feature = "debug")]
+    #[serde(skip)]
+    pub cdl: Option<Box<crate::debugger::CodeDataLog>>,
+    /// Opt-in call-graph profiling, also while it's set.
+    #[cfg(feature = "debug")]
+    #[serde(skip)]
+    pub profiler: Option<Box<crate::debugger::Profiler>>,
+    /// Steps taken so far, halted ones included: the position time-travel
+    /// debugging seeks to. Save states and rewind snapshots carry it apart,
+    /// so the serialized `Cpu` is the same with and without `debug`.
+    #[cfg(feature = "debug")]
+    #[serde(skip)]
+    pub steps: u64,
 }
 
 impl Cpu {
@@ -26,6 +44,14 @@ impl Cpu {
             step_ctx: StepCtx::default(),
             clock,
             stop_m_cycles: 0,
+            #[cfg(feature = "debug")]
+            probe: Default::default(),
+            #[cfg(feature = "debug")]
+            cdl: None,
+            #[cfg(feature = "debug")]
+            profiler: None,
+            #[cfg(feature = "debug")]
+            steps: 0,
         }
     }
 
@@ -48,6 +74,8 @@ impl Cpu {
     pub fn goto_addr(&mut self, addr: u16) {
         self.clock.tick_m_cycles(1); // internal: branch decision?
         self.registers.pc = addr;
+        #[cfg(feature = "debug")]
+        self.log_cdl(addr, crate::debugger::CodeDataLog::JUMP_TARGET);
     }
 
     #[inline(always)]
@@ -55,12 +83,19 @@ impl Cpu {
         self.clock.tick_m_cycles(1); // internal: branch decision?
         self.push16(self.registers.pc);
         self.registers.pc = addr;
+        #[cfg(feature = "debug")]
+        {
+            self.log_cdl(addr, crate::debugger::CodeDataLog::SUB_ENTRY);
+            self.profile_call(addr);
+        }
     }
 
     /// Reads 8bit immediate data by PC and increments PC + 1. Costs 1 M-Cycle.
     #[inline(always)]
     pub fn read_pc(&mut self) -> u8 {
         let value = self.clock.bus.read(self.registers.pc);
+        #[cfg(feature = "debug")]
+        self.log_cdl(self.registers.pc, crate::debugger::CodeDataLog::CODE);
         self.registers.pc = self.registers.pc.wrapping_add(1);
         self.clock.tick_m_cycles(1);
 
@@ -77,6 +112,11 @@ impl Cpu {
     #[inline(always)]
     pub fn read_memory(&mut self, address: u16) -> u8 {
         let value = self.clock.bus.read(address);
+        #[cfg(feature = "debug")]
+        {
+            self.probe.on_read(address, value);
+            self.log_cdl(address, crate::debugger::CodeDataLog::DATA);
+        }
         self.clock.tick_m_cycles(1);
 
         value
@@ -86,18 +126,78 @@ impl Cpu {
     #[inline(always)]
     pub fn write_to_memory(&mut self, address: u16, value: u8) {
         self.clock.bus.write(address, value);
+        #[cfg(feature = "debug")]
+        self.probe.on_write(address, value);
         self.clock.tick_m_cycles(1);
     }
 
+    /// Marks how the ROM byte at `addr` was used, when recording coverage.
+    #[cfg(feature = "debug")]
+    #[inline(always)]
+    pub fn log_cdl(&mut self, addr: u16, flags: u8) {
+        if let Some(cdl) = self.cdl.as_mut() {
+            cdl.log(&self.clock.bus.cart, addr, flags);
+        }
+    }
+
+    /// Starts profiling calls from the current cycle and frame.
+    #[cfg(feature = "debug")]
+    pub fn start_profiler(&mut self) {
+        let frame = self.clock.bus.io.ppu.current_frame;
+        let profiler = crate::debugger::Profiler::new(self.clock.get_m_cycles(), frame);
+        self.profiler = Some(Box::new(profiler));
+    }
+
+    /// Tells the profiler a call into `addr` was made, when profiling.
+    #[cfg(feature = "debug")]
+    #[inline(always)]
+    pub fn profile_call(&mut self, addr: u16) {
+        if let Some(mut profiler) = self.profiler.take() {
+            let routine = crate::debugger::Routine {
+                bank: crate::debugger::get_bank_at(self, addr),
+                addr,
+            };
+            let frame = self.clock.bus.io.ppu.current_frame;
+            profiler.on_call(routine, self.registers.sp, self.clock.get_m_cycles(), frame);
+            self.profiler = Some(profiler);
+        }
+    }
+
+    /// Tells the profiler a return was made, when profiling.
+    #[cfg(feature = "debug")]
+    #[inline(always)]
+    pub fn profile_return(&mut self) {
+        if let Some(profiler) = self.profiler.as_mut() {
+            let frame = self.clock.bus.io.ppu.current_frame;
+            profiler.on_return(self.registers.sp, self.clock.get_m_cycles(), frame);
+        }
+    }
+
+    /// Steps with the debugger attached. Returns why execution stopped when
+    /// a breakpoint or watchpoint is hit; a pre-step hit doesn't execute.
     #[inline]
-    pub fn step_debug(&mut self, debugger: &mut crate::debugger::Debugger) {
+    pub fn step_debug(&mut self, debugger: &mut Debugger) -> Option<BreakReason> {
+        if let Some(reason) = debugger.check_before(self) {
+            return Some(reason);
+        }
+
         debugger.print(self);
         debugger.poll_serial(&mut self.clock.bus);
+        let ly = self.clock.bus.io.ppu.lcd.ly;
+        #[cfg(feature = "debug")]
+        self.probe.begin(debugger.breakpoints.watches_access());
         self.step();
+
+        debugger.check_after(self, ly)
     }
 
     #[inline]
     pub fn step(&mut self) {
+        #[cfg(feature = "debug")]
+        {
+            self.steps += 1;
+        }
+
         if self.stop_m_cycles > 0 {
             self.stop_m_cycles -= 1;
             return;
@@ -118,6 +218,11 @@ impl Cpu {
         }
 
         self.step_ctx.opcode = self.read_pc();
+        #[cfg(feature = "debug")]
+        self.log_cdl(
+            self.registers.pc.wrapping_sub(1),
+            crate::debugger::CodeDataLog::OPCODE,
+        );
         let prev_enabling_ime = self.enabling_ime;
         self.execute_opcode();
 
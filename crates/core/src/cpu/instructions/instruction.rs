@@ -28,6 +28,34 @@ impl Instruction {
         self.cond_type
     }
 
+    /// Encoded length in bytes, opcode included.
+    pub fn get_length(&self) -> u16 {
+        match self.addr_mode {
+            AddressMode::R_D16(_)
+            | AddressMode::D16
+            | AddressMode::A16_R(_)
+            | AddressMode::R_A16(_) => 3,
+            AddressMode::R_D8(_)
+            | AddressMode::R_A8(_)
+            | AddressMode::R_HA8(_)
+            | AddressMode::A8_R(_)
+            | AddressMode::LH_SPi8
+            | AddressMode::D8
+            | AddressMode::MR_D8(_) => 2,
+            AddressMode::IMP
+            | AddressMode::R(_)
+            | AddressMode::R_R(_, _)
+            | AddressMode::MR_R(_, _)
+            | AddressMode::R_MR(_, _)
+            | AddressMode::R_HMR(_, _)
+            | AddressMode::R_HLI(_)
+            | AddressMode::R_HLD(_)
+            | AddressMode::HLI_R(_)
+            | AddressMode::HLD_R(_)
+            | AddressMode::MR(_) => 1,
+        }
+    }
+
     pub const fn unknown(_opcode: u8) -> Self {
         Self::new(Mnemonic::Unknown, None, 0, AddressMode::IMP)
     }
@@ -4,6 +4,8 @@ impl Cpu {
     #[inline(always)]
     pub fn execute_jp_no_hl(&mut self) {
         self.registers.pc = self.registers.get_register::<{ RegisterType::HL as u8 }>();
+        #[cfg(feature = "debug")]
+        self.log_cdl(self.registers.pc, crate::debugger::CodeDataLog::JUMP_TARGET);
     }
 
     #[inline(always)]
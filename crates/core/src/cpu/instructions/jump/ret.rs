@@ -15,6 +15,8 @@ impl Cpu {
             let addr = (hi << 8) | lo;
             self.registers.pc = addr;
             self.clock.tick_m_cycles(1); // internal: set PC?
+            #[cfg(feature = "debug")]
+            self.profile_return();
         }
     }
 }
@@ -52,6 +52,36 @@ mod tests {
         assert_eq!(M_CYCLES_BY_OPCODES[opcode], cpu.clock.get_m_cycles());
     }
 
+    #[test]
+    pub fn test_length_matches_pc_advance() {
+        for (opcode, instr) in INSTRUCTIONS.iter().enumerate() {
+            let branches = matches!(
+                instr.get_mnemonic(),
+                Mnemonic::Jp
+                    | Mnemonic::Jr
+                    | Mnemonic::Call
+                    | Mnemonic::Ret
+                    | Mnemonic::Reti
+                    | Mnemonic::Rst
+                    | Mnemonic::Halt
+                    | Mnemonic::Stop
+                    | Mnemonic::Unknown
+            );
+
+            if branches {
+                continue;
+            }
+
+            let clock = Clock::new(Bus::with_bytes(vec![0; 0x10000], Io::default()));
+            let mut cpu = Cpu::new(clock);
+            cpu.registers.pc = 0;
+            cpu.clock.bus.write(0, opcode as u8);
+            cpu.step();
+
+            assert_eq!(instr.get_length(), cpu.registers.pc, "opcode {opcode:02X}");
+        }
+    }
+
     #[test]
     pub fn test_m_cycles_call() {
         let clock = Clock::new(
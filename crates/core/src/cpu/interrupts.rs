@@ -35,6 +35,11 @@ impl Cpu {
         self.registers.pc = match interrupt {
             Some((addr, it)) => {
                 self.clock.bus.io.interrupts.ack_interrupt(it);
+                #[cfg(feature = "debug")]
+                self.clock
+                    .bus
+                    .io
+                    .record_event(crate::debugger::EventKind::Dispatch(it));
                 addr
             }
             None => {
@@ -42,11 +47,16 @@ impl Cpu {
                 0x0000
             },
         };
+        #[cfg(feature = "debug")]
+        {
+            self.log_cdl(self.registers.pc, crate::debugger::CodeDataLog::SUB_ENTRY);
+            self.profile_call(self.registers.pc);
+        }
         self.clock.tick_m_cycles(1);
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum InterruptType {
     VBlank = 1,
@@ -64,6 +74,10 @@ pub struct Interrupts {
     pub ime: bool,
     /// Interrupt enable register
     pub ie: u8,
+    /// Requests since the event timeline last took them.
+    #[cfg(feature = "debug")]
+    #[serde(skip)]
+    pub requested: u8,
 }
 
 impl Interrupts {
@@ -75,11 +89,19 @@ impl Interrupts {
             },
             ime: false,
             ie: 0x0,
+            #[cfg(feature = "debug")]
+            requested: 0,
         }
     }
 
     #[inline(always)]
     pub fn get_pending(&mut self) -> Option<(u16, InterruptType)> {
+        self.peek_pending()
+    }
+
+    /// The highest-priority pending interrupt and its vector, if any.
+    #[inline(always)]
+    pub fn peek_pending(&self) -> Option<(u16, InterruptType)> {
         for (address, interrupt_type) in INTERRUPTS {
             if self.is_pending(interrupt_type) {
                 return Some((address, interrupt_type));
@@ -88,9 +110,23 @@ impl Interrupts {
 
         None
     }
+
+    /// Interrupt that would be dispatched before the next instruction.
+    #[inline(always)]
+    pub fn next_dispatch(&self) -> Option<(u16, InterruptType)> {
+        if self.ime {
+            return self.peek_pending();
+        }
+
+        None
+    }
     #[inline(always)]
     pub const fn request_interrupt(&mut self, it: InterruptType) {
         self.int_flags |= it as u8;
+        #[cfg(feature = "debug")]
+        {
+            self.requested |= it as u8;
+        }
     }
 
     #[inline(always)]
@@ -178,6 +178,48 @@ impl Registers {
         }
     }
 
+    /// Runtime-dispatched register read, for tooling that picks the register
+    /// at runtime (debugger conditions) rather than per opcode.
+    pub fn read(&mut self, r: RegisterType) -> u16 {
+        match r {
+            RegisterType::A => self.a as u16,
+            RegisterType::F => self.flags.get_byte() as u16,
+            RegisterType::B => self.b as u16,
+            RegisterType::C => self.c as u16,
+            RegisterType::D => self.d as u16,
+            RegisterType::E => self.e as u16,
+            RegisterType::H => self.h as u16,
+            RegisterType::L => self.l as u16,
+            RegisterType::AF => self.get_af(),
+            RegisterType::BC => self.get_bc(),
+            RegisterType::DE => self.get_de(),
+            RegisterType::HL => self.get_hl(),
+            RegisterType::PC => self.pc,
+            RegisterType::SP => self.sp,
+        }
+    }
+
+    /// Runtime-dispatched counterpart of [`Self::read`]; 8-bit registers
+    /// take the low byte.
+    pub fn write(&mut self, r: RegisterType, value: u16) {
+        match r {
+            RegisterType::A => self.a = value as u8,
+            RegisterType::F => self.flags.set_byte(value as u8),
+            RegisterType::B => self.b = value as u8,
+            RegisterType::C => self.c = value as u8,
+            RegisterType::D => self.d = value as u8,
+            RegisterType::E => self.e = value as u8,
+            RegisterType::H => self.h = value as u8,
+            RegisterType::L => self.l = value as u8,
+            RegisterType::AF => self.set_af(value),
+            RegisterType::BC => self.set_bc(value),
+            RegisterType::DE => self.set_de(value),
+            RegisterType::HL => self.set_hl(value),
+            RegisterType::PC => self.pc = value,
+            RegisterType::SP => self.sp = value,
+        }
+    }
+
     #[inline(always)]
     pub fn get_af(&mut self) -> u16 {
         (self.a as u16) << 8 | self.flags.get_byte() as u16
@@ -0,0 +1,596 @@
+use super::Symbols;
+use crate::cpu::interrupts::InterruptType;
+use crate::cpu::{Cpu, RegisterType};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefix of the CB opcode page; `0xCB00 | op` selects a prefixed opcode in
+/// [`Breakpoints::opcodes`].
+pub const CB_PREFIX: u8 = 0xCB;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccessKind {
+    Read,
+    Write,
+    Exec,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemAccess {
+    pub addr: u16,
+    pub value: u8,
+    pub kind: AccessKind,
+}
+
+/// Records the memory accesses made by the CPU during one step, so access
+/// watchpoints can be matched once the instruction has completed. Only the
+/// `debug` feature wires it into the CPU; without it every bus access stays
+/// free of the extra branch and read/write watchpoints never fire.
+#[derive(Debug, Clone, Default)]
+pub struct Probe {
+    enabled: bool,
+    /// Records on every step, whatever `begin` is told.
+    always: bool,
+    accesses: Vec<MemAccess>,
+}
+
+impl Probe {
+    #[inline(always)]
+    pub fn begin(&mut self, enabled: bool) {
+        self.enabled = enabled || self.always;
+        self.accesses.clear();
+    }
+
+    /// Keeps the probe on for a watcher other than the debugger, e.g. a
+    /// script's write hooks.
+    #[inline(always)]
+    pub fn set_always(&mut self, always: bool) {
+        self.always = always;
+    }
+
+    #[inline(always)]
+    pub fn on_read(&mut self, addr: u16, value: u8) {
+        if self.enabled {
+            self.accesses.push(MemAccess {
+                addr,
+                value,
+                kind: AccessKind::Read,
+            });
+        }
+    }
+
+    #[inline(always)]
+    pub fn on_write(&mut self, addr: u16, value: u8) {
+        if self.enabled {
+            self.accesses.push(MemAccess {
+                addr,
+                value,
+                kind: AccessKind::Write,
+            });
+        }
+    }
+
+    pub fn accesses(&self) -> &[MemAccess] {
+        &self.accesses
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operand {
+    Reg(RegisterType),
+    Mem(u16),
+}
+
+impl Operand {
+    pub fn read(&self, cpu: &mut Cpu) -> u16 {
+        match self {
+            Operand::Reg(r) => cpu.registers.read(*r),
+            Operand::Mem(addr) => cpu.clock.bus.read(*addr) as u16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CmpOp {
+    pub const fn symbol(&self) -> &'static str {
+        match self {
+            CmpOp::Eq => "==",
+            CmpOp::Ne => "!=",
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+        }
+    }
+
+    pub fn apply(&self, lhs: u16, rhs: u16) -> bool {
+        match self {
+            CmpOp::Eq => lhs == rhs,
+            CmpOp::Ne => lhs != rhs,
+            CmpOp::Lt => lhs < rhs,
+            CmpOp::Le => lhs <= rhs,
+            CmpOp::Gt => lhs > rhs,
+            CmpOp::Ge => lhs >= rhs,
+        }
+    }
+}
+
+/// `<register|[address]> <op> <value>`, e.g. `A == $10` or `[C0A0] > 3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Condition {
+    pub lhs: Operand,
+    pub op: CmpOp,
+    pub value: u16,
+}
+
+impl Condition {
+    pub fn new(lhs: Operand, op: CmpOp, value: u16) -> Self {
+        Self { lhs, op, value }
+    }
+
+    pub fn eval(&self, cpu: &mut Cpu) -> bool {
+        self.op.apply(self.lhs.read(cpu), self.value)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.lhs {
+            Operand::Reg(r) => write!(f, "{r:?}")?,
+            Operand::Mem(addr) => write!(f, "[{addr:04X}]")?,
+        }
+
+        write!(f, " {} ${:X}", self.op.symbol(), self.value)
+    }
+}
+
+impl Operand {
+    /// A register, matched case-insensitively, or `[addr]` where `addr` may
+    /// be a label.
+    pub fn parse(s: &str, symbols: &Symbols) -> Result<Self, String> {
+        let s = s.trim();
+
+        if let Some(addr) = s.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+            return symbols.resolve(addr).map(Operand::Mem);
+        }
+
+        RegisterType::get_all()
+            .iter()
+            .find(|r| format!("{r:?}").eq_ignore_ascii_case(s))
+            .map(|r| Operand::Reg(*r))
+            .ok_or_else(|| format!("unknown register '{s}'"))
+    }
+}
+
+impl FromStr for Operand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Operand::parse(s, &Symbols::default())
+    }
+}
+
+impl Condition {
+    pub fn parse(s: &str, symbols: &Symbols) -> Result<Self, String> {
+        // two-char symbols first so `<=` isn't taken for `<`
+        let ops = [
+            CmpOp::Eq,
+            CmpOp::Ne,
+            CmpOp::Le,
+            CmpOp::Ge,
+            CmpOp::Lt,
+            CmpOp::Gt,
+        ];
+
+        for op in ops {
+            if let Some((lhs, value)) = s.split_once(op.symbol()) {
+                let lhs = Operand::parse(lhs, symbols)?;
+                return Ok(Condition::new(lhs, op, parse_number(value)?));
+            }
+        }
+
+        Err(format!("no comparison in condition '{s}'"))
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Condition::parse(s, &Symbols::default())
+    }
+}
+
+/// `$FF` and `0xFF` are hex, anything else decimal.
+pub fn parse_number(s: &str) -> Result<u16, String> {
+    let s = s.trim();
+    let hex = s.strip_prefix('$').or_else(|| s.strip_prefix("0x"));
+
+    match hex {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|e| format!("invalid number '{s}': {e}"))
+}
+
+/// Like [`parse_number`], but bare digits are hex as in `[C000]`.
+pub fn parse_addr(s: &str) -> Result<u16, String> {
+    let s = s.trim();
+    let hex = s
+        .strip_prefix('$')
+        .or_else(|| s.strip_prefix("0x"))
+        .unwrap_or(s);
+
+    u16::from_str_radix(hex, 16).map_err(|e| format!("invalid address '{s}': {e}"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Breakpoint {
+    pub addr: u16,
+    pub condition: Option<Condition>,
+    pub enabled: bool,
+}
+
+impl Breakpoint {
+    pub fn new(addr: u16) -> Self {
+        Self {
+            addr,
+            condition: None,
+            enabled: true,
+        }
+    }
+
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+}
+
+/// Fires on any matching access to `start..=end`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watchpoint {
+    pub start: u16,
+    pub end: u16,
+    pub read: bool,
+    pub write: bool,
+    pub exec: bool,
+    pub enabled: bool,
+}
+
+impl Watchpoint {
+    pub fn new(start: u16, end: u16) -> Self {
+        Self {
+            start: start.min(end),
+            end: start.max(end),
+            read: false,
+            write: false,
+            exec: false,
+            enabled: true,
+        }
+    }
+
+    pub fn read(mut self) -> Self {
+        self.read = true;
+        self
+    }
+
+    pub fn write(mut self) -> Self {
+        self.write = true;
+        self
+    }
+
+    pub fn exec(mut self) -> Self {
+        self.exec = true;
+        self
+    }
+
+    pub fn matches(&self, addr: u16, kind: AccessKind) -> bool {
+        let kind_matches = match kind {
+            AccessKind::Read => self.read,
+            AccessKind::Write => self.write,
+            AccessKind::Exec => self.exec,
+        };
+
+        self.enabled && kind_matches && (self.start..=self.end).contains(&addr)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakReason {
+    Breakpoint {
+        pc: u16,
+    },
+    Watchpoint(MemAccess),
+    Interrupt(InterruptType),
+    Ly(u8),
+    Opcode {
+        pc: u16,
+        opcode: u16,
+    },
+    /// A step or run-to-cursor finished.
+    Step {
+        pc: u16,
+    },
+}
+
+impl fmt::Display for BreakReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BreakReason::Breakpoint { pc } => write!(f, "breakpoint at {pc:04X}"),
+            BreakReason::Watchpoint(access) => match access.kind {
+                AccessKind::Read => {
+                    write!(f, "read {:02X} from {:04X}", access.value, access.addr)
+                }
+                AccessKind::Write => {
+                    write!(f, "write {:02X} to {:04X}", access.value, access.addr)
+                }
+                AccessKind::Exec => write!(f, "exec at {:04X}", access.addr),
+            },
+            BreakReason::Interrupt(it) => write!(f, "interrupt {it:?}"),
+            BreakReason::Ly(ly) => write!(f, "LY = {ly}"),
+            BreakReason::Step { pc } => write!(f, "step at {pc:04X}"),
+            BreakReason::Opcode { pc, opcode } => {
+                if *opcode > 0xFF {
+                    write!(f, "opcode CB {:02X} at {pc:04X}", opcode & 0xFF)
+                } else {
+                    write!(f, "opcode {opcode:02X} at {pc:04X}")
+                }
+            }
+        }
+    }
+}
+
+/// Everything the debugger can stop on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Breakpoints {
+    pub pc: Vec<Breakpoint>,
+    pub watch: Vec<Watchpoint>,
+    /// Mask of `InterruptType` bits to break on before dispatch.
+    pub interrupts: u8,
+    /// Break when LY changes to this line.
+    pub ly: Option<u8>,
+    /// Break before executing these opcodes; `0xCBxx` for prefixed ones.
+    pub opcodes: Vec<u16>,
+}
+
+impl Breakpoints {
+    pub fn is_empty(&self) -> bool {
+        self.pc.is_empty()
+            && self.watch.is_empty()
+            && self.interrupts == 0
+            && self.ly.is_none()
+            && self.opcodes.is_empty()
+    }
+
+    /// Adds a breakpoint at `addr`, or toggles it off if one is already set.
+    /// Returns whether a breakpoint is now set.
+    pub fn toggle(&mut self, addr: u16) -> bool {
+        if let Some(i) = self.pc.iter().position(|x| x.addr == addr) {
+            self.pc.remove(i);
+            return false;
+        }
+
+        self.pc.push(Breakpoint::new(addr));
+        true
+    }
+
+    pub fn has_pc(&self, addr: u16) -> bool {
+        self.pc.iter().any(|x| x.enabled && x.addr == addr)
+    }
+
+    pub fn set_interrupt(&mut self, it: InterruptType, on: bool) {
+        if on {
+            self.interrupts |= it as u8;
+        } else {
+            self.interrupts &= !(it as u8);
+        }
+    }
+
+    /// Whether any watchpoint needs the CPU's data accesses recorded.
+    pub fn watches_access(&self) -> bool {
+        self.watch.iter().any(|x| x.enabled && (x.read || x.write))
+    }
+
+    /// Checks the instruction at `pc` before it executes; `pending` is the
+    /// interrupt about to be dispatched, which redirects execution to its
+    /// vector first.
+    pub fn check_before(
+        &self,
+        cpu: &mut Cpu,
+        pending: Option<(u16, InterruptType)>,
+    ) -> Option<BreakReason> {
+        if let Some((_, it)) = pending {
+            if self.interrupts & it as u8 != 0 {
+                return Some(BreakReason::Interrupt(it));
+            }
+        }
+
+        let pc = pending.map(|(addr, _)| addr).unwrap_or(cpu.registers.pc);
+
+        for bp in self.pc.iter().filter(|x| x.enabled && x.addr == pc) {
+            if bp.condition.is_none_or(|c| c.eval(cpu)) {
+                return Some(BreakReason::Breakpoint { pc });
+            }
+        }
+
+        let opcode = cpu.clock.bus.read(pc);
+
+        if self.watch.iter().any(|x| x.matches(pc, AccessKind::Exec)) {
+            return Some(BreakReason::Watchpoint(MemAccess {
+                addr: pc,
+                value: opcode,
+                kind: AccessKind::Exec,
+            }));
+        }
+
+        if !self.opcodes.is_empty() {
+            let opcode = if opcode == CB_PREFIX {
+                0xCB00 | cpu.clock.bus.read(pc.wrapping_add(1)) as u16
+            } else {
+                opcode as u16
+            };
+
+            if self.opcodes.contains(&opcode) {
+                return Some(BreakReason::Opcode { pc, opcode });
+            }
+        }
+
+        None
+    }
+
+    /// Checks what the last step did: its recorded accesses and LY changes.
+    pub fn check_after(&self, accesses: &[MemAccess], prev_ly: u8, ly: u8) -> Option<BreakReason> {
+        for access in accesses {
+            if self
+                .watch
+                .iter()
+                .any(|x| x.matches(access.addr, access.kind))
+            {
+                return Some(BreakReason::Watchpoint(*access));
+            }
+        }
+
+        match self.ly {
+            Some(target) if prev_ly != ly && ly == target => Some(BreakReason::Ly(ly)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary::clock::Clock;
+    use crate::auxiliary::io::Io;
+    use crate::bus::Bus;
+
+    fn new_cpu(program: &[u8]) -> Cpu {
+        let mut bytes = vec![0; 0x10000];
+        bytes[..program.len()].copy_from_slice(program);
+        let mut cpu = Cpu::new(Clock::new(Bus::with_bytes(bytes, Io::default())));
+        cpu.registers.pc = 0;
+
+        cpu
+    }
+
+    #[test]
+    fn test_conditional_breakpoint() {
+        let mut cpu = new_cpu(&[0x00]);
+        let mut bps = Breakpoints::default();
+        let cond = Condition::new(Operand::Reg(RegisterType::A), CmpOp::Eq, 0x42);
+        bps.pc.push(Breakpoint::new(0).with_condition(cond));
+
+        cpu.registers.a = 0x10;
+        assert_eq!(bps.check_before(&mut cpu, None), None);
+
+        cpu.registers.a = 0x42;
+        assert_eq!(
+            bps.check_before(&mut cpu, None),
+            Some(BreakReason::Breakpoint { pc: 0 })
+        );
+    }
+
+    #[test]
+    fn test_memory_condition() {
+        let mut cpu = new_cpu(&[0x00]);
+        cpu.clock.bus.write(0xC000, 5);
+        let cond = Condition::new(Operand::Mem(0xC000), CmpOp::Gt, 3);
+
+        assert!(cond.eval(&mut cpu));
+        assert_eq!(cond.to_string(), "[C000] > $3");
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        let cond: Condition = "[C000] > $3".parse().unwrap();
+        assert_eq!(cond, Condition::new(Operand::Mem(0xC000), CmpOp::Gt, 3));
+
+        let cond: Condition = "hl<=0x10".parse().unwrap();
+        assert_eq!(
+            cond,
+            Condition::new(Operand::Reg(RegisterType::HL), CmpOp::Le, 0x10)
+        );
+
+        assert!("A = 1".parse::<Condition>().is_err());
+        assert!("Q == 1".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn test_interrupt_redirects_pc() {
+        let mut cpu = new_cpu(&[0x00]);
+        let mut bps = Breakpoints::default();
+        bps.pc.push(Breakpoint::new(0x40));
+        let pending = Some((0x40, InterruptType::VBlank));
+
+        assert_eq!(
+            bps.check_before(&mut cpu, pending),
+            Some(BreakReason::Breakpoint { pc: 0x40 })
+        );
+
+        bps.set_interrupt(InterruptType::VBlank, true);
+        assert_eq!(
+            bps.check_before(&mut cpu, pending),
+            Some(BreakReason::Interrupt(InterruptType::VBlank))
+        );
+    }
+
+    #[test]
+    fn test_cb_opcode() {
+        let mut cpu = new_cpu(&[0xCB, 0x37]);
+        let mut bps = Breakpoints::default();
+        bps.opcodes.push(0xCB37);
+
+        assert_eq!(
+            bps.check_before(&mut cpu, None),
+            Some(BreakReason::Opcode {
+                pc: 0,
+                opcode: 0xCB37
+            })
+        );
+    }
+
+    #[test]
+    fn test_watchpoint_range() {
+        let mut bps = Breakpoints::default();
+        bps.watch.push(Watchpoint::new(0xC010, 0xC000).write());
+        let access = |addr, kind| MemAccess {
+            addr,
+            value: 0,
+            kind,
+        };
+
+        assert_eq!(
+            bps.check_after(&[access(0xC005, AccessKind::Read)], 0, 0),
+            None
+        );
+        assert_eq!(
+            bps.check_after(&[access(0xC010, AccessKind::Write)], 0, 0),
+            Some(BreakReason::Watchpoint(access(0xC010, AccessKind::Write)))
+        );
+        assert_eq!(
+            bps.check_after(&[access(0xC011, AccessKind::Write)], 0, 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ly_break_on_change_only() {
+        let bps = Breakpoints {
+            ly: Some(10),
+            ..Default::default()
+        };
+
+        assert_eq!(bps.check_after(&[], 9, 10), Some(BreakReason::Ly(10)));
+        assert_eq!(bps.check_after(&[], 10, 10), None);
+    }
+}
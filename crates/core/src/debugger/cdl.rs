@@ -0,0 +1,199 @@
+use super::disasm::BANK_SIZE;
+use super::parse_labels;
+use crate::cart::Cart;
+use crc::{Crc, CRC_32_ISO_HDLC};
+use std::fmt::Write;
+
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+const HEADER: &[u8; 5] = b"CDLv2";
+
+/// Code/Data Log: how each ROM byte was used, by its offset in the ROM so
+/// banks don't alias. The flag layout is the one FCEUX, Mesen and BizHawk
+/// share (code in bit 0, data in bit 1), saved with Mesen's `CDLv2` header
+/// and the ROM's CRC32. The CPU fills it in with the `debug` feature.
+#[derive(Debug, Clone, Default)]
+pub struct CodeDataLog {
+    flags: Vec<u8>,
+    rom_crc: u32,
+}
+
+impl CodeDataLog {
+    /// Fetched as an opcode or an operand.
+    pub const CODE: u8 = 0x01;
+    /// Read by a load.
+    pub const DATA: u8 = 0x02;
+    /// Landed on by a taken jump.
+    pub const JUMP_TARGET: u8 = 0x04;
+    /// Landed on by a call, an RST or an interrupt.
+    pub const SUB_ENTRY: u8 = 0x08;
+    /// Fetched as an opcode. Other tools don't set it and ignore it.
+    pub const OPCODE: u8 = 0x10;
+
+    pub fn new(rom: &[u8]) -> Self {
+        Self {
+            flags: vec![0; rom.len()],
+            rom_crc: CRC32.checksum(rom),
+        }
+    }
+
+    /// Reads a log saved by [`Self::to_bytes`] or a headerless one that's
+    /// just the flags, as long as it's for `rom`.
+    pub fn from_bytes(bytes: &[u8], rom: &[u8]) -> Result<Self, String> {
+        let mut log = Self::new(rom);
+
+        let flags = match bytes.strip_prefix(HEADER) {
+            Some(rest) if rest.len() >= 4 => {
+                let crc = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+                if crc != log.rom_crc {
+                    return Err(format!(
+                        "CDL is for another ROM (CRC32 {crc:08X}, ROM is {:08X})",
+                        log.rom_crc
+                    ));
+                }
+                &rest[4..]
+            }
+            _ => bytes,
+        };
+
+        if flags.len() != rom.len() {
+            return Err(format!(
+                "CDL covers {} bytes, the ROM has {}",
+                flags.len(),
+                rom.len()
+            ));
+        }
+
+        log.flags.copy_from_slice(flags);
+
+        Ok(log)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER.len() + 4 + self.flags.len());
+        bytes.extend_from_slice(HEADER);
+        bytes.extend_from_slice(&self.rom_crc.to_le_bytes());
+        bytes.extend_from_slice(&self.flags);
+
+        bytes
+    }
+
+    /// Flags for `addr` in the bank `cart` has mapped there; RAM is ignored.
+    #[inline]
+    pub fn log(&mut self, cart: &Cart, addr: u16, flags: u8) {
+        if addr > 0x7FFF {
+            return;
+        }
+
+        let offset = cart.rom_bank(addr) as usize * BANK_SIZE + (addr as usize & 0x3FFF);
+        if let Some(x) = self.flags.get_mut(offset) {
+            *x |= flags;
+        }
+    }
+
+    /// One flag byte per ROM byte.
+    pub fn flags(&self) -> &[u8] {
+        &self.flags
+    }
+
+    /// Bytes with any of `flags` set.
+    pub fn count(&self, flags: u8) -> usize {
+        self.flags.iter().filter(|x| *x & flags != 0).count()
+    }
+
+    /// An lcov tracefile over the labels of a `.sym` or `.map` file: each
+    /// label is a function and a line of `source`, its symbol file, hit
+    /// once per opcode executed up to the next label in its bank.
+    pub fn to_lcov(&self, source: &str, text: &str) -> String {
+        let mut labels: Vec<_> = parse_labels(text)
+            .into_iter()
+            .filter(|x| x.addr <= 0x7FFF)
+            .map(|x| (get_offset(x.bank, x.addr), x))
+            .filter(|(offset, _)| *offset < self.flags.len())
+            .collect();
+        labels.sort_by_key(|(offset, _)| *offset);
+        labels.dedup_by_key(|(offset, _)| *offset);
+
+        let mut hits = Vec::with_capacity(labels.len());
+        for (i, (offset, label)) in labels.iter().enumerate() {
+            let bank_end = (offset / BANK_SIZE + 1) * BANK_SIZE;
+            let end = match labels.get(i + 1) {
+                Some((next, _)) => (*next).min(bank_end),
+                None => bank_end,
+            };
+            let end = end.min(self.flags.len());
+            let count = self.flags[*offset..end]
+                .iter()
+                .filter(|x| *x & Self::OPCODE != 0)
+                .count();
+
+            hits.push((label, count));
+        }
+
+        let mut lcov = format!("TN:\nSF:{source}\n");
+        for (label, _) in hits.iter() {
+            writeln!(lcov, "FN:{},{}", label.line + 1, label.name).ok();
+        }
+        for (label, count) in hits.iter() {
+            writeln!(lcov, "FNDA:{count},{}", label.name).ok();
+        }
+        let covered = hits.iter().filter(|(_, x)| *x > 0).count();
+        writeln!(lcov, "FNF:{}\nFNH:{covered}", hits.len()).ok();
+        for (label, count) in hits.iter() {
+            writeln!(lcov, "DA:{},{count}", label.line + 1).ok();
+        }
+        writeln!(lcov, "LF:{}\nLH:{covered}\nend_of_record", hits.len()).ok();
+
+        lcov
+    }
+}
+
+fn get_offset(bank: u16, addr: u16) -> usize {
+    match addr {
+        0x0000..=0x3FFF => addr as usize,
+        _ => bank.max(1) as usize * BANK_SIZE + (addr as usize - BANK_SIZE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let rom = vec![0x3C; 2 * BANK_SIZE];
+        let mut log = CodeDataLog::new(&rom);
+        log.flags[0x150] = CodeDataLog::CODE | CodeDataLog::OPCODE;
+        log.flags[0x4000] = CodeDataLog::DATA;
+
+        let bytes = log.to_bytes();
+        assert_eq!(&bytes[..5], b"CDLv2");
+        let read = CodeDataLog::from_bytes(&bytes, &rom).unwrap();
+        assert_eq!(read.flags(), log.flags());
+        assert_eq!(read.count(CodeDataLog::CODE | CodeDataLog::DATA), 2);
+
+        let raw = CodeDataLog::from_bytes(log.flags(), &rom).unwrap();
+        assert_eq!(raw.flags(), log.flags());
+
+        let other = vec![0; 2 * BANK_SIZE];
+        assert!(CodeDataLog::from_bytes(&bytes, &other).is_err());
+        assert!(CodeDataLog::from_bytes(&bytes[..100], &rom).is_err());
+    }
+
+    #[test]
+    fn test_lcov() {
+        let rom = vec![0; 3 * BANK_SIZE];
+        let mut log = CodeDataLog::new(&rom);
+        log.flags[0x150] = CodeDataLog::OPCODE;
+        log.flags[0x151] = CodeDataLog::OPCODE;
+        log.flags[2 * BANK_SIZE] = CodeDataLog::OPCODE;
+
+        let sym = "00:0150 Main\n00:0160 Unused\n02:4000 Far\n00:c000 wRam\n";
+        let lcov = log.to_lcov("game.sym", sym);
+        assert_eq!(
+            lcov,
+            "TN:\nSF:game.sym\nFN:1,Main\nFN:2,Unused\nFN:3,Far\n\
+             FNDA:2,Main\nFNDA:0,Unused\nFNDA:1,Far\nFNF:3\nFNH:2\n\
+             DA:1,2\nDA:2,0\nDA:3,1\nLF:3\nLH:2\nend_of_record\n"
+        );
+    }
+}
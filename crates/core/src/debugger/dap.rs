@@ -0,0 +1,981 @@
+//! A Debug Adapter Protocol server on a local TCP port, so editors such as
+//! VS Code can attach to the running ROM (a `"debugServer": <port>` launch
+//! config). Without line info there are no source breakpoints: breakpoints are
+//! set from the disassembly view, watchpoints as data breakpoints and
+//! interrupts as exception filters.
+
+use super::{
+    find_instructions_before, get_asm_string_at, get_call_stack, get_length_at, parse_addr,
+    parse_number, BreakReason, Breakpoint, Breakpoints, Condition, Debugger, Operand, StepMode,
+    Symbols, Watchpoint,
+};
+use crate::cpu::instructions::Instruction;
+use crate::cpu::interrupts::InterruptType;
+use crate::cpu::{Cpu, RegisterType};
+use serde_json::{json, Value};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+/// The SM83 is the only thread.
+const THREAD_ID: u64 = 1;
+const REGISTERS_REF: u64 = 1;
+const HARDWARE_REF: u64 = 2;
+const MAX_STACK_DEPTH: usize = 32;
+/// Instructions a disassembly goes to at most either way, as many as there
+/// are addresses.
+const MAX_INSTRUCTIONS: usize = 0x10000;
+const REGISTERS: [RegisterType; 13] = [
+    RegisterType::A,
+    RegisterType::F,
+    RegisterType::B,
+    RegisterType::C,
+    RegisterType::D,
+    RegisterType::E,
+    RegisterType::H,
+    RegisterType::L,
+    RegisterType::BC,
+    RegisterType::DE,
+    RegisterType::HL,
+    RegisterType::SP,
+    RegisterType::PC,
+];
+const HARDWARE: [(&str, u16); 5] = [
+    ("IE", 0xFFFF),
+    ("IF", 0xFF0F),
+    ("LCDC", 0xFF40),
+    ("STAT", 0xFF41),
+    ("LY", 0xFF44),
+];
+const EXCEPTION_FILTERS: [(&str, &str, InterruptType); 5] = [
+    ("vblank", "VBlank interrupt", InterruptType::VBlank),
+    ("stat", "STAT interrupt", InterruptType::LCDStat),
+    ("timer", "Timer interrupt", InterruptType::Timer),
+    ("serial", "Serial interrupt", InterruptType::Serial),
+    ("joypad", "Joypad interrupt", InterruptType::Joypad),
+];
+
+/// Serves one client at a time. It never blocks: the frontend calls
+/// [`DapServer::poll`] from its loop and reports breaks back with
+/// [`DapServer::on_break`].
+pub struct DapServer {
+    listener: TcpListener,
+    client: Option<DapClient>,
+    session: DapSession,
+}
+
+struct DapClient {
+    stream: TcpStream,
+    buf: Vec<u8>,
+}
+
+impl DapServer {
+    pub fn bind(port: u16) -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| e.to_string())?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            listener,
+            client: None,
+            session: DapSession::default(),
+        })
+    }
+
+    /// Accepts a client and handles the requests it sent since the last poll.
+    /// `stopped` is whether emulation is on a break. Returns whether it should
+    /// resume.
+    pub fn poll(&mut self, cpu: &mut Cpu, debugger: &mut Debugger, stopped: bool) -> bool {
+        if self.client.is_none() {
+            self.accept();
+        }
+
+        let Some(client) = self.client.as_mut() else {
+            return false;
+        };
+
+        let connected = client.receive();
+        self.session.stopped = stopped;
+        let mut resume = false;
+
+        while let Some(msg) = take_message(&mut client.buf) {
+            resume |= self.session.handle(&msg, cpu, debugger);
+        }
+
+        self.flush();
+
+        if !connected || self.session.disconnected {
+            log::info!("Debug adapter client disconnected");
+            self.client = None;
+            resume |= self.session.end(debugger);
+        }
+
+        resume
+    }
+
+    /// Tells the client execution stopped.
+    pub fn on_break(&mut self, reason: &BreakReason) {
+        if self.client.is_some() {
+            self.session.on_break(reason);
+            self.flush();
+        }
+    }
+
+    fn accept(&mut self) {
+        let stream = match self.listener.accept() {
+            Ok((stream, addr)) => {
+                log::info!("Debug adapter client connected from {addr}");
+                stream
+            }
+            Err(err) => {
+                if err.kind() != ErrorKind::WouldBlock {
+                    log::warn!("Debug adapter accept failed: {err}");
+                }
+
+                return;
+            }
+        };
+
+        if let Err(err) = stream.set_nonblocking(true) {
+            log::warn!("Debug adapter client setup failed: {err}");
+            return;
+        }
+
+        stream.set_nodelay(true).ok();
+        self.session = DapSession::default();
+        self.client = Some(DapClient {
+            stream,
+            buf: Vec::new(),
+        });
+    }
+
+    fn flush(&mut self) {
+        let Some(client) = self.client.as_mut() else {
+            self.session.outbox.clear();
+            return;
+        };
+
+        for msg in self.session.outbox.drain(..) {
+            if let Err(err) = client.send(&msg) {
+                log::warn!("Debug adapter send failed: {err}");
+                self.session.disconnected = true;
+                break;
+            }
+        }
+    }
+}
+
+impl DapClient {
+    /// Reads everything available. Returns false once the client is gone.
+    fn receive(&mut self) -> bool {
+        let mut chunk = [0; 4096];
+
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return false,
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return true,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => return false,
+            }
+        }
+    }
+
+    fn send(&mut self, msg: &Value) -> std::io::Result<()> {
+        let body = msg.to_string();
+        let frame = format!("Content-Length: {}\r\n\r\n{body}", body.len());
+
+        // blocking while writing, a large response may not fit the socket
+        // buffer at once
+        self.stream.set_nonblocking(false)?;
+        let result = self.stream.write_all(frame.as_bytes());
+        self.stream.set_nonblocking(true)?;
+
+        result
+    }
+}
+
+/// Protocol state of the connected client, independent of the transport.
+#[derive(Default)]
+struct DapSession {
+    seq: u64,
+    outbox: Vec<Value>,
+    /// Events to send once the current response is out.
+    deferred: Vec<Value>,
+    stopped: bool,
+    resume: bool,
+    /// Reason to report for the next [`BreakReason::Step`] when it wasn't a
+    /// plain step, i.e. a pause or the stop on entry.
+    step_reason: Option<&'static str>,
+    disconnected: bool,
+    instruction_bps: Vec<Breakpoint>,
+    function_bps: Vec<Breakpoint>,
+}
+
+impl DapSession {
+    /// Handles one request. Returns whether execution should resume.
+    fn handle(&mut self, req: &Value, cpu: &mut Cpu, debugger: &mut Debugger) -> bool {
+        if req["type"] != "request" {
+            return false;
+        }
+
+        let command = req["command"].as_str().unwrap_or_default();
+        let args = &req["arguments"];
+        let result = match command {
+            "initialize" => {
+                self.defer_event("initialized", Value::Null);
+                Ok(capabilities())
+            }
+            "launch" | "attach" => {
+                if args["stopOnEntry"].as_bool().unwrap_or_default() {
+                    self.stop(debugger, "entry");
+                }
+
+                Ok(Value::Null)
+            }
+            "configurationDone" => Ok(Value::Null),
+            "setBreakpoints" => Ok(set_source_breakpoints(args)),
+            "setInstructionBreakpoints" => {
+                let (bps, body) = parse_breakpoints(args, &debugger.symbols, |x| {
+                    get_instruction_addr(x, &debugger.symbols)
+                });
+                self.instruction_bps = bps;
+                self.apply_breakpoints(debugger);
+                Ok(body)
+            }
+            "setFunctionBreakpoints" => {
+                let (bps, body) = parse_breakpoints(args, &debugger.symbols, |x| {
+                    get_function_addr(x, &debugger.symbols)
+                });
+                self.function_bps = bps;
+                self.apply_breakpoints(debugger);
+                Ok(body)
+            }
+            "setExceptionBreakpoints" => Ok(set_exception_breakpoints(args, debugger)),
+            "dataBreakpointInfo" => Ok(data_breakpoint_info(args, &debugger.symbols)),
+            "setDataBreakpoints" => Ok(set_data_breakpoints(args, debugger)),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "SM83" }] })),
+            "stackTrace" => Ok(stack_trace(args, cpu, &debugger.symbols)),
+            "scopes" => Ok(scopes()),
+            "variables" => variables(args, cpu),
+            "setVariable" => set_variable(args, cpu),
+            "evaluate" => evaluate(args, cpu, &debugger.symbols),
+            "readMemory" => read_memory(args, cpu),
+            "disassemble" => disassemble(args, cpu, &debugger.symbols),
+            "continue" => {
+                self.resume();
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "next" => self.step(debugger, StepMode::over(cpu)),
+            "stepIn" => self.step(debugger, StepMode::Into),
+            "stepOut" => self.step(debugger, StepMode::out(cpu)),
+            "pause" => {
+                self.stop(debugger, "pause");
+                Ok(Value::Null)
+            }
+            "disconnect" => {
+                self.disconnected = true;
+                Ok(Value::Null)
+            }
+            _ => Err(format!("unsupported request '{command}'")),
+        };
+
+        self.respond(req, command, result);
+        self.outbox.append(&mut self.deferred);
+
+        std::mem::take(&mut self.resume)
+    }
+
+    fn on_break(&mut self, reason: &BreakReason) {
+        let step_reason = self.step_reason.take();
+        let dap_reason = match reason {
+            BreakReason::Breakpoint { .. } => "instruction breakpoint",
+            BreakReason::Watchpoint(_) => "data breakpoint",
+            BreakReason::Interrupt(_) => "exception",
+            BreakReason::Ly(_) | BreakReason::Opcode { .. } => "breakpoint",
+            BreakReason::Step { .. } => step_reason.unwrap_or("step"),
+        };
+
+        self.stopped = true;
+        self.send_stopped(dap_reason, &reason.to_string());
+    }
+
+    /// Drops what the client set up. Returns whether execution should resume.
+    fn end(&mut self, debugger: &mut Debugger) -> bool {
+        debugger.breakpoints = Breakpoints::default();
+        debugger.clear_step_mode();
+        self.disconnected = false;
+
+        std::mem::take(&mut self.stopped)
+    }
+
+    /// The client owns all PC breakpoints while attached.
+    fn apply_breakpoints(&self, debugger: &mut Debugger) {
+        debugger.breakpoints.pc.clear();
+        debugger
+            .breakpoints
+            .pc
+            .extend_from_slice(&self.instruction_bps);
+        debugger
+            .breakpoints
+            .pc
+            .extend_from_slice(&self.function_bps);
+    }
+
+    fn step(&mut self, debugger: &mut Debugger, mode: StepMode) -> Result<Value, String> {
+        debugger.set_step_mode(mode);
+        self.resume();
+
+        Ok(Value::Null)
+    }
+
+    /// Stops right away when already stopped, otherwise before the next
+    /// instruction.
+    fn stop(&mut self, debugger: &mut Debugger, reason: &'static str) {
+        if self.stopped {
+            let event = stopped_event(reason, "");
+            self.defer_event("stopped", event);
+        } else {
+            debugger.set_step_mode(StepMode::Into);
+            self.step_reason = Some(reason);
+        }
+    }
+
+    fn resume(&mut self) {
+        self.stopped = false;
+        self.resume = true;
+    }
+
+    fn respond(&mut self, req: &Value, command: &str, result: Result<Value, String>) {
+        self.seq += 1;
+        let mut response = json!({
+            "seq": self.seq,
+            "type": "response",
+            "request_seq": req["seq"],
+            "command": command,
+            "success": result.is_ok(),
+        });
+
+        match result {
+            Ok(Value::Null) => {}
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = Value::String(message),
+        }
+
+        self.outbox.push(response);
+    }
+
+    fn send_stopped(&mut self, reason: &str, description: &str) {
+        let event = self.new_event("stopped", stopped_event(reason, description));
+        self.outbox.push(event);
+    }
+
+    fn defer_event(&mut self, event: &str, body: Value) {
+        let event = self.new_event(event, body);
+        self.deferred.push(event);
+    }
+
+    fn new_event(&mut self, event: &str, body: Value) -> Value {
+        self.seq += 1;
+        let mut msg = json!({ "seq": self.seq, "type": "event", "event": event });
+
+        if !body.is_null() {
+            msg["body"] = body;
+        }
+
+        msg
+    }
+}
+
+fn stopped_event(reason: &str, description: &str) -> Value {
+    json!({
+        "reason": reason,
+        "description": description,
+        "threadId": THREAD_ID,
+        "allThreadsStopped": true,
+    })
+}
+
+fn capabilities() -> Value {
+    let filters: Vec<Value> = EXCEPTION_FILTERS
+        .iter()
+        .map(|(filter, label, _)| json!({ "filter": filter, "label": label, "default": false }))
+        .collect();
+
+    json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsInstructionBreakpoints": true,
+        "supportsConditionalBreakpoints": true,
+        "supportsFunctionBreakpoints": true,
+        "supportsDataBreakpoints": true,
+        "supportsDisassembleRequest": true,
+        "supportsReadMemoryRequest": true,
+        "supportsSetVariable": true,
+        "supportsEvaluateForHovers": true,
+        "exceptionBreakpointFilters": filters,
+    })
+}
+
+fn set_source_breakpoints(args: &Value) -> Value {
+    let count = args["breakpoints"].as_array().map_or(0, Vec::len);
+    let bp = json!({
+        "verified": false,
+        "message": "No line info; set breakpoints in the disassembly view",
+    });
+
+    json!({ "breakpoints": vec![bp; count] })
+}
+
+/// Parses a `setInstructionBreakpoints` or `setFunctionBreakpoints` list.
+/// Each is the client's whole set of its kind.
+fn parse_breakpoints(
+    args: &Value,
+    symbols: &Symbols,
+    get_addr: impl Fn(&Value) -> Result<u16, String>,
+) -> (Vec<Breakpoint>, Value) {
+    let requested = args["breakpoints"].as_array().cloned().unwrap_or_default();
+    let mut bps = Vec::with_capacity(requested.len());
+
+    let results: Vec<Value> = requested
+        .iter()
+        .map(|x| {
+            let bp = get_addr(x).and_then(|addr| {
+                let bp = Breakpoint::new(addr);
+
+                match x["condition"].as_str().filter(|x| !x.is_empty()) {
+                    Some(condition) => Ok(bp.with_condition(Condition::parse(condition, symbols)?)),
+                    None => Ok(bp),
+                }
+            });
+
+            match bp {
+                Ok(bp) => {
+                    let addr = bp.addr;
+                    bps.push(bp);
+                    json!({
+                        "verified": true,
+                        "instructionReference": format!("0x{addr:04X}"),
+                    })
+                }
+                Err(err) => json!({ "verified": false, "message": err }),
+            }
+        })
+        .collect();
+
+    (bps, json!({ "breakpoints": results }))
+}
+
+fn get_instruction_addr(bp: &Value, symbols: &Symbols) -> Result<u16, String> {
+    let reference = bp["instructionReference"].as_str().unwrap_or_default();
+    let offset = bp["offset"].as_i64().unwrap_or_default();
+
+    Ok(symbols.resolve(reference)?.wrapping_add(offset as u16))
+}
+
+/// Function breakpoints are set by label name.
+fn get_function_addr(bp: &Value, symbols: &Symbols) -> Result<u16, String> {
+    let name = bp["name"].as_str().unwrap_or_default();
+
+    symbols
+        .find(name)
+        .map(|(_, addr)| addr)
+        .ok_or_else(|| format!("no label '{name}'"))
+}
+
+fn set_exception_breakpoints(args: &Value, debugger: &mut Debugger) -> Value {
+    let filters = args["filters"].as_array().cloned().unwrap_or_default();
+    debugger.breakpoints.interrupts = 0;
+
+    for (id, _, it) in EXCEPTION_FILTERS.iter() {
+        let on = filters.iter().any(|x| x == id);
+        debugger.breakpoints.set_interrupt(*it, on);
+    }
+
+    Value::Null
+}
+
+/// Data breakpoints are on memory only; their id is `C000` or `C000-C003`.
+fn data_breakpoint_info(args: &Value, symbols: &Symbols) -> Value {
+    let name = args["name"].as_str().unwrap_or_default();
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    let bytes = args["bytes"].as_u64().unwrap_or(1).clamp(1, 0x10000);
+
+    let Ok(start) = symbols.resolve(name) else {
+        return json!({
+            "dataId": null,
+            "description": "Only memory addresses can be watched",
+        });
+    };
+
+    let end = start.saturating_add((bytes - 1) as u16);
+    let id = if end == start {
+        format!("{start:04X}")
+    } else {
+        format!("{start:04X}-{end:04X}")
+    };
+
+    json!({
+        "dataId": id,
+        "description": format!("[{id}]"),
+        "accessTypes": ["read", "write", "readWrite"],
+        "canPersist": true,
+    })
+}
+
+/// Replaces all watchpoints, like [`set_instruction_breakpoints`].
+fn set_data_breakpoints(args: &Value, debugger: &mut Debugger) -> Value {
+    let requested = args["breakpoints"].as_array().cloned().unwrap_or_default();
+    debugger.breakpoints.watch.clear();
+
+    let results: Vec<Value> = requested
+        .iter()
+        .map(|x| {
+            let id = x["dataId"].as_str().unwrap_or_default();
+            let range = match id.split_once('-') {
+                Some((start, end)) => parse_addr(start).and_then(|s| Ok((s, parse_addr(end)?))),
+                None => parse_addr(id).map(|s| (s, s)),
+            };
+
+            let Ok((start, end)) = range else {
+                return json!({ "verified": false, "message": format!("invalid data id '{id}'") });
+            };
+
+            let wp = Watchpoint::new(start, end);
+            let wp = match x["accessType"].as_str().unwrap_or("write") {
+                "read" => wp.read(),
+                "readWrite" => wp.read().write(),
+                _ => wp.write(),
+            };
+            debugger.breakpoints.watch.push(wp);
+
+            json!({ "verified": true })
+        })
+        .collect();
+
+    json!({ "breakpoints": results })
+}
+
+/// The current PC, then the return addresses found on the stack.
+fn stack_trace(args: &Value, cpu: &Cpu, symbols: &Symbols) -> Value {
+    let mut frames = vec![cpu.registers.pc];
+    frames.extend(get_call_stack(cpu, MAX_STACK_DEPTH));
+
+    let start = args["startFrame"].as_u64().unwrap_or_default() as usize;
+    let levels = match args["levels"].as_u64() {
+        Some(levels) if levels > 0 => levels as usize,
+        _ => frames.len(),
+    };
+
+    let stack: Vec<Value> = frames
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(levels)
+        .map(|(i, addr)| {
+            json!({
+                "id": i,
+                "name": symbols
+                    .describe(cpu, *addr)
+                    .unwrap_or_else(|| format!("${addr:04X}")),
+                "line": 0,
+                "column": 0,
+                "instructionPointerReference": format!("0x{addr:04X}"),
+            })
+        })
+        .collect();
+
+    json!({ "stackFrames": stack, "totalFrames": frames.len() })
+}
+
+fn scopes() -> Value {
+    json!({
+        "scopes": [
+            {
+                "name": "Registers",
+                "presentationHint": "registers",
+                "variablesReference": REGISTERS_REF,
+                "expensive": false,
+            },
+            {
+                "name": "Hardware",
+                "variablesReference": HARDWARE_REF,
+                "expensive": false,
+            },
+        ]
+    })
+}
+
+fn variables(args: &Value, cpu: &mut Cpu) -> Result<Value, String> {
+    let vars: Vec<Value> = match args["variablesReference"].as_u64() {
+        Some(REGISTERS_REF) => {
+            let mut vars: Vec<Value> = REGISTERS
+                .iter()
+                .map(|r| register_variable(cpu, *r))
+                .collect();
+            vars.push(json!({
+                "name": "Flags",
+                "value": cpu.registers.flags.display(),
+                "variablesReference": 0,
+            }));
+
+            vars
+        }
+        Some(HARDWARE_REF) => {
+            let mut vars = vec![json!({
+                "name": "IME",
+                "value": cpu.clock.bus.io.interrupts.ime.to_string(),
+                "variablesReference": 0,
+            })];
+            vars.extend(HARDWARE.iter().map(|(name, addr)| {
+                json!({
+                    "name": name,
+                    "value": format!("${:02X}", cpu.clock.bus.read(*addr)),
+                    "variablesReference": 0,
+                })
+            }));
+
+            vars
+        }
+        _ => return Err("unknown variables reference".to_string()),
+    };
+
+    Ok(json!({ "variables": vars }))
+}
+
+fn register_variable(cpu: &mut Cpu, r: RegisterType) -> Value {
+    let value = cpu.registers.read(r);
+    let mut var = json!({
+        "name": format!("{r:?}"),
+        "value": format_value(value, r.is_16bit()),
+        "variablesReference": 0,
+    });
+
+    if r.is_16bit() {
+        var["memoryReference"] = Value::String(format!("0x{value:04X}"));
+    }
+
+    var
+}
+
+fn set_variable(args: &Value, cpu: &mut Cpu) -> Result<Value, String> {
+    if args["variablesReference"].as_u64() != Some(REGISTERS_REF) {
+        return Err("only registers can be set".to_string());
+    }
+
+    let name = args["name"].as_str().unwrap_or_default();
+    let Ok(Operand::Reg(r)) = name.parse::<Operand>() else {
+        return Err(format!("'{name}' can't be set"));
+    };
+
+    let value = parse_number(args["value"].as_str().unwrap_or_default())?;
+    cpu.registers.write(r, value);
+
+    Ok(json!({ "value": format_value(cpu.registers.read(r), r.is_16bit()) }))
+}
+
+/// A register, `[addr]`, a label's address or a breakpoint condition like
+/// `A == $10`.
+fn evaluate(args: &Value, cpu: &mut Cpu, symbols: &Symbols) -> Result<Value, String> {
+    let expression = args["expression"].as_str().unwrap_or_default();
+
+    let result = if let Ok(condition) = Condition::parse(expression, symbols) {
+        condition.eval(cpu).to_string()
+    } else if let Some((bank, addr)) = symbols.find(expression.trim()) {
+        format!("{bank:02X}:{addr:04X}")
+    } else {
+        let operand = Operand::parse(expression, symbols)?;
+        let is_16bit = matches!(operand, Operand::Reg(r) if r.is_16bit());
+        format_value(operand.read(cpu), is_16bit)
+    };
+
+    Ok(json!({ "result": result, "variablesReference": 0 }))
+}
+
+fn read_memory(args: &Value, cpu: &Cpu) -> Result<Value, String> {
+    let (addr, valid) = memory_address(args)?;
+    let count = args["count"].as_u64().unwrap_or_default() as usize;
+
+    if !valid {
+        return Ok(json!({ "address": format!("0x{addr:X}"), "unreadableBytes": count }));
+    }
+
+    let readable = count.min(0x10000 - addr as usize);
+    let bytes: Vec<u8> = (0..readable)
+        .map(|i| cpu.clock.bus.read(addr.wrapping_add(i as u16)))
+        .collect();
+
+    Ok(json!({
+        "address": format!("0x{addr:04X}"),
+        "data": encode_base64(&bytes),
+        "unreadableBytes": count - readable,
+    }))
+}
+
+/// Disassembles from `memoryReference + offset`, starting `instructionOffset`
+/// instructions away. Code that can't be decoded backwards far enough is
+/// padded with data bytes so the count still lines up.
+fn disassemble(args: &Value, cpu: &Cpu, symbols: &Symbols) -> Result<Value, String> {
+    let (base, _) = memory_address(args)?;
+    let max = MAX_INSTRUCTIONS as i64;
+    let instruction_offset = args["instructionOffset"]
+        .as_i64()
+        .unwrap_or_default()
+        .clamp(-max, max);
+    let count = args["instructionCount"]
+        .as_u64()
+        .map_or(0, |x| x.min(MAX_INSTRUCTIONS as u64) as usize);
+    let mut instructions = Vec::with_capacity(count);
+    let mut addr = base;
+
+    if instruction_offset < 0 {
+        let wanted = instruction_offset.unsigned_abs() as usize;
+        let before = find_instructions_before(cpu, base, wanted);
+        let first = before.first().copied().unwrap_or(base);
+
+        for i in (1..=wanted - before.len()).rev() {
+            let data = first.wrapping_sub(i as u16);
+            let byte = cpu.clock.bus.read(data);
+            instructions.push(disassembled(
+                data,
+                format!("{byte:02X}"),
+                format!("db ${byte:02X}"),
+                None,
+            ));
+        }
+
+        addr = first;
+    } else {
+        for _ in 0..instruction_offset {
+            addr = addr.wrapping_add(get_length_at(cpu, addr));
+        }
+    }
+
+    while instructions.len() < count {
+        let instruction = Instruction::get_by_opcode(cpu.clock.bus.read(addr));
+        let len = instruction.get_length();
+        let bytes: Vec<String> = (0..len)
+            .map(|i| format!("{:02X}", cpu.clock.bus.read(addr.wrapping_add(i))))
+            .collect();
+
+        instructions.push(disassembled(
+            addr,
+            bytes.join(" "),
+            get_asm_string_at(cpu, addr, instruction, symbols),
+            symbols.get_at(cpu, addr),
+        ));
+        addr = addr.wrapping_add(len);
+    }
+
+    Ok(json!({ "instructions": instructions }))
+}
+
+fn disassembled(addr: u16, bytes: String, asm: String, symbol: Option<&str>) -> Value {
+    let mut instruction = json!({
+        "address": format!("0x{addr:04X}"),
+        "instructionBytes": bytes,
+        "instruction": asm,
+    });
+
+    if let Some(symbol) = symbol {
+        instruction["symbol"] = Value::String(symbol.to_string());
+    }
+
+    instruction
+}
+
+/// `memoryReference + offset`, and whether it's inside the address space.
+fn memory_address(args: &Value) -> Result<(u16, bool), String> {
+    let reference = args["memoryReference"].as_str().unwrap_or_default();
+    let addr = parse_addr(reference)? as i64 + args["offset"].as_i64().unwrap_or_default();
+
+    match u16::try_from(addr) {
+        Ok(addr) => Ok((addr, true)),
+        Err(_) => Ok((addr.clamp(0, 0xFFFF) as u16, false)),
+    }
+}
+
+fn format_value(value: u16, is_16bit: bool) -> String {
+    if is_16bit {
+        format!("${value:04X}")
+    } else {
+        format!("${value:02X} ({value})")
+    }
+}
+
+/// Takes the first complete `Content-Length` framed message off `buf`.
+fn take_message(buf: &mut Vec<u8>) -> Option<Value> {
+    loop {
+        let header_end = buf.windows(4).position(|x| x == b"\r\n\r\n")?;
+        let body_start = header_end + 4;
+        let len = std::str::from_utf8(&buf[..header_end])
+            .ok()
+            .and_then(|header| {
+                header
+                    .lines()
+                    .find_map(|x| x.strip_prefix("Content-Length:"))
+                    .and_then(|x| x.trim().parse::<usize>().ok())
+            });
+
+        let Some(len) = len else {
+            log::warn!("Debug adapter message without Content-Length");
+            buf.drain(..body_start);
+            continue;
+        };
+
+        if buf.len() < body_start + len {
+            return None;
+        }
+
+        let body: Vec<u8> = buf.drain(..body_start + len).skip(body_start).collect();
+
+        match serde_json::from_slice(&body) {
+            Ok(msg) => return Some(msg),
+            Err(err) => log::warn!("Debug adapter message is not JSON: {err}"),
+        }
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - i * 6)) as usize & 0x3F] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary::clock::Clock;
+    use crate::auxiliary::io::Io;
+    use crate::bus::Bus;
+
+    fn new_cpu(program: &[u8]) -> Cpu {
+        let mut bytes = vec![0; 0x10000];
+        bytes[..program.len()].copy_from_slice(program);
+        let mut cpu = Cpu::new(Clock::new(Bus::with_bytes(bytes, Io::default())));
+        cpu.registers.pc = 0;
+        cpu.registers.sp = 0xFFFE;
+
+        cpu
+    }
+
+    fn request(command: &str, arguments: Value) -> Value {
+        json!({ "seq": 1, "type": "request", "command": command, "arguments": arguments })
+    }
+
+    #[test]
+    fn test_take_message() {
+        let body = r#"{"seq":1,"type":"request","command":"threads"}"#;
+        let frame = format!("Content-Length: {}\r\n\r\n{body}", body.len());
+        let mut buf = format!("{frame}{frame}").into_bytes();
+        buf.extend_from_slice(&frame.as_bytes()[..10]);
+
+        assert_eq!(take_message(&mut buf).unwrap()["command"], "threads");
+        assert!(take_message(&mut buf).is_some());
+        assert!(take_message(&mut buf).is_none());
+        assert_eq!(buf.len(), 10);
+    }
+
+    #[test]
+    fn test_breakpoints_and_stack() {
+        // CALL $0010 at 0, the callee at $10
+        let mut cpu = new_cpu(&[0xCD, 0x10, 0x00]);
+        let mut debugger = Debugger::new_disabled();
+        let mut session = DapSession::default();
+        let args = json!({ "breakpoints": [
+            { "instructionReference": "0x0010", "condition": "A == $1" },
+            { "instructionReference": "0x0020", "condition": "A =" },
+        ]});
+
+        session.handle(
+            &request("setInstructionBreakpoints", args),
+            &mut cpu,
+            &mut debugger,
+        );
+
+        let body = &session.outbox[0]["body"];
+        assert_eq!(body["breakpoints"][0]["verified"], true);
+        assert_eq!(body["breakpoints"][1]["verified"], false);
+        assert_eq!(debugger.breakpoints.pc.len(), 1);
+
+        cpu.step();
+        let frames = stack_trace(&Value::Null, &cpu, &Symbols::default());
+        let pcs: Vec<&str> = frames["stackFrames"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["instructionPointerReference"].as_str().unwrap())
+            .collect();
+        assert_eq!(pcs, ["0x0010", "0x0003"]);
+    }
+
+    #[test]
+    fn test_read_memory() {
+        let mut cpu = new_cpu(&[]);
+        cpu.clock.bus.write(0xC000, b'M');
+        cpu.clock.bus.write(0xC001, b'a');
+        let args = json!({ "memoryReference": "0xC000", "count": 2 });
+
+        assert_eq!(read_memory(&args, &cpu).unwrap()["data"], "TWE=");
+
+        let args = json!({ "memoryReference": "0xFFFF", "count": 4 });
+        assert_eq!(read_memory(&args, &cpu).unwrap()["unreadableBytes"], 3);
+    }
+
+    #[test]
+    fn test_disassemble_bounds() {
+        let cpu = new_cpu(&[]);
+        let symbols = Symbols::default();
+        let count = |args| {
+            let result = disassemble(&args, &cpu, &symbols).unwrap();
+            result["instructions"].as_array().unwrap().len()
+        };
+
+        let args = json!({ "memoryReference": "0x100", "instructionCount": u64::MAX });
+        assert_eq!(count(args), MAX_INSTRUCTIONS);
+        let args = json!({
+            "memoryReference": "0x100",
+            "instructionOffset": i64::MAX,
+            "instructionCount": 2,
+        });
+        assert_eq!(count(args), 2);
+        let args = json!({
+            "memoryReference": "0x100",
+            "instructionOffset": i64::MIN,
+            "instructionCount": 2,
+        });
+        assert!(count(args) >= 2);
+    }
+
+    #[test]
+    fn test_data_breakpoint_info() {
+        let symbols = Symbols::default();
+        let info = |args| data_breakpoint_info(&args, &symbols)["dataId"].clone();
+
+        assert_eq!(info(json!({ "name": "[$C000]" })), "C000");
+        assert_eq!(info(json!({ "name": "[$C000]", "bytes": 2 })), "C000-C001");
+        assert_eq!(
+            info(json!({ "name": "[$0]", "bytes": 0x10000 })),
+            "0000-FFFF"
+        );
+        assert_eq!(
+            info(json!({ "name": "[$FFF0]", "bytes": u64::MAX })),
+            "FFF0-FFFF"
+        );
+    }
+}
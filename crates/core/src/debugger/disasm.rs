@@ -0,0 +1,630 @@
+use super::{CodeDataLog, Symbols};
+use crate::cpu::instructions::{AddressMode, Instruction, JumpCondition, Mnemonic};
+use crate::cpu::RegisterType;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::ops::Range;
+
+pub const BANK_SIZE: usize = 0x4000;
+
+/// The header's start address and the interrupt vectors. RST vectors are
+/// only followed from the RSTs that reach them.
+const ENTRY_POINTS: [u16; 6] = [0x0100, 0x0040, 0x0048, 0x0050, 0x0058, 0x0060];
+
+/// What control does after an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Next,
+    /// JP or JR to the decoded address; conditional ones also fall through.
+    Jump {
+        conditional: bool,
+    },
+    /// CALL or RST to the decoded address, which returns after it.
+    Call,
+    Return {
+        conditional: bool,
+    },
+    /// `jp hl`: the target isn't known statically.
+    Indirect,
+}
+
+/// An instruction decoded from bytes alone, in RGBDS syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub len: u16,
+    pub flow: Flow,
+    /// Absolute address operand: the target of a jump or call, or the
+    /// memory address or 16-bit immediate a load uses.
+    pub addr: Option<u16>,
+    /// `@` stands for `addr` where it can be shown as a label.
+    text: String,
+}
+
+impl Decoded {
+    /// The instruction with `label` in place of its address operand.
+    pub fn format(&self, label: Option<&str>) -> String {
+        match (self.addr, label) {
+            (_, Some(label)) => self.text.replace('@', label),
+            (Some(addr), None) => self.text.replace('@', &format!("${addr:04X}")),
+            (None, None) => self.text.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for Decoded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.format(None))
+    }
+}
+
+/// Decodes the instruction at the start of `bytes`, which sits at `pc`.
+/// `None` for opcodes the SM83 doesn't have, a STOP whose second byte isn't
+/// zero (RGBDS always emits `10 00`) and instructions cut off by the end.
+pub fn decode(bytes: &[u8], pc: u16) -> Option<Decoded> {
+    let opcode = *bytes.first()?;
+    let instruction = Instruction::get_by_opcode(opcode);
+    let mnemonic = instruction.get_mnemonic();
+    let len = match mnemonic {
+        Mnemonic::Stop => 2,
+        _ => instruction.get_length(),
+    };
+    let bytes = bytes.get(..len as usize)?;
+    let n8 = bytes.get(1).copied().unwrap_or_default();
+    let n16 = u16::from_le_bytes([n8, bytes.get(2).copied().unwrap_or_default()]);
+
+    let name = format!("{mnemonic:?}").to_lowercase();
+    let cond = match instruction.get_condition() {
+        None | Some(JumpCondition::None) => None,
+        Some(cond) => Some(format!("{cond:?}").to_lowercase()),
+    };
+    let conditional = cond.is_some();
+    let with_cond = |operand: &str| match &cond {
+        Some(cond) if operand.is_empty() => format!("{name} {cond}"),
+        Some(cond) => format!("{name} {cond}, {operand}"),
+        None if operand.is_empty() => name.clone(),
+        None => format!("{name} {operand}"),
+    };
+
+    let decoded = |text: String, addr: Option<u16>, flow: Flow| Decoded {
+        len,
+        flow,
+        addr,
+        text,
+    };
+
+    let decoded = match (mnemonic, instruction.get_address_mode()) {
+        (Mnemonic::Unknown, _) => return None,
+        (Mnemonic::Prefix, _) => decoded(get_cb_text(n8), None, Flow::Next),
+        (Mnemonic::Stop, _) if n8 != 0 => return None,
+        (Mnemonic::Rst, _) => decoded(
+            format!("rst ${:02X}", instruction.operand_addr),
+            Some(instruction.operand_addr),
+            Flow::Call,
+        ),
+        (Mnemonic::Jp, AddressMode::R(_)) => decoded("jp hl".to_string(), None, Flow::Indirect),
+        (Mnemonic::Jp, _) => decoded(with_cond("@"), Some(n16), Flow::Jump { conditional }),
+        (Mnemonic::Jr, _) => {
+            let target = pc.wrapping_add(2).wrapping_add(n8 as i8 as u16);
+            decoded(with_cond("@"), Some(target), Flow::Jump { conditional })
+        }
+        (Mnemonic::Call, _) => decoded(with_cond("@"), Some(n16), Flow::Call),
+        (Mnemonic::Ret, _) => decoded(with_cond(""), None, Flow::Return { conditional }),
+        (Mnemonic::Reti, _) => decoded(name.clone(), None, Flow::Return { conditional }),
+        (_, mode) => {
+            let (operands, addr) = get_operands(mode, n8, n16)?;
+            // RGBDS style leaves out the implied A of these
+            let operands = match mnemonic {
+                Mnemonic::Sub | Mnemonic::And | Mnemonic::Xor | Mnemonic::Or | Mnemonic::Cp => {
+                    operands
+                        .strip_prefix("a, ")
+                        .unwrap_or(&operands)
+                        .to_string()
+                }
+                _ => operands,
+            };
+
+            decoded(with_cond(&operands), addr, Flow::Next)
+        }
+    };
+
+    Some(decoded)
+}
+
+fn get_operands(mode: AddressMode, n8: u8, n16: u16) -> Option<(String, Option<u16>)> {
+    let reg = |r: RegisterType| format!("{r:?}").to_lowercase();
+    let e8 = n8 as i8;
+
+    let operands = match mode {
+        AddressMode::IMP => String::new(),
+        AddressMode::R(r) => reg(r),
+        AddressMode::R_R(r1, r2) => format!("{}, {}", reg(r1), reg(r2)),
+        AddressMode::MR_R(r1, r2) => format!("[{}], {}", reg(r1), reg(r2)),
+        AddressMode::R_MR(r1, r2) | AddressMode::R_HMR(r1, r2) => {
+            format!("{}, [{}]", reg(r1), reg(r2))
+        }
+        AddressMode::MR(r) => format!("[{}]", reg(r)),
+        AddressMode::R_HLI(r) => format!("{}, [hl+]", reg(r)),
+        AddressMode::R_HLD(r) => format!("{}, [hl-]", reg(r)),
+        AddressMode::HLI_R(r) => format!("[hl+], {}", reg(r)),
+        AddressMode::HLD_R(r) => format!("[hl-], {}", reg(r)),
+        AddressMode::R_D8(RegisterType::SP) => format!("sp, {e8}"),
+        AddressMode::R_D8(r) => format!("{}, ${n8:02X}", reg(r)),
+        AddressMode::MR_D8(r) => format!("[{}], ${n8:02X}", reg(r)),
+        AddressMode::R_HA8(r) | AddressMode::R_A8(r) => format!("{}, [$FF{n8:02X}]", reg(r)),
+        AddressMode::A8_R(r) => format!("[$FF{n8:02X}], {}", reg(r)),
+        AddressMode::LH_SPi8 if e8 < 0 => format!("hl, sp{e8}"),
+        AddressMode::LH_SPi8 => format!("hl, sp+{e8}"),
+        AddressMode::R_D16(r) => return Some((format!("{}, @", reg(r)), Some(n16))),
+        AddressMode::R_A16(r) => return Some((format!("{}, [@]", reg(r)), Some(n16))),
+        AddressMode::A16_R(r) => return Some((format!("[@], {}", reg(r)), Some(n16))),
+        AddressMode::D8 | AddressMode::D16 => return None,
+    };
+
+    Some((operands, None))
+}
+
+fn get_cb_text(op: u8) -> String {
+    const OPERANDS: [&str; 8] = ["b", "c", "d", "e", "h", "l", "[hl]", "a"];
+    const SHIFTS: [&str; 8] = ["rlc", "rrc", "rl", "rr", "sla", "sra", "swap", "srl"];
+
+    let operand = OPERANDS[(op & 0x07) as usize];
+    let y = (op >> 3) & 0x07;
+
+    match op >> 6 {
+        0 => format!("{} {operand}", SHIFTS[y as usize]),
+        1 => format!("bit {y}, {operand}"),
+        2 => format!("res {y}, {operand}"),
+        _ => format!("set {y}, {operand}"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Data,
+    Code,
+    /// Operand bytes of the instruction before.
+    Operand,
+    /// Read as data and never run, per a code/data log. Tracing stops here.
+    Read,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Target {
+    Jump,
+    Call,
+}
+
+/// A ROM split into code and data by following control flow from its entry
+/// points. Everything is keyed by ROM offset, so a `$4000` address means a
+/// different byte in each bank.
+pub struct Disassembly<'a> {
+    rom: &'a [u8],
+    marks: Vec<Mark>,
+    targets: BTreeMap<usize, Target>,
+    /// Jump and call targets resolved while tracing, by instruction offset.
+    /// A bank switched just before a far call is only known there.
+    refs: HashMap<usize, usize>,
+}
+
+impl<'a> Disassembly<'a> {
+    pub fn new(rom: &'a [u8]) -> Self {
+        Self::with_cdl(rom, &[])
+    }
+
+    /// Also traces from the code a [`CodeDataLog`] saw run, and keeps bytes
+    /// it only saw read out of the code. A log from a tool that doesn't
+    /// mark opcodes starts traces at runs of code and at jump targets.
+    pub fn with_cdl(rom: &'a [u8], cdl: &[u8]) -> Self {
+        let mut disasm = Self {
+            rom,
+            marks: vec![Mark::Data; rom.len()],
+            targets: BTreeMap::new(),
+            refs: HashMap::new(),
+        };
+
+        let cdl = &cdl[..cdl.len().min(rom.len())];
+        for (mark, flags) in disasm.marks.iter_mut().zip(cdl) {
+            if flags & (CodeDataLog::CODE | CodeDataLog::DATA) == CodeDataLog::DATA {
+                *mark = Mark::Read;
+            }
+        }
+
+        let has_opcodes = cdl.iter().any(|x| x & CodeDataLog::OPCODE != 0);
+        let entries = CodeDataLog::OPCODE | CodeDataLog::JUMP_TARGET | CodeDataLog::SUB_ENTRY;
+        for (offset, flags) in cdl.iter().enumerate() {
+            let starts_code = match has_opcodes {
+                true => flags & CodeDataLog::OPCODE != 0,
+                false => {
+                    flags & entries != 0
+                        || (flags & CodeDataLog::CODE != 0
+                            && (offset == 0 || cdl[offset - 1] & CodeDataLog::CODE == 0))
+                }
+            };
+
+            if starts_code {
+                let (bank, addr) = get_location(offset);
+                disasm.trace(bank, addr);
+            }
+        }
+
+        for addr in ENTRY_POINTS {
+            disasm.trace(0, addr);
+        }
+
+        disasm
+    }
+
+    pub fn bank_count(&self) -> u16 {
+        self.rom.len().div_ceil(BANK_SIZE) as u16
+    }
+
+    /// The addresses `bank` is mapped at that the ROM has bytes for.
+    pub fn bank_range(&self, bank: u16) -> Range<u16> {
+        let start = if bank == 0 { 0 } else { BANK_SIZE as u16 };
+        let offset = bank as usize * BANK_SIZE;
+        let len = self.rom.len().saturating_sub(offset).min(BANK_SIZE);
+
+        start..start + len as u16
+    }
+
+    /// Whether an instruction was traced starting at `addr` in `bank`.
+    pub fn is_code(&self, bank: u16, addr: u16) -> bool {
+        self.get_offset(bank, addr)
+            .is_some_and(|x| self.marks[x] == Mark::Code)
+    }
+
+    /// Follows control flow from `addr` in `bank`, marking what it reaches
+    /// as code. Paths end at returns, unconditional jumps, `jp hl`, bytes
+    /// that don't decode and code traced before.
+    pub fn trace(&mut self, bank: u16, addr: u16) {
+        let Some(start) = self.get_offset(bank, addr) else {
+            return;
+        };
+        let mut pending = vec![(start, None)];
+
+        while let Some((mut offset, mut switched_bank)) = pending.pop() {
+            let bank_end = (offset / BANK_SIZE + 1) * BANK_SIZE;
+            let mut loaded_a = None;
+
+            while offset < bank_end.min(self.rom.len()) && self.marks[offset] == Mark::Data {
+                let (bank, pc) = get_location(offset);
+                let bytes = &self.rom[offset..bank_end.min(self.rom.len())];
+                let Some(decoded) = decode(bytes, pc) else {
+                    break;
+                };
+
+                let end = offset + decoded.len as usize;
+                if self.marks[offset + 1..end].iter().any(|x| *x != Mark::Data) {
+                    break;
+                }
+
+                self.marks[offset] = Mark::Code;
+                self.marks[offset + 1..end].fill(Mark::Operand);
+
+                // `ld a, n8` then `ld [$2000-$3FFF], a` selects the bank far
+                // calls after it land in
+                if bytes[0] == 0xEA && matches!(decoded.addr, Some(0x2000..=0x3FFF)) {
+                    switched_bank = loaded_a.map(|x: u8| (x as u16).max(1));
+                }
+                loaded_a = (bytes[0] == 0x3E).then_some(bytes[1]);
+
+                let target = match decoded.flow {
+                    Flow::Jump { .. } | Flow::Call => decoded
+                        .addr
+                        .and_then(|x| self.resolve(bank, x, switched_bank)),
+                    _ => None,
+                };
+
+                if let Some(target) = target {
+                    let kind = match decoded.flow {
+                        Flow::Call => Target::Call,
+                        _ => Target::Jump,
+                    };
+                    let entry = self.targets.entry(target).or_insert(kind);
+                    *entry = (*entry).max(kind);
+                    self.refs.insert(offset, target);
+                    pending.push((target, switched_bank));
+                }
+
+                match decoded.flow {
+                    Flow::Jump { conditional } | Flow::Return { conditional } if !conditional => {
+                        break
+                    }
+                    Flow::Indirect => break,
+                    _ => offset = end,
+                }
+            }
+        }
+    }
+
+    /// An RGBDS listing of `sections`, given as a bank and an address range
+    /// in it, that assembles back to the same bytes. Labels come from
+    /// `symbols` or are generated for traced jump and call targets; RAM
+    /// labels the code uses are defined as constants up front.
+    pub fn listing(&self, sections: &[(u16, Range<u16>)], symbols: &Symbols) -> String {
+        let sections: Vec<(u16, Range<usize>)> = sections
+            .iter()
+            .filter_map(|(bank, range)| {
+                let start = self.get_offset(*bank, range.start)?;
+                let len = range.end.saturating_sub(range.start) as usize;
+                let bank_end = (start / BANK_SIZE + 1) * BANK_SIZE;
+
+                Some((
+                    *bank,
+                    start..(start + len).min(bank_end).min(self.rom.len()),
+                ))
+            })
+            .collect();
+        let labels = self.get_labels(&sections, symbols);
+        let mut constants = BTreeMap::new();
+        let mut body = String::new();
+
+        for (bank, range) in sections.iter() {
+            let bank_start = self.bank_range(*bank).start;
+            let (_, start) = get_location(range.start);
+            let kind = if *bank == 0 {
+                format!("ROM0[${start:04X}]")
+            } else {
+                format!("ROMX[${start:04X}], BANK[${bank:X}]")
+            };
+            let name = match start == bank_start {
+                true => format!("ROM Bank ${bank:03X}"),
+                false => format!("ROM Bank ${bank:03X} ${start:04X}"),
+            };
+            writeln!(body, "\nSECTION \"{name}\", {kind}\n").ok();
+
+            let mut offset = range.start;
+            let mut data: Vec<u8> = Vec::new();
+
+            while offset < range.end {
+                let (bank, pc) = get_location(offset);
+                let label = labels.get(&offset);
+                let decoded = match self.marks[offset] {
+                    Mark::Code => decode(&self.rom[offset..range.end], pc),
+                    _ => None,
+                };
+
+                if (label.is_some() || decoded.is_some() || data.len() == 8) && !data.is_empty() {
+                    write_data(&mut body, &data);
+                    data.clear();
+                }
+
+                if let Some(label) = label {
+                    writeln!(body, "{label}:").ok();
+                }
+
+                let Some(decoded) = decoded else {
+                    data.push(self.rom[offset]);
+                    offset += 1;
+                    continue;
+                };
+
+                let label = match (decoded.flow, decoded.addr) {
+                    (Flow::Jump { .. } | Flow::Call, _) => self
+                        .refs
+                        .get(&offset)
+                        .and_then(|x| labels.get(x))
+                        .map(String::as_str),
+                    (_, Some(addr @ 0x0000..=0x7FFF)) => self
+                        .resolve(bank, addr, None)
+                        .and_then(|x| labels.get(&x))
+                        .map(String::as_str),
+                    (_, Some(addr)) => symbols
+                        .get_in(None, addr)
+                        .filter(|x| is_identifier(x))
+                        .inspect(|x| {
+                            constants.insert(addr, x.to_string());
+                        }),
+                    (_, None) => None,
+                };
+
+                writeln!(body, "    {}", decoded.format(label)).ok();
+                offset += decoded.len as usize;
+            }
+
+            if !data.is_empty() {
+                write_data(&mut body, &data);
+            }
+        }
+
+        let mut listing = String::new();
+        for (addr, name) in constants.iter() {
+            writeln!(listing, "DEF {name} EQU ${addr:04X}").ok();
+        }
+        listing.push_str(&body);
+
+        listing
+    }
+
+    /// Names for the label positions the listing will emit: instruction
+    /// starts and data bytes inside `sections`, but not operand bytes.
+    fn get_labels(
+        &self,
+        sections: &[(u16, Range<usize>)],
+        symbols: &Symbols,
+    ) -> HashMap<usize, String> {
+        let mut offsets: BTreeSet<usize> = self.targets.keys().copied().collect();
+        for (_, range) in sections.iter() {
+            offsets.extend(range.clone().filter(|x| {
+                let (bank, addr) = get_location(*x);
+                symbols.get(bank, addr).is_some()
+            }));
+        }
+
+        offsets
+            .into_iter()
+            .filter(|x| self.marks[*x] != Mark::Operand)
+            .filter(|x| sections.iter().any(|(_, range)| range.contains(x)))
+            .map(|offset| {
+                let (bank, addr) = get_location(offset);
+                let name = match (symbols.get(bank, addr), self.targets.get(&offset)) {
+                    (Some(name), _) => name.to_string(),
+                    (None, Some(Target::Call)) => format!("Call_{bank:03x}_{addr:04x}"),
+                    (None, _) => format!("Jump_{bank:03x}_{addr:04x}"),
+                };
+
+                (offset, name)
+            })
+            .collect()
+    }
+
+    /// ROM offset of `target` as code running from `bank` sees it. From
+    /// bank 0 the switchable bank is unknown unless the ROM only has one.
+    fn resolve(&self, bank: u16, target: u16, switched_bank: Option<u16>) -> Option<usize> {
+        match target {
+            0x0000..=0x3FFF => self.get_offset(0, target),
+            0x4000..=0x7FFF => {
+                let bank = match bank {
+                    0 => switched_bank.or((self.bank_count() == 2).then_some(1))?,
+                    _ => bank,
+                };
+                self.get_offset(bank, target)
+            }
+            _ => None,
+        }
+    }
+
+    fn get_offset(&self, bank: u16, addr: u16) -> Option<usize> {
+        let offset = match addr {
+            0x0000..=0x3FFF => addr as usize,
+            0x4000..=0x7FFF => bank.max(1) as usize * BANK_SIZE + addr as usize - BANK_SIZE,
+            _ => return None,
+        };
+
+        (offset < self.rom.len()).then_some(offset)
+    }
+}
+
+fn get_location(offset: usize) -> (u16, u16) {
+    let bank = offset / BANK_SIZE;
+    let addr = match bank {
+        0 => offset,
+        _ => BANK_SIZE + offset % BANK_SIZE,
+    };
+
+    (bank as u16, addr as u16)
+}
+
+fn write_data(out: &mut String, bytes: &[u8]) {
+    let bytes: Vec<String> = bytes.iter().map(|x| format!("${x:02X}")).collect();
+    writeln!(out, "    db {}", bytes.join(", ")).ok();
+}
+
+/// Local labels (`Label.loop`) can't be constant names.
+fn is_identifier(name: &str) -> bool {
+    !name.contains('.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(bytes: &[u8], pc: u16) -> String {
+        decode(bytes, pc).map(|x| x.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_decode_rgbds_syntax() {
+        assert_eq!(text(&[0x00], 0), "nop");
+        assert_eq!(text(&[0x01, 0x34, 0x12], 0), "ld bc, $1234");
+        assert_eq!(text(&[0x08, 0x00, 0xC0], 0), "ld [$C000], sp");
+        assert_eq!(text(&[0x10, 0x00], 0), "stop");
+        assert_eq!(text(&[0x18, 0xFE], 0x150), "jr $0150");
+        assert_eq!(text(&[0x20, 0x02], 0x150), "jr nz, $0154");
+        assert_eq!(text(&[0x2A], 0), "ld a, [hl+]");
+        assert_eq!(text(&[0x32], 0), "ld [hl-], a");
+        assert_eq!(text(&[0x36, 0x05], 0), "ld [hl], $05");
+        assert_eq!(text(&[0x80], 0), "add a, b");
+        assert_eq!(text(&[0xAF], 0), "xor a");
+        assert_eq!(text(&[0xFE, 0x90], 0), "cp $90");
+        assert_eq!(text(&[0xC4, 0x00, 0x40], 0), "call nz, $4000");
+        assert_eq!(text(&[0xC8], 0), "ret z");
+        assert_eq!(text(&[0xCB, 0x7E], 0), "bit 7, [hl]");
+        assert_eq!(text(&[0xCB, 0x37], 0), "swap a");
+        assert_eq!(text(&[0xE0, 0x40], 0), "ldh [$FF40], a");
+        assert_eq!(text(&[0xF2], 0), "ldh a, [c]");
+        assert_eq!(text(&[0xE8, 0xFE], 0), "add sp, -2");
+        assert_eq!(text(&[0xF8, 0x05], 0), "ld hl, sp+5");
+        assert_eq!(text(&[0xE9], 0), "jp hl");
+        assert_eq!(text(&[0xEA, 0x00, 0x20], 0), "ld [$2000], a");
+        assert_eq!(text(&[0xFF], 0), "rst $38");
+
+        assert_eq!(decode(&[0xD3], 0), None);
+        assert_eq!(decode(&[0x10, 0x01], 0), None);
+        assert_eq!(decode(&[0xC3, 0x50], 0), None);
+    }
+
+    #[test]
+    fn test_trace_and_listing() {
+        let mut rom = vec![0; 3 * BANK_SIZE];
+        // $0100: nop; jp $0150
+        rom[0x100..0x104].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01]);
+        // $0150: call $0160; ld a, 2; ld [$2000], a; call $4000; jr $0150
+        rom[0x150..0x15D].copy_from_slice(&[
+            0xCD, 0x60, 0x01, 0x3E, 0x02, 0xEA, 0x00, 0x20, 0xCD, 0x00, 0x40, 0x18, 0xF3,
+        ]);
+        // $0160: ld a, [$C000]; ret
+        rom[0x160..0x164].copy_from_slice(&[0xFA, 0x00, 0xC0, 0xC9]);
+        // bank 2 $4000: ret
+        rom[2 * BANK_SIZE] = 0xC9;
+        // the vectors are reached by nothing but the header says they're code
+        rom[0x40..0x68].fill(0xD9);
+
+        let disasm = Disassembly::new(&rom);
+        assert!(disasm.is_code(0, 0x15B));
+        assert!(disasm.is_code(0, 0x163));
+        assert!(disasm.is_code(2, 0x4000));
+        assert!(!disasm.is_code(1, 0x4000));
+        assert!(!disasm.is_code(0, 0x164));
+
+        let symbols = Symbols::parse("00:0160 ReadCounter\n00:c000 wCounter\n");
+        let listing = disasm.listing(&[(0, 0x0150..0x0168)], &symbols);
+        let expected = "DEF wCounter EQU $C000
+
+SECTION \"ROM Bank $000 $0150\", ROM0[$0150]
+
+Jump_000_0150:
+    call ReadCounter
+    ld a, $02
+    ld [$2000], a
+    call $4000
+    jr Jump_000_0150
+    db $00, $00, $00
+ReadCounter:
+    ld a, [wCounter]
+    ret
+    db $00, $00, $00, $00
+";
+        assert_eq!(listing, expected);
+
+        let listing = disasm.listing(&[(0, 0x0000..0x4000), (2, 0x4000..0x4001)], &symbols);
+        assert!(listing.contains("    call Call_002_4000\n"));
+        assert!(listing.contains(
+            "SECTION \"ROM Bank $002\", ROMX[$4000], BANK[$2]\n\nCall_002_4000:\n    ret\n"
+        ));
+    }
+
+    #[test]
+    fn test_cdl_coverage() {
+        let mut rom = vec![0; 2 * BANK_SIZE];
+        // $0100: jp hl; $0200: ld a, [$0300]; ret
+        rom[0x100] = 0xE9;
+        rom[0x200..0x204].copy_from_slice(&[0xFA, 0x00, 0x03, 0xC9]);
+        rom[0x300] = 0xC9;
+
+        let mut cdl = vec![0; rom.len()];
+        cdl[0x200] = CodeDataLog::CODE | CodeDataLog::OPCODE | CodeDataLog::JUMP_TARGET;
+        cdl[0x201..0x203].fill(CodeDataLog::CODE);
+        cdl[0x203] = CodeDataLog::CODE | CodeDataLog::OPCODE;
+        cdl[0x300] = CodeDataLog::DATA;
+
+        assert!(!Disassembly::new(&rom).is_code(0, 0x200));
+        let disasm = Disassembly::with_cdl(&rom, &cdl);
+        assert!(disasm.is_code(0, 0x203));
+        assert!(!disasm.is_code(0, 0x300));
+
+        // without opcode marks the run of code still starts a trace
+        cdl.iter_mut().for_each(|x| *x &= !CodeDataLog::OPCODE);
+        assert!(Disassembly::with_cdl(&rom, &cdl).is_code(0, 0x203));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::bus::Bus;
+use crate::cart::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+use crate::ppu::vram::{VRAM_ADDR_START, VRAM_BANK_SIZE};
+
+const WRAM_BANK_SIZE: usize = 0x1000;
+
+/// A memory space for inspection: the CPU's map as the program sees it, or
+/// one bank of ROM or RAM whatever bank is selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemoryRegion {
+    #[default]
+    Bus,
+    Rom,
+    Vram,
+    Sram,
+    Wram,
+}
+
+impl MemoryRegion {
+    pub const ALL: [MemoryRegion; 5] = [
+        MemoryRegion::Bus,
+        MemoryRegion::Rom,
+        MemoryRegion::Vram,
+        MemoryRegion::Sram,
+        MemoryRegion::Wram,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemoryRegion::Bus => "BUS",
+            MemoryRegion::Rom => "ROM",
+            MemoryRegion::Vram => "VRAM",
+            MemoryRegion::Sram => "SRAM",
+            MemoryRegion::Wram => "WRAM",
+        }
+    }
+
+    /// Banks the loaded cart and model have; none for a cart without RAM.
+    pub fn banks(&self, bus: &Bus) -> usize {
+        let cgb = bus.io.ppu.lcd.is_cgb_mode();
+
+        match self {
+            MemoryRegion::Bus => 1,
+            MemoryRegion::Rom => bus.cart.data.len().div_ceil(ROM_BANK_SIZE),
+            MemoryRegion::Vram => 1 + cgb as usize,
+            MemoryRegion::Sram => bus.cart.ram_size().div_ceil(RAM_BANK_SIZE),
+            MemoryRegion::Wram => 2 + 6 * cgb as usize,
+        }
+    }
+
+    /// First CPU address `bank` is mapped at and its length.
+    pub fn range(&self, bank: usize) -> (u16, usize) {
+        match self {
+            MemoryRegion::Bus => (0x0000, 0x10000),
+            MemoryRegion::Rom if bank == 0 => (0x0000, ROM_BANK_SIZE),
+            MemoryRegion::Rom => (0x4000, ROM_BANK_SIZE),
+            MemoryRegion::Vram => (VRAM_ADDR_START, VRAM_BANK_SIZE),
+            MemoryRegion::Sram => (0xA000, RAM_BANK_SIZE),
+            MemoryRegion::Wram if bank == 0 => (0xC000, WRAM_BANK_SIZE),
+            MemoryRegion::Wram => (0xD000, WRAM_BANK_SIZE),
+        }
+    }
+
+    /// Reads `addr` of `bank`; `addr` must be in [`Self::range`].
+    pub fn read(&self, bus: &Bus, bank: usize, addr: u16) -> u8 {
+        match self {
+            MemoryRegion::Bus => bus.read(addr),
+            MemoryRegion::Rom => {
+                let index = rom_index(bank, addr);
+                bus.cart.data.rom().get(index).copied().unwrap_or(0xFF)
+            }
+            MemoryRegion::Vram => bus.io.ppu.video_ram.read_from_bank(bank as u8, addr),
+            MemoryRegion::Sram => bus.cart.read_ram_bank(bank as u8, addr),
+            MemoryRegion::Wram => bus.io.ram.read_wram_in(bank as u8, addr),
+        }
+    }
+
+    /// Writes `addr` of `bank` in place. The CPU map goes through the bus,
+    /// registers and all, except ROM, which is patched in the mapped bank
+    /// instead of reaching the MBC.
+    pub fn write(&self, bus: &mut Bus, bank: usize, addr: u16, value: u8) {
+        match self {
+            MemoryRegion::Bus if addr < 0x8000 => {
+                let bank = bus.cart.rom_bank(addr) as usize;
+                MemoryRegion::Rom.write(bus, bank, addr, value);
+            }
+            MemoryRegion::Bus => bus.write(addr, value),
+            MemoryRegion::Rom => {
+                let index = rom_index(bank, addr);
+
+                if index < bus.cart.data.len() {
+                    bus.cart.data.write(index, value);
+                }
+            }
+            MemoryRegion::Vram => bus.io.ppu.video_ram.write_to_bank(bank as u8, addr, value),
+            MemoryRegion::Sram => bus.cart.write_ram_bank(bank as u8, addr, value),
+            MemoryRegion::Wram => bus.io.ram.write_wram_in(bank as u8, addr, value),
+        }
+    }
+}
+
+fn rom_index(bank: usize, addr: u16) -> usize {
+    bank * ROM_BANK_SIZE + (addr as usize & (ROM_BANK_SIZE - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cart::Cart;
+
+    #[test]
+    fn test_banked_access() {
+        let mut rom = vec![0; 4 * ROM_BANK_SIZE];
+        // MBC1+RAM, 64K of ROM and 32K of RAM
+        rom[0x147] = 0x02;
+        rom[0x148] = 0x01;
+        rom[0x149] = 0x03;
+        rom[2 * ROM_BANK_SIZE + 0x10] = 0x42;
+        let cart = Cart::new(rom.into_boxed_slice()).unwrap();
+        let mut bus = Bus::new(cart, Default::default(), None);
+
+        assert_eq!(MemoryRegion::Rom.banks(&bus), 4);
+        assert_eq!(MemoryRegion::Sram.banks(&bus), 4);
+        assert_eq!(MemoryRegion::Rom.read(&bus, 2, 0x4010), 0x42);
+
+        // ROM edits through the CPU map land in the mapped bank
+        bus.write(0x2000, 2);
+        MemoryRegion::Bus.write(&mut bus, 0, 0x4011, 0x43);
+        assert_eq!(MemoryRegion::Rom.read(&bus, 2, 0x4011), 0x43);
+        assert_eq!(bus.read(0x4011), 0x43);
+
+        // unselected RAM banks are still reachable
+        MemoryRegion::Sram.write(&mut bus, 3, 0xA000, 7);
+        assert_eq!(MemoryRegion::Sram.read(&bus, 3, 0xA000), 7);
+        assert_eq!(MemoryRegion::Sram.read(&bus, 0, 0xA000), 0);
+    }
+}
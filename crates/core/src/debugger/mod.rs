@@ -0,0 +1,488 @@
+mod breakpoint;
+mod cdl;
+mod dap;
+pub mod disasm;
+mod memory;
+mod profiler;
+mod step;
+mod symbols;
+mod timeline;
+
+pub use breakpoint::*;
+pub use cdl::CodeDataLog;
+pub use dap::DapServer;
+pub use memory::MemoryRegion;
+pub use profiler::{Profiler, Routine, RoutineStats};
+pub use step::*;
+pub use symbols::*;
+pub use timeline::{
+    is_traced_register, EventKind, FrameEvents, Timeline, TimelineEvent, TimelineSample,
+};
+
+use crate::bus::Bus;
+use crate::cpu::instructions::{AddressMode, Instruction, JumpCondition, Mnemonic};
+use crate::cpu::Cpu;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DebugLogType {
+    None,
+    Asm,
+    GbDoc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Debugger {
+    pub log_type: DebugLogType,
+    serial_bytes: Vec<u8>,
+    serial_enabled: bool,
+    #[serde(default)]
+    pub breakpoints: Breakpoints,
+    #[serde(skip)]
+    step_mode: Option<StepMode>,
+    /// Set after a pre-step break so resuming executes the instruction that
+    /// was stopped on instead of hitting it again.
+    #[serde(skip)]
+    resuming: bool,
+    /// SP before the last instruction checked when it was a return, to tell
+    /// whether it was taken.
+    #[serde(skip)]
+    return_sp: Option<u16>,
+    /// Labels for disassembly and the Asm log, from the ROM's `.sym` file.
+    #[serde(skip)]
+    pub symbols: Symbols,
+}
+
+impl Debugger {
+    pub fn new_disabled() -> Self {
+        Self::new(DebugLogType::None, false)
+    }
+
+    pub fn new(log_type: DebugLogType, serial_enabled: bool) -> Self {
+        Debugger {
+            serial_bytes: vec![],
+            log_type,
+            serial_enabled,
+            breakpoints: Breakpoints::default(),
+            step_mode: None,
+            resuming: false,
+            return_sp: None,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Arms a one-shot stop; execution resumes past the current instruction
+    /// even if it's the one that triggered the last break.
+    pub fn set_step_mode(&mut self, mode: StepMode) {
+        self.step_mode = Some(mode);
+        self.resuming = true;
+    }
+
+    pub fn clear_step_mode(&mut self) {
+        self.step_mode = None;
+    }
+
+    pub fn is_stepping(&self) -> bool {
+        self.step_mode.is_some()
+    }
+
+    /// Lets the next step run unchecked, as after a break before it.
+    pub fn resume(&mut self) {
+        self.resuming = true;
+    }
+
+    /// Whether execution can stop before the next step: not while the CPU
+    /// is halted or stalled by STOP, since PC doesn't advance then.
+    pub fn can_stop(cpu: &Cpu) -> bool {
+        let halted = cpu.clock.is_cpu_halted() && !cpu.clock.bus.io.interrupts.has_pending();
+
+        cpu.stop_m_cycles == 0 && !halted
+    }
+
+    /// Checks the instruction about to execute, when it [can stop](Self::can_stop).
+    pub fn check_before(&mut self, cpu: &mut Cpu) -> Option<BreakReason> {
+        if (self.breakpoints.is_empty() && self.step_mode.is_none()) || !Self::can_stop(cpu) {
+            return None;
+        }
+
+        let pending = cpu.clock.bus.io.interrupts.next_dispatch();
+        let sp = cpu.registers.sp;
+        let returned = self.return_sp.is_some_and(|x| sp == x.wrapping_add(2));
+        self.return_sp = match (self.step_mode, pending) {
+            (Some(StepMode::Out { .. }), None) => Self::is_return(cpu).then_some(sp),
+            _ => None,
+        };
+
+        if self.resuming {
+            self.resuming = false;
+            return None;
+        }
+
+        let reason = self.breakpoints.check_before(cpu, pending).or_else(|| {
+            let pc = pending.map(|(addr, _)| addr).unwrap_or(cpu.registers.pc);
+            self.step_mode
+                .filter(|x| x.is_done(pc, sp, returned))
+                .map(|_| BreakReason::Step { pc })
+        });
+
+        if reason.is_some() {
+            self.step_mode = None;
+            self.resuming = true;
+        }
+
+        reason
+    }
+
+    fn is_return(cpu: &mut Cpu) -> bool {
+        let opcode = cpu.clock.bus.read(cpu.registers.pc);
+
+        matches!(
+            Instruction::get_by_opcode(opcode).get_mnemonic(),
+            Mnemonic::Ret | Mnemonic::Reti
+        )
+    }
+
+    pub fn check_after(&mut self, cpu: &Cpu, prev_ly: u8) -> Option<BreakReason> {
+        if self.breakpoints.is_empty() {
+            return None;
+        }
+
+        #[cfg(feature = "debug")]
+        let accesses = cpu.probe.accesses();
+        #[cfg(not(feature = "debug"))]
+        let accesses = &[];
+
+        let reason = self
+            .breakpoints
+            .check_after(accesses, prev_ly, cpu.clock.bus.io.ppu.lcd.ly);
+
+        if reason.is_some() {
+            self.step_mode = None;
+        }
+
+        reason
+    }
+
+    pub fn print(&mut self, cpu: &mut Cpu) {
+        self.print_gb_doctor(cpu);
+        self.print_asm(cpu);
+    }
+
+    pub fn print_serial(&self) {
+        if !self.get_serial_msg().is_empty() {
+            log::info!("Serial: {}", self.get_serial_msg());
+        }
+    }
+
+    pub fn get_serial_msg<'a>(&'a self) -> Cow<'a, str> {
+        String::from_utf8_lossy(&self.serial_bytes[..])
+    }
+
+    pub fn poll_serial(&mut self, bus: &mut Bus) {
+        if self.serial_enabled && bus.io.serial.has_data() {
+            self.serial_bytes.push(bus.io.serial.take_data());
+        }
+    }
+
+    pub fn print_gb_doctor(&self, cpu: &mut Cpu) {
+        if self.log_type != DebugLogType::GbDoc {
+            return;
+        }
+
+        let pc_mem = format!(
+            "PCMEM:{:02X},{:02X},{:02X},{:02X}",
+            cpu.clock.bus.read(cpu.registers.pc),
+            cpu.clock.bus.read(cpu.registers.pc.wrapping_add(1)),
+            cpu.clock.bus.read(cpu.registers.pc.wrapping_add(2)),
+            cpu.clock.bus.read(cpu.registers.pc.wrapping_add(3))
+        );
+        println!(
+            "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} {}",
+            cpu.registers.a,
+            cpu.registers.flags.get_byte(),
+            cpu.registers.b,
+            cpu.registers.c,
+            cpu.registers.d,
+            cpu.registers.e,
+            cpu.registers.h,
+            cpu.registers.l,
+            cpu.registers.sp,
+            cpu.registers.pc,
+            pc_mem
+        );
+    }
+
+    pub fn print_asm(&self, cpu: &mut Cpu) {
+        if self.log_type != DebugLogType::Asm {
+            return;
+        }
+
+        let opcode = peek_imm8(cpu, 0);
+        let instruction = Instruction::get_by_opcode(opcode);
+        let pc = cpu.registers.pc;
+
+        if let Some(label) = self.symbols.get_at(cpu, pc) {
+            log::info!("{label}:");
+        }
+
+        log::info!(
+            "{:08} - {:04X}: {:<20} ({:02X}) A: {:02X} F: {} BC: {:02X}{:02X} DE: {:02X}{:02X} HL: {:02X}{:02X}",
+            cpu.clock.get_t_cycles(),
+            pc,
+            get_asm_string_at(cpu, pc, instruction, &self.symbols),
+            opcode,
+            cpu.registers.a,
+            cpu.registers.flags.display(),
+            cpu.registers.b,
+            cpu.registers.c,
+            cpu.registers.d,
+            cpu.registers.e,
+            cpu.registers.h,
+            cpu.registers.l
+        );
+    }
+}
+
+pub fn get_asm_string(cpu: &Cpu, instruction: &Instruction) -> String {
+    get_asm_string_at(cpu, cpu.registers.pc, instruction, &Symbols::default())
+}
+
+/// Same as [`get_asm_string`] for the instruction at `addr` instead of PC,
+/// with jump targets and addresses that have a label shown by name.
+pub fn get_asm_string_at(
+    cpu: &Cpu,
+    addr: u16,
+    instruction: &Instruction,
+    symbols: &Symbols,
+) -> String {
+    let mode = instruction.get_address_mode();
+    let mnemonic = instruction.get_mnemonic();
+    let cond = instruction.get_condition().unwrap_or(JumpCondition::None);
+    let cond = match cond {
+        JumpCondition::None => "".to_string(),
+        _ => format!(" {cond:?}"),
+    };
+    let byte1 = peek_byte(cpu, addr, 1);
+    let byte2 = peek_byte(cpu, addr, 2);
+
+    if mnemonic == Mnemonic::Prefix {
+        return get_cb_asm_string(byte1);
+    }
+
+    let imm16 = u16::from_le_bytes([byte1, byte2]);
+    let str = match mode {
+        AddressMode::IMP => format!("{mnemonic:?}{cond}"),
+        AddressMode::R_D16(r1) => {
+            format!("{mnemonic:?}{cond} {r1:?},${:04X}", imm16)
+        }
+        AddressMode::R_A16(r1) => {
+            format!("{mnemonic:?}{cond} {r1:?},(${:04X})", imm16)
+        }
+        AddressMode::R(r1) => {
+            format!("{mnemonic:?}{cond} {r1:?}")
+        }
+        AddressMode::R_R(r1, r2) => {
+            format!("{mnemonic:?}{cond} {r1:?},{r2:?}")
+        }
+        AddressMode::MR_R(r1, r2) => {
+            format!("{mnemonic:?}{cond} ({r1:?}),{r2:?}")
+        }
+        AddressMode::MR(r1) => {
+            format!("{mnemonic:?}{cond} ({r1:?})")
+        }
+        AddressMode::R_MR(r1, r2) => {
+            format!("{mnemonic:?}{cond} {r1:?},({r2:?})")
+        }
+        AddressMode::R_HMR(r1, r2) => {
+            format!("{mnemonic:?}{cond} {r1:?},(FF00+{r2:?})")
+        }
+        AddressMode::R_D8(r1) => {
+            format!("{mnemonic:?}{cond} {r1:?},${:02X}", byte1)
+        }
+        AddressMode::R_HA8(r1) | AddressMode::R_A8(r1) => {
+            format!("{mnemonic:?}{cond} {r1:?},(FF00+${:02X})", byte1)
+        }
+        AddressMode::R_HLI(r1) => {
+            format!("{mnemonic:?}{cond} {r1:?},(HL+)")
+        }
+        AddressMode::R_HLD(r1) => {
+            format!("{mnemonic:?}{cond} {r1:?},(HL-)")
+        }
+        AddressMode::HLI_R(r1) => {
+            format!("{mnemonic:?}{cond} (HL+),{r1:?}")
+        }
+        AddressMode::HLD_R(r1) => {
+            format!("{mnemonic:?}{cond} (HL-),{r1:?}")
+        }
+        AddressMode::A8_R(r2) => {
+            format!("{mnemonic:?}{cond} (${:02X}),{r2:?}", byte1)
+        }
+        AddressMode::LH_SPi8 => {
+            format!("{mnemonic:?}{cond} (HL,SP+{:?})", byte1)
+        }
+        AddressMode::D8 => {
+            format!("{mnemonic:?}{cond} ${:02X}", byte1)
+        }
+        AddressMode::D16 => {
+            format!("{mnemonic:?}{cond} ${:04X}", imm16)
+        }
+        AddressMode::MR_D8(r1) => {
+            format!("{mnemonic:?}{cond} ({r1:?}),${:02X}", byte1)
+        }
+        AddressMode::A16_R(r2) => {
+            format!("{mnemonic:?}{cond} (${:04X}),{r2:?}", imm16)
+        }
+    };
+
+    let str = str.to_uppercase();
+    let target = match mode {
+        AddressMode::R_D16(_)
+        | AddressMode::R_A16(_)
+        | AddressMode::D16
+        | AddressMode::A16_R(_) => Some((imm16, format!("${imm16:04X}"))),
+        AddressMode::D8 if mnemonic == Mnemonic::Jr => {
+            let target = addr.wrapping_add(2).wrapping_add(byte1 as i8 as u16);
+            Some((target, format!("${byte1:02X}")))
+        }
+        _ => None,
+    };
+
+    match target.and_then(|(x, raw)| Some((symbols.get_at(cpu, x)?, raw))) {
+        Some((label, raw)) => str.replacen(&raw, label, 1),
+        None => str,
+    }
+}
+
+/// CB-prefixed opcodes are decoded from their bit layout: `xx yyy zzz` with
+/// `x` the group, `y` the operation or bit index and `z` the operand.
+pub fn get_cb_asm_string(op: u8) -> String {
+    const OPERANDS: [&str; 8] = ["B", "C", "D", "E", "H", "L", "(HL)", "A"];
+    const SHIFTS: [&str; 8] = ["RLC", "RRC", "RL", "RR", "SLA", "SRA", "SWAP", "SRL"];
+
+    let operand = OPERANDS[(op & 0x07) as usize];
+    let y = (op >> 3) & 0x07;
+
+    match op >> 6 {
+        0 => format!("{} {operand}", SHIFTS[y as usize]),
+        1 => format!("BIT {y},{operand}"),
+        2 => format!("RES {y},{operand}"),
+        _ => format!("SET {y},{operand}"),
+    }
+}
+
+/// Length in bytes of the instruction at `addr`.
+pub fn get_length_at(cpu: &Cpu, addr: u16) -> u16 {
+    Instruction::get_by_opcode(cpu.clock.bus.read(addr)).get_length()
+}
+
+/// Instructions can't be decoded backwards, so this tries starts before
+/// `addr` whose forward decoding lands on it exactly, and keeps the one
+/// giving the most (up to `count`) preceding instructions.
+pub fn find_instructions_before(cpu: &Cpu, addr: u16, count: usize) -> Vec<u16> {
+    let max_back = count.saturating_mul(3).min(0xFFFF) as u16;
+    let mut best = Vec::new();
+
+    for back in (1..=max_back).rev() {
+        let start = addr.wrapping_sub(back);
+        let mut lines = Vec::with_capacity(back as usize);
+        let mut curr = start;
+
+        while curr != addr {
+            // overshot addr without landing on it
+            if curr.wrapping_sub(start) > back {
+                lines.clear();
+                break;
+            }
+
+            lines.push(curr);
+            curr = curr.wrapping_add(get_length_at(cpu, curr));
+        }
+
+        if lines.len() > best.len() {
+            best = lines;
+        }
+
+        // a shorter start can't give more, every instruction takes a byte
+        if best.len() >= count || best.len() >= back as usize {
+            break;
+        }
+    }
+
+    best.split_off(best.len().saturating_sub(count))
+}
+
+/// Return addresses found on the stack, innermost first. The stack doesn't
+/// record frames, so a word counts as one when the bytes before it decode as
+/// a CALL or RST; pushed data can still be mistaken for one.
+pub fn get_call_stack(cpu: &Cpu, max_depth: usize) -> Vec<u16> {
+    const MAX_SCAN_WORDS: u16 = 64;
+    let bus = &cpu.clock.bus;
+    let mut frames = Vec::new();
+    let mut addr = cpu.registers.sp;
+
+    for _ in 0..MAX_SCAN_WORDS {
+        if frames.len() >= max_depth || addr >= 0xFFFE {
+            break;
+        }
+
+        let ret = u16::from_le_bytes([bus.read(addr), bus.read(addr + 1)]);
+        let is_call = matches!(
+            bus.read(ret.wrapping_sub(3)),
+            0xC4 | 0xCC | 0xCD | 0xD4 | 0xDC
+        );
+        let is_rst = bus.read(ret.wrapping_sub(1)) & 0xC7 == 0xC7;
+
+        if is_call || is_rst {
+            frames.push(ret);
+        }
+
+        addr += 2;
+    }
+
+    frames
+}
+
+fn peek_imm8(cpu: &Cpu, offset: u16) -> u8 {
+    peek_byte(cpu, cpu.registers.pc, offset)
+}
+
+fn peek_byte(cpu: &Cpu, addr: u16, offset: u16) -> u8 {
+    cpu.clock.bus.read(addr.wrapping_add(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cb_asm_string() {
+        assert_eq!(get_cb_asm_string(0x37), "SWAP A");
+        assert_eq!(get_cb_asm_string(0x7E), "BIT 7,(HL)");
+        assert_eq!(get_cb_asm_string(0x80), "RES 0,B");
+        assert_eq!(get_cb_asm_string(0xFD), "SET 7,L");
+    }
+
+    #[test]
+    fn test_asm_string_labels() {
+        use crate::auxiliary::clock::Clock;
+        use crate::auxiliary::io::Io;
+
+        // CALL $0150; JR -2
+        let mut bytes = vec![0; 0x10000];
+        bytes[..5].copy_from_slice(&[0xCD, 0x50, 0x01, 0x18, 0xFE]);
+        let cpu = Cpu::new(Clock::new(Bus::with_bytes(bytes, Io::default())));
+        let symbols = Symbols::parse("00:0150 Main\n00:0003 .loop\n");
+        let asm = |addr| {
+            let instruction = Instruction::get_by_opcode(cpu.clock.bus.read(addr));
+            get_asm_string_at(&cpu, addr, instruction, &symbols)
+        };
+
+        assert_eq!(asm(0), "CALL Main");
+        assert_eq!(asm(3), "JR .loop");
+        assert_eq!(
+            get_asm_string_at(&cpu, 0, Instruction::get_by_opcode(0xCD), &Symbols::default()),
+            "CALL $0150"
+        );
+    }
+}
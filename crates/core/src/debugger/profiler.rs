@@ -0,0 +1,317 @@
+use super::Symbols;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Calls deeper than this drop their outermost frames, so code that never
+/// returns (or unwinds the stack by hand) can't grow the shadow stack forever.
+const MAX_DEPTH: usize = 256;
+
+/// Where a routine starts: its entry address and the bank mapped there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Routine {
+    pub bank: Option<u16>,
+    pub addr: u16,
+}
+
+impl Routine {
+    /// Code that runs outside any call: the entry point and the main loop.
+    pub const ROOT: Routine = Routine {
+        bank: Some(0),
+        addr: 0x0100,
+    };
+
+    pub fn name(&self, symbols: &Symbols) -> String {
+        if let Some(name) = symbols.get_in(self.bank, self.addr) {
+            return name.to_string();
+        }
+
+        match self.bank {
+            Some(bank) => format!("{bank:02X}:{:04X}", self.addr),
+            None => format!("??:{:04X}", self.addr),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RoutineStats {
+    pub calls: u64,
+    /// M-cycles in the routine and everything it called, counted as it
+    /// returns.
+    pub inclusive: u64,
+    /// M-cycles in the routine's own code.
+    pub exclusive: u64,
+}
+
+impl RoutineStats {
+    fn add(&mut self, other: &RoutineStats) {
+        self.calls += other.calls;
+        self.inclusive += other.inclusive;
+        self.exclusive += other.exclusive;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CallFrame {
+    routine: Routine,
+    /// SP right after the return address was pushed.
+    sp: u16,
+    /// [`Profiler::time`] at the call.
+    start: u64,
+}
+
+/// Call-graph profiler. The CPU reports calls, RSTs, interrupt entries and
+/// returns; a shadow call stack matched by SP attributes the M-cycles in
+/// between to routines, both in total and per frame.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    stack: Vec<CallFrame>,
+    totals: HashMap<Routine, RoutineStats>,
+    current: HashMap<Routine, RoutineStats>,
+    /// Stats of the last complete frame, hottest first.
+    last_frame: Vec<(Routine, RoutineStats)>,
+    /// Most inclusive M-cycles a routine took in one frame.
+    peaks: HashMap<Routine, u64>,
+    /// M-cycles profiled. The clock's own count restarts on speed switches
+    /// and state loads, which would leave open calls starting in the future.
+    time: u64,
+    /// The clock's M-cycles at the last event.
+    cycles: usize,
+    frame: usize,
+    frames: usize,
+    /// [`Self::time`] when the current frame started.
+    frame_start: u64,
+    /// M-cycles of the last complete frame, twice as many in double speed.
+    last_frame_cycles: u64,
+    /// M-cycles of all complete frames.
+    frames_cycles: u64,
+}
+
+impl Profiler {
+    pub fn new(cycles: usize, frame: usize) -> Self {
+        Self {
+            stack: vec![CallFrame {
+                routine: Routine::ROOT,
+                sp: u16::MAX,
+                start: 0,
+            }],
+            totals: HashMap::new(),
+            current: HashMap::new(),
+            last_frame: Vec::new(),
+            peaks: HashMap::new(),
+            time: 0,
+            cycles,
+            frame,
+            frames: 0,
+            frame_start: 0,
+            last_frame_cycles: 0,
+            frames_cycles: 0,
+        }
+    }
+
+    /// A call to `routine` pushed its return address, leaving SP at `sp`.
+    pub fn on_call(&mut self, routine: Routine, sp: u16, cycles: usize, frame: usize) {
+        self.advance(cycles, frame);
+
+        if self.stack.len() >= MAX_DEPTH {
+            self.stack.remove(1);
+        }
+
+        self.stack.push(CallFrame {
+            routine,
+            sp,
+            start: self.time,
+        });
+        self.current.entry(routine).or_default().calls += 1;
+    }
+
+    /// A return popped its address, leaving SP at `sp`. Also closes the
+    /// frames a routine skipped by dropping return addresses itself.
+    pub fn on_return(&mut self, sp: u16, cycles: usize, frame: usize) {
+        self.advance(cycles, frame);
+
+        while self.stack.len() > 1 && self.stack[self.stack.len() - 1].sp < sp {
+            let call = self.stack.pop().expect("not the root");
+
+            // a recursive routine is only timed by its outermost call
+            if self.stack.iter().all(|x| x.routine != call.routine) {
+                let elapsed = self.time - call.start;
+                self.current.entry(call.routine).or_default().inclusive += elapsed;
+            }
+        }
+    }
+
+    /// Routines by total M-cycles, hottest first.
+    pub fn totals(&self) -> Vec<(Routine, RoutineStats)> {
+        let mut totals = self.totals.clone();
+        for (routine, stats) in self.current.iter() {
+            totals.entry(*routine).or_default().add(stats);
+        }
+
+        sort_hottest(totals.into_iter().collect())
+    }
+
+    pub fn last_frame(&self) -> &[(Routine, RoutineStats)] {
+        &self.last_frame
+    }
+
+    /// M-cycles the last complete frame took, to weigh
+    /// [`Self::last_frame`] against.
+    pub fn last_frame_cycles(&self) -> u64 {
+        self.last_frame_cycles
+    }
+
+    pub fn peak(&self, routine: Routine) -> u64 {
+        self.peaks.get(&routine).copied().unwrap_or_default()
+    }
+
+    /// Complete frames seen.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// The routines of the call stack, outermost first.
+    pub fn call_stack(&self) -> impl Iterator<Item = Routine> + '_ {
+        self.stack.iter().map(|x| x.routine)
+    }
+
+    /// A table of the `limit` hottest routines: calls and M-cycles, per frame
+    /// on average and at their worst.
+    pub fn report(&self, symbols: &Symbols, limit: usize) -> String {
+        let frames = self.frames.max(1) as u64;
+        let frame_cycles = (self.frames_cycles / frames).max(1);
+        let mut out = format!(
+            "{:<24} {:>8} {:>10} {:>10} {:>8} {:>8} {:>6}\n",
+            "routine", "calls", "incl", "excl", "excl/fr", "peak/fr", "frame%"
+        );
+
+        for (routine, stats) in self.totals().into_iter().take(limit) {
+            let per_frame = stats.exclusive / frames;
+            writeln!(
+                out,
+                "{:<24} {:>8} {:>10} {:>10} {:>8} {:>8} {:>5.1}%",
+                routine.name(symbols),
+                stats.calls,
+                stats.inclusive,
+                stats.exclusive,
+                per_frame,
+                self.peak(routine),
+                per_frame as f64 * 100.0 / frame_cycles as f64
+            )
+            .ok();
+        }
+
+        out
+    }
+
+    /// Credits the cycles since the last event to the running routine and
+    /// closes the frame when a new one started.
+    fn advance(&mut self, cycles: usize, frame: usize) {
+        // a count that went back was reset, and started again from 0
+        let elapsed = match cycles.checked_sub(self.cycles) {
+            Some(x) => x,
+            None => cycles,
+        } as u64;
+        let routine = self.stack[self.stack.len() - 1].routine;
+        self.current.entry(routine).or_default().exclusive += elapsed;
+        self.time += elapsed;
+        self.cycles = cycles;
+
+        if frame != self.frame {
+            self.end_frame();
+            self.frame = frame;
+        }
+    }
+
+    fn end_frame(&mut self) {
+        let current = std::mem::take(&mut self.current);
+
+        for (routine, stats) in current.iter() {
+            self.totals.entry(*routine).or_default().add(stats);
+            let peak = self.peaks.entry(*routine).or_default();
+            *peak = (*peak).max(stats.inclusive.max(stats.exclusive));
+        }
+
+        self.last_frame = sort_hottest(current.into_iter().collect());
+        self.frames += 1;
+        self.last_frame_cycles = self.time - self.frame_start;
+        self.frames_cycles += self.last_frame_cycles;
+        self.frame_start = self.time;
+    }
+}
+
+fn sort_hottest(mut stats: Vec<(Routine, RoutineStats)>) -> Vec<(Routine, RoutineStats)> {
+    stats.sort_by_key(|(routine, x)| {
+        let heat = x.inclusive.max(x.exclusive);
+        (Reverse(heat), Reverse(x.exclusive), *routine)
+    });
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routine(addr: u16) -> Routine {
+        Routine {
+            bank: Some(0),
+            addr,
+        }
+    }
+
+    #[test]
+    fn test_inclusive_exclusive() {
+        let mut profiler = Profiler::new(0, 0);
+        // main 10, call A at SP FFFC: A 5, call B: B 20, back in A 3, main 2
+        profiler.on_call(routine(0x200), 0xFFFC, 10, 0);
+        profiler.on_call(routine(0x300), 0xFFFA, 15, 0);
+        profiler.on_return(0xFFFC, 35, 0);
+        profiler.on_return(0xFFFE, 38, 0);
+        profiler.on_call(routine(0x200), 0xFFFC, 40, 1);
+
+        let stats: HashMap<_, _> = profiler.totals().into_iter().collect();
+        assert_eq!(stats[&Routine::ROOT].exclusive, 12);
+        assert_eq!(
+            stats[&routine(0x200)],
+            RoutineStats {
+                calls: 2,
+                inclusive: 28,
+                exclusive: 8
+            }
+        );
+        assert_eq!(stats[&routine(0x300)].inclusive, 20);
+        assert_eq!(profiler.frames(), 1);
+        assert_eq!(profiler.last_frame()[0].0, routine(0x200));
+        assert_eq!(profiler.peak(routine(0x300)), 20);
+    }
+
+    #[test]
+    fn test_unwound_stack() {
+        let mut profiler = Profiler::new(0, 0);
+        profiler.on_call(routine(0x200), 0xFFFC, 0, 0);
+        profiler.on_call(routine(0x300), 0xFFFA, 0, 0);
+        // B drops its return address and returns straight to main
+        profiler.on_return(0xFFFE, 10, 0);
+        assert_eq!(profiler.call_stack().collect::<Vec<_>>(), [Routine::ROOT]);
+
+        // a pushed address used as a jump doesn't close anything
+        profiler.on_call(routine(0x200), 0xFFFC, 10, 0);
+        profiler.on_return(0xFFFC, 12, 0);
+        assert_eq!(profiler.call_stack().count(), 2);
+    }
+
+    #[test]
+    fn test_clock_reset() {
+        let mut profiler = Profiler::new(100, 0);
+        profiler.on_call(routine(0x200), 0xFFFC, 110, 0);
+        // a speed switch zeroed the clock
+        profiler.on_return(0xFFFE, 5, 0);
+        profiler.on_call(routine(0x200), 0xFFFC, 2 * 17556, 1);
+
+        let stats: HashMap<_, _> = profiler.totals().into_iter().collect();
+        assert_eq!(stats[&routine(0x200)].inclusive, 5);
+        assert_eq!(stats[&Routine::ROOT].exclusive, 10 + 2 * 17556 - 5);
+        assert_eq!(profiler.last_frame_cycles(), 15 + 2 * 17556 - 5);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::cpu::instructions::{Instruction, Mnemonic};
+use crate::cpu::Cpu;
+
+/// A one-shot stop the debugger arms for stepping and run-to-cursor. Unlike
+/// [`super::Breakpoints`] it's cleared by the first break of any kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepMode {
+    /// Stop before the next instruction.
+    Into,
+    /// Stop at `addr` once the stack is back at `sp` or above, so recursive
+    /// calls of the stepped-over routine don't stop early. Run-to-cursor
+    /// passes 0 to stop at any depth.
+    Until { addr: u16, sp: u16 },
+    /// Stop once the current routine returns, i.e. a return takes SP above
+    /// `sp`. A POP doesn't count, the routine may pop what it was given.
+    Out { sp: u16 },
+}
+
+impl StepMode {
+    /// Steps over CALL and RST, into anything else.
+    pub fn over(cpu: &Cpu) -> Self {
+        let pc = cpu.registers.pc;
+        let instruction = Instruction::get_by_opcode(cpu.clock.bus.read(pc));
+
+        match instruction.get_mnemonic() {
+            Mnemonic::Call | Mnemonic::Rst => StepMode::Until {
+                addr: pc.wrapping_add(instruction.get_length()),
+                sp: cpu.registers.sp,
+            },
+            _ => StepMode::Into,
+        }
+    }
+
+    pub fn out(cpu: &Cpu) -> Self {
+        StepMode::Out {
+            sp: cpu.registers.sp,
+        }
+    }
+
+    pub fn run_to(addr: u16) -> Self {
+        StepMode::Until { addr, sp: 0 }
+    }
+
+    /// Whether execution should stop before the instruction at `pc`,
+    /// `returned` telling whether the last one was a return taken.
+    pub fn is_done(&self, pc: u16, sp: u16, returned: bool) -> bool {
+        match *self {
+            StepMode::Into => true,
+            StepMode::Until { addr, sp: start_sp } => pc == addr && sp >= start_sp,
+            StepMode::Out { sp: start_sp } => returned && sp > start_sp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary::clock::Clock;
+    use crate::auxiliary::io::Io;
+    use crate::bus::Bus;
+    use crate::debugger::{BreakReason, Debugger};
+
+    #[test]
+    fn test_step_over_call() {
+        // CALL $0010; NOP
+        let mut bytes = vec![0; 0x10000];
+        bytes[..4].copy_from_slice(&[0xCD, 0x10, 0x00, 0x00]);
+        let mut cpu = Cpu::new(Clock::new(Bus::with_bytes(bytes, Io::default())));
+        cpu.registers.pc = 0;
+        cpu.registers.sp = 0xFFFE;
+
+        let mode = StepMode::over(&cpu);
+        assert_eq!(
+            mode,
+            StepMode::Until {
+                addr: 3,
+                sp: 0xFFFE
+            }
+        );
+        // inside the call the return address is pushed
+        assert!(!mode.is_done(3, 0xFFFC, false));
+        assert!(mode.is_done(3, 0xFFFE, false));
+
+        cpu.registers.pc = 3;
+        assert_eq!(StepMode::over(&cpu), StepMode::Into);
+    }
+
+    #[test]
+    fn test_step_out() {
+        let mode = StepMode::Out { sp: 0xDFF0 };
+
+        assert!(!mode.is_done(0x200, 0xDFEE, true));
+        assert!(!mode.is_done(0x200, 0xDFF0, true));
+        // a POP of what the caller pushed
+        assert!(!mode.is_done(0x201, 0xDFF2, false));
+        assert!(mode.is_done(0x150, 0xDFF2, true));
+    }
+
+    #[test]
+    fn test_step_out_past_pop() {
+        // CALL $0010; NOP, then PUSH BC; NOP; POP BC; RET at $0010
+        let mut bytes = vec![0; 0x10000];
+        bytes[..4].copy_from_slice(&[0xCD, 0x10, 0x00, 0x00]);
+        bytes[0x10..0x14].copy_from_slice(&[0xC5, 0x00, 0xC1, 0xC9]);
+        let mut cpu = Cpu::new(Clock::new(Bus::with_bytes(bytes, Io::default())));
+        cpu.registers.pc = 0;
+        cpu.registers.sp = 0xFFFE;
+        let mut debugger = Debugger::new_disabled();
+
+        while cpu.registers.pc != 0x11 {
+            cpu.step_debug(&mut debugger);
+        }
+
+        debugger.set_step_mode(StepMode::out(&cpu));
+        let reason = (0..10).find_map(|_| cpu.step_debug(&mut debugger));
+        assert_eq!(reason, Some(BreakReason::Step { pc: 3 }));
+    }
+}
@@ -0,0 +1,237 @@
+use super::parse_addr;
+use crate::cpu::Cpu;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Labels from a `.sym` file, keyed by `bank:addr`. RGBDS, no$gmb and wla-dx
+/// all write `BB:AAAA Label` lines; wla-dx groups them under a `[labels]`
+/// section next to others that are skipped. RGBDS `.map` files work too.
+#[derive(Debug, Clone, Default)]
+pub struct Symbols {
+    labels: BTreeMap<(u16, u16), String>,
+    addrs: HashMap<String, (u16, u16)>,
+}
+
+impl Symbols {
+    pub fn parse(text: &str) -> Self {
+        let mut symbols = Self::default();
+
+        for label in parse_labels(text) {
+            symbols.insert(label.bank, label.addr, label.name);
+        }
+
+        symbols
+    }
+
+    /// The `.sym` file RGBDS and wla-dx write next to a ROM.
+    pub fn path_for_rom(rom_path: &Path) -> PathBuf {
+        rom_path.with_extension("sym")
+    }
+
+    /// Keeps the first label of an address; later ones are aliases only
+    /// reachable by name.
+    pub fn insert(&mut self, bank: u16, addr: u16, name: &str) {
+        self.labels
+            .entry((bank, addr))
+            .or_insert_with(|| name.to_string());
+        self.addrs.insert(name.to_string(), (bank, addr));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn get(&self, bank: u16, addr: u16) -> Option<&str> {
+        self.labels.get(&(bank, addr)).map(String::as_str)
+    }
+
+    /// The label at `addr` in `bank`, or in any bank when it's unknown.
+    pub fn get_in(&self, bank: Option<u16>, addr: u16) -> Option<&str> {
+        match bank {
+            Some(bank) => self.get(bank, addr),
+            None => self
+                .labels
+                .iter()
+                .find(|((_, x), _)| *x == addr)
+                .map(|(_, name)| name.as_str()),
+        }
+    }
+
+    /// The label at `addr` in the bank currently mapped there.
+    pub fn get_at(&self, cpu: &Cpu, addr: u16) -> Option<&str> {
+        self.get_in(get_bank_at(cpu, addr), addr)
+    }
+
+    /// `Label` or `Label+$N` for the closest label at or before `addr` in the
+    /// same bank and memory region.
+    pub fn describe_in(&self, bank: Option<u16>, addr: u16) -> Option<String> {
+        if let Some(name) = self.get_in(bank, addr) {
+            return Some(name.to_string());
+        }
+
+        let bank = bank?;
+        let region_start = get_region_start(addr);
+        let ((_, label_addr), name) = self
+            .labels
+            .range((bank, region_start)..=(bank, addr))
+            .next_back()?;
+
+        Some(format!("{name}+${:X}", addr - label_addr))
+    }
+
+    pub fn describe(&self, cpu: &Cpu, addr: u16) -> Option<String> {
+        self.describe_in(get_bank_at(cpu, addr), addr)
+    }
+
+    pub fn find(&self, name: &str) -> Option<(u16, u16)> {
+        self.addrs.get(name).copied()
+    }
+
+    /// A label name or an address as [`parse_addr`] reads it.
+    pub fn resolve(&self, s: &str) -> Result<u16, String> {
+        match self.find(s.trim()) {
+            Some((_, addr)) => Ok(addr),
+            None => parse_addr(s),
+        }
+    }
+}
+
+/// A label as it appears in a symbol file, with its 0-based line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolLine<'a> {
+    pub line: usize,
+    pub bank: u16,
+    pub addr: u16,
+    pub name: &'a str,
+}
+
+/// The labels of a `.sym` file or of an RGBDS `.map` file, where they're
+/// `$AAAA = Label` lines under a `ROMX bank #N:` heading, in file order.
+pub fn parse_labels(text: &str) -> Vec<SymbolLine<'_>> {
+    let mut labels = Vec::new();
+    let mut in_labels = true;
+    let mut map_bank = 0;
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split(';').next().unwrap_or_default().trim();
+
+        if let Some(section) = line.strip_prefix('[') {
+            in_labels = section.trim_end_matches(']') == "labels";
+            continue;
+        }
+
+        // `ROMX bank #1:`, or `ROM Bank #1:` in older RGBDS versions
+        let heading = line.to_ascii_lowercase();
+        if let Some((_, bank)) = heading
+            .strip_suffix(':')
+            .and_then(|x| x.split_once("bank #"))
+        {
+            map_bank = bank
+                .split(|x: char| !x.is_ascii_digit())
+                .next()
+                .and_then(|x| x.parse().ok())
+                .unwrap_or_default();
+            continue;
+        }
+
+        if let Some((addr, name)) = line.strip_prefix('$').and_then(|x| x.split_once(" = ")) {
+            if let Ok(addr) = u16::from_str_radix(addr, 16) {
+                labels.push(SymbolLine {
+                    line: i,
+                    bank: map_bank,
+                    addr,
+                    name: name.trim(),
+                });
+            }
+            continue;
+        }
+
+        if !in_labels {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let (Some(location), Some(name)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+
+        let Some((bank, addr)) = location.split_once(':') else {
+            continue;
+        };
+
+        if let (Ok(bank), Ok(addr)) = (u16::from_str_radix(bank, 16), u16::from_str_radix(addr, 16))
+        {
+            labels.push(SymbolLine {
+                line: i,
+                bank,
+                addr,
+                name,
+            });
+        }
+    }
+
+    labels
+}
+
+/// Bank mapped at `addr` as `.sym` files number it, or `None` for regions
+/// whose bank isn't tracked here (VRAM and cartridge RAM).
+pub fn get_bank_at(cpu: &Cpu, addr: u16) -> Option<u16> {
+    match addr {
+        0x0000..=0x7FFF => Some(cpu.clock.bus.cart.rom_bank(addr)),
+        0xD000..=0xDFFF => Some((cpu.clock.bus.io.ram.read_wram_bank() & 0x07).max(1) as u16),
+        0xC000..=0xCFFF | 0xFF80..=0xFFFE => Some(0),
+        _ => None,
+    }
+}
+
+fn get_region_start(addr: u16) -> u16 {
+    match addr {
+        0x0000..=0x3FFF => 0x0000,
+        0x4000..=0x7FFF => 0x4000,
+        0x8000..=0x9FFF => 0x8000,
+        0xA000..=0xBFFF => 0xA000,
+        0xC000..=0xCFFF => 0xC000,
+        0xD000..=0xDFFF => 0xD000,
+        0xE000..=0xFF7F => addr,
+        _ => 0xFF80,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_formats() {
+        let rgbds =
+            "; File generated by rgblink\n00:0150 Main\n01:4000 Bank1Fn\n00:c000 wCounter\n";
+        let symbols = Symbols::parse(rgbds);
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(symbols.get(1, 0x4000), Some("Bank1Fn"));
+        assert_eq!(symbols.find("wCounter"), Some((0, 0xC000)));
+
+        let wla = "[information]\nversion 2\n[labels]\n0000:0150 main\n[definitions]\n00000001 _sizeof_x\n";
+        let symbols = Symbols::parse(wla);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols.resolve("main"), Ok(0x150));
+        assert_eq!(symbols.resolve("$200"), Ok(0x200));
+
+        let map = "ROM0 bank #0:\n\tSECTION: $0150-$0152 ($0003 bytes) [\"Main\"]\n\t         $0150 = Main\n\nROMX bank #2:\n\tSECTION: $4000-$4000 ($0001 bytes) [\"Far\"]\n\t         $4000 = FarFn\n";
+        let labels = parse_labels(map);
+        assert_eq!(labels.len(), 2);
+        assert_eq!(
+            labels[1],
+            SymbolLine {
+                line: 6,
+                bank: 2,
+                addr: 0x4000,
+                name: "FarFn"
+            }
+        );
+        assert_eq!(Symbols::parse(map).get(0, 0x150), Some("Main"));
+    }
+}
@@ -0,0 +1,392 @@
+use crate::cpu::interrupts::InterruptType;
+use crate::ppu::lcd::PpuMode;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Dots per microsecond: the PPU runs at 4.194304 MHz in either speed.
+const DOTS_PER_US: f64 = 4.194304;
+/// A frame with the LCD off never ends, so its events stop here.
+const MAX_FRAME_EVENTS: usize = 1 << 16;
+const INTERRUPT_TYPES: [InterruptType; 5] = [
+    InterruptType::VBlank,
+    InterruptType::LCDStat,
+    InterruptType::Timer,
+    InterruptType::Serial,
+    InterruptType::Joypad,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum EventKind {
+    /// The PPU entered a mode.
+    Mode(PpuMode),
+    /// An interrupt was requested: a timer overflow, a finished serial
+    /// transfer, VBlank or a button press.
+    Request(InterruptType),
+    /// A STAT interrupt was requested; `lyc` when LY matching LYC was one
+    /// of its sources.
+    Stat {
+        lyc: bool,
+    },
+    /// The CPU jumped to an interrupt handler.
+    Dispatch(InterruptType),
+    OamDma {
+        active: bool,
+    },
+    /// General-purpose VRAM DMA, or one HBlank DMA block.
+    Hdma {
+        active: bool,
+    },
+    /// A write to an LCD, scroll, window or palette register.
+    Write {
+        addr: u16,
+        value: u8,
+    },
+}
+
+impl EventKind {
+    /// Where Chrome's trace viewer draws it.
+    fn track(&self) -> u8 {
+        match self {
+            EventKind::Mode(_) => 1,
+            EventKind::Request(_) | EventKind::Stat { .. } | EventKind::Dispatch(_) => 2,
+            EventKind::OamDma { .. } | EventKind::Hdma { .. } => 3,
+            EventKind::Write { .. } => 4,
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let on_off = |active: bool| if active { "ON" } else { "OFF" };
+
+        match self {
+            EventKind::Mode(mode) => write!(f, "MODE {} {mode:?}", *mode as u8),
+            EventKind::Request(it) => write!(f, "REQ {it:?}"),
+            EventKind::Stat { lyc: true } => f.write_str("STAT LYC"),
+            EventKind::Stat { lyc: false } => f.write_str("STAT"),
+            EventKind::Dispatch(it) => write!(f, "INT {it:?}"),
+            EventKind::OamDma { active } => write!(f, "OAM DMA {}", on_off(*active)),
+            EventKind::Hdma { active } => write!(f, "HDMA {}", on_off(*active)),
+            EventKind::Write { addr, value } => {
+                write!(f, "{} {value:02X}", register_name(*addr))
+            }
+        }
+    }
+}
+
+/// Whether writes to `addr` go on the timeline: the registers raster
+/// effects change mid-frame.
+pub fn is_traced_register(addr: u16) -> bool {
+    matches!(addr, 0xFF40..=0xFF4B | 0xFF68..=0xFF6B)
+}
+
+fn register_name(addr: u16) -> &'static str {
+    match addr {
+        0xFF40 => "LCDC",
+        0xFF41 => "STAT",
+        0xFF42 => "SCY",
+        0xFF43 => "SCX",
+        0xFF44 => "LY",
+        0xFF45 => "LYC",
+        0xFF46 => "DMA",
+        0xFF47 => "BGP",
+        0xFF48 => "OBP0",
+        0xFF49 => "OBP1",
+        0xFF4A => "WY",
+        0xFF4B => "WX",
+        0xFF68 => "BCPS",
+        0xFF69 => "BCPD",
+        0xFF6A => "OCPS",
+        0xFF6B => "OCPD",
+        _ => "IO",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TimelineEvent {
+    /// Dots since the timeline started.
+    pub time: u64,
+    pub ly: u8,
+    /// Dot within the line.
+    pub dot: u16,
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FrameEvents {
+    pub frame: usize,
+    pub events: Vec<TimelineEvent>,
+    /// Events past the per-frame limit.
+    pub dropped: usize,
+}
+
+/// Hardware state the timeline compares every dot to find what changed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimelineSample {
+    pub frame: usize,
+    pub ly: u8,
+    pub dot: u16,
+    pub mode: PpuMode,
+    pub oam_dma: bool,
+    pub hdma: bool,
+    /// Interrupts requested since the last sample.
+    pub requested: u8,
+    /// Whether the LYC source is enabled and matching.
+    pub lyc: bool,
+}
+
+/// Per-frame record of hardware events with the dot they happened on, for
+/// the last `capacity` frames.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    frames: VecDeque<FrameEvents>,
+    capacity: usize,
+    time: u64,
+    last: TimelineSample,
+}
+
+impl Timeline {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity + 1),
+            capacity: capacity.max(1),
+            time: 0,
+            last: TimelineSample::default(),
+        }
+    }
+
+    /// Takes the state after a dot and records what changed.
+    pub fn sample(&mut self, sample: TimelineSample) {
+        let last = std::mem::replace(&mut self.last, sample);
+        self.time += 1;
+
+        // the first sample only sets the baseline
+        if self.time == 1 {
+            return;
+        }
+
+        if sample.mode != last.mode {
+            self.push(&sample, EventKind::Mode(sample.mode));
+        }
+
+        for it in INTERRUPT_TYPES
+            .iter()
+            .filter(|x| sample.requested & **x as u8 != 0)
+        {
+            let kind = match it {
+                InterruptType::LCDStat => EventKind::Stat { lyc: sample.lyc },
+                _ => EventKind::Request(*it),
+            };
+            self.push(&sample, kind);
+        }
+
+        if sample.oam_dma != last.oam_dma {
+            self.push(
+                &sample,
+                EventKind::OamDma {
+                    active: sample.oam_dma,
+                },
+            );
+        }
+
+        if sample.hdma != last.hdma {
+            self.push(
+                &sample,
+                EventKind::Hdma {
+                    active: sample.hdma,
+                },
+            );
+        }
+    }
+
+    /// Records an event between samples, at the last sampled dot.
+    pub fn record(&mut self, kind: EventKind) {
+        let last = self.last;
+        self.push(&last, kind);
+    }
+
+    /// Frames oldest first; the last one is still running.
+    pub fn frames(&self) -> impl Iterator<Item = &FrameEvents> {
+        self.frames.iter()
+    }
+
+    pub fn last_complete_frame(&self) -> Option<&FrameEvents> {
+        self.frames.iter().rev().nth(1)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.frames).unwrap_or_default()
+    }
+
+    /// The events in Chrome's trace event format, for `chrome://tracing`
+    /// or Perfetto: modes and transfers as spans, the rest as instants.
+    pub fn to_chrome_trace(&self) -> String {
+        let mut events: Vec<Value> = ["PPU", "Interrupts", "DMA", "Registers"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                json!({
+                    "name": "thread_name", "ph": "M", "pid": 1, "tid": i + 1,
+                    "args": { "name": name },
+                })
+            })
+            .collect();
+        // the open span of each track: PPU mode, OAM DMA, HDMA
+        let mut open: [Option<&TimelineEvent>; 3] = [None; 3];
+        let ts = |time: u64| time as f64 / DOTS_PER_US;
+
+        for frame in self.frames.iter() {
+            for event in frame.events.iter() {
+                let span = match event.kind {
+                    EventKind::Mode(_) => Some((0, true)),
+                    EventKind::OamDma { active } => Some((1, active)),
+                    EventKind::Hdma { active } => Some((2, active)),
+                    _ => None,
+                };
+
+                let Some((slot, starts)) = span else {
+                    events.push(json!({
+                        "name": event.kind.to_string(), "ph": "i", "s": "t", "pid": 1,
+                        "tid": event.kind.track(), "ts": ts(event.time),
+                        "args": { "frame": frame.frame, "ly": event.ly, "dot": event.dot },
+                    }));
+                    continue;
+                };
+
+                if let Some(start) = open[slot].take() {
+                    events.push(span_event(start, ts(start.time), ts(event.time)));
+                }
+
+                if starts {
+                    open[slot] = Some(event);
+                }
+            }
+        }
+
+        for start in open.into_iter().flatten() {
+            events.push(span_event(start, ts(start.time), ts(self.time)));
+        }
+
+        json!({ "traceEvents": events, "displayTimeUnit": "ns" }).to_string()
+    }
+
+    fn push(&mut self, at: &TimelineSample, kind: EventKind) {
+        if self.frames.back().is_none_or(|x| x.frame != at.frame) {
+            if self.frames.len() > self.capacity {
+                self.frames.pop_front();
+            }
+
+            self.frames.push_back(FrameEvents {
+                frame: at.frame,
+                ..Default::default()
+            });
+        }
+
+        let frame = self.frames.back_mut().expect("just pushed");
+
+        if frame.events.len() >= MAX_FRAME_EVENTS {
+            frame.dropped += 1;
+            return;
+        }
+
+        frame.events.push(TimelineEvent {
+            time: self.time,
+            ly: at.ly,
+            dot: at.dot,
+            kind,
+        });
+    }
+}
+
+fn span_event(start: &TimelineEvent, ts: f64, end: f64) -> Value {
+    let name = match start.kind {
+        EventKind::OamDma { .. } => "OAM DMA".to_string(),
+        EventKind::Hdma { .. } => "HDMA".to_string(),
+        kind => kind.to_string(),
+    };
+
+    json!({
+        "name": name, "ph": "X", "pid": 1, "tid": start.kind.track(), "ts": ts, "dur": end - ts,
+        "args": { "ly": start.ly, "dot": start.dot },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(frame: usize, dot: u16, mode: PpuMode) -> TimelineSample {
+        TimelineSample {
+            frame,
+            dot,
+            mode,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sample_changes() {
+        let mut timeline = Timeline::new(1);
+        timeline.sample(sample(0, 79, PpuMode::Oam));
+        timeline.sample(sample(0, 80, PpuMode::Transfer));
+        timeline.record(EventKind::Write {
+            addr: 0xFF43,
+            value: 4,
+        });
+        timeline.sample(TimelineSample {
+            requested: InterruptType::LCDStat as u8 | InterruptType::Timer as u8,
+            lyc: true,
+            ..sample(0, 81, PpuMode::Transfer)
+        });
+
+        let kinds: Vec<_> = timeline
+            .frames()
+            .next()
+            .unwrap()
+            .events
+            .iter()
+            .map(|x| x.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::Mode(PpuMode::Transfer),
+                EventKind::Write {
+                    addr: 0xFF43,
+                    value: 4
+                },
+                EventKind::Stat { lyc: true },
+                EventKind::Request(InterruptType::Timer),
+            ]
+        );
+        assert_eq!(kinds[1].to_string(), "SCX 04");
+
+        // a new frame rolls the oldest one out
+        timeline.sample(sample(1, 0, PpuMode::HBlank));
+        timeline.sample(sample(2, 0, PpuMode::Oam));
+        let frames: Vec<_> = timeline.frames().map(|x| x.frame).collect();
+        assert_eq!(frames, [1, 2]);
+        assert_eq!(timeline.last_complete_frame().unwrap().frame, 1);
+    }
+
+    #[test]
+    fn test_chrome_trace_spans() {
+        let mut timeline = Timeline::new(2);
+        timeline.sample(sample(0, 0, PpuMode::HBlank));
+        timeline.sample(sample(0, 1, PpuMode::Oam));
+        timeline.sample(sample(0, 2, PpuMode::Transfer));
+
+        let trace: Value = serde_json::from_str(&timeline.to_chrome_trace()).unwrap();
+        let spans: Vec<_> = trace["traceEvents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|x| x["ph"] == "X")
+            .collect();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0]["name"], "MODE 2 Oam");
+        assert_eq!(spans[0]["dur"].as_f64().unwrap(), 1.0 / DOTS_PER_US);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::cart::mbc3::RtcClock;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -15,25 +16,53 @@ impl Default for GbModel {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EmuConfig {
-    pub rewind_size: usize,
+    /// MiB of rewind history, 0 turns rewind off.
+    #[serde(default = "default_rewind_budget")]
+    pub rewind_budget: usize,
+    /// Frames between rewind snapshots, also how many each rewound one skips.
     pub rewind_frames: usize,
+    /// Plays rewound audio backwards instead of muting it.
+    #[serde(default = "default_rewind_audio")]
+    pub rewind_audio: bool,
+    /// Frames shown ahead of the real one to hide input lag, 0 turns it off.
+    #[serde(default)]
+    pub run_ahead: usize,
+    /// Runs ahead on a copy instead of rolling the live CPU back.
+    #[serde(default)]
+    pub run_ahead_second_instance: bool,
     pub normal_speed: f64,
     pub slow_speed: f64,
     pub turbo_speed: f64,
     pub spin_duration: Duration,
     pub model: Option<GbModel>,
+    /// Time source of MBC3 cartridge clocks; the emulated ones keep runs
+    /// deterministic.
+    #[serde(default)]
+    pub rtc_clock: RtcClock,
+}
+
+fn default_rewind_budget() -> usize {
+    64
+}
+
+fn default_rewind_audio() -> bool {
+    true
 }
 
 impl Default for EmuConfig {
     fn default() -> Self {
         Self {
-            rewind_size: 120,
-            rewind_frames: 60,
+            rewind_budget: default_rewind_budget(),
+            rewind_frames: 1,
+            rewind_audio: default_rewind_audio(),
+            run_ahead: 0,
+            run_ahead_second_instance: false,
             normal_speed: 1.0,
             slow_speed: 0.5,
             turbo_speed: 5.0,
             spin_duration: Duration::from_millis(1),
             model: None,
+            rtc_clock: RtcClock::default(),
         }
     }
 }
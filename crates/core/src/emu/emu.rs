@@ -1,19 +1,26 @@
+use crate::apu::apu::ApuConfig;
 use crate::apu::Apu;
 use crate::auxiliary::clock::Clock;
 use crate::auxiliary::io::Io;
 use crate::auxiliary::joypad::Joypad;
 use crate::bus::Bus;
+use crate::cart::mbc3::RtcClock;
 use crate::cart::Cart;
 use crate::cpu::Cpu;
 use crate::emu::config::EmuConfig;
+#[cfg(feature = "debug")]
+use crate::emu::history::History;
+use crate::emu::movie::{self, MovieSession};
+use crate::emu::netplay::{Netplay, NetplayStatus, Transport};
+use crate::emu::rewind::{self, Rewind};
 use crate::emu::runtime::{EmuRuntime, RunMode};
 use crate::emu::state::{EmuSaveState, EmuState};
+use crate::emu::state_file;
 use crate::cart::header::CgbFlag;
 use crate::ppu::cgb_boot_palette::{self, DmgCompatPalette};
 use crate::ppu::framebuffer::FrameBuffer;
-use crate::ppu::lcd::Lcd;
+use crate::ppu::lcd::{green_colors, Lcd};
 use crate::ppu::Ppu;
-use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 use std::{mem, thread};
 
@@ -26,8 +33,18 @@ pub struct Emu {
     pub state: EmuState,
     pub runtime: EmuRuntime,
     prev_speed_multiplier: f64,
-    rewind_buffer: VecDeque<EmuSaveState>,
-    last_rewind_frame: usize,
+    pub(super) rewind: Rewind,
+    pub(super) last_rewind_frame: usize,
+    /// One frame of audio collected before it's played: a rewound one,
+    /// which plays backwards, or one a netplay session ran.
+    frame_samples: Vec<f32>,
+    /// The movie being recorded or played, driven every frame. Set it with
+    /// [`Self::start_movie`], which also sets the clock it runs on.
+    pub movie: Option<MovieSession>,
+    /// The netplay session, which runs the frames while there is one.
+    pub netplay: Option<Netplay>,
+    #[cfg(feature = "debug")]
+    pub(super) history: History,
 }
 
 impl Emu {
@@ -36,12 +53,27 @@ impl Emu {
             runtime,
             prev_speed_multiplier: config.normal_speed,
             state: EmuState::Running,
-            rewind_buffer: VecDeque::with_capacity(config.rewind_size),
+            rewind: Rewind::default(),
             last_rewind_frame: 0,
+            frame_samples: Vec::new(),
+            movie: None,
+            netplay: None,
+            #[cfg(feature = "debug")]
+            history: History::default(),
             config,
         })
     }
 
+    /// An instance with no cart or debugger, showing DMG games in
+    /// [`green_colors`], for frontends embedding the core.
+    pub fn with_config(config: EmuConfig) -> Result<Self, String> {
+        let lcd = Lcd::new(green_colors(), config.model.unwrap_or_default());
+        let io = Io::new(Ppu::new(lcd), Apu::new(ApuConfig::default()));
+        let bus = Bus::new(Cart::empty(), io, config.model);
+
+        Self::new(config, EmuRuntime::without_debugger(bus))
+    }
+
     #[inline(always)]
     /// The authentic CGB boot-ROM colorization for the loaded cart, or `None`
     /// when no monochrome cart is loaded (CGB games already provide color).
@@ -65,21 +97,49 @@ impl Emu {
     /// Runs emulation for one frame. Return whether the emulation is on time.
     #[inline(always)]
     pub fn run_frame(&mut self, callback: &mut impl EmuAudioCallback) -> bool {
+        if self.netplay.is_some() {
+            return self.run_netplay_frame(callback);
+        }
+
         match self.state {
             EmuState::Rewind => {
-                if let Some(state) = self.rewind_buffer.pop_back() {
-                    self.load_save_state(state);
-                    thread::sleep(Duration::from_millis(250));
+                match self.rewind.pop() {
+                    Some(Ok(cpu)) => {
+                        self.restore_rewind(cpu);
+                        #[cfg(feature = "debug")]
+                        self.history.truncate(self.runtime.cpu.steps);
+                    }
+                    Some(Err(err)) => {
+                        log::warn!("Rewind dropped: {err}");
+                        self.rewind.clear();
+                    }
+                    None => {}
                 }
 
-                self.runtime.run_frame(callback);
+                // the restore released the buttons
+                self.movie_before_frame();
+                #[cfg(feature = "debug")]
+                self.record_input();
+                self.run_rewind_frame(callback);
+                self.movie_after_frame();
             }
             EmuState::Running => {
+                self.movie_before_frame();
+                #[cfg(feature = "debug")]
+                self.record_input();
                 self.runtime.run_frame(callback);
+                self.movie_after_frame();
                 self.push_rewind();
+                self.run_ahead();
             }
         };
 
+        self.pace()
+    }
+
+    /// Sleeps until the frame is due. Returns whether it's on time.
+    #[inline(always)]
+    fn pace(&mut self) -> bool {
         let real_elapsed = self.runtime.cpu.clock.time.elapsed();
         let emulated_time = self.calc_emulated_time();
         let on_time = emulated_time >= real_elapsed;
@@ -136,8 +196,7 @@ impl Emu {
     pub fn create_save_state(&mut self) -> EmuSaveState {
         // The snapshot never needs the ROM image: load_save_state swaps the
         // live one back in. Move it out for the clone so a snapshot doesn't
-        // copy (and the rewind buffer doesn't retain) megabytes of read-only
-        // ROM per entry.
+        // copy megabytes of read-only ROM.
         let data = mem::take(&mut self.runtime.cpu.clock.bus.cart.data);
         let state = EmuSaveState {
             cpu: self.runtime.cpu.clone(),
@@ -150,22 +209,138 @@ impl Emu {
 
     #[inline(always)]
     fn push_rewind(&mut self) {
-        if self.config.rewind_size > 0 {
+        if self.config.rewind_budget > 0 {
             let curr_frame = self.runtime.cpu.clock.bus.io.ppu.current_frame;
             let diff = curr_frame.saturating_sub(self.last_rewind_frame);
 
             if diff >= self.config.rewind_frames {
-                if self.rewind_buffer.len() > self.config.rewind_size {
-                    self.rewind_buffer.pop_front();
+                let budget = self.config.rewind_budget * 1024 * 1024;
+
+                match self.rewind.push(&mut self.runtime.cpu, budget) {
+                    #[cfg(feature = "debug")]
+                    Ok(true) => self.history.trim(self.rewind.steps(0)),
+                    Ok(_) => {}
+                    Err(err) => log::warn!("{err}"),
                 }
 
-                let state = self.create_save_state();
-                self.rewind_buffer.push_back(state);
                 self.last_rewind_frame = curr_frame;
             }
         }
     }
 
+    /// Drives `movie` from the next frame on, with the MBC3 clock on
+    /// [`movie::RTC_CLOCK`] until it stops.
+    pub fn start_movie(&mut self, movie: MovieSession) {
+        self.movie = Some(movie);
+        let rtc_clock = self.rtc_clock();
+        self.runtime.cpu.clock.bus.cart.set_rtc_clock(rtc_clock);
+    }
+
+    /// Takes the movie, going back to the configured clock.
+    pub fn stop_movie(&mut self) -> Option<MovieSession> {
+        let movie = self.movie.take();
+        let rtc_clock = self.rtc_clock();
+        self.runtime.cpu.clock.bus.cart.set_rtc_clock(rtc_clock);
+
+        movie
+    }
+
+    /// Where the MBC3 clock takes its time from: the configured source, or
+    /// emulated time while a movie runs.
+    pub fn rtc_clock(&self) -> RtcClock {
+        match self.movie {
+            Some(_) => movie::RTC_CLOCK,
+            None => self.config.rtc_clock,
+        }
+    }
+
+    fn movie_before_frame(&mut self) {
+        if let Some(movie) = self.movie.as_mut() {
+            movie.before_frame(&mut self.runtime.cpu);
+        }
+    }
+
+    fn movie_after_frame(&mut self) {
+        // a break leaves the frame half run
+        if self.runtime.is_break() {
+            return;
+        }
+
+        if let Some(movie) = self.movie.as_mut() {
+            movie.after_frame(&self.runtime.cpu);
+        }
+    }
+
+    /// Tells the movie a state was loaded, so it branches.
+    pub(super) fn movie_loaded(&mut self) {
+        if let Some(movie) = self.movie.as_mut() {
+            movie.on_load(&self.runtime.cpu);
+        }
+    }
+
+    /// Runs the frame after a rewound snapshot, its audio played backwards
+    /// or not at all.
+    fn run_rewind_frame(&mut self, callback: &mut impl EmuAudioCallback) {
+        let mut samples = mem::take(&mut self.frame_samples);
+        samples.clear();
+        self.runtime.run_frame(&mut SampleSink(&mut samples));
+
+        if self.config.rewind_audio {
+            // reversing the interleaved samples swaps the channels as well
+            samples.reverse();
+            samples.chunks_exact_mut(2).for_each(|pair| pair.swap(0, 1));
+            callback.update(&samples, &self.runtime);
+        }
+
+        self.frame_samples = samples;
+    }
+
+    /// Runs the next frame of the netplay session, or waits a moment if the
+    /// peer is behind.
+    fn run_netplay_frame(&mut self, callback: &mut impl EmuAudioCallback) -> bool {
+        let Some(netplay) = self.netplay.as_mut() else {
+            return true;
+        };
+
+        let cpu = &mut self.runtime.cpu;
+        let pressed = cpu.clock.bus.io.joypad.get_pressed();
+        let mut samples = mem::take(&mut self.frame_samples);
+        samples.clear();
+        let status = netplay.advance(cpu, pressed, Some(&mut samples));
+
+        if !samples.is_empty() {
+            callback.update(&samples, &self.runtime);
+        }
+
+        self.frame_samples = samples;
+
+        if status != NetplayStatus::Ran {
+            // the wait isn't emulated time to catch up on, and there's no
+            // new frame to show
+            thread::sleep(Duration::from_millis(1));
+            self.runtime.cpu.clock.reset();
+            return false;
+        }
+
+        self.pace()
+    }
+
+    /// Powers the loaded game on again for a netplay session as `player` 0
+    /// or 1. Battery RAM stays behind, as the peer's would rarely match.
+    pub fn start_netplay(
+        &mut self,
+        player: usize,
+        delay: usize,
+        transport: Transport,
+    ) -> Result<(), String> {
+        let rom = self.runtime.cpu.clock.bus.cart.data.rom().to_vec();
+        self.load_cart(Cart::new(rom.into_boxed_slice())?);
+        let netplay = Netplay::new(&mut self.runtime.cpu, player, delay, transport)?;
+        self.netplay = Some(netplay);
+
+        Ok(())
+    }
+
     pub fn load_cart(&mut self, cart: Cart) {
         let dmg_palette = &self.runtime.cpu.clock.bus.io.ppu.lcd.dmg_palette;
         let lcd = Lcd::new(
@@ -175,42 +350,99 @@ impl Emu {
         let ppu = Ppu::new(lcd);
         let apu = Apu::new(self.runtime.cpu.clock.bus.io.apu.config.clone());
         let io = Io::new(ppu, apu);
-        let bus = Bus::new(cart, io, self.config.model);
+        let mut bus = Bus::new(cart, io, self.config.model);
+        bus.cart.power_on_rtc(self.config.rtc_clock);
 
         let clock = Clock::new(bus);
         self.runtime.cpu = Cpu::new(clock);
+        // cheats are per game
+        self.runtime.set_cheats(&[]);
 
         self.state = EmuState::Running;
         self.runtime.cpu.clock.reset();
-        self.rewind_buffer.clear();
+        self.rewind.clear();
+        self.movie = None;
+        self.netplay = None;
+        #[cfg(feature = "debug")]
+        {
+            self.history = History::default();
+        }
     }
 
-    pub fn load_save_state(&mut self, mut save_state: EmuSaveState) {
-        // reconstruct cart
-        mem::swap(
-            &mut save_state.cpu.clock.bus.cart.data,
-            &mut self.runtime.cpu.clock.bus.cart.data,
-        );
-        let cart = save_state
-            .cart_save_state
-            .into_cart(save_state.cpu.clock.bus.cart.data);
-        save_state.cpu.clock.bus.cart = cart;
-        self.runtime.cpu = save_state.cpu;
+    /// Loads a state file, refusing one of another game or model, see
+    /// [`state_file::decode_for`].
+    pub fn load_save_state_file(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let state = state_file::decode_for(bytes, &self.runtime.cpu)?;
+        self.load_save_state(state);
+
+        Ok(())
+    }
+
+    pub fn load_save_state(&mut self, save_state: EmuSaveState) {
+        #[cfg(feature = "debug")]
+        let steps = self.runtime.cpu.steps;
+        self.restore_save_state(save_state);
+
+        // the step count keeps going so the rewind snapshots stay in order,
+        // but replaying from one can't reach past the load
+        #[cfg(feature = "debug")]
+        {
+            self.runtime.cpu.steps = steps;
+            self.history.cut(steps);
+        }
+
+        self.movie_loaded();
+    }
+
+    fn restore_save_state(&mut self, save_state: EmuSaveState) {
+        let cpu = save_state.into_cpu(&mut self.runtime.cpu);
+        self.restore_cpu(cpu);
+    }
+
+    fn restore_rewind(&mut self, mut cpu: Cpu) {
+        rewind::carry_over(&mut self.runtime.cpu, &mut cpu);
+        self.restore_cpu(cpu);
+
+        if let Some(movie) = self.movie.as_mut() {
+            movie.on_rewind(&self.runtime.cpu);
+        }
+    }
+
+    fn restore_cpu(&mut self, cpu: Cpu) {
+        self.runtime.cpu = cpu;
+        // states don't carry the clock setting
+        let rtc_clock = self.rtc_clock();
+        self.runtime.cpu.clock.bus.cart.set_rtc_clock(rtc_clock);
         self.runtime.cpu.clock.bus.io.joypad = Joypad::default(); // reset controls
         self.runtime.cpu.clock.reset();
+        // snapshots go on from the restored frame, not the one left
+        self.last_rewind_frame = self.runtime.cpu.clock.bus.io.ppu.current_frame;
+    }
+}
+
+/// Collects a frame's audio instead of playing it.
+pub struct SampleSink<'a>(pub &'a mut Vec<f32>);
+
+impl EmuAudioCallback for SampleSink<'_> {
+    fn update(&mut self, output: &[f32], _runtime: &EmuRuntime) {
+        self.0.extend_from_slice(output);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cart::mbc::MbcVariant;
+    use crate::emu::movie::HASH_INTERVAL;
+    use crate::emu::state_file::SaveStateInfo;
 
     fn new_emu() -> Emu {
         // an all-zero 32 KiB image is a valid RomOnly header
         let cart = Cart::new(vec![0u8; 0x8000].into_boxed_slice()).unwrap();
         let bus = Bus::new(cart, Default::default(), None);
+        let runtime = EmuRuntime::without_debugger(bus);
 
-        Emu::new(EmuConfig::default(), EmuRuntime::new(bus)).unwrap()
+        Emu::new(EmuConfig::default(), runtime).unwrap()
     }
 
     #[test]
@@ -230,4 +462,103 @@ mod tests {
         assert_eq!(emu.runtime.cpu.registers.pc, pc);
         assert_eq!(emu.runtime.cpu.clock.bus.cart.data.rom().len(), 0x8000);
     }
+
+    #[test]
+    fn test_rewind_resumes_snapshots() {
+        let mut emu = new_emu();
+        let mut samples = Vec::new();
+
+        for _ in 0..10 {
+            emu.runtime.run_frame(&mut SampleSink(&mut samples));
+            emu.push_rewind();
+        }
+
+        let len = emu.rewind.len();
+        let cpu = emu.rewind.pop().unwrap().unwrap();
+        emu.restore_rewind(cpu);
+        emu.runtime.run_frame(&mut SampleSink(&mut samples));
+        emu.push_rewind();
+        assert_eq!(emu.rewind.len(), len);
+    }
+
+    #[test]
+    fn test_load_save_state_file() {
+        let mut emu = new_emu();
+        let state = emu.create_save_state();
+        let info = SaveStateInfo::new(&emu.runtime.cpu);
+        let bytes = state_file::encode(&state, &info).unwrap();
+        assert!(emu.load_save_state_file(&bytes).is_ok());
+
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x14F] = 1;
+        emu.load_cart(Cart::new(rom.into_boxed_slice()).unwrap());
+        let err = emu.load_save_state_file(&bytes).unwrap_err();
+        assert!(err.contains("another ROM"), "{err}");
+    }
+
+    /// An MBC3 cart copying the clock's seconds to $C000 every loop, with a
+    /// battery save last synced a second into 1970, on the wall clock.
+    fn new_rtc_emu() -> Emu {
+        let program = [
+            0x3E, 0x0A, 0xEA, 0x00, 0x00, // LD A,$0A; LD ($0000),A
+            0x3E, 0x08, 0xEA, 0x00, 0x40, // LD A,$08; LD ($4000),A
+            0xAF, 0xEA, 0x00, 0x60, // XOR A; LD ($6000),A
+            0x3C, 0xEA, 0x00, 0x60, // INC A; LD ($6000),A
+            0xFA, 0x00, 0xA0, 0xEA, 0x00, 0xC0, // LD A,($A000); LD ($C000),A
+            0x18, 0xF0, // JR -16
+        ];
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x100..0x100 + program.len()].copy_from_slice(&program);
+        // MBC3+TIMER+BATTERY
+        rom[0x147] = 0x0F;
+        let mut cart = Cart::new(rom.into_boxed_slice()).unwrap();
+        let mut battery = [0u8; 48];
+        battery[40] = 1;
+        cart.load_ram(battery.into());
+
+        let mut emu = new_emu();
+        emu.load_cart(cart);
+
+        emu
+    }
+
+    fn run_movie_frames(emu: &mut Emu, frames: usize) {
+        let mut samples = Vec::new();
+
+        for _ in 0..frames {
+            emu.movie_before_frame();
+            emu.runtime.run_frame(&mut SampleSink(&mut samples));
+            emu.movie_after_frame();
+        }
+    }
+
+    #[test]
+    fn test_movie_rtc() {
+        let mut emu = new_rtc_emu();
+        let state = emu.create_save_state();
+        let info = SaveStateInfo::new(&emu.runtime.cpu);
+        emu.start_movie(MovieSession::record(&state, &info, &[], true).unwrap());
+        assert_eq!(emu.runtime.cpu.clock.bus.cart.rtc_clock(), movie::RTC_CLOCK);
+        run_movie_frames(&mut emu, 2 * HASH_INTERVAL + 10);
+
+        let mut movie = emu.stop_movie().unwrap().into_movie();
+        let cart = &emu.runtime.cpu.clock.bus.cart;
+        assert_eq!(cart.rtc_clock(), RtcClock::WallClock);
+
+        // played back decades later, which the host's clock would count
+        let (info, mut state) = state_file::decode(&movie.state).unwrap();
+        if let MbcVariant::Mbc3(mbc) = &mut state.cart_save_state.mbc {
+            mbc.clear_rtc_anchor();
+        }
+        movie.state = state_file::encode(&state, &info.unwrap()).unwrap();
+
+        let mut emu = new_rtc_emu();
+        emu.load_save_state(movie.start_state(&emu.runtime.cpu).unwrap());
+        emu.start_movie(MovieSession::play(movie, &emu.runtime.cpu, true));
+        run_movie_frames(&mut emu, 2 * HASH_INTERVAL + 10);
+
+        let session = emu.stop_movie().unwrap();
+        assert_eq!(session.movie().hashes.len(), 2);
+        assert_eq!(session.desync(), None);
+    }
 }
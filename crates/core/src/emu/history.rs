@@ -0,0 +1,434 @@
+//! Time-travel debugging on the rewind snapshots. The CPU counts its steps
+//! and the joypad is logged whenever it changes between frames, so restoring
+//! the closest earlier snapshot and replaying forward reproduces any past step
+//! exactly. Replay applies the current cheats: changing them, or poking memory
+//! from the debugger, makes the older history diverge.
+
+use super::rewind;
+use super::Emu;
+use crate::cheats::RamWrite;
+use crate::cpu::Cpu;
+use crate::debugger::{AccessKind, BreakReason, Breakpoints, Debugger, Watchpoint};
+use crate::ppu::framebuffer::FrameBuffer;
+use std::collections::VecDeque;
+
+/// Input and save-state loads by CPU step, back to the oldest rewind snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    /// Buttons held from a step on, logged when they change.
+    inputs: VecDeque<(u64, u8)>,
+    /// Steps where a save state was loaded; replay can't cross them.
+    cuts: VecDeque<u64>,
+}
+
+impl History {
+    pub fn record(&mut self, step: u64, pressed: u8) {
+        match self.inputs.back_mut() {
+            Some(last) if last.1 == pressed => {}
+            Some(last) if last.0 == step => last.1 = pressed,
+            _ => self.inputs.push_back((step, pressed)),
+        }
+    }
+
+    pub fn cut(&mut self, step: u64) {
+        self.cuts.push_back(step);
+    }
+
+    /// Forgets everything after `step`, which is now the present.
+    pub fn truncate(&mut self, step: u64) {
+        self.inputs.retain(|x| x.0 <= step);
+        self.cuts.retain(|x| *x <= step);
+    }
+
+    /// Forgets everything before `step`, the oldest snapshot.
+    pub fn trim(&mut self, step: u64) {
+        self.inputs.retain(|x| x.0 >= step);
+        self.cuts.retain(|x| *x > step);
+    }
+
+    pub fn can_replay(&self, from: u64, to: u64) -> bool {
+        !self.cuts.iter().any(|x| from < *x && *x <= to)
+    }
+}
+
+/// The last write to an address, found by [`Emu::last_write`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LastWrite {
+    /// Position right after the write.
+    pub step: u64,
+    /// The instruction that wrote.
+    pub pc: u16,
+    pub value: u8,
+}
+
+/// A scratch CPU stepping forward from a snapshot, with the logged input.
+struct Replay<'a> {
+    cpu: Cpu,
+    debugger: Debugger,
+    inputs: &'a VecDeque<(u64, u8)>,
+    next_input: usize,
+    cheats: &'a [RamWrite],
+}
+
+impl Replay<'_> {
+    /// Like `EmuRuntime::step`, without audio. Breaks before an instruction
+    /// leave it unexecuted until the next call.
+    fn step(&mut self) -> Option<BreakReason> {
+        while let Some(&(step, pressed)) = self.inputs.get(self.next_input) {
+            if step > self.cpu.steps {
+                break;
+            }
+
+            self.cpu.clock.bus.io.joypad.set_pressed(pressed);
+            self.next_input += 1;
+        }
+
+        let frame = self.cpu.clock.bus.io.ppu.current_frame;
+        let reason = self.cpu.step_debug(&mut self.debugger);
+
+        if frame != self.cpu.clock.bus.io.ppu.current_frame {
+            for write in self.cheats.iter() {
+                write.apply(&mut self.cpu.clock.bus);
+            }
+        }
+
+        let apu = &mut self.cpu.clock.bus.io.apu;
+        if apu.buffer_ready() {
+            apu.clear_buffer();
+        }
+
+        reason
+    }
+
+    fn run_to(&mut self, step: u64) {
+        while self.cpu.steps < step {
+            self.step();
+        }
+    }
+}
+
+impl Emu {
+    pub(super) fn record_input(&mut self) {
+        let cpu = &self.runtime.cpu;
+        let pressed = cpu.clock.bus.io.joypad.get_pressed();
+        self.history.record(cpu.steps, pressed);
+    }
+
+    /// Goes back to the last step the debugger could have stopped before.
+    pub fn reverse_step(&mut self) -> Result<(), String> {
+        let step = self.search_back(&Breakpoints::default(), |replay, end| {
+            let mut found = None;
+
+            while replay.cpu.steps < end {
+                if Debugger::can_stop(&replay.cpu) {
+                    found = Some(replay.cpu.steps);
+                }
+
+                replay.step();
+            }
+
+            found
+        })?;
+
+        let step = step.ok_or("No earlier step in the rewind history")?;
+        self.seek(step)?;
+        self.resume_debugger();
+
+        Ok(())
+    }
+
+    /// Goes back to the last breakpoint or watchpoint hit, or to the oldest
+    /// step in the rewind history when there was none.
+    pub fn reverse_continue(&mut self) -> Result<Option<BreakReason>, String> {
+        let breakpoints = match self.runtime.debugger_mut() {
+            Some(debugger) => debugger.breakpoints.clone(),
+            None => return Err("Debugger is off".to_string()),
+        };
+        let now = self.runtime.cpu.steps;
+
+        let hit = self.search_back(&breakpoints, |replay, end| {
+            let mut last = None;
+
+            while replay.cpu.steps < end {
+                let step = replay.cpu.steps;
+
+                if let Some(reason) = replay.step() {
+                    // a break before an instruction stops where it was
+                    let before = replay.cpu.steps == step;
+
+                    if replay.cpu.steps < now {
+                        last = Some((replay.cpu.steps, before, reason));
+                    }
+                }
+            }
+
+            last
+        })?;
+
+        let Some((step, before, reason)) = hit else {
+            let oldest = self.windows(now).last().map(|x| self.rewind.steps(x.0));
+            self.seek(oldest.ok_or("No rewind history")?)?;
+            self.resume_debugger();
+
+            return Ok(None);
+        };
+
+        self.seek(step)?;
+
+        if before {
+            self.resume_debugger();
+        }
+
+        Ok(Some(reason))
+    }
+
+    /// Finds the last write to `addr` in the rewind history, without
+    /// leaving the present; [`Emu::seek`] to its step to go there.
+    pub fn last_write(&self, addr: u16) -> Result<Option<LastWrite>, String> {
+        let breakpoints = Breakpoints {
+            watch: vec![Watchpoint::new(addr, addr).write()],
+            ..Default::default()
+        };
+
+        self.search_back(&breakpoints, |replay, end| {
+            let mut last = None;
+
+            while replay.cpu.steps < end {
+                let pc = replay.cpu.registers.pc;
+
+                if let Some(BreakReason::Watchpoint(access)) = replay.step() {
+                    if access.kind == AccessKind::Write {
+                        last = Some(LastWrite {
+                            step: replay.cpu.steps,
+                            pc,
+                            value: access.value,
+                        });
+                    }
+                }
+            }
+
+            last
+        })
+    }
+
+    /// Goes back to a past `step`, replaying from the closest snapshot before
+    /// it. The snapshots and input after it are dropped: running on from
+    /// there makes a new future.
+    pub fn seek(&mut self, step: u64) -> Result<(), String> {
+        if step > self.runtime.cpu.steps {
+            return Err(format!("Step {step} is in the future"));
+        }
+
+        let index = (0..self.rewind.len())
+            .rposition(|i| {
+                let start = self.rewind.steps(i);
+                start <= step && self.history.can_replay(start, step)
+            })
+            .ok_or_else(|| format!("Step {step} is out of the rewind history"))?;
+
+        let mut cpu = {
+            let mut replay = self.replay(index, &Breakpoints::default())?;
+            replay.run_to(step);
+            replay.cpu
+        };
+
+        rewind::carry_over(&mut self.runtime.cpu, &mut cpu);
+        self.runtime.cpu = cpu;
+        self.runtime.cpu.clock.reset();
+        self.rewind.truncate(index + 1);
+        self.history.truncate(step);
+        self.last_rewind_frame = self.runtime.cpu.clock.bus.io.ppu.current_frame;
+        self.movie_loaded();
+
+        if let Some(debugger) = self.runtime.debugger_mut() {
+            debugger.clear_step_mode();
+        }
+
+        Ok(())
+    }
+
+    /// Replays the history before now one snapshot window at a time, newest
+    /// first, until `find` returns something for a window. It gets the
+    /// replay at the window's start and the step where the window ends.
+    fn search_back<T>(
+        &self,
+        breakpoints: &Breakpoints,
+        mut find: impl FnMut(&mut Replay, u64) -> Option<T>,
+    ) -> Result<Option<T>, String> {
+        let windows = self.windows(self.runtime.cpu.steps);
+
+        if windows.is_empty() {
+            return Err("No rewind history".to_string());
+        }
+
+        for (index, end) in windows {
+            let mut replay = self.replay(index, breakpoints)?;
+
+            if let Some(found) = find(&mut replay, end) {
+                return Ok(Some(found));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The snapshots before `now` that replay can reach it from, newest
+    /// first, each with the step where the next one takes over.
+    fn windows(&self, now: u64) -> Vec<(usize, u64)> {
+        let mut windows = Vec::new();
+        let mut end = now;
+
+        for index in (0..self.rewind.len()).rev() {
+            let start = self.rewind.steps(index);
+
+            if start >= end {
+                continue;
+            }
+
+            if !self.history.can_replay(start, now) {
+                break;
+            }
+
+            windows.push((index, end));
+            end = start;
+        }
+
+        windows
+    }
+
+    fn replay(&self, index: usize, breakpoints: &Breakpoints) -> Result<Replay<'_>, String> {
+        let mut cpu = self.rewind.get(index)?;
+        cpu.clock.bus.cart.data = self.runtime.cpu.clock.bus.cart.data.clone();
+        cpu.clock.bus.io.ppu.lcd.buffer = FrameBuffer::default();
+        cpu.clock.bus.cart.set_rtc_clock(self.rtc_clock());
+
+        let mut debugger = Debugger::new_disabled();
+        debugger.breakpoints = breakpoints.clone();
+        let inputs = &self.history.inputs;
+
+        Ok(Replay {
+            next_input: inputs.partition_point(|x| x.0 < cpu.steps),
+            cpu,
+            debugger,
+            inputs,
+            cheats: self.runtime.ram_cheats(),
+        })
+    }
+
+    /// Stopped before an instruction, resuming runs it rather than breaking
+    /// on it again.
+    fn resume_debugger(&mut self) {
+        if let Some(debugger) = self.runtime.debugger_mut() {
+            debugger.resume();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cart::Cart;
+    use crate::cheats::Cheat;
+    use crate::emu::config::EmuConfig;
+    use crate::emu::runtime::EmuRuntime;
+    use crate::emu::SampleSink;
+
+    fn new_emu() -> Emu {
+        // LD A,$20; LDH ($00),A; LDH A,($00); LD ($C000),A; JR -11
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x100..0x10B].copy_from_slice(&[
+            0x3E, 0x20, 0xE0, 0x00, 0xF0, 0x00, 0xEA, 0x00, 0xC0, 0x18, 0xF5,
+        ]);
+        let cart = Cart::new(rom.into_boxed_slice()).unwrap();
+        let bus = Bus::new(cart, Default::default(), None);
+        let runtime = EmuRuntime::new(bus, Some(Debugger::new_disabled()));
+        let mut emu = Emu::new(EmuConfig::default(), runtime).unwrap();
+
+        emu.rewind.push(&mut emu.runtime.cpu, usize::MAX).unwrap();
+
+        emu
+    }
+
+    fn step(emu: &mut Emu, count: usize) {
+        for _ in 0..count {
+            emu.runtime.cpu.step();
+        }
+    }
+
+    #[test]
+    fn test_reverse_step_replays_input() {
+        let mut emu = new_emu();
+        step(&mut emu, 6);
+        emu.runtime.cpu.clock.bus.io.joypad.right = true;
+        emu.record_input();
+        step(&mut emu, 5);
+
+        let cpu = &emu.runtime.cpu;
+        let expected = (
+            cpu.registers.pc,
+            cpu.registers.a,
+            cpu.clock.bus.read(0xC000),
+        );
+        // right reads as a low bit 0 once the loop stored it
+        assert_eq!(expected.2 & 1, 0);
+        step(&mut emu, 1);
+
+        emu.reverse_step().unwrap();
+        let cpu = &emu.runtime.cpu;
+        assert_eq!(cpu.steps, 11);
+        assert_eq!(
+            (
+                cpu.registers.pc,
+                cpu.registers.a,
+                cpu.clock.bus.read(0xC000)
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_reverse_continue_and_last_write() {
+        let mut emu = new_emu();
+        let debugger = emu.runtime.debugger_mut().unwrap();
+        debugger.breakpoints.toggle(0x0106);
+        step(&mut emu, 20);
+
+        // the store runs as steps 4, 9, 14 and 19
+        let reason = emu.reverse_continue().unwrap();
+        assert_eq!(reason, Some(BreakReason::Breakpoint { pc: 0x0106 }));
+        assert_eq!(emu.runtime.cpu.steps, 18);
+        assert_eq!(emu.reverse_continue().unwrap(), reason);
+        assert_eq!(emu.runtime.cpu.steps, 13);
+
+        let write = emu.last_write(0xC000).unwrap().unwrap();
+        assert_eq!((write.step, write.pc), (9, 0x0106));
+        assert_eq!(emu.runtime.cpu.steps, 13);
+
+        emu.runtime.debugger_mut().unwrap().breakpoints = Breakpoints::default();
+        assert_eq!(emu.reverse_continue().unwrap(), None);
+        assert_eq!(emu.runtime.cpu.steps, 0);
+        assert!(emu.reverse_step().is_err());
+    }
+
+    #[test]
+    fn test_replay_matches_cheats() {
+        let mut emu = new_emu();
+        emu.runtime.set_cheats(&[Cheat {
+            name: "C100".to_string(),
+            code: "014200C1".to_string(),
+            enabled: true,
+        }]);
+        let mut samples = Vec::new();
+        let frame = emu.runtime.cpu.clock.bus.io.ppu.current_frame;
+
+        // a frame ended by scanlines gets the writes as one run whole does
+        while frame == emu.runtime.cpu.clock.bus.io.ppu.current_frame {
+            emu.runtime.run_scanline(&mut SampleSink(&mut samples));
+        }
+
+        assert_eq!(emu.runtime.cpu.clock.bus.read(0xC100), 0x42);
+        emu.reverse_step().unwrap();
+        assert_eq!(emu.runtime.cpu.clock.bus.read(0xC100), 0x42);
+    }
+}
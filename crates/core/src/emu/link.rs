@@ -0,0 +1,130 @@
+//! A link cable between two consoles run side by side. They take turns by
+//! instruction, always the one behind in emulated time, and a transfer one
+//! of them clocks swaps the bytes when it ends, handing the byte to a
+//! partner waiting on the external clock. Bits aren't exchanged one by one,
+//! which no game relies on as long as the bytes arrive together.
+use crate::cpu::Cpu;
+
+/// 4 MHz cycles of a frame.
+pub const FRAME_CYCLES: u64 = 70224;
+
+/// How far each console of a linked pair has run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Link {
+    frame: u64,
+    /// 4 MHz cycles each console has run.
+    cycles: [u64; 2],
+}
+
+impl Link {
+    /// Frames run so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Runs both consoles to the end of the next frame. `on_step` is called
+    /// with the index and CPU of each console after it steps.
+    pub fn run_frame(&mut self, cpus: [&mut Cpu; 2], mut on_step: impl FnMut(usize, &mut Cpu)) {
+        let [a, b] = cpus;
+        self.frame += 1;
+        let end = self.frame * FRAME_CYCLES;
+
+        while self.cycles[0] < end || self.cycles[1] < end {
+            // ties go to the first, so both sides of a netplay session
+            // interleave the same way
+            let i = usize::from(self.cycles[1] < self.cycles[0]);
+            let (cpu, partner) = if i == 0 {
+                (&mut *a, &mut *b)
+            } else {
+                (&mut *b, &mut *a)
+            };
+
+            self.cycles[i] += step(cpu);
+            exchange(cpu, partner);
+            on_step(i, cpu);
+        }
+    }
+}
+
+/// Steps `cpu`, returning the 4 MHz cycles it took.
+fn step(cpu: &mut Cpu) -> u64 {
+    let before = cpu.clock.get_m_cycles();
+    cpu.step();
+    let after = cpu.clock.get_m_cycles();
+    // the count restarts on a speed switch; an empty step still counts, so
+    // the pair can't stall
+    let m_cycles = if after >= before {
+        after - before
+    } else {
+        after
+    }
+    .max(1) as u64;
+
+    if cpu.clock.bus.io.cgb_speed.double_speed {
+        m_cycles * 2
+    } else {
+        m_cycles * 4
+    }
+}
+
+fn exchange(cpu: &mut Cpu, partner: &mut Cpu) {
+    let Some(byte) = cpu.clock.bus.io.serial.take_link_sent() else {
+        return;
+    };
+
+    let io = &mut partner.clock.bus.io;
+
+    if io.serial.is_link_waiting() {
+        let received = io.serial.link_clock_in(byte, &mut io.interrupts);
+        cpu.clock.bus.io.serial.set_link_received(received);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary::clock::Clock;
+    use crate::bus::Bus;
+    use crate::cart::Cart;
+
+    /// Sends `byte` on the clock `sc` selects and stores what comes back at
+    /// $C000.
+    fn new_cpu(byte: u8, sc: u8) -> Cpu {
+        // LD A,byte; LDH ($01),A; LD A,sc; LDH ($02),A; wait: LDH A,($02);
+        // BIT 7,A; JR NZ,wait; LDH A,($01); LD ($C000),A; JR -2
+        let program = [
+            0x3E, byte, 0xE0, 0x01, 0x3E, sc, 0xE0, 0x02, 0xF0, 0x02, 0xCB, 0x7F, 0x20, 0xFA, 0xF0,
+            0x01, 0xEA, 0x00, 0xC0, 0x18, 0xFE,
+        ];
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x100..0x100 + program.len()].copy_from_slice(&program);
+        let cart = Cart::new(rom.into_boxed_slice()).unwrap();
+
+        Cpu::new(Clock::new(Bus::new(cart, Default::default(), None)))
+    }
+
+    #[test]
+    fn test_exchange() {
+        let mut master = new_cpu(0x42, 0x81);
+        let mut slave = new_cpu(0x99, 0x80);
+        let mut link = Link::default();
+        let mut steps = [0; 2];
+        link.run_frame([&mut master, &mut slave], |i, _| steps[i] += 1);
+
+        assert_eq!(link.frame(), 1);
+        assert!(steps[0] > 0 && steps[1] > 0);
+        assert_eq!(master.clock.bus.read(0xC000), 0x99);
+        assert_eq!(slave.clock.bus.read(0xC000), 0x42);
+    }
+
+    #[test]
+    fn test_open_line() {
+        // with no one waiting the master reads the open line
+        let mut master = new_cpu(0x42, 0x81);
+        let mut other = new_cpu(0x99, 0x81);
+        Link::default().run_frame([&mut master, &mut other], |_, _| {});
+
+        assert_eq!(master.clock.bus.read(0xC000), 0xFF);
+        assert_eq!(other.clock.bus.read(0xC000), 0xFF);
+    }
+}
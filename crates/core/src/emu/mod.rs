@@ -1,6 +1,14 @@
 pub mod config;
 pub mod emu;
+#[cfg(feature = "debug")]
+pub mod history;
+pub mod link;
+pub mod movie;
+pub mod netplay;
+pub mod rewind;
+mod run_ahead;
 pub mod runtime;
 pub mod state;
+pub mod state_file;
 
 pub use emu::*;
@@ -0,0 +1,511 @@
+//! Input movies: the buttons held each frame from a starting state, enough
+//! to play a session back exactly. A movie starts from a save state made
+//! when recording began, right after a reset for one recorded from power
+//! on, so battery RAM and the RTC come along. Every [`HASH_INTERVAL`]
+//! frames a hash of the machine is stored too, and playback compares
+//! against it to tell when it stopped following the recording. The MBC3
+//! clock runs on emulated time meanwhile, see [`RTC_CLOCK`].
+//!
+//! Loading a state while a movie runs branches it: when recording, the
+//! input after that point is dropped and recording goes on from there.
+//! Read-write playback switches to recording the same way, read-only
+//! playback keeps playing the movie from the loaded frame.
+use crate::cart::mbc::MbcVariant;
+use crate::cart::mbc3::RtcClock;
+use crate::cheats::{Cheat, RamWrite};
+use crate::cpu::Cpu;
+use crate::emu::runtime;
+use crate::emu::state::EmuSaveState;
+use crate::emu::state_file::{self, SaveStateInfo};
+use crc::{Crc, CRC_32_ISO_HDLC};
+use serde::{Deserialize, Serialize};
+use std::mem;
+
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+pub const MAGIC: [u8; 8] = *b"OXGBCMOV";
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Frames between two state hashes.
+pub const HASH_INTERVAL: usize = 60;
+
+/// The MBC3 clock a movie runs on. The starting state holds the clock's
+/// registers, and emulated time carries them on the same way on every
+/// playback, where the host's time would differ from the recording's.
+/// [`Emu::start_movie`] sets it, frontends driving a [`MovieSession`]
+/// themselves set it on the cart.
+///
+/// [`Emu::start_movie`]: crate::emu::Emu::start_movie
+pub const RTC_CLOCK: RtcClock = RtcClock::Emulated;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Movie {
+    /// Version of the core that recorded it.
+    pub emu_version: String,
+    /// Recorded from a reset rather than from a game in progress.
+    pub power_on: bool,
+    /// Where it starts, a save-state file with the ROM and model in its
+    /// info.
+    pub state: Vec<u8>,
+    /// Cheats that were on, they change the game as much as the input.
+    pub cheats: Vec<Cheat>,
+    /// Buttons held each frame, as [`Joypad::get_pressed`] bits.
+    ///
+    /// [`Joypad::get_pressed`]: crate::auxiliary::joypad::Joypad::get_pressed
+    pub inputs: Vec<u8>,
+    /// [`state_hash`] after every [`HASH_INTERVAL`] frames.
+    pub hashes: Vec<u32>,
+    /// Times recording went back to an earlier frame and went on from there.
+    pub rerecords: u32,
+}
+
+impl Movie {
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        postcard::to_extend(self, bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let rest = bytes.strip_prefix(&MAGIC).ok_or("not a movie file")?;
+
+        if rest.len() < 2 {
+            return Err("movie is truncated".to_string());
+        }
+
+        let (version, payload) = rest.split_at(2);
+        let version = u16::from_le_bytes([version[0], version[1]]);
+
+        if version > FORMAT_VERSION {
+            return Err(format!(
+                "movie format {version} is newer than this build reads ({FORMAT_VERSION})"
+            ));
+        }
+
+        postcard::from_bytes(payload).map_err(|e| format!("invalid movie: {e}"))
+    }
+
+    /// Info of the starting state: ROM identity, model and frame.
+    pub fn info(&self) -> Result<SaveStateInfo, String> {
+        state_file::decode_info(&self.state)?.ok_or_else(|| "movie has no state info".to_string())
+    }
+
+    /// The starting state, refused unless it's of the ROM `cpu` runs, on
+    /// the same model.
+    pub fn start_state(&self, cpu: &Cpu) -> Result<EmuSaveState, String> {
+        self.info()?;
+
+        state_file::decode_for(&self.state, cpu)
+    }
+
+    /// Length in frames.
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MovieMode {
+    Record,
+    Play,
+    /// Read-only playback ran out of input.
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MovieEvent {
+    /// The state hash after `frame` differs from the recorded one.
+    Desync { frame: usize },
+    /// Playback reached the end of the input.
+    Ended,
+}
+
+/// A movie being recorded or played back. [`Emu`] drives it around every
+/// frame it runs; headless frontends use [`MovieSession::play_frame`].
+///
+/// [`Emu`]: crate::emu::Emu
+#[derive(Debug, Clone)]
+pub struct MovieSession {
+    movie: Movie,
+    mode: MovieMode,
+    read_only: bool,
+    /// PPU frame count at the first frame of the movie.
+    start_frame: usize,
+    /// First frame found out of sync.
+    desync: Option<usize>,
+    /// Input was recorded, so the movie differs from the one it started as.
+    modified: bool,
+    events: Vec<MovieEvent>,
+}
+
+impl MovieSession {
+    /// Starts recording from `state`, made of the running game with `info`.
+    pub fn record(
+        state: &EmuSaveState,
+        info: &SaveStateInfo,
+        cheats: &[Cheat],
+        power_on: bool,
+    ) -> Result<Self, String> {
+        let movie = Movie {
+            emu_version: env!("CARGO_PKG_VERSION").to_string(),
+            power_on,
+            state: state_file::encode(state, info)?,
+            cheats: cheats.to_vec(),
+            inputs: Vec::new(),
+            hashes: Vec::new(),
+            rerecords: 0,
+        };
+
+        Ok(Self::new(movie, MovieMode::Record, false, &state.cpu))
+    }
+
+    /// Plays `movie` back on `cpu`, which must have its starting state
+    /// loaded.
+    pub fn play(movie: Movie, cpu: &Cpu, read_only: bool) -> Self {
+        Self::new(movie, MovieMode::Play, read_only, cpu)
+    }
+
+    fn new(movie: Movie, mode: MovieMode, read_only: bool, cpu: &Cpu) -> Self {
+        Self {
+            movie,
+            mode,
+            read_only,
+            start_frame: cpu.clock.bus.io.ppu.current_frame,
+            desync: None,
+            modified: false,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn movie(&self) -> &Movie {
+        &self.movie
+    }
+
+    pub fn into_movie(self) -> Movie {
+        self.movie
+    }
+
+    pub fn mode(&self) -> MovieMode {
+        self.mode
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Setting it while recording stops recording and plays the movie
+    /// from there, so loading a state goes back on the input so far.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+
+        if read_only && self.mode == MovieMode::Record {
+            self.mode = MovieMode::Play;
+        }
+    }
+
+    /// Frames of input so far.
+    pub fn len(&self) -> usize {
+        self.movie.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.movie.is_empty()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    pub fn desync(&self) -> Option<usize> {
+        self.desync
+    }
+
+    /// Frames of the movie `cpu` has run, `None` before it started.
+    pub fn frame(&self, cpu: &Cpu) -> Option<usize> {
+        cpu.clock
+            .bus
+            .io
+            .ppu
+            .current_frame
+            .checked_sub(self.start_frame)
+    }
+
+    pub fn take_events(&mut self) -> Vec<MovieEvent> {
+        mem::take(&mut self.events)
+    }
+
+    /// Sets the buttons of the coming frame when playing, or stores the
+    /// held ones when recording.
+    pub fn before_frame(&mut self, cpu: &mut Cpu) {
+        let Some(frame) = self.frame(cpu) else {
+            return;
+        };
+        let joypad = &mut cpu.clock.bus.io.joypad;
+
+        if self.mode == MovieMode::Play {
+            if let Some(&pressed) = self.movie.inputs.get(frame) {
+                joypad.set_pressed(pressed);
+                return;
+            }
+
+            self.events.push(MovieEvent::Ended);
+            self.mode = if self.read_only {
+                MovieMode::Finished
+            } else {
+                MovieMode::Record
+            };
+        }
+
+        if self.mode == MovieMode::Record {
+            self.record_input(frame, joypad.get_pressed());
+        }
+    }
+
+    /// Stores or checks the state hash at the end of every interval.
+    pub fn after_frame(&mut self, cpu: &Cpu) {
+        let Some(frame) = self.frame(cpu) else {
+            return;
+        };
+
+        if frame == 0 || frame % HASH_INTERVAL != 0 {
+            return;
+        }
+
+        let index = frame / HASH_INTERVAL - 1;
+        let hash = state_hash(cpu);
+
+        match self.mode {
+            MovieMode::Record if self.movie.hashes.len() >= index => {
+                self.movie.hashes.truncate(index);
+                self.movie.hashes.push(hash);
+            }
+            MovieMode::Play
+                if self.desync.is_none()
+                    && self.movie.hashes.get(index).is_some_and(|x| *x != hash) =>
+            {
+                self.desync = Some(frame);
+                self.events.push(MovieEvent::Desync { frame });
+            }
+            _ => {}
+        }
+    }
+
+    /// Branches the movie after a state was loaded into `cpu`, counting a
+    /// rerecord when that goes back on the recorded input.
+    pub fn on_load(&mut self, cpu: &Cpu) {
+        self.on_rewind(cpu);
+
+        if self.mode == MovieMode::Record && self.frame(cpu).is_some_and(|x| x < self.len()) {
+            self.movie.rerecords += 1;
+        }
+    }
+
+    /// Branches the movie after `cpu` was rewound. Unlike a load it isn't a
+    /// rerecord, or every rewound frame would count as one.
+    pub fn on_rewind(&mut self, cpu: &Cpu) {
+        if self.mode == MovieMode::Play && !self.read_only {
+            self.mode = MovieMode::Record;
+        }
+
+        // a hash checked after this point says nothing about the new branch
+        if self
+            .desync
+            .is_some_and(|x| self.frame(cpu).is_none_or(|frame| frame < x))
+        {
+            self.desync = None;
+        }
+    }
+
+    /// Runs one frame of the movie on a bare `cpu`, with the GameShark
+    /// `cheats` of [`Movie::cheats`].
+    pub fn play_frame(&mut self, cpu: &mut Cpu, cheats: &[RamWrite]) {
+        self.before_frame(cpu);
+        runtime::run_silent_frame(cpu, cheats);
+        self.after_frame(cpu);
+    }
+
+    fn record_input(&mut self, frame: usize, pressed: u8) {
+        let inputs = &mut self.movie.inputs;
+        self.modified = true;
+        inputs.truncate(frame);
+        self.movie.hashes.truncate(frame / HASH_INTERVAL);
+        // frames the movie never saw, e.g. while the emulator was stopped
+        inputs.resize(frame, 0);
+        inputs.push(pressed);
+    }
+}
+
+/// CRC-32 of what a game's course depends on: the CPU registers, RAM, video
+/// memory and the cart's banks, RAM and clock. Audio and the screen follow
+/// from these, and leaving them out keeps the hash the same across
+/// frontends with different sound and palette settings. The MBC3 clock's
+/// wall-clock anchor is left out too, as it tells when a run was made
+/// rather than how it went.
+pub fn state_hash(cpu: &Cpu) -> u32 {
+    let bus = &cpu.clock.bus;
+    let ppu = &bus.io.ppu;
+    let mut mbc = bus.cart.mbc.clone();
+
+    if let MbcVariant::Mbc3(mbc) = &mut mbc {
+        mbc.clear_rtc_anchor();
+    }
+
+    let parts = (
+        &cpu.registers,
+        &bus.io.ram,
+        &ppu.video_ram,
+        &ppu.oam_ram,
+        &mbc,
+    );
+
+    match postcard::to_allocvec(&parts) {
+        Ok(bytes) => CRC32.checksum(&bytes),
+        Err(err) => {
+            log::warn!("Failed to hash state: {err}");
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary::clock::Clock;
+    use crate::bus::Bus;
+    use crate::cart::Cart;
+
+    /// Keeps adding the held action buttons to $C000.
+    fn new_cpu() -> Cpu {
+        // LD A,$10; LDH ($00),A; LDH A,($00); CPL; AND $0F; LD HL,$C000;
+        // ADD (HL); LD (HL),A; JR -16
+        let program = [
+            0x3E, 0x10, 0xE0, 0x00, 0xF0, 0x00, 0x2F, 0xE6, 0x0F, 0x21, 0x00, 0xC0, 0x86, 0x77,
+            0x18, 0xF0,
+        ];
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x100..0x100 + program.len()].copy_from_slice(&program);
+        let cart = Cart::new(rom.into_boxed_slice()).unwrap();
+
+        Cpu::new(Clock::new(Bus::new(cart, Default::default(), None)))
+    }
+
+    fn snapshot(cpu: &mut Cpu) -> EmuSaveState {
+        let data = mem::take(&mut cpu.clock.bus.cart.data);
+        let state = EmuSaveState {
+            cpu: cpu.clone(),
+            cart_save_state: cpu.clock.bus.cart.create_save_state(),
+        };
+        cpu.clock.bus.cart.data = data;
+
+        state
+    }
+
+    fn record(cpu: &mut Cpu, frames: usize) -> MovieSession {
+        let info = SaveStateInfo::new(cpu);
+        let mut session = MovieSession::record(&snapshot(cpu), &info, &[], true).unwrap();
+
+        for i in 0..frames {
+            cpu.clock.bus.io.joypad.set_pressed((i % 3) as u8 * 4);
+            session.play_frame(cpu, &[]);
+        }
+
+        session
+    }
+
+    fn play(movie: Movie, read_only: bool) -> (Cpu, MovieSession) {
+        let mut cpu = new_cpu();
+        let state = movie.start_state(&cpu).unwrap();
+        let cpu = state.into_cpu(&mut cpu);
+        let session = MovieSession::play(movie, &cpu, read_only);
+
+        (cpu, session)
+    }
+
+    #[test]
+    fn test_record_and_play() {
+        let mut cpu = new_cpu();
+        let movie = record(&mut cpu, 130).into_movie();
+        assert_eq!(movie.len(), 130);
+        assert_eq!(movie.hashes.len(), 2);
+
+        let movie = Movie::decode(&movie.encode().unwrap()).unwrap();
+        let (mut played, mut session) = play(movie, true);
+
+        for _ in 0..131 {
+            session.play_frame(&mut played, &[]);
+        }
+
+        assert_eq!(session.take_events(), [MovieEvent::Ended]);
+        assert_eq!(session.mode(), MovieMode::Finished);
+        assert_eq!(played.clock.bus.read(0xC000), cpu.clock.bus.read(0xC000));
+        assert_eq!(state_hash(&played), state_hash(&cpu));
+    }
+
+    #[test]
+    fn test_hash_ignores_rtc_anchor() {
+        let mut rom = vec![0u8; 0x8000];
+        // MBC3+TIMER+BATTERY
+        rom[0x147] = 0x0F;
+        let cart = Cart::new(rom.into_boxed_slice()).unwrap();
+        let mut cpu = Cpu::new(Clock::new(Bus::new(cart, Default::default(), None)));
+        cpu.clock.bus.cart.power_on_rtc(RtcClock::Emulated);
+        let hash = state_hash(&cpu);
+
+        // going back to the wall clock moves the anchor to now
+        cpu.clock.bus.cart.set_rtc_clock(RtcClock::WallClock);
+        assert_eq!(state_hash(&cpu), hash);
+    }
+
+    #[test]
+    fn test_desync() {
+        let mut cpu = new_cpu();
+        let mut movie = record(&mut cpu, 120).into_movie();
+        movie.inputs[70] ^= 4;
+        let (mut cpu, mut session) = play(movie, true);
+
+        for _ in 0..120 {
+            session.play_frame(&mut cpu, &[]);
+        }
+
+        assert_eq!(session.take_events(), [MovieEvent::Desync { frame: 120 }]);
+        assert_eq!(session.desync(), Some(120));
+    }
+
+    #[test]
+    fn test_read_write_branch() {
+        let mut cpu = new_cpu();
+        let movie = record(&mut cpu, 100).into_movie();
+        let (mut cpu, mut session) = play(movie.clone(), false);
+
+        for _ in 0..50 {
+            session.play_frame(&mut cpu, &[]);
+        }
+
+        let branch = snapshot(&mut cpu);
+        session.play_frame(&mut cpu, &[]);
+        cpu = branch.into_cpu(&mut cpu);
+        session.on_load(&cpu);
+        assert_eq!(session.mode(), MovieMode::Record);
+
+        cpu.clock.bus.io.joypad.set_pressed(0);
+        session.play_frame(&mut cpu, &[]);
+
+        let branched = session.into_movie();
+        assert_eq!(branched.len(), 51);
+        assert_eq!(branched.rerecords, 1);
+        assert_eq!(branched.inputs[..50], movie.inputs[..50]);
+        assert_eq!(branched.hashes.len(), 0);
+
+        // read-only keeps playing the movie after a load
+        let (mut cpu, mut session) = play(movie, true);
+        session.play_frame(&mut cpu, &[]);
+        let branch = snapshot(&mut cpu);
+        cpu = branch.into_cpu(&mut cpu);
+        session.on_load(&cpu);
+        assert_eq!(session.mode(), MovieMode::Play);
+    }
+}
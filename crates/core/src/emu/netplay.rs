@@ -0,0 +1,824 @@
+//! Rollback netplay for two players over UDP, in the manner of GGPO. Both
+//! machines run both consoles of a [`Link`]ed pair, each side sending only
+//! the buttons of its own player. Until the peer's input for a frame
+//! arrives, it's taken to be the same as the last one received; when it
+//! turns out different, both consoles roll back to a snapshot of that frame
+//! and run again with the real input, so a mispredicted frame costs a
+//! re-run rather than a wait. A side only waits when it gets more than
+//! [`MAX_PREDICTION`] frames ahead of the peer's input.
+//!
+//! Every [`HASH_INTERVAL`] frames each side hashes both consoles at the
+//! newest frame whose input it has from both players and sends the hashes
+//! along, so a session that stopped being the same on both machines is
+//! reported rather than played on.
+use super::link::Link;
+use super::{movie, rewind, run_ahead};
+use crate::cart::mbc3::RtcClock;
+use crate::cpu::Cpu;
+use crate::emu::config::GbModel;
+use crc::{Crc, CRC_32_ISO_HDLC};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// Frames a side may run past the newest input it has from the peer.
+pub const MAX_PREDICTION: usize = 8;
+
+/// Frames between two desync checks.
+pub const HASH_INTERVAL: usize = 60;
+
+/// How long the peer may go unheard before the session ends.
+pub const PEER_TIMEOUT: Duration = Duration::from_secs(5);
+
+const MAGIC: [u8; 4] = *b"OXNP";
+/// Most inputs a packet carries; the rest follow once these are acked.
+const MAX_PACKET_INPUTS: usize = 64;
+const MAX_PACKET_LEN: usize = 1024;
+/// Least frames between two waits that let a peer running behind catch up.
+const SYNC_INTERVAL: usize = 30;
+/// Hashes kept on each side to compare.
+const KEPT_HASHES: usize = 16;
+/// How often to send while waiting, when there's nothing new to say.
+const RESEND_INTERVAL: Duration = Duration::from_millis(16);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Packet {
+    Hello {
+        version: u16,
+        rom_crc: u32,
+        model: GbModel,
+        player: u8,
+    },
+    Input {
+        /// Frame of the first of `inputs`.
+        start: u32,
+        inputs: Vec<u8>,
+        /// How many of the receiver's inputs the sender has, all in order.
+        ack: u32,
+        frame: u32,
+        /// Frames `frame` is ahead of the last one the sender heard of.
+        advantage: i32,
+        /// The sender's newest hashes and their frame.
+        hash: Option<(u32, [u32; 2])>,
+    },
+}
+
+impl Packet {
+    fn encode(&self) -> Option<Vec<u8>> {
+        match postcard::to_extend(self, MAGIC.to_vec()) {
+            Ok(bytes) => Some(bytes),
+            Err(err) => {
+                log::warn!("Failed to encode netplay packet: {err}");
+                None
+            }
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        postcard::from_bytes(bytes.strip_prefix(&MAGIC)?).ok()
+    }
+}
+
+/// A UDP socket talking to one peer. It can hold packets back and drop some
+/// of them, to try a session out on one machine as if over a real network.
+pub struct Transport {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    latency: Duration,
+    loss: f32,
+    /// xorshift state picking the packets to drop.
+    rng: u32,
+    /// Packets held back and when they're due.
+    queue: VecDeque<(Instant, Vec<u8>)>,
+}
+
+impl Transport {
+    /// Binds `local`, e.g. `0.0.0.0:7845`, to talk to `peer`.
+    pub fn bind(local: &str, peer: &str) -> Result<Self, String> {
+        let socket = UdpSocket::bind(local).map_err(|e| format!("failed to bind {local}: {e}"))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| format!("failed to set up socket: {e}"))?;
+        let peer = peer
+            .to_socket_addrs()
+            .map_err(|e| format!("invalid peer address {peer}: {e}"))?
+            .next()
+            .ok_or_else(|| format!("invalid peer address {peer}"))?;
+
+        Ok(Self {
+            socket,
+            peer,
+            latency: Duration::ZERO,
+            loss: 0.0,
+            rng: 0x9E37_79B9,
+            queue: VecDeque::new(),
+        })
+    }
+
+    /// Delays every packet sent by `latency` and drops the given fraction of
+    /// them.
+    pub fn with_conditions(mut self, latency: Duration, loss: f32) -> Self {
+        self.latency = latency;
+        self.loss = loss.clamp(0.0, 1.0);
+
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, String> {
+        self.socket.local_addr().map_err(|e| e.to_string())
+    }
+
+    fn send(&mut self, bytes: Vec<u8>) {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+
+        if (self.rng as f32 / u32::MAX as f32) < self.loss {
+            return;
+        }
+
+        self.queue.push_back((Instant::now() + self.latency, bytes));
+        self.flush();
+    }
+
+    /// Sends the held back packets that are due.
+    fn flush(&mut self) {
+        let now = Instant::now();
+
+        while let Some((due, bytes)) = self.queue.front() {
+            if *due > now {
+                break;
+            }
+
+            // a lost packet is made up for by the ones after it
+            if let Err(err) = self.socket.send_to(bytes, self.peer) {
+                log::debug!("Netplay send failed: {err}");
+            }
+
+            self.queue.pop_front();
+        }
+    }
+
+    /// The next packet from the peer, if one came.
+    fn recv(&mut self, buf: &mut [u8]) -> Option<usize> {
+        loop {
+            match self.socket.recv_from(buf) {
+                Ok((len, from)) if from == self.peer => return Some(len),
+                Ok(_) => {}
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return None,
+                Err(err) => {
+                    log::debug!("Netplay receive failed: {err}");
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetplayEvent {
+    Connected,
+    /// The consoles were found to differ from the peer's at `frame`.
+    Desync {
+        frame: usize,
+    },
+    /// The session is over, for the given reason.
+    Ended(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetplayStatus {
+    /// No answer from the peer yet.
+    Connecting,
+    /// Too far ahead of the peer, the frame wasn't run.
+    Waiting,
+    Ran,
+    Ended,
+}
+
+/// Both consoles as they were at the start of `frame`.
+struct Snapshot {
+    frame: usize,
+    link: Link,
+    local: Cpu,
+    peer: Cpu,
+}
+
+/// A netplay session. The local console stays with the caller and is
+/// passed to [`Netplay::advance`] every frame, the session keeps the other.
+pub struct Netplay {
+    transport: Transport,
+    player: usize,
+    /// The console of the remote player.
+    peer: Cpu,
+    link: Link,
+    frame: usize,
+    /// Buttons of the local player by frame. Input delay starts it off with
+    /// that many frames of nothing pressed.
+    local_inputs: Vec<u8>,
+    /// Buttons of the remote player received so far, all in order.
+    remote_inputs: Vec<u8>,
+    /// Buttons the remote player's console ran with by frame, received or
+    /// predicted.
+    used_inputs: Vec<u8>,
+    snapshots: VecDeque<Snapshot>,
+    /// Hashes of both consoles by player and their frame.
+    hashes: VecDeque<(usize, [u32; 2])>,
+    peer_hashes: VecDeque<(usize, [u32; 2])>,
+    /// The newest frame hashed.
+    hashed: Option<usize>,
+    rom_crc: u32,
+    model: GbModel,
+    connected: bool,
+    /// Whether the peer has shown by sending input that it got the hello.
+    peer_ready: bool,
+    ended: bool,
+    last_heard: Instant,
+    last_sent: Option<Instant>,
+    peer_ack: usize,
+    peer_frame: usize,
+    peer_advantage: i32,
+    last_sync_wait: usize,
+    rollbacks: usize,
+    desync: Option<usize>,
+    events: Vec<NetplayEvent>,
+}
+
+impl Netplay {
+    /// Starts a session on `local`, a console just powered on, as `player`
+    /// 0 or 1. `delay` frames of input delay trade a little lag for fewer
+    /// rollbacks.
+    pub fn new(
+        local: &mut Cpu,
+        player: usize,
+        delay: usize,
+        transport: Transport,
+    ) -> Result<Self, String> {
+        if player > 1 {
+            return Err(format!("invalid player {}, expected 1 or 2", player + 1));
+        }
+
+        let cart = &mut local.clock.bus.cart;
+
+        if cart.is_empty() {
+            return Err("no game loaded".to_string());
+        }
+
+        // both sides have to see the same time, whatever their host clocks
+        cart.power_on_rtc(RtcClock::Epoch(0));
+        let rom_crc = CRC32.checksum(cart.data.rom());
+        let model = local.clock.bus.io.ppu.lcd.model;
+
+        Ok(Self {
+            transport,
+            player,
+            peer: local.clone(),
+            link: Link::default(),
+            frame: 0,
+            local_inputs: vec![0; delay],
+            remote_inputs: Vec::new(),
+            used_inputs: Vec::new(),
+            snapshots: VecDeque::new(),
+            hashes: VecDeque::new(),
+            peer_hashes: VecDeque::new(),
+            hashed: None,
+            rom_crc,
+            model,
+            connected: false,
+            peer_ready: false,
+            ended: false,
+            last_heard: Instant::now(),
+            last_sent: None,
+            peer_ack: 0,
+            peer_frame: 0,
+            peer_advantage: 0,
+            last_sync_wait: 0,
+            rollbacks: 0,
+            desync: None,
+            events: Vec::new(),
+        })
+    }
+
+    /// Runs the next frame with the local player holding `pressed`, unless
+    /// the session has to wait for the peer. The local console's audio goes
+    /// to `samples`, when given.
+    pub fn advance(
+        &mut self,
+        local: &mut Cpu,
+        pressed: u8,
+        samples: Option<&mut Vec<f32>>,
+    ) -> NetplayStatus {
+        if self.ended {
+            return NetplayStatus::Ended;
+        }
+
+        self.transport.flush();
+        let rollback = self.receive();
+
+        if self.ended {
+            return NetplayStatus::Ended;
+        }
+
+        if !self.connected {
+            if self
+                .last_sent
+                .is_none_or(|x| x.elapsed() >= RESEND_INTERVAL)
+            {
+                self.send_hello();
+                self.last_sent = Some(Instant::now());
+            }
+
+            return NetplayStatus::Connecting;
+        }
+
+        if self.last_heard.elapsed() > PEER_TIMEOUT {
+            self.end("peer timed out".to_string());
+            return NetplayStatus::Ended;
+        }
+
+        if let Some(frame) = rollback {
+            self.rollback(local, frame);
+        }
+
+        self.check_hashes();
+
+        let status = if self.should_wait() {
+            NetplayStatus::Waiting
+        } else {
+            self.local_inputs.push(pressed);
+            self.save_snapshot(local);
+            self.run_frame(local, samples);
+            NetplayStatus::Ran
+        };
+
+        if status == NetplayStatus::Ran
+            || self
+                .last_sent
+                .is_none_or(|x| x.elapsed() >= RESEND_INTERVAL)
+        {
+            self.send_input();
+            self.last_sent = Some(Instant::now());
+        }
+
+        // the frontend keeps track of the buttons on the joypad
+        local.clock.bus.io.joypad.set_pressed(pressed);
+
+        status
+    }
+
+    /// 0 for player 1, 1 for player 2.
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Times frames were run again over a wrong prediction.
+    pub fn rollbacks(&self) -> usize {
+        self.rollbacks
+    }
+
+    /// The first frame found to differ from the peer's.
+    pub fn desync(&self) -> Option<usize> {
+        self.desync
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// The remote player's console.
+    pub fn peer(&self) -> &Cpu {
+        &self.peer
+    }
+
+    pub fn take_events(&mut self) -> Vec<NetplayEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn end(&mut self, reason: String) {
+        log::warn!("Netplay ended: {reason}");
+        self.ended = true;
+        self.events.push(NetplayEvent::Ended(reason));
+    }
+
+    /// Handles what the peer sent, returning the first frame run with a
+    /// wrong prediction.
+    fn receive(&mut self) -> Option<usize> {
+        let mut buf = [0; MAX_PACKET_LEN];
+        let mut rollback = None;
+
+        while let Some(len) = self.transport.recv(&mut buf) {
+            let Some(packet) = Packet::decode(&buf[..len]) else {
+                continue;
+            };
+
+            self.last_heard = Instant::now();
+
+            match packet {
+                Packet::Hello {
+                    version,
+                    rom_crc,
+                    model,
+                    player,
+                } => self.on_hello(version, rom_crc, model, player),
+                Packet::Input {
+                    start,
+                    inputs,
+                    ack,
+                    frame,
+                    advantage,
+                    hash,
+                } if self.connected => {
+                    self.peer_ready = true;
+                    self.peer_ack = self.peer_ack.max(ack as usize);
+
+                    if frame as usize >= self.peer_frame {
+                        self.peer_frame = frame as usize;
+                        self.peer_advantage = advantage;
+                    }
+
+                    if let Some((frame, hash)) = hash {
+                        let frame = frame as usize;
+
+                        if self.peer_hashes.back().is_none_or(|x| x.0 < frame) {
+                            self.peer_hashes.push_back((frame, hash));
+
+                            if self.peer_hashes.len() > KEPT_HASHES {
+                                self.peer_hashes.pop_front();
+                            }
+                        }
+                    }
+
+                    let start = start as usize;
+                    let received = self.remote_inputs.len();
+
+                    // a later packet fills the gap
+                    if start > received {
+                        continue;
+                    }
+
+                    for (frame, input) in (start..).zip(inputs).skip(received - start) {
+                        let used = self.used_inputs.get(frame);
+
+                        if rollback.is_none() && used.is_some_and(|x| *x != input) {
+                            rollback = Some(frame);
+                        }
+
+                        self.remote_inputs.push(input);
+                    }
+                }
+                Packet::Input { .. } => {}
+            }
+
+            if self.ended {
+                return None;
+            }
+        }
+
+        rollback
+    }
+
+    fn on_hello(&mut self, version: u16, rom_crc: u32, model: GbModel, player: u8) {
+        let err = if version != PROTOCOL_VERSION {
+            Some(format!(
+                "peer speaks protocol {version}, this build {PROTOCOL_VERSION}"
+            ))
+        } else if rom_crc != self.rom_crc {
+            Some("peer runs a different ROM".to_string())
+        } else if model != self.model {
+            Some(format!("peer runs the {model:?} model"))
+        } else if player as usize == self.player {
+            Some(format!("both sides are player {}", self.player + 1))
+        } else {
+            None
+        };
+
+        if let Some(err) = err {
+            self.end(err);
+        } else if !self.connected {
+            self.connected = true;
+            self.events.push(NetplayEvent::Connected);
+        }
+    }
+
+    fn send_hello(&mut self) {
+        let hello = Packet::Hello {
+            version: PROTOCOL_VERSION,
+            rom_crc: self.rom_crc,
+            model: self.model,
+            player: self.player as u8,
+        };
+
+        if let Some(bytes) = hello.encode() {
+            self.transport.send(bytes);
+        }
+    }
+
+    fn send_input(&mut self) {
+        // until the peer answers, it may have missed the hello
+        if !self.peer_ready {
+            self.send_hello();
+        }
+
+        let start = self.peer_ack.min(self.local_inputs.len());
+        let end = self.local_inputs.len().min(start + MAX_PACKET_INPUTS);
+        let input = Packet::Input {
+            start: start as u32,
+            inputs: self.local_inputs[start..end].to_vec(),
+            ack: self.remote_inputs.len() as u32,
+            frame: self.frame as u32,
+            advantage: self.advantage(),
+            hash: self
+                .hashes
+                .back()
+                .map(|(frame, hash)| (*frame as u32, *hash)),
+        };
+
+        if let Some(bytes) = input.encode() {
+            self.transport.send(bytes);
+        }
+    }
+
+    /// Frames run past the last one heard of from the peer.
+    fn advantage(&self) -> i32 {
+        self.frame as i32 - self.peer_frame as i32
+    }
+
+    fn should_wait(&mut self) -> bool {
+        if self.frame >= self.remote_inputs.len() + MAX_PREDICTION {
+            return true;
+        }
+
+        // seen from both sides, being further ahead than the peer means
+        // running faster, which only makes the peer roll back more
+        let ahead = self.advantage() - self.peer_advantage >= 2;
+
+        if ahead && self.frame >= self.last_sync_wait + SYNC_INTERVAL {
+            self.last_sync_wait = self.frame;
+            return true;
+        }
+
+        false
+    }
+
+    /// Hashes the snapshots on the frames due a check once the input of both
+    /// players up to them is in, and compares with the peer's hashes.
+    fn check_hashes(&mut self) {
+        let confirmed = self.remote_inputs.len();
+
+        for snapshot in &self.snapshots {
+            let frame = snapshot.frame;
+
+            if frame % HASH_INTERVAL != 0
+                || frame > confirmed
+                || self.hashed.is_some_and(|x| frame <= x)
+            {
+                continue;
+            }
+
+            let local = movie::state_hash(&snapshot.local);
+            let peer = movie::state_hash(&snapshot.peer);
+            let hash = if self.player == 0 {
+                [local, peer]
+            } else {
+                [peer, local]
+            };
+
+            self.hashed = Some(frame);
+            self.hashes.push_back((frame, hash));
+
+            if self.hashes.len() > KEPT_HASHES {
+                self.hashes.pop_front();
+            }
+        }
+
+        if self.desync.is_some() {
+            return;
+        }
+
+        let desync = self.peer_hashes.iter().find(|(frame, hash)| {
+            self.hashes
+                .iter()
+                .any(|(own_frame, own)| own_frame == frame && own != hash)
+        });
+
+        if let Some((frame, _)) = desync {
+            log::warn!("Netplay desync at frame {frame}");
+            self.desync = Some(*frame);
+            self.events.push(NetplayEvent::Desync { frame: *frame });
+        }
+    }
+
+    fn save_snapshot(&mut self, local: &mut Cpu) {
+        if self.snapshots.len() == MAX_PREDICTION + 2 {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back(Snapshot {
+            frame: self.frame,
+            link: self.link,
+            local: run_ahead::copy_cpu(local),
+            peer: run_ahead::copy_cpu(&mut self.peer),
+        });
+    }
+
+    /// Goes back to the start of frame `to` and runs up to the current frame
+    /// again with the input received since.
+    fn rollback(&mut self, local: &mut Cpu, to: usize) {
+        let Some(index) = self.snapshots.iter().position(|x| x.frame == to) else {
+            self.end(format!("no snapshot to roll back to frame {to}"));
+            return;
+        };
+
+        let mut snapshot = self.snapshots.drain(index..).next().unwrap();
+        rewind::carry_over(local, &mut snapshot.local);
+        rewind::carry_over(&mut self.peer, &mut snapshot.peer);
+        *local = snapshot.local;
+        self.peer = snapshot.peer;
+        self.link = snapshot.link;
+
+        let end = self.frame;
+        self.frame = to;
+        self.used_inputs.truncate(to);
+
+        while self.frame < end {
+            self.save_snapshot(local);
+            self.run_frame(local, None);
+        }
+
+        self.rollbacks += 1;
+    }
+
+    fn run_frame(&mut self, local: &mut Cpu, mut samples: Option<&mut Vec<f32>>) {
+        let frame = self.frame;
+        let remote = self.remote_inputs.get(frame).or(self.remote_inputs.last());
+        let remote = remote.copied().unwrap_or_default();
+        self.used_inputs.push(remote);
+
+        local
+            .clock
+            .bus
+            .io
+            .joypad
+            .set_pressed(self.local_inputs[frame]);
+        self.peer.clock.bus.io.joypad.set_pressed(remote);
+
+        let player = self.player;
+        let cpus = if player == 0 {
+            [&mut *local, &mut self.peer]
+        } else {
+            [&mut self.peer, &mut *local]
+        };
+
+        self.link.run_frame(cpus, |i, cpu| {
+            let apu = &mut cpu.clock.bus.io.apu;
+
+            if !apu.buffer_ready() {
+                return;
+            }
+
+            if let Some(samples) = samples.as_deref_mut().filter(|_| i == player) {
+                samples.extend_from_slice(apu.get_buffer());
+            }
+
+            apu.clear_buffer();
+        });
+
+        self.frame += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary::clock::Clock;
+    use crate::bus::Bus;
+    use crate::cart::Cart;
+    use std::thread;
+
+    /// Keeps adding the held action buttons to $C000.
+    fn new_cpu() -> Cpu {
+        // LD A,$10; LDH ($00),A; LDH A,($00); CPL; AND $0F; LD HL,$C000;
+        // ADD (HL); LD (HL),A; JR -16
+        let program = [
+            0x3E, 0x10, 0xE0, 0x00, 0xF0, 0x00, 0x2F, 0xE6, 0x0F, 0x21, 0x00, 0xC0, 0x86, 0x77,
+            0x18, 0xF0,
+        ];
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x100..0x100 + program.len()].copy_from_slice(&program);
+        let cart = Cart::new(rom.into_boxed_slice()).unwrap();
+
+        Cpu::new(Clock::new(Bus::new(cart, Default::default(), None)))
+    }
+
+    /// Two transports on localhost talking to each other.
+    fn pair(latency: Duration, loss: f32) -> [Transport; 2] {
+        let mut a = Transport::bind("127.0.0.1:0", "127.0.0.1:9").unwrap();
+        let mut b = Transport::bind("127.0.0.1:0", "127.0.0.1:9").unwrap();
+        a.peer = b.local_addr().unwrap();
+        b.peer = a.local_addr().unwrap();
+        b.rng = 0x1234_5678;
+
+        [a, b].map(|x| x.with_conditions(latency, loss))
+    }
+
+    /// Runs both sides in turn until both ran `frames` frames, calling `poke`
+    /// with the side and its console before every frame.
+    fn run(
+        frames: usize,
+        latency: Duration,
+        loss: f32,
+        mut poke: impl FnMut(usize, &mut Cpu),
+    ) -> [(Netplay, Cpu); 2] {
+        let [a, b] = pair(latency, loss);
+        let mut sides = [(0, a), (1, b)].map(|(player, transport)| {
+            let mut cpu = new_cpu();
+            let netplay = Netplay::new(&mut cpu, player, 1, transport).unwrap();
+            (netplay, cpu)
+        });
+        let start = Instant::now();
+
+        while sides.iter().any(|(netplay, _)| netplay.frame() < frames) {
+            assert!(start.elapsed() < Duration::from_secs(60), "session stalled");
+
+            // a side done keeps going, or what the other still needs from
+            // it might never come
+            for (side, (netplay, cpu)) in sides.iter_mut().enumerate() {
+                poke(side, cpu);
+                let pressed = ((netplay.frame() / 7 + side) % 4) as u8 * 4;
+                let status = netplay.advance(cpu, pressed, None);
+                assert_ne!(status, NetplayStatus::Ended);
+            }
+
+            thread::sleep(Duration::from_micros(200));
+        }
+
+        sides
+    }
+
+    #[test]
+    fn test_session() {
+        let frames = HASH_INTERVAL + 2 * MAX_PREDICTION + 1;
+        let [(a, a_cpu), (b, b_cpu)] = run(frames, Duration::from_millis(10), 0.2, |_, _| {});
+
+        assert!(a.is_connected() && b.is_connected());
+        assert!(a.rollbacks() > 0 || b.rollbacks() > 0);
+        assert_eq!(a.desync(), None);
+        assert_eq!(b.desync(), None);
+
+        // both got as far as the second check and agree on it
+        let hash = |x: &Netplay| x.hashes.iter().find(|x| x.0 == HASH_INTERVAL).copied();
+        assert!(hash(&a).is_some());
+        assert_eq!(hash(&a), hash(&b));
+
+        assert_ne!(a_cpu.clock.bus.read(0xC000), 0);
+        assert_ne!(b_cpu.clock.bus.read(0xC000), 0);
+    }
+
+    #[test]
+    fn test_desync() {
+        let frames = MAX_PREDICTION * 2;
+        let [(a, _), (b, _)] = run(frames, Duration::ZERO, 0.0, |side, cpu| {
+            if side == 0 {
+                cpu.clock.bus.write(0xC001, 1);
+            }
+        });
+
+        // the check of frame 0 already sees the write
+        assert_eq!(a.desync().or(b.desync()), Some(0));
+    }
+
+    #[test]
+    fn test_rejects_other_rom() {
+        let [a, b] = pair(Duration::ZERO, 0.0);
+        let mut a_cpu = new_cpu();
+        let mut a = Netplay::new(&mut a_cpu, 0, 0, a).unwrap();
+        let mut b_cpu = new_cpu();
+        b_cpu.clock.bus.cart.data = Cart::new(vec![1u8; 0x8000].into_boxed_slice())
+            .unwrap()
+            .data;
+        let mut b = Netplay::new(&mut b_cpu, 1, 0, b).unwrap();
+
+        for _ in 0..100 {
+            a.advance(&mut a_cpu, 0, None);
+
+            if b.advance(&mut b_cpu, 0, None) == NetplayStatus::Ended {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let reason = NetplayEvent::Ended("peer runs a different ROM".to_string());
+        assert_eq!(b.take_events(), vec![reason]);
+    }
+}
@@ -0,0 +1,431 @@
+//! Rewind history in a fixed memory budget. Each snapshot is the postcard
+//! encoding of the `Cpu` without the ROM image and the framebuffer. Every
+//! [`KEYFRAME_INTERVAL`] snapshots one is kept whole, the ones in between
+//! only as their XOR against it. Both are run-length coded as pairs of
+//! varints, a run of unchanged bytes and a count of literal bytes, followed
+//! by the literals:
+//!
+//! ```text
+//! { zeros  literals  [u8; literals] }*
+//! ```
+//!
+//! A frame usually touches a few KiB of RAM and registers, so a delta costs
+//! a fraction of the full state and minutes fit in tens of MiB. Over the
+//! budget, the oldest keyframe goes together with the deltas against it.
+use crate::cpu::Cpu;
+use crate::ppu::framebuffer::FrameBuffer;
+use std::collections::VecDeque;
+use std::mem;
+
+/// Snapshots per keyframe, a second of frames at the default interval.
+const KEYFRAME_INTERVAL: usize = 60;
+/// Shorter runs of unchanged bytes stay in the literals, a new pair would
+/// cost about as much.
+const MIN_RUN: usize = 3;
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    /// Coded against nothing rather than the last keyframe.
+    key: bool,
+    /// Length of the decoded state.
+    len: usize,
+    data: Vec<u8>,
+    #[cfg(feature = "debug")]
+    steps: u64,
+}
+
+impl Snapshot {
+    fn cost(&self) -> usize {
+        self.data.len() + mem::size_of::<Self>()
+    }
+
+    /// The `Cpu` of `raw`, this snapshot decoded, with the step count it
+    /// keeps apart.
+    fn decode(&self, raw: &[u8]) -> Result<Cpu, String> {
+        let cpu = decode_cpu(raw)?;
+        #[cfg(feature = "debug")]
+        let cpu = Cpu {
+            steps: self.steps,
+            ..cpu
+        };
+
+        Ok(cpu)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Rewind {
+    snapshots: VecDeque<Snapshot>,
+    /// Bytes held by the snapshots.
+    size: usize,
+    /// Decoded newest keyframe, the base of the next delta. Dropped when
+    /// that keyframe goes and decoded again on demand.
+    base: Option<Vec<u8>>,
+}
+
+impl Rewind {
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Bytes the history takes, not counting the cached keyframe.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.size = 0;
+        self.base = None;
+    }
+
+    /// Adds a snapshot of `cpu`, then drops the oldest ones while over
+    /// `budget` bytes. Returns whether any were dropped.
+    pub fn push(&mut self, cpu: &mut Cpu, budget: usize) -> Result<bool, String> {
+        let raw = encode_cpu(cpu)?;
+        let since_key = self.snapshots.iter().rev().position(|x| x.key);
+        let delta = match since_key {
+            Some(x) if x + 1 < KEYFRAME_INTERVAL => {
+                let data = encode_delta(self.base()?, &raw);
+                // a field that changed size shifts everything after it
+                (data.len() <= raw.len() / 2).then_some(data)
+            }
+            _ => None,
+        };
+
+        let snapshot = Snapshot {
+            key: delta.is_none(),
+            len: raw.len(),
+            data: delta.unwrap_or_else(|| encode_delta(&[], &raw)),
+            #[cfg(feature = "debug")]
+            steps: cpu.steps,
+        };
+
+        if snapshot.key {
+            self.base = Some(raw);
+        }
+
+        self.size += snapshot.cost();
+        self.snapshots.push_back(snapshot);
+
+        let mut dropped = false;
+
+        while self.size > budget {
+            // the oldest group ends where the next keyframe starts
+            let Some(next) = self.snapshots.iter().skip(1).position(|x| x.key) else {
+                break;
+            };
+
+            for snapshot in self.snapshots.drain(..=next) {
+                self.size -= snapshot.cost();
+            }
+
+            dropped = true;
+        }
+
+        Ok(dropped)
+    }
+
+    /// Takes the newest snapshot. The `Cpu` comes without the ROM image and
+    /// with an empty framebuffer.
+    pub fn pop(&mut self) -> Option<Result<Cpu, String>> {
+        let snapshot = self.snapshots.pop_back()?;
+        self.size -= snapshot.cost();
+
+        let raw = if snapshot.key {
+            self.base = None;
+            decode_delta(&[], &snapshot.data, snapshot.len)
+        } else {
+            self.base()
+                .and_then(|base| decode_delta(base, &snapshot.data, snapshot.len))
+        };
+
+        Some(raw.and_then(|x| snapshot.decode(&x)))
+    }
+
+    /// The snapshot at `index`, oldest first, as [`Rewind::pop`] gives it.
+    pub fn get(&self, index: usize) -> Result<Cpu, String> {
+        let key = self
+            .snapshots
+            .range(..=index)
+            .rposition(|x| x.key)
+            .ok_or_else(|| format!("no rewind snapshot {index}"))?;
+        let snapshot = &self.snapshots[key];
+        let mut raw = decode_delta(&[], &snapshot.data, snapshot.len)?;
+
+        let snapshot = &self.snapshots[index];
+
+        if key != index {
+            raw = decode_delta(&raw, &snapshot.data, snapshot.len)?;
+        }
+
+        snapshot.decode(&raw)
+    }
+
+    /// Step count of the snapshot at `index`.
+    #[cfg(feature = "debug")]
+    pub fn steps(&self, index: usize) -> u64 {
+        self.snapshots[index].steps
+    }
+
+    /// Keeps the oldest `len` snapshots.
+    pub fn truncate(&mut self, len: usize) {
+        while self.snapshots.len() > len {
+            let snapshot = self.snapshots.pop_back().unwrap();
+            self.size -= snapshot.cost();
+
+            if snapshot.key {
+                self.base = None;
+            }
+        }
+    }
+
+    fn base(&mut self) -> Result<&[u8], String> {
+        if self.base.is_none() {
+            let snapshot = self
+                .snapshots
+                .iter()
+                .rev()
+                .find(|x| x.key)
+                .ok_or("rewind history has no keyframe")?;
+            self.base = Some(decode_delta(&[], &snapshot.data, snapshot.len)?);
+        }
+
+        Ok(self.base.as_deref().unwrap())
+    }
+}
+
+/// Moves what a snapshot leaves out from the running `live` CPU to `cpu`:
+/// the ROM image, the framebuffer and the opt-in taps.
+pub(super) fn carry_over(live: &mut Cpu, cpu: &mut Cpu) {
+    let (live_bus, bus) = (&mut live.clock.bus, &mut cpu.clock.bus);
+    mem::swap(&mut bus.cart.data, &mut live_bus.cart.data);
+    mem::swap(&mut bus.io.ppu.lcd.buffer, &mut live_bus.io.ppu.lcd.buffer);
+    bus.io.apu.swap_scope(&mut live_bus.io.apu);
+
+    #[cfg(feature = "debug")]
+    {
+        cpu.cdl = live.cdl.take();
+        cpu.profiler = live.profiler.take();
+        cpu.clock.bus.io.timeline = live.clock.bus.io.timeline.take();
+    }
+}
+
+fn encode_cpu(cpu: &mut Cpu) -> Result<Vec<u8>, String> {
+    let bus = &mut cpu.clock.bus;
+    let data = mem::take(&mut bus.cart.data);
+    let buffer = mem::replace(&mut bus.io.ppu.lcd.buffer, FrameBuffer::new(Box::default()));
+    let result = postcard::to_stdvec(cpu);
+
+    let bus = &mut cpu.clock.bus;
+    bus.cart.data = data;
+    bus.io.ppu.lcd.buffer = buffer;
+
+    result.map_err(|e| format!("failed to encode rewind snapshot: {e}"))
+}
+
+fn decode_cpu(raw: &[u8]) -> Result<Cpu, String> {
+    postcard::from_bytes(raw).map_err(|e| format!("invalid rewind snapshot: {e}"))
+}
+
+/// Codes `raw` as its XOR against `base`, bytes past the end of `base`
+/// against zero.
+fn encode_delta(base: &[u8], raw: &[u8]) -> Vec<u8> {
+    let diff = |i: usize| raw[i] ^ base.get(i).copied().unwrap_or_default();
+    let mut data = Vec::new();
+    let mut i = 0;
+
+    while i < raw.len() {
+        let start = i;
+
+        while i < raw.len() && diff(i) == 0 {
+            i += 1;
+        }
+
+        let zeros = i - start;
+        let mut end = i;
+        let mut run = 0;
+
+        while end < raw.len() && run < MIN_RUN {
+            run = if diff(end) == 0 { run + 1 } else { 0 };
+            end += 1;
+        }
+
+        if run == MIN_RUN {
+            end -= MIN_RUN;
+        }
+
+        write_varint(&mut data, zeros);
+        write_varint(&mut data, end - i);
+        data.extend((i..end).map(diff));
+        i = end;
+    }
+
+    data.shrink_to_fit();
+    data
+}
+
+fn decode_delta(base: &[u8], mut data: &[u8], len: usize) -> Result<Vec<u8>, String> {
+    let corrupt = || "corrupt rewind snapshot".to_string();
+    let mut raw = Vec::with_capacity(len);
+
+    while !data.is_empty() {
+        let zeros = read_varint(&mut data)?;
+        let literals = read_varint(&mut data)?;
+        let at = raw.len();
+
+        if at + zeros + literals > len || literals > data.len() {
+            return Err(corrupt());
+        }
+
+        let unchanged = base.get(at..).unwrap_or_default();
+        raw.extend_from_slice(&unchanged[..zeros.min(unchanged.len())]);
+        raw.resize(at + zeros, 0);
+
+        for byte in &data[..literals] {
+            raw.push(byte ^ base.get(raw.len()).copied().unwrap_or_default());
+        }
+
+        data = &data[literals..];
+    }
+
+    if raw.len() != len {
+        return Err(corrupt());
+    }
+
+    Ok(raw)
+}
+
+/// LEB128, seven bits a byte, low first.
+fn write_varint(data: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    data.push(value as u8);
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<usize, String> {
+    let mut value = 0usize;
+
+    for shift in (0..usize::BITS).step_by(7) {
+        let (&byte, rest) = data.split_first().ok_or("truncated rewind snapshot")?;
+        *data = rest;
+        value |= ((byte & 0x7F) as usize) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err("corrupt rewind snapshot".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary::clock::Clock;
+    use crate::auxiliary::io::Io;
+    use crate::bus::Bus;
+    use crate::cart::Cart;
+
+    fn new_cpu() -> Cpu {
+        Cpu::new(Clock::new(Bus::new(Cart::empty(), Io::default(), None)))
+    }
+
+    #[test]
+    fn test_delta_codec() {
+        let base = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let cases: [&[u8]; 5] = [
+            &base,
+            &[1, 2, 0, 4, 5, 6, 7, 8, 9, 11],
+            &[9, 9, 9],
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 0, 0, 0, 0, 5],
+            &[],
+        ];
+
+        for raw in cases {
+            let data = encode_delta(&base, raw);
+            assert_eq!(decode_delta(&base, &data, raw.len()).unwrap(), raw);
+        }
+
+        // the same bytes are one run
+        assert_eq!(encode_delta(&base, &base), [10, 0]);
+        assert!(decode_delta(&base, &[10, 0], 11).is_err());
+        assert!(decode_delta(&base, &[0, 5, 1], 5).is_err());
+    }
+
+    #[test]
+    fn test_push_and_pop() {
+        let mut cpu = new_cpu();
+        let mut rewind = Rewind::default();
+
+        for i in 0..KEYFRAME_INTERVAL as u16 + 10 {
+            cpu.registers.pc = i;
+            cpu.clock.bus.io.ram.write_wram(0xC000 + i, i as u8 + 1);
+            rewind.push(&mut cpu, usize::MAX).unwrap();
+        }
+
+        // the frame buffer isn't kept, and a delta is far smaller than a keyframe
+        assert!(rewind.size() < cpu.clock.bus.io.ppu.lcd.buffer.len());
+        assert_eq!(rewind.len(), KEYFRAME_INTERVAL + 10);
+        assert_eq!(rewind.get(3).unwrap().registers.pc, 3);
+
+        for i in (0..KEYFRAME_INTERVAL as u16 + 10).rev() {
+            let cpu = rewind.pop().unwrap().unwrap();
+            let ram = &cpu.clock.bus.io.ram;
+            assert_eq!(cpu.registers.pc, i);
+            assert_eq!(ram.read_wram(0xC000 + i), i as u8 + 1);
+            assert_eq!(ram.read_wram(0xC001 + i), 0);
+            assert!(cpu.clock.bus.io.ppu.lcd.buffer.is_empty());
+        }
+
+        assert!(rewind.pop().is_none());
+        assert_eq!(rewind.size(), 0);
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut cpu = new_cpu();
+        let mut rewind = Rewind::default();
+        let mut push = |rewind: &mut Rewind, i: usize, budget: usize| {
+            cpu.registers.a = i as u8;
+            rewind.push(&mut cpu, budget).unwrap()
+        };
+
+        for i in 0..KEYFRAME_INTERVAL {
+            push(&mut rewind, i, usize::MAX);
+        }
+
+        // room for two groups and a half
+        let budget = rewind.size() * 5 / 2;
+        let count = KEYFRAME_INTERVAL * 5 + 10;
+        let mut dropped = false;
+
+        for i in KEYFRAME_INTERVAL..count {
+            dropped |= push(&mut rewind, i, budget);
+        }
+
+        // whole groups went, the newest snapshots stay
+        assert!(dropped);
+        assert!(rewind.size() <= budget);
+        assert!(rewind.snapshots[0].key);
+        assert_eq!(rewind.len(), KEYFRAME_INTERVAL * 2 + 10);
+        assert_eq!(
+            rewind.get(0).unwrap().registers.a,
+            (count - rewind.len()) as u8
+        );
+        assert_eq!(
+            rewind.pop().unwrap().unwrap().registers.a,
+            (count - 1) as u8
+        );
+
+        rewind.truncate(1);
+        assert_eq!(rewind.len(), 1);
+    }
+}
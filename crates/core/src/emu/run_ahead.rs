@@ -0,0 +1,123 @@
+//! Run-ahead: after each real frame the emulation runs a few more with the
+//! same input and shows the last of them, so a press shows up that many
+//! frames sooner. What was run ahead is then dropped, either by rolling the
+//! CPU back to a copy taken before, or by running ahead on the copy and
+//! leaving the live CPU alone. The latter keeps the opt-in taps, like the
+//! APU scope and the debugger's timeline, from seeing frames that never
+//! happened.
+use super::rewind;
+use super::Emu;
+use crate::cpu::Cpu;
+use std::mem;
+
+impl Emu {
+    pub(super) fn run_ahead(&mut self) {
+        let frames = self.config.run_ahead;
+
+        if frames == 0 || self.runtime.is_break() {
+            return;
+        }
+
+        let second_instance = self.config.run_ahead_second_instance;
+        let mut copy = copy_cpu(&mut self.runtime.cpu);
+
+        if second_instance {
+            mem::swap(&mut self.runtime.cpu, &mut copy);
+            let (ahead, live) = (&mut self.runtime.cpu.clock.bus, &mut copy.clock.bus);
+            mem::swap(&mut ahead.cart.data, &mut live.cart.data);
+        }
+
+        for _ in 0..frames {
+            self.runtime.run_silent_frame();
+        }
+
+        let mut ahead = mem::replace(&mut self.runtime.cpu, copy);
+
+        if second_instance {
+            let (ahead, live) = (&mut ahead.clock.bus, &mut self.runtime.cpu.clock.bus);
+            mem::swap(&mut ahead.cart.data, &mut live.cart.data);
+            mem::swap(&mut ahead.io.ppu.lcd.buffer, &mut live.io.ppu.lcd.buffer);
+        } else {
+            rewind::carry_over(&mut ahead, &mut self.runtime.cpu);
+        }
+    }
+}
+
+/// A copy of `cpu` without the ROM image and the opt-in taps, which would
+/// cost more to clone than the rest.
+pub(super) fn copy_cpu(cpu: &mut Cpu) -> Cpu {
+    let data = mem::take(&mut cpu.clock.bus.cart.data);
+    #[cfg(feature = "debug")]
+    let taps = (
+        cpu.cdl.take(),
+        cpu.profiler.take(),
+        cpu.clock.bus.io.timeline.take(),
+    );
+
+    let copy = cpu.clone();
+
+    cpu.clock.bus.cart.data = data;
+    #[cfg(feature = "debug")]
+    {
+        (cpu.cdl, cpu.profiler, cpu.clock.bus.io.timeline) = taps;
+    }
+
+    copy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cart::Cart;
+    use crate::emu::config::EmuConfig;
+    use crate::emu::runtime::EmuRuntime;
+    use crate::emu::EmuAudioCallback;
+
+    struct Mute;
+
+    impl EmuAudioCallback for Mute {
+        fn update(&mut self, _output: &[f32], _runtime: &EmuRuntime) {}
+    }
+
+    fn new_emu(run_ahead: usize, second_instance: bool) -> Emu {
+        // INC A; LD ($C000),A; JR -6
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x100..0x106].copy_from_slice(&[0x3C, 0xEA, 0x00, 0xC0, 0x18, 0xFA]);
+        let cart = Cart::new(rom.into_boxed_slice()).unwrap();
+        let bus = Bus::new(cart, Default::default(), None);
+        let runtime = EmuRuntime::without_debugger(bus);
+        let config = EmuConfig {
+            run_ahead,
+            run_ahead_second_instance: second_instance,
+            ..Default::default()
+        };
+
+        Emu::new(config, runtime).unwrap()
+    }
+
+    fn run(emu: &mut Emu, frames: usize) -> (u16, u8, usize) {
+        for _ in 0..frames {
+            emu.runtime.run_frame(&mut Mute);
+            emu.run_ahead();
+        }
+
+        let cpu = &emu.runtime.cpu;
+        (
+            cpu.registers.pc,
+            cpu.clock.bus.read(0xC000),
+            cpu.clock.bus.io.ppu.current_frame,
+        )
+    }
+
+    #[test]
+    fn test_run_ahead_rolls_back() {
+        let expected = run(&mut new_emu(0, false), 3);
+
+        for second_instance in [false, true] {
+            let mut emu = new_emu(2, second_instance);
+            assert_eq!(run(&mut emu, 3), expected);
+            assert_eq!(emu.runtime.cpu.clock.bus.cart.data.rom().len(), 0x8000);
+        }
+    }
+}
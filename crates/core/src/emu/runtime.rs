@@ -2,11 +2,14 @@ use std::fmt;
 
 use crate::auxiliary::clock::Clock;
 use crate::bus::Bus;
+use crate::cheats::{self, Cheat, RamWrite};
 use crate::cpu::Cpu;
 #[cfg(feature = "debug")]
-use crate::debugger::Debugger;
+use crate::debugger::{BreakReason, Debugger};
 pub use crate::emu::state::{EmuSaveState, SaveStateCmd};
 use crate::emu::EmuAudioCallback;
+#[cfg(feature = "script")]
+use crate::script::ScriptHost;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Copy, Serialize, Deserialize)]
@@ -36,8 +39,15 @@ impl fmt::Display for RunMode {
 pub struct EmuRuntime {
     pub mode: RunMode,
     pub cpu: Cpu,
+    /// GameShark codes, written at the end of every frame.
+    ram_cheats: Vec<RamWrite>,
     #[cfg(feature = "debug")]
     debugger: Option<Debugger>,
+    /// Set when the debugger stops execution, until the frontend takes it.
+    #[cfg(feature = "debug")]
+    break_reason: Option<BreakReason>,
+    #[cfg(feature = "script")]
+    script: Option<ScriptHost>,
 }
 
 impl EmuRuntime {
@@ -46,8 +56,13 @@ impl EmuRuntime {
         Self {
             mode: RunMode::Normal,
             cpu: Cpu::new(Clock::new(bus)),
+            ram_cheats: Vec::new(),
             #[cfg(feature = "debug")]
             debugger,
+            #[cfg(feature = "debug")]
+            break_reason: None,
+            #[cfg(feature = "script")]
+            script: None,
         }
     }
 
@@ -56,42 +71,107 @@ impl EmuRuntime {
         Self {
             mode: RunMode::Normal,
             cpu: Cpu::new(Clock::new(bus)),
+            ram_cheats: Vec::new(),
         }
     }
 
+    /// The same with or without the `debug` feature, for frontends that
+    /// never attach a debugger.
+    #[cfg(feature = "debug")]
+    pub fn without_debugger(bus: Bus) -> Self {
+        Self::new(bus, None)
+    }
+
+    #[cfg(not(feature = "debug"))]
+    pub fn without_debugger(bus: Bus) -> Self {
+        Self::new(bus)
+    }
+
     #[inline]
     pub fn set_mode(&mut self, mode: RunMode) {
         self.mode = mode;
     }
 
+    /// Applies the enabled `cheats`, replacing the previous ones: Game Genie
+    /// codes patch ROM reads, GameShark codes write RAM once per frame.
+    pub fn set_cheats(&mut self, cheats: &[Cheat]) {
+        let (patches, writes) = cheats::compile(cheats);
+        self.cpu.clock.bus.cart.data.set_patches(patches);
+        self.ram_cheats = writes;
+    }
+
+    /// The GameShark writes applied at the end of every frame.
+    #[cfg(feature = "debug")]
+    pub(crate) fn ram_cheats(&self) -> &[RamWrite] {
+        &self.ram_cheats
+    }
+
     #[inline(always)]
     pub fn run_frame(&mut self, callback: &mut impl EmuAudioCallback) {
         let start_frame = self.cpu.clock.bus.io.ppu.current_frame;
 
         while start_frame == self.cpu.clock.bus.io.ppu.current_frame {
             self.step(callback);
+
+            if self.is_break() {
+                return;
+            }
         }
     }
 
+    /// Runs a frame that gets thrown away, like run-ahead's: no debugger,
+    /// scripts or audio.
+    pub(crate) fn run_silent_frame(&mut self) {
+        run_silent_frame(&mut self.cpu, &self.ram_cheats);
+    }
+
     #[inline(always)]
     pub fn run_scanline(&mut self, callback: &mut impl EmuAudioCallback) {
         let start_scanline = self.cpu.clock.bus.io.ppu.lcd.ly;
 
         while start_scanline == self.cpu.clock.bus.io.ppu.lcd.ly {
             self.step(callback);
+
+            if self.is_break() {
+                return;
+            }
         }
     }
 
     #[inline(always)]
     pub fn step(&mut self, callback: &mut impl EmuAudioCallback) {
+        let frame = self.cpu.clock.bus.io.ppu.current_frame;
+
+        #[cfg(feature = "script")]
+        if let Some(script) = self.script.as_mut() {
+            script.before_step(&mut self.cpu);
+        }
+
         #[cfg(feature = "debug")]
         if let Some(debugger) = self.debugger.as_mut() {
-            self.cpu.step_debug(debugger);
+            if let Some(reason) = self.cpu.step_debug(debugger) {
+                log::info!("Break: {reason}");
+                self.break_reason = Some(reason);
+            }
+        } else {
+            self.cpu.step();
         }
 
         #[cfg(not(feature = "debug"))]
         self.cpu.step();
 
+        #[cfg(feature = "script")]
+        if let Some(script) = self.script.as_mut() {
+            script.after_step(&mut self.cpu);
+        }
+
+        // on the step ending the frame, however it's run, as replay does
+        if frame != self.cpu.clock.bus.io.ppu.current_frame {
+            for write in self.ram_cheats.iter() {
+                write.apply(&mut self.cpu.clock.bus);
+            }
+        }
+
         if self.cpu.clock.bus.io.apu.buffer_ready() {
             let output = self.cpu.clock.bus.io.apu.get_buffer();
             callback.update(output, self);
@@ -99,6 +179,58 @@ impl EmuRuntime {
         }
     }
 
+    /// Whether the debugger has stopped execution and is waiting to be resumed.
+    #[cfg(feature = "debug")]
+    #[inline(always)]
+    pub fn is_break(&self) -> bool {
+        self.break_reason.is_some()
+    }
+
+    #[cfg(not(feature = "debug"))]
+    #[inline(always)]
+    pub const fn is_break(&self) -> bool {
+        false
+    }
+
+    /// Takes the reason of the last break, resuming execution.
+    #[cfg(feature = "debug")]
+    pub fn take_break(&mut self) -> Option<BreakReason> {
+        self.break_reason.take()
+    }
+
+    #[cfg(feature = "debug")]
+    pub fn debugger_mut(&mut self) -> Option<&mut Debugger> {
+        self.debugger.as_mut()
+    }
+
+    /// The CPU and debugger together, for tools that drive both.
+    #[cfg(feature = "debug")]
+    pub fn debug_parts(&mut self) -> Option<(&mut Cpu, &mut Debugger)> {
+        self.debugger.as_mut().map(|x| (&mut self.cpu, x))
+    }
+
+    /// Replaces the running script; `None` unloads it.
+    #[cfg(feature = "script")]
+    pub fn set_script(&mut self, script: Option<ScriptHost>) {
+        if script.is_none() {
+            self.cpu.probe.set_always(false);
+        }
+
+        self.script = script;
+    }
+
+    #[cfg(feature = "script")]
+    pub fn script_mut(&mut self) -> Option<&mut ScriptHost> {
+        self.script.as_mut()
+    }
+
+    /// The CPU and script together, e.g. to draw the script's overlay onto
+    /// the frame.
+    #[cfg(feature = "script")]
+    pub fn script_parts(&mut self) -> Option<(&mut Cpu, &mut ScriptHost)> {
+        self.script.as_mut().map(|x| (&mut self.cpu, x))
+    }
+
     #[cfg(feature = "debug")]
     pub fn toggle_debug(&mut self) {
         if let Some(debugger) = &mut self.debugger {
@@ -112,3 +244,22 @@ impl EmuRuntime {
         }
     }
 }
+
+/// Runs `cpu` to the next frame with its audio dropped, then applies the
+/// GameShark `cheats`. Headless frontends drive a bare [`Cpu`] with it.
+pub fn run_silent_frame(cpu: &mut Cpu, cheats: &[RamWrite]) {
+    let start_frame = cpu.clock.bus.io.ppu.current_frame;
+
+    while start_frame == cpu.clock.bus.io.ppu.current_frame {
+        cpu.step();
+
+        let apu = &mut cpu.clock.bus.io.apu;
+        if apu.buffer_ready() {
+            apu.clear_buffer();
+        }
+    }
+
+    for write in cheats.iter() {
+        write.apply(&mut cpu.clock.bus);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::cart::CartSaveState;
 use crate::cpu::Cpu;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{fmt, mem};
 
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum EmuState {
@@ -35,3 +35,19 @@ pub struct EmuSaveState {
     pub cpu: Cpu,
     pub cart_save_state: CartSaveState,
 }
+
+impl EmuSaveState {
+    /// The state's CPU with its cart rebuilt around the ROM image of `live`,
+    /// which is left without one. The cart keeps the RTC clock of `live`.
+    pub fn into_cpu(mut self, live: &mut Cpu) -> Cpu {
+        mem::swap(
+            &mut self.cpu.clock.bus.cart.data,
+            &mut live.clock.bus.cart.data,
+        );
+        self.cpu.clock.bus.cart = self.cart_save_state.into_cart(self.cpu.clock.bus.cart.data);
+        let rtc_clock = live.clock.bus.cart.rtc_clock();
+        self.cpu.clock.bus.cart.set_rtc_clock(rtc_clock);
+
+        self.cpu
+    }
+}
@@ -0,0 +1,672 @@
+//! The on-disk save-state format. A file is the magic, the format version
+//! and a list of sections, each with a tag, its own version and its length:
+//!
+//! ```text
+//! "OXGBCSTA" u16 format  { [u8; 4] tag  u16 version  u32 len  payload }*
+//! ```
+//!
+//! All numbers are little endian and each payload is postcard. Splitting the
+//! `Cpu` graph per component means a change to one of them only touches its
+//! own section: bump that section's version and add a [`Migration`] from the
+//! old payload, and older states keep loading. Unknown sections are skipped,
+//! so a state can carry extras that older builds ignore.
+use crate::auxiliary::clock::{Clock, ClockState};
+use crate::auxiliary::dma::{OamDma, VramDma};
+use crate::auxiliary::io::{CgbSpeed, CgbUndocumented, Serial};
+use crate::auxiliary::joypad::Joypad;
+use crate::auxiliary::timer::Timer;
+use crate::bus::Bus;
+use crate::cart::header::CartHeader;
+use crate::cart::CartSaveState;
+use crate::cpu::interrupts::Interrupts;
+use crate::cpu::{Cpu, Registers, StepCtx};
+use crate::emu::config::GbModel;
+use crate::emu::state::EmuSaveState;
+use crate::ppu::framebuffer::FrameBuffer;
+use crate::ppu::{LCD_X_RES, LCD_Y_RES};
+use crc::{Crc, CRC_32_ISO_HDLC};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use web_time::{Instant, SystemTime};
+
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+pub const MAGIC: [u8; 8] = *b"OXGBCSTA";
+pub const FORMAT_VERSION: u16 = 1;
+
+pub type SectionTag = [u8; 4];
+
+pub const INFO: SectionTag = *b"INFO";
+pub const CPU: SectionTag = *b"CPU ";
+pub const IO: SectionTag = *b"IO  ";
+pub const PPU: SectionTag = *b"PPU ";
+pub const APU: SectionTag = *b"APU ";
+pub const RAM: SectionTag = *b"RAM ";
+pub const CART: SectionTag = *b"CART";
+
+/// Current version of each section; a payload of an older one goes through
+/// [`MIGRATIONS`] first.
+const SECTION_VERSIONS: [(SectionTag, u16); 7] = [
+    (INFO, 2),
+    (CPU, 2),
+    (IO, 1),
+    (PPU, 1),
+    (APU, 1),
+    (RAM, 1),
+    (CART, 1),
+];
+
+/// Turns a section payload of one version into the next one's.
+pub type Migration = fn(&[u8]) -> Result<Vec<u8>, String>;
+
+/// `(tag, from version, migration)`, applied in turn until the payload is
+/// current.
+const MIGRATIONS: &[(SectionTag, u16, Migration)] =
+    &[(INFO, 1, migrate_info_v1), (CPU, 1, migrate_cpu_v1)];
+
+/// `CPU ` v2: registers, IME pending, step context, STOP wait, step count
+/// and the clock's counters.
+type CpuSection = (Registers, bool, StepCtx, u32, u64, ClockState);
+/// `IO  ` v1: everything on the bus besides PPU, APU, RAM and the cart.
+type IoSection = (
+    Serial,
+    Timer,
+    Interrupts,
+    Joypad,
+    CgbSpeed,
+    CgbUndocumented,
+    OamDma,
+    VramDma,
+);
+/// `(tag, version, payload)` of a section as stored.
+type RawSection<'a> = (SectionTag, u16, &'a [u8]);
+
+/// [`EmuSaveState`] as files from before the container hold it: postcard of
+/// the whole `Cpu` graph, frozen at its shape then, as `Cpu` and `Clock`
+/// have gained fields since.
+#[derive(Deserialize)]
+struct LegacyState {
+    cpu: LegacyCpu,
+    cart_save_state: CartSaveState,
+}
+
+#[derive(Deserialize)]
+struct LegacyCpu {
+    registers: Registers,
+    enabling_ime: bool,
+    clock: LegacyClock,
+    step_ctx: StepCtx,
+    stop_m_cycles: u32,
+}
+
+/// Without the RTC cycle count.
+#[derive(Deserialize)]
+struct LegacyClock {
+    #[serde(with = "crate::instant_serde")]
+    _time: Instant,
+    bus: Bus,
+    cpu_halted: bool,
+    m_cycles: usize,
+    ds_phase: bool,
+    device_phase: bool,
+}
+
+impl LegacyState {
+    fn migrate(self) -> EmuSaveState {
+        let LegacyCpu {
+            registers,
+            enabling_ime,
+            clock,
+            step_ctx,
+            stop_m_cycles,
+        } = self.cpu;
+        let mut cpu = Cpu::new(Clock::new(clock.bus));
+        cpu.registers = registers;
+        cpu.enabling_ime = enabling_ime;
+        cpu.step_ctx = step_ctx;
+        cpu.stop_m_cycles = stop_m_cycles;
+        cpu.clock.set_state(ClockState {
+            cpu_halted: clock.cpu_halted,
+            m_cycles: clock.m_cycles,
+            ds_phase: clock.ds_phase,
+            device_phase: clock.device_phase,
+            rtc_cycles: 0,
+        });
+
+        EmuSaveState {
+            cpu,
+            cart_save_state: self.cart_save_state,
+        }
+    }
+}
+
+/// Width of [`SaveStateInfo::thumbnail`], every other pixel of a line.
+pub const THUMBNAIL_WIDTH: usize = LCD_X_RES as usize / 2;
+/// Height of [`SaveStateInfo::thumbnail`], every other line.
+pub const THUMBNAIL_HEIGHT: usize = LCD_Y_RES as usize / 2;
+
+/// What a state was made from, to tell states apart without loading them
+/// and to refuse one made from another ROM.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveStateInfo {
+    /// Version of the core that wrote it.
+    pub emu_version: String,
+    /// The ROM header's global checksum.
+    pub rom_checksum: u16,
+    /// CRC-32 of the whole ROM, `None` in `INFO` v1 states.
+    pub rom_crc: Option<u32>,
+    pub model: GbModel,
+    /// Unix time in seconds, 0 when unknown.
+    pub created: u64,
+    /// PPU frame count when it was made.
+    pub frame: usize,
+    /// Screen at that point, RGB565 like the framebuffer, empty when
+    /// unknown.
+    pub thumbnail: Vec<u8>,
+}
+
+impl SaveStateInfo {
+    /// Describes the running `cpu`, which still has the ROM a state leaves
+    /// out.
+    pub fn new(cpu: &Cpu) -> Self {
+        let (rom_checksum, rom_crc) = rom_identity(cpu);
+        let ppu = &cpu.clock.bus.io.ppu;
+
+        Self {
+            emu_version: env!("CARGO_PKG_VERSION").to_string(),
+            rom_checksum,
+            rom_crc: Some(rom_crc),
+            model: ppu.lcd.model,
+            created: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|x| x.as_secs())
+                .unwrap_or_default(),
+            frame: ppu.current_frame,
+            thumbnail: thumbnail(&ppu.lcd.buffer),
+        }
+    }
+
+    /// Fails unless the state was made from the ROM `cpu` runs, on the same
+    /// model.
+    pub fn check(&self, cpu: &Cpu) -> Result<(), String> {
+        let (checksum, crc) = rom_identity(cpu);
+        let model = cpu.clock.bus.io.ppu.lcd.model;
+
+        if self.model != model {
+            return Err(format!(
+                "state is for another model ({:?}, running {model:?})",
+                self.model
+            ));
+        }
+
+        if self.rom_checksum != checksum {
+            return Err(format!(
+                "state is for another ROM (checksum {:04X}, loaded {checksum:04X})",
+                self.rom_checksum
+            ));
+        }
+
+        match self.rom_crc {
+            Some(x) if x != crc => Err(format!(
+                "state's ROM data differs (CRC {x:08X}, loaded {crc:08X})"
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `INFO` v1 lacked all but the version, checksum and model.
+fn migrate_info_v1(payload: &[u8]) -> Result<Vec<u8>, String> {
+    let (emu_version, rom_checksum, model): (String, u16, GbModel) =
+        postcard::from_bytes(payload).map_err(|e| e.to_string())?;
+    let info = SaveStateInfo {
+        emu_version,
+        rom_checksum,
+        rom_crc: None,
+        model,
+        created: 0,
+        frame: 0,
+        thumbnail: Vec::new(),
+    };
+
+    postcard::to_allocvec(&info).map_err(|e| e.to_string())
+}
+
+/// `CPU ` v1 clocks had no RTC cycle count. Postcard writes a struct as a
+/// tuple of its fields, so the clock's counters are read as one.
+fn migrate_cpu_v1(payload: &[u8]) -> Result<Vec<u8>, String> {
+    type ClockV1 = (bool, usize, bool, bool);
+    let (registers, enabling_ime, step_ctx, stop_m_cycles, steps, clock): (
+        Registers,
+        bool,
+        StepCtx,
+        u32,
+        u64,
+        ClockV1,
+    ) = postcard::from_bytes(payload).map_err(|e| e.to_string())?;
+    let (cpu_halted, m_cycles, ds_phase, device_phase) = clock;
+    let clock = (cpu_halted, m_cycles, ds_phase, device_phase, 0u32);
+
+    postcard::to_allocvec(&(
+        registers,
+        enabling_ime,
+        step_ctx,
+        stop_m_cycles,
+        steps,
+        clock,
+    ))
+    .map_err(|e| e.to_string())
+}
+
+/// Header global checksum, 0 without a header, and the CRC-32 of the ROM.
+fn rom_identity(cpu: &Cpu) -> (u16, u32) {
+    let rom = cpu.clock.bus.cart.data.rom();
+    let checksum = if rom.len() >= 0x150 {
+        CartHeader::parse_global_checksum(rom)
+    } else {
+        0
+    };
+
+    (checksum, CRC32.checksum(rom))
+}
+
+fn thumbnail(buffer: &FrameBuffer) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 2);
+
+    for y in 0..THUMBNAIL_HEIGHT {
+        for x in 0..THUMBNAIL_WIDTH {
+            let offset = y * 2 * FrameBuffer::PITCH + x * 2 * FrameBuffer::BYTES_PER_PIXEL;
+            bytes.extend_from_slice(&buffer[offset..offset + FrameBuffer::BYTES_PER_PIXEL]);
+        }
+    }
+
+    bytes
+}
+
+/// Writes `state` in the current format.
+pub fn encode(state: &EmuSaveState, info: &SaveStateInfo) -> Result<Vec<u8>, String> {
+    let cpu = &state.cpu;
+    let io = &cpu.clock.bus.io;
+    #[cfg(feature = "debug")]
+    let steps = cpu.steps;
+    #[cfg(not(feature = "debug"))]
+    let steps = 0u64;
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+
+    write_section(&mut bytes, INFO, info)?;
+    let cpu_section = (
+        &cpu.registers,
+        cpu.enabling_ime,
+        &cpu.step_ctx,
+        cpu.stop_m_cycles,
+        steps,
+        cpu.clock.state(),
+    );
+    write_section(&mut bytes, CPU, &cpu_section)?;
+    let io_section = (
+        &io.serial,
+        &io.timer,
+        &io.interrupts,
+        &io.joypad,
+        &io.cgb_speed,
+        &io.undoc,
+        &cpu.clock.bus.oam_dma,
+        &cpu.clock.bus.vram_dma,
+    );
+    write_section(&mut bytes, IO, &io_section)?;
+    write_section(&mut bytes, PPU, &io.ppu)?;
+    write_section(&mut bytes, APU, &io.apu)?;
+    write_section(&mut bytes, RAM, &io.ram)?;
+    write_section(&mut bytes, CART, &state.cart_save_state)?;
+
+    Ok(bytes)
+}
+
+/// Reads a state of the game `cpu` runs, refusing one of another ROM or
+/// model. Only files from before the container, which have no info, go
+/// unchecked.
+pub fn decode_for(bytes: &[u8], cpu: &Cpu) -> Result<EmuSaveState, String> {
+    let (info, state) = decode(bytes)?;
+
+    if let Some(info) = info {
+        info.check(cpu)?;
+    }
+
+    Ok(state)
+}
+
+/// Reads a state in any format version, migrating old sections. Files from
+/// before the container are bare postcard of [`EmuSaveState`], and come
+/// without info.
+pub(crate) fn decode(bytes: &[u8]) -> Result<(Option<SaveStateInfo>, EmuSaveState), String> {
+    let Some(raw) = read_raw_sections(bytes)? else {
+        let state: LegacyState =
+            postcard::from_bytes(bytes).map_err(|e| format!("invalid save state: {e}"))?;
+        return Ok((None, state.migrate()));
+    };
+
+    let mut sections = Vec::with_capacity(raw.len());
+
+    for (tag, version, payload) in raw {
+        if let Some(current) = current_version(tag) {
+            sections.push((tag, migrate(tag, version, current, payload)?));
+        }
+    }
+
+    let section = |tag: SectionTag| {
+        sections
+            .iter()
+            .find(|(x, _)| *x == tag)
+            .map(|(_, payload)| payload.as_slice())
+            .ok_or_else(|| format!("save state has no {} section", tag_name(tag)))
+    };
+
+    let info: SaveStateInfo = read_section(INFO, section(INFO)?)?;
+    let (registers, enabling_ime, step_ctx, stop_m_cycles, _steps, clock): CpuSection =
+        read_section(CPU, section(CPU)?)?;
+    let (serial, timer, interrupts, joypad, cgb_speed, undoc, oam_dma, vram_dma): IoSection =
+        read_section(IO, section(IO)?)?;
+
+    let mut cpu = Cpu::new(Clock::default());
+    cpu.registers = registers;
+    cpu.enabling_ime = enabling_ime;
+    cpu.step_ctx = step_ctx;
+    cpu.stop_m_cycles = stop_m_cycles;
+    #[cfg(feature = "debug")]
+    {
+        cpu.steps = _steps;
+    }
+    cpu.clock.set_state(clock);
+
+    let bus = &mut cpu.clock.bus;
+    bus.oam_dma = oam_dma;
+    bus.vram_dma = vram_dma;
+    bus.io.serial = serial;
+    bus.io.timer = timer;
+    bus.io.interrupts = interrupts;
+    bus.io.joypad = joypad;
+    bus.io.cgb_speed = cgb_speed;
+    bus.io.undoc = undoc;
+    bus.io.ppu = read_section(PPU, section(PPU)?)?;
+    bus.io.apu = read_section(APU, section(APU)?)?;
+    bus.io.ram = read_section(RAM, section(RAM)?)?;
+    let cart_save_state = read_section(CART, section(CART)?)?;
+
+    Ok((
+        Some(info),
+        EmuSaveState {
+            cpu,
+            cart_save_state,
+        },
+    ))
+}
+
+/// Reads only the info of a state, `None` for one from before the
+/// container.
+pub fn decode_info(bytes: &[u8]) -> Result<Option<SaveStateInfo>, String> {
+    let Some(raw) = read_raw_sections(bytes)? else {
+        return Ok(None);
+    };
+    let (_, version, payload) = raw
+        .into_iter()
+        .find(|(tag, _, _)| *tag == INFO)
+        .ok_or("save state has no INFO section")?;
+    let payload = migrate(
+        INFO,
+        version,
+        current_version(INFO).unwrap_or_default(),
+        payload,
+    )?;
+
+    read_section(INFO, &payload).map(Some)
+}
+
+/// Each section as stored, `None` without the magic.
+fn read_raw_sections(bytes: &[u8]) -> Result<Option<Vec<RawSection<'_>>>, String> {
+    let Some(rest) = bytes.strip_prefix(&MAGIC) else {
+        return Ok(None);
+    };
+
+    let mut reader = Reader(rest);
+    let format = u16::from_le_bytes(reader.take()?);
+
+    if format > FORMAT_VERSION {
+        return Err(format!(
+            "save state format {format} is newer than this build reads ({FORMAT_VERSION})"
+        ));
+    }
+
+    let mut sections = Vec::new();
+
+    while !reader.0.is_empty() {
+        let tag: SectionTag = reader.take()?;
+        let version = u16::from_le_bytes(reader.take()?);
+        let len = u32::from_le_bytes(reader.take()?) as usize;
+        sections.push((tag, version, reader.take_slice(len)?));
+    }
+
+    Ok(Some(sections))
+}
+
+fn current_version(tag: SectionTag) -> Option<u16> {
+    SECTION_VERSIONS
+        .iter()
+        .find(|(x, _)| *x == tag)
+        .map(|(_, version)| *version)
+}
+
+fn migrate(tag: SectionTag, from: u16, to: u16, payload: &[u8]) -> Result<Vec<u8>, String> {
+    if from > to {
+        return Err(format!(
+            "save state {} section v{from} is newer than this build reads (v{to})",
+            tag_name(tag)
+        ));
+    }
+
+    let mut payload = payload.to_vec();
+
+    for version in from..to {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|(x, v, _)| *x == tag && *v == version)
+            .map(|(_, _, f)| f)
+            .ok_or_else(|| {
+                format!(
+                    "no migration for save state {} section v{version}",
+                    tag_name(tag)
+                )
+            })?;
+        payload = migration(&payload)?;
+    }
+
+    Ok(payload)
+}
+
+fn write_section(
+    bytes: &mut Vec<u8>,
+    tag: SectionTag,
+    value: &impl Serialize,
+) -> Result<(), String> {
+    let payload = postcard::to_allocvec(value).map_err(|e| e.to_string())?;
+    let version = current_version(tag).unwrap_or_default();
+
+    bytes.extend_from_slice(&tag);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+
+    Ok(())
+}
+
+fn read_section<T: DeserializeOwned>(tag: SectionTag, payload: &[u8]) -> Result<T, String> {
+    postcard::from_bytes(payload)
+        .map_err(|e| format!("invalid save state {} section: {e}", tag_name(tag)))
+}
+
+fn tag_name(tag: SectionTag) -> String {
+    String::from_utf8_lossy(&tag).trim_end().to_string()
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take_slice(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < len {
+            return Err("save state is truncated".to_string());
+        }
+
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+
+        Ok(head)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take_slice(N)?.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary::io::Io;
+    use crate::cart::mbc::MbcVariant;
+    use crate::cart::Cart;
+
+    fn new_state() -> (EmuSaveState, SaveStateInfo) {
+        let mut cpu = Cpu::new(Clock::new(Bus::with_bytes(vec![0; 0x10000], Io::default())));
+        cpu.registers.a = 0x42;
+        cpu.registers.pc = 0x1234;
+        cpu.clock.bus.io.ram.write_wram(0xC010, 7);
+        cpu.clock.bus.io.ppu.lcd.scroll_x = 9;
+        cpu.clock.bus.io.timer.write(0xFF06, 0x5A);
+        let info = SaveStateInfo::new(&cpu);
+        let cart_save_state = cpu.clock.bus.cart.create_save_state();
+
+        (
+            EmuSaveState {
+                cpu,
+                cart_save_state,
+            },
+            info,
+        )
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (state, info) = new_state();
+        let bytes = encode(&state, &info).unwrap();
+        assert!(bytes.starts_with(&MAGIC));
+
+        let (decoded_info, decoded) = decode(&bytes).unwrap();
+        let cpu = &decoded.cpu;
+        assert_eq!(decode_info(&bytes).unwrap().as_ref(), Some(&info));
+        assert_eq!(decoded_info, Some(info));
+        assert_eq!(cpu.registers.a, 0x42);
+        assert_eq!(cpu.registers.pc, 0x1234);
+        assert_eq!(cpu.clock.bus.io.ram.read_wram(0xC010), 7);
+        assert_eq!(cpu.clock.bus.io.ppu.lcd.scroll_x, 9);
+        assert_eq!(cpu.clock.bus.io.timer.read(0xFF06), 0x5A);
+    }
+
+    /// A state the last build before the container saved: an MBC3 cart with
+    /// a timer, 1000 steps into `LD HL,$C000; INC (HL); JR -3`, then A set
+    /// to $42.
+    #[test]
+    fn test_legacy() {
+        let bytes = include_bytes!("../../tests/state/legacy.state");
+        let (info, state) = decode(bytes).unwrap();
+        let cpu = &state.cpu;
+        assert_eq!(info, None);
+        assert!(decode_info(bytes).unwrap().is_none());
+        assert_eq!(cpu.registers.a, 0x42);
+        assert_eq!(cpu.registers.pc, 0x0104);
+        assert_eq!(cpu.registers.get_hl(), 0xC000);
+        assert_eq!(cpu.clock.bus.io.ram.read_wram(0xC000), 244);
+        assert_eq!(cpu.clock.state().rtc_cycles, 0);
+        assert!(matches!(state.cart_save_state.mbc, MbcVariant::Mbc3(_)));
+    }
+
+    #[test]
+    fn test_sections() {
+        let (state, info) = new_state();
+        let bytes = encode(&state, &info).unwrap();
+        let sections = read_raw_sections(&bytes).unwrap().unwrap();
+        let tags: Vec<_> = sections.iter().map(|(tag, _, _)| *tag).collect();
+        assert_eq!(tags, [INFO, CPU, IO, PPU, APU, RAM, CART]);
+
+        // an unknown section is skipped
+        let mut extended = bytes.clone();
+        extended.extend_from_slice(b"XTRA");
+        extended.extend_from_slice(&1u16.to_le_bytes());
+        extended.extend_from_slice(&2u32.to_le_bytes());
+        extended.extend_from_slice(&[1, 2]);
+        assert!(decode(&extended).is_ok());
+
+        // a section from a newer build, or one cut short, is refused
+        let mut newer = bytes.clone();
+        let at = MAGIC.len() + 2 + 4;
+        newer[at..at + 2].copy_from_slice(&9u16.to_le_bytes());
+        assert!(decode(&newer).unwrap_err().contains("INFO section v9"));
+        assert!(decode(&bytes[..bytes.len() - 1])
+            .unwrap_err()
+            .contains("truncated"));
+
+        // older sections without a migration can't be read
+        let err = migrate(CPU, 0, 1, &[]).unwrap_err();
+        assert_eq!(err, "no migration for save state CPU section v0");
+
+        // v1 clocks start the RTC second over
+        let cpu = &state.cpu;
+        let v1 = postcard::to_allocvec(&(
+            &cpu.registers,
+            cpu.enabling_ime,
+            &cpu.step_ctx,
+            cpu.stop_m_cycles,
+            0u64,
+            (false, 123usize, false, false),
+        ))
+        .unwrap();
+        let payload = migrate(CPU, 1, 2, &v1).unwrap();
+        let (registers, .., clock): CpuSection = read_section(CPU, &payload).unwrap();
+        let mut migrated = Clock::default();
+        migrated.set_state(clock);
+        assert_eq!(registers.pc, 0x1234);
+        assert_eq!(migrated.get_m_cycles(), 123);
+    }
+
+    #[test]
+    fn test_rom_identity() {
+        let new_cpu = |byte: u8, checksum: u8| {
+            let mut rom = vec![0u8; 0x8000];
+            rom[0x4000] = byte;
+            rom[0x14F] = checksum;
+            let cart = Cart::new(rom.into_boxed_slice()).unwrap();
+            Cpu::new(Clock::new(Bus::new(cart, Default::default(), None)))
+        };
+        let cpu = new_cpu(0, 1);
+        let info = SaveStateInfo::new(&cpu);
+        assert_eq!(info.rom_checksum, 1);
+        assert_eq!(info.thumbnail.len(), THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 2);
+        assert!(info.created > 0);
+        assert!(info.check(&cpu).is_ok());
+
+        let err = info.check(&new_cpu(0, 2)).unwrap_err();
+        assert!(err.contains("another ROM"), "{err}");
+        let err = info.check(&new_cpu(1, 1)).unwrap_err();
+        assert!(err.contains("differs"), "{err}");
+        let mut cgb = new_cpu(0, 1);
+        cgb.clock.bus.io.ppu.lcd.model = GbModel::Cgb;
+        let err = info.check(&cgb).unwrap_err();
+        assert!(err.contains("another model"), "{err}");
+
+        // v1 info has no CRC, so only the checksum is checked
+        let v1 = postcard::to_allocvec(&(&info.emu_version, 1u16, info.model)).unwrap();
+        let payload = migrate(INFO, 1, 2, &v1).unwrap();
+        let migrated: SaveStateInfo = read_section(INFO, &payload).unwrap();
+        assert_eq!(migrated.rom_crc, None);
+        assert!(migrated.check(&new_cpu(1, 1)).is_ok());
+    }
+}
@@ -15,10 +15,12 @@
 use crate::auxiliary::clock::Clock;
 use crate::auxiliary::io::Io;
 use crate::bus::Bus;
+use crate::cart::mbc3::RtcClock;
 use crate::cart::Cart;
 use crate::cpu::Cpu;
 use crate::debugger::{DebugLogType, Debugger};
 use crate::emu::config::GbModel;
+use crate::ppu::{LINES_PER_FRAME, TICKS_PER_LINE};
 use std::path::Path;
 use std::time::Duration;
 use web_time::Instant;
@@ -84,9 +86,11 @@ impl TestRun {
     }
 }
 
-/// Build a ready-to-step CPU from raw ROM bytes.
+/// Build a ready-to-step CPU from raw ROM bytes. Runs here aren't paced to
+/// real time, so an MBC3 clock counts emulated time instead.
 pub fn build_cpu(rom: Box<[u8]>, model: Option<GbModel>) -> Result<Cpu, String> {
-    let cart = Cart::new(rom)?;
+    let mut cart = Cart::new(rom)?;
+    cart.power_on_rtc(RtcClock::Emulated);
     let bus = Bus::new(cart, Io::default(), model);
 
     Ok(Cpu::new(Clock::new(bus)))
@@ -181,6 +185,17 @@ pub fn run_duration(cpu: &mut Cpu, duration: Duration) {
     }
 }
 
+/// Step `cpu` for `frames` frames. Counted in cycles while the LCD is off,
+/// as no vblank comes then.
+pub fn run_frames(cpu: &mut Cpu, frames: usize) {
+    let end = cpu.clock.get_t_cycles() + frames * LINES_PER_FRAME * TICKS_PER_LINE;
+    let end_frame = cpu.clock.bus.io.ppu.current_frame + frames;
+
+    while cpu.clock.bus.io.ppu.current_frame < end_frame && cpu.clock.get_t_cycles() < end {
+        cpu.step();
+    }
+}
+
 fn finish(outcome: TestOutcome, debugger: &Debugger, start: Instant) -> TestRun {
     TestRun {
         outcome,
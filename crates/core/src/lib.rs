@@ -13,11 +13,14 @@ pub mod apu;
 pub mod auxiliary;
 pub mod bus;
 pub mod cart;
+pub mod cheats;
 pub mod cpu;
 pub mod debugger;
 pub mod emu;
 pub mod harness;
 pub mod ppu;
+#[cfg(feature = "script")]
+pub mod script;
 
 /// Returns true if the n-th bit of byte is set, false otherwise.
 #[inline(always)]
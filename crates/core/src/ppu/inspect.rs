@@ -0,0 +1,158 @@
+//! Whole-VRAM pictures for debug views: tile maps and tile data drawn
+//! outside of the scanline renderer, with the palettes currently loaded.
+
+use crate::ppu::fetcher::{bgw_tile_index_in_area, calc_bgw_tile_addr};
+use crate::ppu::tile::{
+    get_color_id, PixelColor, TileFlags, TILES_COUNT, TILE_BITS_COUNT, TILE_BIT_SIZE, TILE_HEIGHT,
+    TILE_SET_DATA_1_START, TILE_WIDTH,
+};
+use crate::ppu::Ppu;
+
+/// Tiles per side of a tile map.
+pub const TILEMAP_TILES: usize = 32;
+/// Pixels per side of a tile map.
+pub const TILEMAP_SIZE: usize = TILEMAP_TILES * TILE_WIDTH as usize;
+/// Tiles per row of a bank in [`Ppu::render_tiles`].
+pub const TILE_SHEET_COLUMNS: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct PixelImage {
+    pub width: usize,
+    pub height: usize,
+    /// Row by row.
+    pub pixels: Vec<PixelColor>,
+}
+
+impl PixelImage {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![PixelColor::default(); width * height],
+        }
+    }
+
+    #[inline(always)]
+    pub fn get(&self, x: usize, y: usize) -> PixelColor {
+        self.pixels[y * self.width + x]
+    }
+
+    #[inline(always)]
+    fn set(&mut self, x: usize, y: usize, color: PixelColor) {
+        self.pixels[y * self.width + x] = color;
+    }
+}
+
+impl Ppu {
+    /// The tile map at `map_addr` the way the BG layer would show it: the
+    /// current tile data area and palettes, and in CGB mode each tile's
+    /// attributes (VRAM bank, palette and flips).
+    pub fn render_tilemap(&self, map_addr: u16) -> PixelImage {
+        let cgb = self.lcd.is_cgb_mode();
+        let data_area = self.lcd.control.get_bgw_data_area();
+        let mut image = PixelImage::new(TILEMAP_SIZE, TILEMAP_SIZE);
+
+        for i in 0..TILEMAP_TILES * TILEMAP_TILES {
+            let addr = map_addr + i as u16;
+            let index = self.video_ram.read_from_bank(0, addr);
+            let index = bgw_tile_index_in_area(index, data_area);
+            let flags = if cgb {
+                self.video_ram.read_tile_flags(addr)
+            } else {
+                TileFlags::default()
+            };
+            let x = i % TILEMAP_TILES * TILE_WIDTH as usize;
+            let y = i / TILEMAP_TILES * TILE_HEIGHT as usize;
+
+            for row in 0..TILE_HEIGHT as u8 {
+                let src_row = if flags.is_y_flip() { 7 - row } else { row };
+                let line_addr = calc_bgw_tile_addr(index, src_row, data_area);
+                self.render_tile_line(&mut image, line_addr, flags, x, y + row as usize);
+            }
+        }
+
+        image
+    }
+
+    /// All tile data in BG palette 0, 16 tiles a row, with the second VRAM
+    /// bank beside the first in CGB mode.
+    pub fn render_tiles(&self) -> PixelImage {
+        let banks = 1 + self.lcd.is_cgb_mode() as usize;
+        let width = banks * TILE_SHEET_COLUMNS * TILE_WIDTH as usize;
+        let height = TILES_COUNT / TILE_SHEET_COLUMNS * TILE_HEIGHT as usize;
+        let mut image = PixelImage::new(width, height);
+
+        for bank in 0..banks {
+            // the bank attribute bit, so the line is read from that bank
+            let flags = TileFlags::from((bank as u8) << 3);
+
+            for i in 0..TILES_COUNT {
+                let addr = TILE_SET_DATA_1_START + i as u16 * TILE_BIT_SIZE;
+                let x = (bank * TILE_SHEET_COLUMNS + i % TILE_SHEET_COLUMNS) * TILE_WIDTH as usize;
+                let y = i / TILE_SHEET_COLUMNS * TILE_HEIGHT as usize;
+
+                for row in 0..TILE_HEIGHT {
+                    let line_addr = addr + row * 2;
+                    self.render_tile_line(&mut image, line_addr, flags, x, y + row as usize);
+                }
+            }
+        }
+
+        image
+    }
+
+    fn render_tile_line(
+        &self,
+        image: &mut PixelImage,
+        line_addr: u16,
+        flags: TileFlags,
+        x: usize,
+        y: usize,
+    ) {
+        let bank = flags.read_cgb_vram_bank();
+        let byte0 = self.video_ram.read_from_bank(bank, line_addr);
+        let byte1 = self.video_ram.read_from_bank(bank, line_addr + 1);
+
+        for bit in 0..TILE_BITS_COUNT {
+            let src_bit = if flags.is_x_flip() { 7 - bit } else { bit };
+            let color_id = get_color_id(byte0, byte1, src_bit);
+            let color = self.lcd.get_bgw_color(color_id, true, flags);
+            image.set(x + bit as usize, y, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emu::config::GbModel;
+    use crate::ppu::tile::BG_TILE_MAP_2_ADDR_START;
+
+    #[test]
+    fn test_render_tilemap_attributes() {
+        let mut ppu = Ppu::default();
+        ppu.lcd.set_model(GbModel::Cgb);
+        // tile 1 in bank 1: a line of color 1 on top, the rest color 0
+        ppu.video_ram.write_to_bank(1, 0x8010, 0xFF);
+        ppu.lcd.cgb_palette.write_color(2, 0, false, 0x001F);
+        ppu.lcd.cgb_palette.write_color(2, 1, false, 0x7C00);
+        // second map entry: tile 1, bank 1, palette 2, flipped vertically
+        ppu.video_ram
+            .write_to_bank(0, BG_TILE_MAP_2_ADDR_START + 1, 1);
+        ppu.video_ram
+            .write_to_bank(1, BG_TILE_MAP_2_ADDR_START + 1, 0b0100_1010);
+
+        let image = ppu.render_tilemap(BG_TILE_MAP_2_ADDR_START);
+        let red = PixelColor::from_bgr555(0x001F);
+        let blue = PixelColor::from_bgr555(0x7C00);
+
+        assert_eq!(image.width, TILEMAP_SIZE);
+        assert_eq!(image.get(8, 0), red);
+        assert_eq!(image.get(15, 7), blue);
+        assert_eq!(ppu.lcd.cgb_palette.read_color(2, 1, false), 0x7C00);
+
+        let tiles = ppu.render_tiles();
+        assert_eq!(tiles.width, 2 * TILE_SHEET_COLUMNS * 8);
+        assert_ne!(tiles.get(128 + 8, 0), tiles.get(128 + 8, 1));
+    }
+}
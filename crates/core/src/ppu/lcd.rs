@@ -77,6 +77,16 @@ pub struct Lcd {
     pub dmg_compat: bool,
 }
 
+/// The classic DMG green, for frontends without a palette setting.
+pub fn green_colors() -> [PixelColor; 4] {
+    [
+        PixelColor::from_hex_rgba("E0F8D0FF"),
+        PixelColor::from_hex_rgba("88C070FF"),
+        PixelColor::from_hex_rgba("346856FF"),
+        PixelColor::from_hex_rgba("081820FF"),
+    ]
+}
+
 impl Default for Lcd {
     fn default() -> Self {
         Self::new(
@@ -658,16 +668,33 @@ impl CgbPalette {
     }
 
     fn get_color(&self, palette_number: u8, color_index: usize, is_obj: bool) -> PixelColor {
-        let palette_number = palette_number as usize;
+        PixelColor::from_bgr555(self.read_color(palette_number, color_index, is_obj))
+    }
+
+    /// Raw BGR555 value of a color, bypassing the index registers.
+    pub fn read_color(&self, palette_number: u8, color_index: usize, is_obj: bool) -> u16 {
         // Each palette = 4 colors × 2 bytes
-        let base = palette_number * 8 + color_index * 2;
+        let base = palette_number as usize * 8 + color_index * 2;
         let ram = if is_obj { &self.obj_ram } else { &self.bg_ram };
 
-        let lo = ram[base];
-        let hi = ram[base + 1];
-        let value = u16::from_le_bytes([lo, hi]);
+        u16::from_le_bytes([ram[base], ram[base + 1]])
+    }
+
+    pub fn write_color(
+        &mut self,
+        palette_number: u8,
+        color_index: usize,
+        is_obj: bool,
+        value: u16,
+    ) {
+        let base = palette_number as usize * 8 + color_index * 2;
+        let ram = if is_obj {
+            &mut self.obj_ram
+        } else {
+            &mut self.bg_ram
+        };
 
-        PixelColor::from_bgr555(value)
+        ram[base..base + 2].copy_from_slice(&(value & 0x7FFF).to_le_bytes());
     }
 
     #[inline(always)]
@@ -2,6 +2,7 @@ pub mod cgb_boot_palette;
 pub mod fetcher;
 pub mod fifo;
 pub mod framebuffer;
+pub mod inspect;
 pub mod lcd;
 pub mod oam;
 pub mod ppu;
@@ -62,6 +62,12 @@ impl Ppu {
         self.fps_counter.as_ref().map(|x| x.get())
     }
 
+    /// Dot within the current line.
+    #[inline(always)]
+    pub fn get_dot(&self) -> usize {
+        self.line_ticks
+    }
+
     #[inline(always)]
     pub fn tick(&mut self, interrupts: &mut Interrupts) {
         if !self.lcd.control.is_lcd_enabled() {
@@ -248,6 +248,11 @@ impl From<u8> for TileFlags {
 }
 
 impl TileFlags {
+    #[inline(always)]
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
     #[inline(always)]
     pub fn read_cgb_palette(&self) -> u8 {
         self.0 & 0b0000_0111 // Extract bits 0-2
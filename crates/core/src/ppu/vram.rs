@@ -101,6 +101,11 @@ impl VideoRam {
         unsafe { *self.banks.get_unchecked(bank as usize).get_unchecked(addr) }
     }
 
+    pub fn write_to_bank(&mut self, bank: u8, addr: u16, val: u8) {
+        let addr = (addr - VRAM_ADDR_START) as usize;
+        self.banks[bank as usize][addr] = val;
+    }
+
     #[inline(always)]
     pub fn read_tile_flags(&self, addr: u16) -> TileFlags {
         self.read_from_bank(1, addr).into()
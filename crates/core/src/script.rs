@@ -0,0 +1,505 @@
+//! Rhai scripts run alongside the game, for bots, HUDs, auto-splitters and
+//! test drivers. A script registers its hooks when loaded:
+//!
+//! ```rhai
+//! let deaths = 0;
+//! on_write(0xC0A0, |addr, value| if value == 0 { deaths += 1 });
+//! on_exec(0x0150, || print(`main at frame ${frame()}`));
+//! on_frame(|| draw_text(1, 1, `DEATHS ${deaths}`));
+//! ```
+//!
+//! Besides the hooks it can read and write memory and registers, hold
+//! buttons, ask the frontend to save or load a state slot and draw onto the
+//! screen. Exec and write hooks come from the debugger's memory probe, so
+//! they see what the CPU executes and writes, not DMA.
+use crate::auxiliary::joypad::JoypadButton;
+use crate::cpu::{Cpu, RegisterType};
+use crate::debugger::AccessKind;
+use crate::emu::state::SaveStateCmd;
+use crate::ppu::tile::PixelColor;
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, FuncArgs, AST};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::ptr;
+use std::rc::Rc;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// What a script drew during the last frame, for the frontend to put over
+/// the picture. Colors left out take the frontend's text color.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptDraw {
+    Text {
+        x: i32,
+        y: i32,
+        text: String,
+        color: Option<PixelColor>,
+    },
+    Pixel {
+        x: i32,
+        y: i32,
+        color: PixelColor,
+    },
+    Rect {
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        color: PixelColor,
+    },
+}
+
+/// Calls that act on the whole emulator rather than the CPU, handed to the
+/// frontend since it owns the save slots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptRequest {
+    SaveState(SaveStateCmd, usize),
+    /// The script is done; `stop()`.
+    Stop,
+}
+
+#[derive(Default)]
+struct Hooks {
+    frame: Vec<FnPtr>,
+    exec: HashMap<u16, Vec<FnPtr>>,
+    write: HashMap<u16, Vec<FnPtr>>,
+}
+
+/// State the registered functions share with the host.
+struct Shared {
+    /// The CPU, only set while the host runs script code on its behalf.
+    cpu: *mut Cpu,
+    hooks: Hooks,
+    drawing: Vec<ScriptDraw>,
+    requests: Vec<ScriptRequest>,
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        Self {
+            cpu: ptr::null_mut(),
+            hooks: Hooks::default(),
+            drawing: Vec::new(),
+            requests: Vec::new(),
+        }
+    }
+}
+
+/// A loaded script and its hooks. Once started, the runtime calls
+/// [`Self::before_step`]/[`Self::after_step`] around every CPU step; after
+/// an error the script stays loaded but no longer runs.
+pub struct ScriptHost {
+    engine: Engine,
+    ast: AST,
+    shared: Rc<RefCell<Shared>>,
+    last_frame: usize,
+    /// What was drawn during the last complete frame.
+    shown: Vec<ScriptDraw>,
+    error: Option<String>,
+}
+
+impl ScriptHost {
+    /// Compiles `source`; nothing runs until [`Self::start`].
+    pub fn new(source: &str) -> Result<Self, String> {
+        let shared = Rc::new(RefCell::new(Shared::default()));
+        let mut engine = Engine::new();
+        engine.on_print(|x| log::info!("script: {x}"));
+        engine.on_debug(|x, _, pos| log::debug!("script {pos}: {x}"));
+        register_api(&mut engine, &shared);
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            engine,
+            ast,
+            shared,
+            last_frame: 0,
+            shown: Vec::new(),
+            error: None,
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read '{}': {e}", path.display()))?;
+
+        Self::new(&source).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Runs the script's top level, which registers the hooks.
+    pub fn start(&mut self, cpu: &mut Cpu) -> Result<(), String> {
+        self.last_frame = cpu.clock.bus.io.ppu.current_frame;
+        self.attach(cpu);
+        let result = self.engine.run_ast(&self.ast);
+        self.detach();
+
+        result.map_err(|e| e.to_string())
+    }
+
+    /// Where `print` goes instead of the log, e.g. stdout for a CLI.
+    pub fn on_print(&mut self, f: impl Fn(&str) + 'static) {
+        self.engine.on_print(f);
+    }
+
+    /// Runs the exec hooks of the instruction about to execute.
+    #[inline(always)]
+    pub fn before_step(&mut self, cpu: &mut Cpu) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let hooks = {
+            let shared = self.shared.borrow();
+            let watch = !shared.hooks.write.is_empty();
+            cpu.probe.set_always(watch);
+            cpu.probe.begin(watch);
+
+            if shared.hooks.exec.is_empty() || cpu.clock.is_cpu_halted() {
+                return;
+            }
+
+            match shared.hooks.exec.get(&cpu.registers.pc) {
+                Some(hooks) => hooks.clone(),
+                None => return,
+            }
+        };
+
+        self.run_hooks(cpu, &hooks, ());
+    }
+
+    /// Runs the write hooks of what the step wrote and, when it finished a
+    /// frame, the frame hooks.
+    #[inline(always)]
+    pub fn after_step(&mut self, cpu: &mut Cpu) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let writes: Vec<_> = {
+            let shared = self.shared.borrow();
+            let hooks = &shared.hooks.write;
+
+            cpu.probe
+                .accesses()
+                .iter()
+                .filter(|x| x.kind == AccessKind::Write)
+                .filter_map(|x| Some((x.addr, x.value, hooks.get(&x.addr)?.clone())))
+                .collect()
+        };
+
+        for (addr, value, hooks) in writes {
+            self.run_hooks(cpu, &hooks, (addr as i64, value as i64));
+        }
+
+        let frame = cpu.clock.bus.io.ppu.current_frame;
+
+        if frame != self.last_frame {
+            self.last_frame = frame;
+            let hooks = self.shared.borrow().hooks.frame.clone();
+            self.run_hooks(cpu, &hooks, ());
+            self.shown = std::mem::take(&mut self.shared.borrow_mut().drawing);
+        }
+    }
+
+    /// One CPU step with the hooks, for frontends that step the CPU directly.
+    pub fn step(&mut self, cpu: &mut Cpu) {
+        self.before_step(cpu);
+        cpu.step();
+        self.after_step(cpu);
+    }
+
+    /// What the script drew during the last frame.
+    pub fn draws(&self) -> &[ScriptDraw] {
+        &self.shown
+    }
+
+    pub fn has_requests(&self) -> bool {
+        !self.shared.borrow().requests.is_empty()
+    }
+
+    pub fn take_requests(&mut self) -> Vec<ScriptRequest> {
+        std::mem::take(&mut self.shared.borrow_mut().requests)
+    }
+
+    /// The error that stopped the script, if any.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn run_hooks(&mut self, cpu: &mut Cpu, hooks: &[FnPtr], args: impl FuncArgs + Clone) {
+        self.attach(cpu);
+
+        for hook in hooks {
+            if let Err(err) = hook.call::<Dynamic>(&self.engine, &self.ast, args.clone()) {
+                log::error!("Script stopped: {err}");
+                self.error = Some(err.to_string());
+                break;
+            }
+        }
+
+        self.detach();
+    }
+
+    fn attach(&mut self, cpu: &mut Cpu) {
+        self.shared.borrow_mut().cpu = cpu;
+    }
+
+    fn detach(&mut self) {
+        self.shared.borrow_mut().cpu = ptr::null_mut();
+    }
+}
+
+/// Runs `f` on the CPU the host attached for the current call.
+fn with_cpu<T>(shared: &RefCell<Shared>, f: impl FnOnce(&mut Cpu) -> T) -> ScriptResult<T> {
+    let cpu = shared.borrow().cpu;
+
+    if cpu.is_null() {
+        return Err("no emulator attached".into());
+    }
+
+    // SAFETY: the pointer is only set by `ScriptHost::attach` from a `&mut Cpu`
+    // the host holds until `detach`, while script code runs and nothing else
+    // touches the CPU.
+    Ok(f(unsafe { &mut *cpu }))
+}
+
+fn register_api(engine: &mut Engine, shared: &Rc<RefCell<Shared>>) {
+    let s = shared.clone();
+    engine.register_fn("on_frame", move |f: FnPtr| {
+        s.borrow_mut().hooks.frame.push(f);
+    });
+    let s = shared.clone();
+    engine.register_fn("on_exec", move |addr: i64, f: FnPtr| {
+        let hooks = &mut s.borrow_mut().hooks.exec;
+        hooks.entry(addr as u16).or_default().push(f);
+    });
+    let s = shared.clone();
+    engine.register_fn("on_write", move |addr: i64, f: FnPtr| {
+        let hooks = &mut s.borrow_mut().hooks.write;
+        hooks.entry(addr as u16).or_default().push(f);
+    });
+
+    let s = shared.clone();
+    engine.register_fn("read_u8", move |addr: i64| {
+        with_cpu(&s, |cpu| cpu.clock.bus.read(addr as u16) as i64)
+    });
+    let s = shared.clone();
+    engine.register_fn("read_u16", move |addr: i64| {
+        with_cpu(&s, |cpu| {
+            let lo = cpu.clock.bus.read(addr as u16);
+            let hi = cpu.clock.bus.read((addr as u16).wrapping_add(1));
+            u16::from_le_bytes([lo, hi]) as i64
+        })
+    });
+    let s = shared.clone();
+    engine.register_fn("write_u8", move |addr: i64, value: i64| {
+        with_cpu(&s, |cpu| cpu.clock.bus.write(addr as u16, value as u8))
+    });
+    let s = shared.clone();
+    engine.register_fn("write_u16", move |addr: i64, value: i64| {
+        with_cpu(&s, |cpu| {
+            let [lo, hi] = (value as u16).to_le_bytes();
+            cpu.clock.bus.write(addr as u16, lo);
+            cpu.clock.bus.write((addr as u16).wrapping_add(1), hi);
+        })
+    });
+
+    let s = shared.clone();
+    engine.register_fn("reg", move |name: &str| {
+        let r = parse_register(name)?;
+        with_cpu(&s, |cpu| cpu.registers.read(r) as i64)
+    });
+    let s = shared.clone();
+    engine.register_fn("set_reg", move |name: &str, value: i64| {
+        let r = parse_register(name)?;
+        with_cpu(&s, |cpu| cpu.registers.write(r, value as u16))
+    });
+    let s = shared.clone();
+    engine.register_fn("frame", move || {
+        with_cpu(&s, |cpu| cpu.clock.bus.io.ppu.current_frame as i64)
+    });
+
+    let s = shared.clone();
+    engine.register_fn("press", move |name: &str| {
+        let button = parse_button(name)?;
+        with_cpu(&s, |cpu| cpu.clock.bus.io.joypad.handle(button, true))
+    });
+    let s = shared.clone();
+    engine.register_fn("release", move |name: &str| {
+        let button = parse_button(name)?;
+        with_cpu(&s, |cpu| cpu.clock.bus.io.joypad.handle(button, false))
+    });
+    let s = shared.clone();
+    engine.register_fn("buttons", move || {
+        with_cpu(&s, |cpu| cpu.clock.bus.io.joypad.get_pressed() as i64)
+    });
+    let s = shared.clone();
+    engine.register_fn("set_buttons", move |bits: i64| {
+        with_cpu(&s, |cpu| cpu.clock.bus.io.joypad.set_pressed(bits as u8))
+    });
+
+    let s = shared.clone();
+    engine.register_fn("save_state", move |slot: i64| {
+        let request = ScriptRequest::SaveState(SaveStateCmd::Create, slot.max(0) as usize);
+        s.borrow_mut().requests.push(request);
+    });
+    let s = shared.clone();
+    engine.register_fn("load_state", move |slot: i64| {
+        let request = ScriptRequest::SaveState(SaveStateCmd::Load, slot.max(0) as usize);
+        s.borrow_mut().requests.push(request);
+    });
+    let s = shared.clone();
+    engine.register_fn("stop", move || {
+        s.borrow_mut().requests.push(ScriptRequest::Stop);
+    });
+
+    let s = shared.clone();
+    engine.register_fn("draw_text", move |x: i64, y: i64, text: &str| {
+        s.borrow_mut().drawing.push(ScriptDraw::Text {
+            x: x as i32,
+            y: y as i32,
+            text: text.to_string(),
+            color: None,
+        });
+    });
+    let s = shared.clone();
+    engine.register_fn(
+        "draw_text",
+        move |x: i64, y: i64, text: &str, color: i64| {
+            s.borrow_mut().drawing.push(ScriptDraw::Text {
+                x: x as i32,
+                y: y as i32,
+                text: text.to_string(),
+                color: Some(to_color(color)),
+            });
+        },
+    );
+    let s = shared.clone();
+    engine.register_fn("draw_pixel", move |x: i64, y: i64, color: i64| {
+        s.borrow_mut().drawing.push(ScriptDraw::Pixel {
+            x: x as i32,
+            y: y as i32,
+            color: to_color(color),
+        });
+    });
+    let s = shared.clone();
+    engine.register_fn(
+        "draw_rect",
+        move |x: i64, y: i64, w: i64, h: i64, color: i64| {
+            s.borrow_mut().drawing.push(ScriptDraw::Rect {
+                x: x as i32,
+                y: y as i32,
+                w: w as i32,
+                h: h as i32,
+                color: to_color(color),
+            });
+        },
+    );
+}
+
+/// `0xRRGGBB`.
+fn to_color(rgb: i64) -> PixelColor {
+    let [_, r, g, b] = (rgb as u32).to_be_bytes();
+
+    PixelColor::new(r, g, b)
+}
+
+fn parse_register(name: &str) -> ScriptResult<RegisterType> {
+    RegisterType::get_all()
+        .iter()
+        .find(|r| format!("{r:?}").eq_ignore_ascii_case(name))
+        .copied()
+        .ok_or_else(|| format!("unknown register '{name}'").into())
+}
+
+fn parse_button(name: &str) -> ScriptResult<JoypadButton> {
+    match name.to_lowercase().as_str() {
+        "start" => Ok(JoypadButton::Start),
+        "select" => Ok(JoypadButton::Select),
+        "a" => Ok(JoypadButton::A),
+        "b" => Ok(JoypadButton::B),
+        "up" => Ok(JoypadButton::Up),
+        "down" => Ok(JoypadButton::Down),
+        "left" => Ok(JoypadButton::Left),
+        "right" => Ok(JoypadButton::Right),
+        other => Err(format!("unknown button '{other}'").into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auxiliary::clock::Clock;
+    use crate::auxiliary::io::Io;
+    use crate::bus::Bus;
+
+    #[test]
+    fn test_exec_write_and_frame_hooks() {
+        // LD A,$2A; LD ($C000),A; JR -5
+        let mut bytes = vec![0; 0x10000];
+        bytes[..6].copy_from_slice(&[0x3E, 0x2A, 0xEA, 0x00, 0xC0, 0x18]);
+        bytes[6] = 0xFB;
+        let mut cpu = Cpu::new(Clock::new(Bus::with_bytes(bytes, Io::default())));
+        cpu.registers.pc = 0;
+
+        let source = r#"
+            let writes = 0;
+            on_exec(0x0002, || {
+                set_reg("b", reg("a") + 1);
+                press("start");
+            });
+            on_write(0xC000, |addr, value| {
+                writes += 1;
+                write_u8(0xC001, value);
+                if writes == 2 { save_state(3); }
+            });
+            on_frame(|| draw_text(1, 2, `W${writes}`, 0xFF0000));
+        "#;
+        let mut host = ScriptHost::new(source).unwrap();
+        host.start(&mut cpu).unwrap();
+
+        for _ in 0..5 {
+            host.step(&mut cpu);
+        }
+
+        assert_eq!(cpu.registers.b, 0x2B);
+        assert!(cpu.clock.bus.io.joypad.start);
+        assert_eq!(cpu.clock.bus.read(0xC001), 0x2A);
+        assert_eq!(
+            host.take_requests(),
+            [ScriptRequest::SaveState(SaveStateCmd::Create, 3)]
+        );
+
+        // the draws of a frame show once it's done, with this step's write
+        assert!(host.draws().is_empty());
+        cpu.clock.bus.io.ppu.current_frame += 1;
+        host.step(&mut cpu);
+        assert_eq!(
+            host.draws(),
+            [ScriptDraw::Text {
+                x: 1,
+                y: 2,
+                text: "W3".to_string(),
+                color: Some(PixelColor::new(0xFF, 0, 0)),
+            }]
+        );
+        assert_eq!(host.error(), None);
+    }
+
+    #[test]
+    fn test_error_stops_script() {
+        let mut cpu = Cpu::new(Clock::new(Bus::with_bytes(vec![0; 0x10000], Io::default())));
+        cpu.registers.pc = 0;
+
+        assert!(ScriptHost::new("let x = ;").is_err());
+
+        let mut host = ScriptHost::new("on_exec(0, || reg(\"Q\"));").unwrap();
+        host.start(&mut cpu).unwrap();
+        host.step(&mut cpu);
+        assert!(host.error().unwrap().contains("unknown register 'Q'"));
+
+        // stays quiet from then on
+        cpu.registers.pc = 0;
+        host.step(&mut cpu);
+    }
+}
@@ -0,0 +1,41 @@
+// Generates the C header for the exported functions into OUT_DIR, where a
+// test checks it against include/oxgbc.h. That copy is kept in the repo so C
+// and C++ users don't need Rust tooling to read it; building with
+// OXGBC_UPDATE_HEADER set rewrites it.
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    let dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let dir = Path::new(&dir);
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("oxgbc.h");
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=OXGBC_UPDATE_HEADER");
+
+    let header = cbindgen::Config::from_file(dir.join("cbindgen.toml"))
+        .map_err(|err| err.to_string())
+        .and_then(|config| {
+            cbindgen::Builder::new()
+                .with_config(config)
+                .with_src(dir.join("src/lib.rs"))
+                .generate()
+                .map_err(|err| err.to_string())
+        });
+
+    match header {
+        Ok(header) => {
+            header.write_to_file(&out);
+
+            if env::var_os("OXGBC_UPDATE_HEADER").is_some() {
+                header.write_to_file(dir.join("include/oxgbc.h"));
+            }
+        }
+        Err(err) => {
+            // left empty, the header test fails instead of the build
+            println!("cargo:warning=failed to generate the C header: {err}");
+            fs::write(&out, "").unwrap();
+        }
+    }
+}
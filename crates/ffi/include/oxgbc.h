@@ -0,0 +1,139 @@
+/* oxGBC C API. Generated by cbindgen from crates/ffi/src/lib.rs, do not edit. */
+
+#ifndef OXGBC_H
+#define OXGBC_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Screen width in pixels.
+#define OXGBC_WIDTH 160
+
+// Screen height in pixels.
+#define OXGBC_HEIGHT 144
+
+// Bytes per framebuffer row, 2 per RGB565 pixel.
+#define OXGBC_PITCH 320
+
+// Audio samples per second and channel.
+#define OXGBC_SAMPLE_RATE 44100
+
+#define OXGBC_BUTTON_START (1 << 0)
+
+#define OXGBC_BUTTON_SELECT (1 << 1)
+
+#define OXGBC_BUTTON_A (1 << 2)
+
+#define OXGBC_BUTTON_B (1 << 3)
+
+#define OXGBC_BUTTON_UP (1 << 4)
+
+#define OXGBC_BUTTON_DOWN (1 << 5)
+
+#define OXGBC_BUTTON_LEFT (1 << 6)
+
+#define OXGBC_BUTTON_RIGHT (1 << 7)
+
+typedef enum {
+  // Picked by the cart, CGB for color games.
+  OXGBC_MODEL_AUTO,
+  OXGBC_MODEL_DMG,
+  OXGBC_MODEL_CGB,
+} OxgbcModel;
+
+// An emulator instance. Instances are independent, but each is for one
+// thread at a time.
+typedef struct Oxgbc Oxgbc;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates an instance with no ROM loaded. Free it with
+// [`oxgbc_destroy`].
+Oxgbc *oxgbc_create(OxgbcModel model);
+
+// # Safety
+// `gb` must be null or from [`oxgbc_create`], and not used after.
+void oxgbc_destroy(Oxgbc *gb);
+
+// Why the last call returning `false` failed, empty before any. Valid
+// until the next call on `gb`.
+//
+// # Safety
+// `gb` must be null or a live instance.
+const char *oxgbc_last_error(const Oxgbc *gb);
+
+// Loads a `.gb` or `.gbc` image and powers on, dropping any battery RAM.
+//
+// # Safety
+// `gb` must be null or a live instance, `data` point to `len` bytes.
+bool oxgbc_load_rom(Oxgbc *gb, const uint8_t *data, size_t len);
+
+// Runs until the next frame is drawn.
+//
+// # Safety
+// `gb` must be null or a live instance.
+bool oxgbc_run_frame(Oxgbc *gb);
+
+// The screen: [`OXGBC_HEIGHT`] rows of [`OXGBC_PITCH`] bytes, each pixel
+// little-endian RGB565. Valid until `gb` is destroyed.
+//
+// # Safety
+// `gb` must be null or a live instance.
+const uint8_t *oxgbc_framebuffer(Oxgbc *gb);
+
+// Moves up to `max` samples of the audio produced so far into `out`,
+// interleaved stereo at [`OXGBC_SAMPLE_RATE`] in -1 to 1. Returns how many
+// it moved, or with a null `out` how many there are. Only the last second is
+// kept.
+//
+// # Safety
+// `gb` must be null or a live instance, `out` null or point to `max` floats.
+size_t oxgbc_audio_drain(Oxgbc *gb, float *out, size_t max);
+
+// Holds the `OXGBC_BUTTON_*` bits in `buttons`, releasing the rest.
+//
+// # Safety
+// `gb` must be null or a live instance.
+void oxgbc_set_buttons(Oxgbc *gb, uint8_t buttons);
+
+// Writes a save state, the desktop app's `.state` format, into `out` when
+// `cap` bytes fit it. Returns its length, 0 on failure.
+//
+// # Safety
+// `gb` must be null or a live instance, `out` null or point to `cap`
+// writable bytes.
+size_t oxgbc_save_state(Oxgbc *gb, uint8_t *out, size_t cap);
+
+// Loads a save state made from the same ROM. The buttons are released.
+//
+// # Safety
+// `gb` must be null or a live instance, `data` point to `len` bytes.
+bool oxgbc_load_state(Oxgbc *gb, const uint8_t *data, size_t len);
+
+// Copies the battery-backed cartridge RAM, followed by the clock for MBC3
+// carts with one, into `out` when `cap` bytes fit it. The layout is the
+// desktop app's `.sav` files. Returns its length, 0 without a battery.
+//
+// # Safety
+// `gb` must be null or a live instance, `out` null or point to `cap`
+// writable bytes.
+size_t oxgbc_get_battery(Oxgbc *gb, uint8_t *out, size_t cap);
+
+// Replaces the battery-backed RAM, as [`oxgbc_get_battery`] returns it.
+// Best called right after [`oxgbc_load_rom`], as the game may have read
+// the old one.
+//
+// # Safety
+// `gb` must be null or a live instance, `data` point to `len` bytes.
+bool oxgbc_set_battery(Oxgbc *gb, const uint8_t *data, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* OXGBC_H */
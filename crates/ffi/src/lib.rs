@@ -0,0 +1,443 @@
+//! C ABI for embedding oxGBC in C, C++, Python (`ctypes`/`cffi`) and other
+//! tools, with `include/oxgbc.h` generated from this file.
+//!
+//! Like the web and libretro builds it's a thin shell around `core`: an
+//! [`Oxgbc`] handle owns an [`Emu`] that runs a frame per
+//! [`oxgbc_run_frame`], as fast as the caller calls it. Functions returning
+//! `bool` leave a message for [`oxgbc_last_error`] on failure; those taking a
+//! buffer return the length of what they'd write, writing only when it fits,
+//! so a first call with a null buffer sizes it.
+
+use core::apu::SAMPLING_FREQUENCY;
+use core::cart::Cart;
+use core::emu::config::{EmuConfig, GbModel};
+use core::emu::state_file::{self, SaveStateInfo};
+use core::emu::{Emu, SampleSink};
+use core::ppu::framebuffer::FrameBuffer;
+use core::ppu::{LCD_X_RES, LCD_Y_RES};
+use std::ffi::{c_char, CString};
+use std::{ptr, slice};
+
+/// Screen width in pixels.
+pub const OXGBC_WIDTH: u32 = 160;
+/// Screen height in pixels.
+pub const OXGBC_HEIGHT: u32 = 144;
+/// Bytes per framebuffer row, 2 per RGB565 pixel.
+pub const OXGBC_PITCH: u32 = 320;
+/// Audio samples per second and channel.
+pub const OXGBC_SAMPLE_RATE: u32 = 44_100;
+
+pub const OXGBC_BUTTON_START: u8 = 1 << 0;
+pub const OXGBC_BUTTON_SELECT: u8 = 1 << 1;
+pub const OXGBC_BUTTON_A: u8 = 1 << 2;
+pub const OXGBC_BUTTON_B: u8 = 1 << 3;
+pub const OXGBC_BUTTON_UP: u8 = 1 << 4;
+pub const OXGBC_BUTTON_DOWN: u8 = 1 << 5;
+pub const OXGBC_BUTTON_LEFT: u8 = 1 << 6;
+pub const OXGBC_BUTTON_RIGHT: u8 = 1 << 7;
+
+// the header needs literals, kept in step with the core here
+const _: () = assert!(OXGBC_WIDTH as usize == LCD_X_RES as usize);
+const _: () = assert!(OXGBC_HEIGHT as usize == LCD_Y_RES as usize);
+const _: () = assert!(OXGBC_PITCH as usize == FrameBuffer::PITCH);
+const _: () = assert!(OXGBC_SAMPLE_RATE == SAMPLING_FREQUENCY);
+
+/// Audio kept for a caller that doesn't drain it: a second, interleaved
+/// stereo.
+const MAX_AUDIO: usize = 2 * OXGBC_SAMPLE_RATE as usize;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OxgbcModel {
+    /// Picked by the cart, CGB for color games.
+    Auto,
+    Dmg,
+    Cgb,
+}
+
+/// An emulator instance. Instances are independent, but each is for one
+/// thread at a time.
+pub struct Oxgbc {
+    emu: Emu,
+    /// Audio not drained yet, at most [`MAX_AUDIO`] samples.
+    audio: Vec<f32>,
+    error: CString,
+    /// Whether a frame ran since the ROM was loaded, as battery RAM set
+    /// before the first one also sets the cartridge clock.
+    started: bool,
+}
+
+impl Oxgbc {
+    fn fail(&mut self, err: impl Into<Vec<u8>>) -> bool {
+        let mut err = err.into();
+        err.retain(|x| *x != 0);
+        self.error = CString::new(err).unwrap_or_default();
+
+        false
+    }
+
+    fn has_rom(&mut self) -> bool {
+        if self.emu.runtime.cpu.clock.bus.cart.is_empty() {
+            return self.fail("no ROM loaded");
+        }
+
+        true
+    }
+}
+
+/// Copies `bytes` into `out` when it fits. Returns their length.
+///
+/// # Safety
+/// `out` must be null or point to `cap` writable bytes.
+unsafe fn write_out(bytes: &[u8], out: *mut u8, cap: usize) -> usize {
+    if !out.is_null() && bytes.len() <= cap {
+        ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+    }
+
+    bytes.len()
+}
+
+/// # Safety
+/// `data` must be null or point to `len` readable bytes.
+unsafe fn read_in<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if data.is_null() {
+        return &[];
+    }
+
+    slice::from_raw_parts(data, len)
+}
+
+/// Creates an instance with no ROM loaded. Free it with
+/// [`oxgbc_destroy`].
+#[no_mangle]
+pub extern "C" fn oxgbc_create(model: OxgbcModel) -> *mut Oxgbc {
+    let model = match model {
+        OxgbcModel::Auto => None,
+        OxgbcModel::Dmg => Some(GbModel::Dmg),
+        OxgbcModel::Cgb => Some(GbModel::Cgb),
+    };
+    let config = EmuConfig {
+        // the caller keeps states if it wants to go back
+        rewind_budget: 0,
+        model,
+        ..EmuConfig::default()
+    };
+    let Ok(emu) = Emu::with_config(config) else {
+        return ptr::null_mut();
+    };
+
+    Box::into_raw(Box::new(Oxgbc {
+        emu,
+        audio: Vec::new(),
+        error: CString::default(),
+        started: false,
+    }))
+}
+
+/// # Safety
+/// `gb` must be null or from [`oxgbc_create`], and not used after.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_destroy(gb: *mut Oxgbc) {
+    if !gb.is_null() {
+        drop(Box::from_raw(gb));
+    }
+}
+
+/// Why the last call returning `false` failed, empty before any. Valid
+/// until the next call on `gb`.
+///
+/// # Safety
+/// `gb` must be null or a live instance.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_last_error(gb: *const Oxgbc) -> *const c_char {
+    match gb.as_ref() {
+        Some(gb) => gb.error.as_ptr(),
+        None => c"null instance".as_ptr(),
+    }
+}
+
+/// Loads a `.gb` or `.gbc` image and powers on, dropping any battery RAM.
+///
+/// # Safety
+/// `gb` must be null or a live instance, `data` point to `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_load_rom(gb: *mut Oxgbc, data: *const u8, len: usize) -> bool {
+    let Some(gb) = gb.as_mut() else {
+        return false;
+    };
+
+    match Cart::new(read_in(data, len).into()) {
+        Ok(cart) => {
+            gb.emu.load_cart(cart);
+            gb.audio.clear();
+            gb.started = false;
+            true
+        }
+        Err(err) => gb.fail(err),
+    }
+}
+
+/// Runs until the next frame is drawn.
+///
+/// # Safety
+/// `gb` must be null or a live instance.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_run_frame(gb: *mut Oxgbc) -> bool {
+    let Some(gb) = gb.as_mut() else {
+        return false;
+    };
+
+    if !gb.has_rom() {
+        return false;
+    }
+
+    gb.emu.runtime.run_frame(&mut SampleSink(&mut gb.audio));
+    gb.started = true;
+
+    if gb.audio.len() > MAX_AUDIO {
+        let excess = gb.audio.len() - MAX_AUDIO;
+        // whole stereo pairs, oldest first
+        gb.audio.drain(..excess + excess % 2);
+    }
+
+    true
+}
+
+/// The screen: [`OXGBC_HEIGHT`] rows of [`OXGBC_PITCH`] bytes, each pixel
+/// little-endian RGB565. Valid until `gb` is destroyed.
+///
+/// # Safety
+/// `gb` must be null or a live instance.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_framebuffer(gb: *mut Oxgbc) -> *const u8 {
+    match gb.as_mut() {
+        Some(gb) => gb.emu.get_framebuffer().as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Moves up to `max` samples of the audio produced so far into `out`,
+/// interleaved stereo at [`OXGBC_SAMPLE_RATE`] in -1 to 1. Returns how many
+/// it moved, or with a null `out` how many there are. Only the last second is
+/// kept.
+///
+/// # Safety
+/// `gb` must be null or a live instance, `out` null or point to `max` floats.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_audio_drain(gb: *mut Oxgbc, out: *mut f32, max: usize) -> usize {
+    let Some(gb) = gb.as_mut() else {
+        return 0;
+    };
+
+    let samples = &mut gb.audio;
+
+    if out.is_null() {
+        return samples.len();
+    }
+
+    // whole stereo pairs
+    let len = samples.len().min(max) / 2 * 2;
+    ptr::copy_nonoverlapping(samples.as_ptr(), out, len);
+    samples.drain(..len);
+
+    len
+}
+
+/// Holds the `OXGBC_BUTTON_*` bits in `buttons`, releasing the rest.
+///
+/// # Safety
+/// `gb` must be null or a live instance.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_set_buttons(gb: *mut Oxgbc, buttons: u8) {
+    if let Some(gb) = gb.as_mut() {
+        gb.emu.runtime.cpu.clock.bus.io.joypad.set_pressed(buttons);
+    }
+}
+
+/// Writes a save state, the desktop app's `.state` format, into `out` when
+/// `cap` bytes fit it. Returns its length, 0 on failure.
+///
+/// # Safety
+/// `gb` must be null or a live instance, `out` null or point to `cap`
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_save_state(gb: *mut Oxgbc, out: *mut u8, cap: usize) -> usize {
+    let Some(gb) = gb.as_mut() else {
+        return 0;
+    };
+
+    if !gb.has_rom() {
+        return 0;
+    }
+
+    let state = gb.emu.create_save_state();
+    let info = SaveStateInfo::new(&gb.emu.runtime.cpu);
+
+    match state_file::encode(&state, &info) {
+        Ok(bytes) => write_out(&bytes, out, cap),
+        Err(err) => {
+            gb.fail(err);
+            0
+        }
+    }
+}
+
+/// Loads a save state made from the same ROM. The buttons are released.
+///
+/// # Safety
+/// `gb` must be null or a live instance, `data` point to `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_load_state(gb: *mut Oxgbc, data: *const u8, len: usize) -> bool {
+    let Some(gb) = gb.as_mut() else {
+        return false;
+    };
+
+    if !gb.has_rom() {
+        return false;
+    }
+
+    match gb.emu.load_save_state_file(read_in(data, len)) {
+        Ok(()) => true,
+        Err(err) => gb.fail(err),
+    }
+}
+
+/// Copies the battery-backed cartridge RAM, followed by the clock for MBC3
+/// carts with one, into `out` when `cap` bytes fit it. The layout is the
+/// desktop app's `.sav` files. Returns its length, 0 without a battery.
+///
+/// # Safety
+/// `gb` must be null or a live instance, `out` null or point to `cap`
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_get_battery(gb: *mut Oxgbc, out: *mut u8, cap: usize) -> usize {
+    let Some(gb) = gb.as_mut() else {
+        return 0;
+    };
+
+    match gb.emu.runtime.cpu.clock.bus.cart.dump_ram() {
+        Some(bytes) => write_out(&bytes, out, cap),
+        None => 0,
+    }
+}
+
+/// Replaces the battery-backed RAM, as [`oxgbc_get_battery`] returns it.
+/// Best called right after [`oxgbc_load_rom`], as the game may have read
+/// the old one.
+///
+/// # Safety
+/// `gb` must be null or a live instance, `data` point to `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn oxgbc_set_battery(gb: *mut Oxgbc, data: *const u8, len: usize) -> bool {
+    let Some(gb) = gb.as_mut() else {
+        return false;
+    };
+
+    let cart = &mut gb.emu.runtime.cpu.clock.bus.cart;
+
+    if cart.dump_ram().is_none() {
+        return gb.fail("the cart has no battery");
+    }
+
+    cart.load_ram(read_in(data, len).into());
+
+    if !gb.started {
+        cart.power_on_rtc(gb.emu.config.rtc_clock);
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    /// An MBC3 cart with a timer, battery and 8 KiB of RAM, adding 1 to
+    /// $A000 every loop.
+    fn new_rom() -> Vec<u8> {
+        // LD A,$0A; LD ($0000),A; LD HL,$A000; INC (HL); JR -3
+        let program = [
+            0x3E, 0x0A, 0xEA, 0x00, 0x00, 0x21, 0x00, 0xA0, 0x34, 0x18, 0xFD,
+        ];
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x100..0x100 + program.len()].copy_from_slice(&program);
+        rom[0x147] = 0x10;
+        rom[0x149] = 0x02;
+
+        rom
+    }
+
+    #[test]
+    fn test_header_current() {
+        let header = include_str!(concat!(env!("OUT_DIR"), "/oxgbc.h"));
+
+        assert!(
+            header == include_str!("../include/oxgbc.h"),
+            "include/oxgbc.h is stale, rebuild with OXGBC_UPDATE_HEADER=1"
+        );
+    }
+
+    unsafe fn battery(gb: *mut Oxgbc) -> Vec<u8> {
+        let mut bytes = vec![0; oxgbc_get_battery(gb, ptr::null_mut(), 0)];
+        oxgbc_get_battery(gb, bytes.as_mut_ptr(), bytes.len());
+
+        bytes
+    }
+
+    #[test]
+    fn test_states() {
+        unsafe {
+            let gb = oxgbc_create(OxgbcModel::Auto);
+            assert!(!oxgbc_run_frame(gb));
+            assert_eq!(CStr::from_ptr(oxgbc_last_error(gb)), c"no ROM loaded");
+
+            let rom = new_rom();
+            assert!(oxgbc_load_rom(gb, rom.as_ptr(), rom.len()));
+            assert!(oxgbc_run_frame(gb));
+
+            let mut state = vec![0; oxgbc_save_state(gb, ptr::null_mut(), 0)];
+            assert_eq!(
+                oxgbc_save_state(gb, state.as_mut_ptr(), state.len()),
+                state.len()
+            );
+            let saved = battery(gb);
+            assert_eq!(saved.len(), 0x2000 + 48);
+
+            oxgbc_run_frame(gb);
+            assert_ne!(battery(gb)[0], saved[0]);
+            assert!(oxgbc_load_state(gb, state.as_ptr(), state.len()));
+            assert_eq!(battery(gb)[0], saved[0]);
+            assert!(!oxgbc_load_state(gb, state.as_ptr(), 8));
+
+            let mut samples = vec![0.0; oxgbc_audio_drain(gb, ptr::null_mut(), 0)];
+            let len = oxgbc_audio_drain(gb, samples.as_mut_ptr(), samples.len());
+            assert!(len > 0 && len == samples.len());
+            assert_eq!(oxgbc_audio_drain(gb, ptr::null_mut(), 0), 0);
+
+            oxgbc_destroy(gb);
+        }
+    }
+
+    #[test]
+    fn test_battery() {
+        unsafe {
+            let gb = oxgbc_create(OxgbcModel::Dmg);
+            let rom = new_rom();
+            oxgbc_load_rom(gb, rom.as_ptr(), rom.len());
+
+            let mut bytes = battery(gb);
+            bytes[1] = 0x42;
+            assert!(oxgbc_set_battery(gb, bytes.as_ptr(), bytes.len()));
+            assert_eq!(battery(gb)[1], 0x42);
+
+            let mut rom = rom;
+            rom[0x147] = 0x00;
+            oxgbc_load_rom(gb, rom.as_ptr(), rom.len());
+            assert_eq!(oxgbc_get_battery(gb, ptr::null_mut(), 0), 0);
+            assert!(!oxgbc_set_battery(gb, bytes.as_ptr(), bytes.len()));
+
+            oxgbc_destroy(gb);
+        }
+    }
+}
@@ -0,0 +1,438 @@
+//! libretro core for oxGBC, loaded by RetroArch and other libretro frontends.
+//!
+//! Like the web build this is a thin shell around `core`: it holds an
+//! [`Emu`](core::emu::Emu) and steps it a frame per `retro_run`, with the
+//! frontend pacing it. Rewind, run-ahead and netplay are the frontend's,
+//! done with `retro_serialize`; achievements and cheats read the memory map.
+//!
+//! libretro cores are global by design, so the game and the frontend's
+//! callbacks live in statics.
+
+mod memory;
+mod options;
+mod session;
+mod sys;
+
+use crate::options::{Options, MODEL_KEY, RTC_KEY, VARIABLES};
+use crate::session::Session;
+use crate::sys::*;
+use core::apu::SAMPLING_FREQUENCY;
+use core::cpu::CPU_CLOCK_SPEED;
+use core::emu::link::FRAME_CYCLES;
+use core::ppu::framebuffer::FrameBuffer;
+use core::ppu::{LCD_X_RES, LCD_Y_RES};
+use std::ffi::{c_char, c_uint, c_void, CStr};
+use std::sync::{Mutex, MutexGuard};
+use std::{ptr, slice};
+
+/// The buttons by `Joypad` bit, with their libretro ids.
+const BUTTONS: [(u32, &CStr); 8] = [
+    (DEVICE_ID_JOYPAD_START, c"Start"),
+    (DEVICE_ID_JOYPAD_SELECT, c"Select"),
+    (DEVICE_ID_JOYPAD_A, c"A"),
+    (DEVICE_ID_JOYPAD_B, c"B"),
+    (DEVICE_ID_JOYPAD_UP, c"Up"),
+    (DEVICE_ID_JOYPAD_DOWN, c"Down"),
+    (DEVICE_ID_JOYPAD_LEFT, c"Left"),
+    (DEVICE_ID_JOYPAD_RIGHT, c"Right"),
+];
+
+#[derive(Clone, Copy)]
+struct Callbacks {
+    environment: Option<EnvironmentFn>,
+    video_refresh: Option<VideoRefreshFn>,
+    audio_sample_batch: Option<AudioSampleBatchFn>,
+    input_poll: Option<InputPollFn>,
+    input_state: Option<InputStateFn>,
+}
+
+static CALLBACKS: Mutex<Callbacks> = Mutex::new(Callbacks {
+    environment: None,
+    video_refresh: None,
+    audio_sample_batch: None,
+    input_poll: None,
+    input_state: None,
+});
+
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+
+fn callbacks() -> Callbacks {
+    *CALLBACKS.lock().unwrap_or_else(|x| x.into_inner())
+}
+
+fn set_callbacks(f: impl FnOnce(&mut Callbacks)) {
+    f(&mut CALLBACKS.lock().unwrap_or_else(|x| x.into_inner()));
+}
+
+fn lock_session() -> MutexGuard<'static, Option<Session>> {
+    SESSION.lock().unwrap_or_else(|x| x.into_inner())
+}
+
+fn environment(cmd: u32, data: *mut c_void) -> bool {
+    match callbacks().environment {
+        // SAFETY: the frontend's callback, with `data` as `cmd` defines it
+        Some(f) => unsafe { f(cmd, data) },
+        None => false,
+    }
+}
+
+/// The options the frontend has chosen.
+fn read_options() -> Options {
+    let get = |key: &CStr| {
+        let mut variable = Variable {
+            key: key.as_ptr(),
+            value: ptr::null(),
+        };
+
+        if !environment(ENVIRONMENT_GET_VARIABLE, (&raw mut variable).cast()) {
+            return None;
+        }
+
+        // SAFETY: a C string the frontend keeps until the next call
+        (!variable.value.is_null()).then(|| unsafe { CStr::from_ptr(variable.value) })
+    };
+    let model = get(MODEL_KEY).and_then(|x| x.to_str().ok());
+    let rtc_clock = get(RTC_KEY).and_then(|x| x.to_str().ok());
+
+    Options::parse(model, rtc_clock)
+}
+
+fn options_updated() -> bool {
+    let mut updated = false;
+
+    environment(ENVIRONMENT_GET_VARIABLE_UPDATE, (&raw mut updated).cast()) && updated
+}
+
+/// The held buttons as `Joypad` bits.
+fn read_input() -> u8 {
+    let callbacks = callbacks();
+    let Some(input_state) = callbacks.input_state else {
+        return 0;
+    };
+
+    if let Some(input_poll) = callbacks.input_poll {
+        // SAFETY: the frontend's callback
+        unsafe { input_poll() };
+    }
+
+    BUTTONS.iter().enumerate().fold(0, |acc, (bit, (id, _))| {
+        // SAFETY: the frontend's callback
+        let state = unsafe { input_state(0, DEVICE_JOYPAD, 0, *id) };
+        acc | (((state != 0) as u8) << bit)
+    })
+}
+
+fn play_audio(samples: &[f32]) {
+    let Some(audio_sample_batch) = callbacks().audio_sample_batch else {
+        return;
+    };
+    let samples: Vec<i16> = samples
+        .iter()
+        .map(|x| (x.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+        .collect();
+    let mut rest = &samples[..samples.len() / 2 * 2];
+
+    // the frontend may take fewer frames than offered
+    while !rest.is_empty() {
+        // SAFETY: the frontend's callback, reading `frames` stereo pairs
+        let taken = unsafe { audio_sample_batch(rest.as_ptr(), rest.len() / 2) };
+
+        if taken == 0 {
+            break;
+        }
+
+        rest = &rest[(taken * 2).min(rest.len())..];
+    }
+}
+
+fn set_input_descriptors() {
+    let mut descriptors: Vec<InputDescriptor> = BUTTONS
+        .iter()
+        .map(|(id, description)| InputDescriptor {
+            port: 0,
+            device: DEVICE_JOYPAD,
+            index: 0,
+            id: *id,
+            description: description.as_ptr(),
+        })
+        .collect();
+    descriptors.push(InputDescriptor {
+        port: 0,
+        device: 0,
+        index: 0,
+        id: 0,
+        description: ptr::null(),
+    });
+
+    environment(
+        ENVIRONMENT_SET_INPUT_DESCRIPTORS,
+        descriptors.as_mut_ptr().cast(),
+    );
+}
+
+fn set_memory_maps(session: &mut Session) {
+    let descriptors = session.memory.descriptors();
+    let mut map = MemoryMap {
+        descriptors: descriptors.as_ptr(),
+        num_descriptors: descriptors.len() as u32,
+    };
+
+    environment(ENVIRONMENT_SET_MEMORY_MAPS, (&raw mut map).cast());
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> c_uint {
+    API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_environment(callback: EnvironmentFn) {
+    set_callbacks(|x| x.environment = Some(callback));
+
+    let mut variables: Vec<Variable> = VARIABLES
+        .iter()
+        .map(|(key, value)| Variable {
+            key: key.as_ptr(),
+            value: value.as_ptr(),
+        })
+        .collect();
+    variables.push(Variable {
+        key: ptr::null(),
+        value: ptr::null(),
+    });
+    environment(ENVIRONMENT_SET_VARIABLES, variables.as_mut_ptr().cast());
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(callback: VideoRefreshFn) {
+    set_callbacks(|x| x.video_refresh = Some(callback));
+}
+
+/// Unused, the batch callback plays the audio.
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_callback: AudioSampleFn) {}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(callback: AudioSampleBatchFn) {
+    set_callbacks(|x| x.audio_sample_batch = Some(callback));
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(callback: InputPollFn) {
+    set_callbacks(|x| x.input_poll = Some(callback));
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(callback: InputStateFn) {
+    set_callbacks(|x| x.input_state = Some(callback));
+}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    *lock_session() = None;
+}
+
+/// # Safety
+/// `info` must point to a `retro_system_info`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut SystemInfo) {
+    let version = concat!(env!("CARGO_PKG_VERSION"), "\0");
+    let info = &mut *info;
+    info.library_name = c"oxGBC".as_ptr();
+    info.library_version = version.as_ptr().cast();
+    info.valid_extensions = c"gb|gbc".as_ptr();
+    info.need_fullpath = false;
+    info.block_extract = false;
+}
+
+/// # Safety
+/// `info` must point to a `retro_system_av_info`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut SystemAvInfo) {
+    *info = SystemAvInfo {
+        geometry: GameGeometry {
+            base_width: LCD_X_RES as u32,
+            base_height: LCD_Y_RES as u32,
+            max_width: LCD_X_RES as u32,
+            max_height: LCD_Y_RES as u32,
+            aspect_ratio: LCD_X_RES as f32 / LCD_Y_RES as f32,
+        },
+        timing: SystemTiming {
+            fps: CPU_CLOCK_SPEED as f64 / FRAME_CYCLES as f64,
+            sample_rate: SAMPLING_FREQUENCY as f64,
+        },
+    };
+}
+
+/// Only the joypad is supported.
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_port: c_uint, _device: c_uint) {}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> c_uint {
+    REGION_NTSC
+}
+
+/// # Safety
+/// `game` must point to a `retro_game_info`, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const GameInfo) -> bool {
+    let Some(game) = game.as_ref() else {
+        return false;
+    };
+
+    let rom = if !game.data.is_null() {
+        slice::from_raw_parts(game.data.cast::<u8>(), game.size).to_vec()
+    } else if !game.path.is_null() {
+        let path = CStr::from_ptr(game.path).to_string_lossy().into_owned();
+        match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(_) => return false,
+        }
+    } else {
+        return false;
+    };
+
+    let mut format = PIXEL_FORMAT_RGB565;
+    if !environment(ENVIRONMENT_SET_PIXEL_FORMAT, (&raw mut format).cast()) {
+        return false;
+    }
+
+    let Ok(mut session) = Session::new(&rom, read_options()) else {
+        return false;
+    };
+
+    set_input_descriptors();
+    set_memory_maps(&mut session);
+    let mut achievements = true;
+    environment(
+        ENVIRONMENT_SET_SUPPORT_ACHIEVEMENTS,
+        (&raw mut achievements).cast(),
+    );
+
+    *lock_session() = Some(session);
+
+    true
+}
+
+/// No special game types.
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(
+    _game_type: c_uint,
+    _info: *const GameInfo,
+    _num_info: usize,
+) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    *lock_session() = None;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    if let Some(session) = lock_session().as_mut() {
+        _ = session.reset();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_run() {
+    let pressed = read_input();
+    let mut session = lock_session();
+    let Some(session) = session.as_mut() else {
+        return;
+    };
+
+    if options_updated() {
+        session.set_options(read_options());
+    }
+
+    play_audio(session.run(pressed));
+
+    if let Some(video_refresh) = callbacks().video_refresh {
+        let framebuffer = session.framebuffer();
+        // SAFETY: the frontend's callback, reading a whole frame
+        unsafe {
+            video_refresh(
+                framebuffer.as_ptr().cast(),
+                LCD_X_RES as u32,
+                LCD_Y_RES as u32,
+                FrameBuffer::PITCH,
+            )
+        };
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    lock_session().as_ref().map_or(0, |x| x.state_size())
+}
+
+/// # Safety
+/// `data` must point to `size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    let mut session = lock_session();
+    let Some(session) = session.as_mut() else {
+        return false;
+    };
+    let buf = slice::from_raw_parts_mut(data.cast::<u8>(), size);
+
+    session.serialize(buf).is_ok()
+}
+
+/// # Safety
+/// `data` must point to `size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    let mut session = lock_session();
+    let Some(session) = session.as_mut() else {
+        return false;
+    };
+    let buf = slice::from_raw_parts(data.cast::<u8>(), size);
+
+    session.unserialize(buf).is_ok()
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {
+    if let Some(session) = lock_session().as_mut() {
+        session.reset_cheats();
+    }
+}
+
+/// # Safety
+/// `code` must be a C string, or null.
+#[no_mangle]
+pub unsafe extern "C" fn retro_cheat_set(index: c_uint, enabled: bool, code: *const c_char) {
+    if code.is_null() {
+        return;
+    }
+
+    if let Some(session) = lock_session().as_mut() {
+        let code = CStr::from_ptr(code).to_string_lossy();
+        session.set_cheat(index as usize, enabled, &code);
+    }
+}
+
+/// Stays valid until the game is unloaded, as a frontend expects.
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(id: c_uint) -> *mut c_void {
+    lock_session()
+        .as_mut()
+        .and_then(|x| x.memory.region(id))
+        .map_or(ptr::null_mut(), |x| x.as_mut_ptr().cast())
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(id: c_uint) -> usize {
+    lock_session()
+        .as_mut()
+        .and_then(|x| x.memory.region(id))
+        .map_or(0, |x| x.len())
+}
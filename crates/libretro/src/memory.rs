@@ -0,0 +1,208 @@
+//! Emulated memory as the frontend sees it. The core's RAM moves whenever a
+//! state is loaded, but RetroArch keeps the pointers it's given at load for
+//! cheats and achievements, so it gets copies instead: refreshed after every
+//! frame, with whatever it wrote into them since put back before the next.
+
+use crate::sys::{
+    MemoryDescriptor, MEMDESC_SAVE_RAM, MEMDESC_SYSTEM_RAM, MEMORY_RTC, MEMORY_SAVE_RAM,
+    MEMORY_SYSTEM_RAM,
+};
+use core::cpu::Cpu;
+use core::emu::config::GbModel;
+use std::ptr;
+
+/// WRAM bank 0 at `$C000`, then banks 1 to 7 as `$D000` shows them.
+pub const WRAM_LEN: usize = 0x8000;
+const WRAM_BANK_LEN: usize = 0x1000;
+/// A DMG has only the first two banks.
+const DMG_WRAM_LEN: usize = 2 * WRAM_BANK_LEN;
+/// `$FF80` to `$FFFE`.
+pub const HRAM_LEN: usize = 0x7F;
+const HRAM_START: u16 = 0xFF80;
+/// Cart RAM is windowed into `$A000` a bank at a time.
+const SRAM_BANK_LEN: usize = 0x2000;
+/// Where RetroArch's achievements look for the CGB's upper WRAM banks.
+const CGB_WRAM_BANKS_START: usize = 0x10000;
+
+/// One region's copy, and what it held after the last frame to tell the
+/// frontend's writes from the core's.
+#[derive(Debug, Default)]
+struct Mirror {
+    bytes: Vec<u8>,
+    last: Vec<u8>,
+}
+
+impl Mirror {
+    fn new(len: usize) -> Self {
+        Self {
+            bytes: vec![0; len],
+            last: vec![0; len],
+        }
+    }
+
+    /// Refills it in place, the length never changes once handed out.
+    fn refill(&mut self, f: impl Fn(usize) -> u8) {
+        for (i, (x, last)) in self.bytes.iter_mut().zip(self.last.iter_mut()).enumerate() {
+            *x = f(i);
+            *last = *x;
+        }
+    }
+
+    fn is_written(&self) -> bool {
+        self.bytes != self.last
+    }
+
+    /// Offsets and values the frontend wrote.
+    fn written(&self) -> impl Iterator<Item = (usize, u8)> + '_ {
+        self.bytes
+            .iter()
+            .zip(&self.last)
+            .enumerate()
+            .filter(|(_, (x, last))| x != last)
+            .map(|(i, (x, _))| (i, *x))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Memory {
+    wram: Mirror,
+    hram: Mirror,
+    /// Battery RAM, empty without a battery.
+    sram: Mirror,
+    /// The MBC3 clock's registers as saved after the RAM, empty without one.
+    rtc: Mirror,
+    is_cgb: bool,
+}
+
+impl Memory {
+    pub fn new(cpu: &Cpu) -> Self {
+        let cart = &cpu.clock.bus.cart;
+        let battery = cart.dump_ram().map_or(0, |x| x.len());
+        let sram_len = battery.min(cart.ram_size());
+        let mut memory = Self {
+            wram: Mirror::new(WRAM_LEN),
+            hram: Mirror::new(HRAM_LEN),
+            sram: Mirror::new(sram_len),
+            rtc: Mirror::new(battery - sram_len),
+            is_cgb: false,
+        };
+        memory.read(cpu);
+
+        memory
+    }
+
+    /// Copies the core's memory after a frame or a state load.
+    pub fn read(&mut self, cpu: &Cpu) {
+        let bus = &cpu.clock.bus;
+        let ram = &bus.io.ram;
+        self.is_cgb = bus.io.ppu.lcd.model == GbModel::Cgb;
+        self.wram.refill(|i| {
+            let (bank, addr) = wram_addr(i);
+            ram.read_wram_in(bank, addr)
+        });
+        self.hram.refill(|i| ram.read_hram(HRAM_START + i as u16));
+
+        if let Some(battery) = bus.cart.dump_ram() {
+            let (sram, rtc) = battery.split_at(self.sram.bytes.len());
+            self.sram.refill(|i| sram[i]);
+            self.rtc.refill(|i| rtc.get(i).copied().unwrap_or_default());
+        }
+    }
+
+    /// Puts back what the frontend wrote since [`Self::read`]. Returns
+    /// whether that reloaded the battery RAM.
+    pub fn write(&mut self, cpu: &mut Cpu) -> bool {
+        let bus = &mut cpu.clock.bus;
+
+        for (i, value) in self.wram.written() {
+            let (bank, addr) = wram_addr(i);
+            bus.io.ram.write_wram_in(bank, addr, value);
+        }
+
+        for (i, value) in self.hram.written() {
+            bus.io.ram.write_hram(HRAM_START + i as u16, value);
+        }
+
+        if !self.sram.is_written() && !self.rtc.is_written() {
+            return false;
+        }
+
+        let battery = [self.sram.bytes.as_slice(), &self.rtc.bytes].concat();
+        bus.cart.load_ram(battery.into_boxed_slice());
+
+        true
+    }
+
+    /// The region `retro_get_memory_data` asks for by `id`, `None` when the
+    /// game has none.
+    pub fn region(&mut self, id: u32) -> Option<&mut [u8]> {
+        let len = if self.is_cgb { WRAM_LEN } else { DMG_WRAM_LEN };
+        let bytes = match id {
+            MEMORY_SAVE_RAM => &mut self.sram.bytes[..],
+            MEMORY_RTC => &mut self.rtc.bytes[..],
+            MEMORY_SYSTEM_RAM => &mut self.wram.bytes[..len],
+            _ => return None,
+        };
+
+        (!bytes.is_empty()).then_some(bytes)
+    }
+
+    /// The memory map for achievements, in the address space RetroArch
+    /// uses for the Game Boy: the CPU's own, with the CGB's WRAM banks 2 to
+    /// 7 at `$10000`.
+    pub fn descriptors(&mut self) -> Vec<MemoryDescriptor> {
+        let wram = self.wram.bytes.as_mut_ptr();
+        let mut descriptors = vec![
+            descriptor(MEMDESC_SYSTEM_RAM, wram, 0xC000, DMG_WRAM_LEN),
+            descriptor(0, self.hram.bytes.as_mut_ptr(), 0xFF80, HRAM_LEN),
+        ];
+
+        if !self.sram.bytes.is_empty() {
+            let len = self.sram.bytes.len().min(SRAM_BANK_LEN);
+            let sram = self.sram.bytes.as_mut_ptr();
+            descriptors.push(descriptor(MEMDESC_SAVE_RAM, sram, 0xA000, len));
+        }
+
+        if self.is_cgb {
+            // SAFETY: the mirror is WRAM_LEN long
+            let banks = unsafe { wram.add(DMG_WRAM_LEN) };
+            let len = WRAM_LEN - DMG_WRAM_LEN;
+            descriptors.push(descriptor(0, banks, CGB_WRAM_BANKS_START, len));
+        }
+
+        descriptors
+    }
+}
+
+/// Bank and CPU address of an offset into the WRAM mirror.
+fn wram_addr(offset: usize) -> (u8, u16) {
+    let bank = offset / WRAM_BANK_LEN;
+    let base = if bank == 0 { 0xC000 } else { 0xD000 };
+
+    (bank as u8, base + (offset % WRAM_BANK_LEN) as u16)
+}
+
+fn descriptor(flags: u64, ptr: *mut u8, start: usize, len: usize) -> MemoryDescriptor {
+    MemoryDescriptor {
+        flags,
+        ptr: ptr.cast(),
+        offset: 0,
+        start,
+        select: 0,
+        disconnect: 0,
+        len,
+        addrspace: ptr::null(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wram_addr() {
+        assert_eq!(wram_addr(0), (0, 0xC000));
+        assert_eq!(wram_addr(0x1FFF), (1, 0xDFFF));
+        assert_eq!(wram_addr(0x7000), (7, 0xD000));
+    }
+}
@@ -0,0 +1,53 @@
+//! Core options, shown in the frontend's menu.
+
+use core::cart::mbc3::RtcClock;
+use core::emu::config::GbModel;
+use std::ffi::CStr;
+
+pub const MODEL_KEY: &CStr = c"oxgbc_model";
+pub const RTC_KEY: &CStr = c"oxgbc_rtc";
+
+/// The definitions `SET_VARIABLES` takes, the first value the default.
+pub const VARIABLES: [(&CStr, &CStr); 2] = [
+    (MODEL_KEY, c"Model (restart); Auto|Game Boy|Game Boy Color"),
+    (RTC_KEY, c"Cartridge clock; Wall clock|Emulated"),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Options {
+    /// `None` picks by the cart, applied when a game is loaded.
+    pub model: Option<GbModel>,
+    pub rtc_clock: RtcClock,
+}
+
+impl Options {
+    /// Reads the values the frontend returned for each key, defaults for
+    /// unknown ones.
+    pub fn parse(model: Option<&str>, rtc_clock: Option<&str>) -> Self {
+        Self {
+            model: match model {
+                Some("Game Boy") => Some(GbModel::Dmg),
+                Some("Game Boy Color") => Some(GbModel::Cgb),
+                _ => None,
+            },
+            rtc_clock: match rtc_clock {
+                Some("Emulated") => RtcClock::Emulated,
+                _ => RtcClock::WallClock,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Options::parse(None, None), Options::default());
+
+        let options = Options::parse(Some("Game Boy Color"), Some("Emulated"));
+        assert_eq!(options.model, Some(GbModel::Cgb));
+        assert_eq!(options.rtc_clock, RtcClock::Emulated);
+    }
+}
@@ -0,0 +1,225 @@
+//! A loaded game, with everything the exported functions do to it short of
+//! the FFI.
+
+use crate::memory::Memory;
+use crate::options::Options;
+use core::cart::Cart;
+use core::cheats::Cheat;
+use core::emu::config::EmuConfig;
+use core::emu::state_file::{self, SaveStateInfo};
+use core::emu::{Emu, SampleSink};
+
+/// Room left in a serialized state for it to grow, as the frontend wants
+/// one size for the whole session.
+const STATE_SLACK: usize = 0x4000;
+/// The length ahead of the state in the frontend's buffer.
+const STATE_LEN_BYTES: usize = 4;
+
+pub struct Session {
+    pub emu: Emu,
+    pub memory: Memory,
+    samples: Vec<f32>,
+    /// Indexed as the frontend numbers them.
+    cheats: Vec<Cheat>,
+    /// Made once, as hashing the ROM for every rewind snapshot is wasted
+    /// work.
+    info: SaveStateInfo,
+    state_size: usize,
+    /// Whether a frame ran since power-on, as the battery RAM the frontend
+    /// writes before the first one also sets the clock.
+    started: bool,
+}
+
+impl Session {
+    pub fn new(rom: &[u8], options: Options) -> Result<Self, String> {
+        let config = EmuConfig {
+            // the frontend rewinds with retro_serialize
+            rewind_budget: 0,
+            model: options.model,
+            rtc_clock: options.rtc_clock,
+            ..EmuConfig::default()
+        };
+        let mut emu = Emu::with_config(config)?;
+        emu.load_cart(Cart::new(rom.into())?);
+
+        let mut info = SaveStateInfo::new(&emu.runtime.cpu);
+        info.thumbnail.clear();
+        let mut session = Self {
+            memory: Memory::new(&emu.runtime.cpu),
+            emu,
+            samples: Vec::new(),
+            cheats: Vec::new(),
+            info,
+            state_size: 0,
+            started: false,
+        };
+        session.state_size = session.encode_state()?.len() + STATE_LEN_BYTES + STATE_SLACK;
+
+        Ok(session)
+    }
+
+    /// Powers the game on again, keeping the battery RAM.
+    pub fn reset(&mut self) -> Result<(), String> {
+        let old = &self.emu.runtime.cpu.clock.bus.cart;
+        let mut cart = Cart::new(old.data.rom().into())?;
+
+        if let Some(ram) = old.dump_ram() {
+            cart.load_ram(ram);
+        }
+
+        self.emu.load_cart(cart);
+        self.emu.runtime.set_cheats(&self.cheats);
+        self.memory.read(&self.emu.runtime.cpu);
+        self.started = false;
+
+        Ok(())
+    }
+
+    /// Applies the options that can change while a game runs.
+    pub fn set_options(&mut self, options: Options) {
+        self.emu.config.rtc_clock = options.rtc_clock;
+        let cart = &mut self.emu.runtime.cpu.clock.bus.cart;
+        cart.set_rtc_clock(options.rtc_clock);
+    }
+
+    /// Runs a frame with the `pressed` buttons, in `Joypad` bit order.
+    /// Returns its audio, interleaved stereo.
+    pub fn run(&mut self, pressed: u8) -> &[f32] {
+        let cpu = &mut self.emu.runtime.cpu;
+
+        if self.memory.write(cpu) && !self.started {
+            cpu.clock.bus.cart.power_on_rtc(self.emu.config.rtc_clock);
+        }
+
+        cpu.clock.bus.io.joypad.set_pressed(pressed);
+        self.samples.clear();
+        self.emu
+            .runtime
+            .run_frame(&mut SampleSink(&mut self.samples));
+        self.memory.read(&self.emu.runtime.cpu);
+        self.started = true;
+
+        &self.samples
+    }
+
+    /// The screen, RGB565.
+    pub fn framebuffer(&mut self) -> &[u8] {
+        self.emu.get_framebuffer()
+    }
+
+    /// What every state takes in the frontend's buffer.
+    pub fn state_size(&self) -> usize {
+        self.state_size
+    }
+
+    /// Writes the state into `buf`, of [`Self::state_size`] bytes: its
+    /// length, then the state file, then zeros.
+    pub fn serialize(&mut self, buf: &mut [u8]) -> Result<(), String> {
+        let bytes = self.encode_state()?;
+        let end = STATE_LEN_BYTES + bytes.len();
+
+        if end > buf.len() {
+            return Err(format!("state of {} bytes doesn't fit", bytes.len()));
+        }
+
+        buf[..STATE_LEN_BYTES].copy_from_slice(&(bytes.len() as u32).to_le_bytes());
+        buf[STATE_LEN_BYTES..end].copy_from_slice(&bytes);
+        buf[end..].fill(0);
+
+        Ok(())
+    }
+
+    pub fn unserialize(&mut self, buf: &[u8]) -> Result<(), String> {
+        let (len, bytes) = buf
+            .split_first_chunk::<STATE_LEN_BYTES>()
+            .ok_or("state too short")?;
+        let bytes = bytes
+            .get(..u32::from_le_bytes(*len) as usize)
+            .ok_or("state too short")?;
+        self.emu.load_save_state_file(bytes)?;
+        self.memory.read(&self.emu.runtime.cpu);
+
+        Ok(())
+    }
+
+    pub fn reset_cheats(&mut self) {
+        self.cheats.clear();
+        self.emu.runtime.set_cheats(&self.cheats);
+    }
+
+    /// Sets the frontend's cheat `index`, which may hold several codes
+    /// joined by `+`.
+    pub fn set_cheat(&mut self, index: usize, enabled: bool, code: &str) {
+        if self.cheats.len() <= index {
+            self.cheats.resize_with(index + 1, Cheat::default);
+        }
+
+        self.cheats[index] = Cheat {
+            name: format!("Cheat {index}"),
+            code: code.to_string(),
+            enabled,
+        };
+        self.emu.runtime.set_cheats(&self.cheats);
+    }
+
+    fn encode_state(&mut self) -> Result<Vec<u8>, String> {
+        let state = self.emu.create_save_state();
+        self.info.frame = self.emu.runtime.cpu.clock.bus.io.ppu.current_frame;
+
+        state_file::encode(&state, &self.info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::{MEMORY_RTC, MEMORY_SAVE_RAM, MEMORY_SYSTEM_RAM};
+
+    /// An MBC3 cart with a timer, battery and 8 KiB of RAM, adding 1 to
+    /// $C000 every loop.
+    fn new_session() -> Session {
+        // LD HL,$C000; INC (HL); JR -3
+        let program = [0x21, 0x00, 0xC0, 0x34, 0x18, 0xFD];
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x100..0x100 + program.len()].copy_from_slice(&program);
+        rom[0x147] = 0x10;
+        rom[0x149] = 0x02;
+
+        Session::new(&rom, Options::default()).unwrap()
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut session = new_session();
+        session.run(0);
+        let mut buf = vec![0; session.state_size()];
+        session.serialize(&mut buf).unwrap();
+        let saved = session.memory.region(MEMORY_SYSTEM_RAM).unwrap()[0];
+
+        session.run(0);
+        assert_ne!(session.memory.region(MEMORY_SYSTEM_RAM).unwrap()[0], saved);
+
+        session.unserialize(&buf).unwrap();
+        assert_eq!(session.memory.region(MEMORY_SYSTEM_RAM).unwrap()[0], saved);
+        assert!(session.unserialize(&buf[..8]).is_err());
+    }
+
+    #[test]
+    fn test_memory_writes() {
+        let mut session = new_session();
+        assert_eq!(
+            session.memory.region(MEMORY_SAVE_RAM).unwrap().len(),
+            0x2000
+        );
+        assert_eq!(session.memory.region(MEMORY_RTC).unwrap().len(), 48);
+
+        session.memory.region(MEMORY_SYSTEM_RAM).unwrap()[0x1000] = 0x42;
+        session.memory.region(MEMORY_SAVE_RAM).unwrap()[0x10] = 0x24;
+        session.run(0);
+
+        let cart = &session.emu.runtime.cpu.clock.bus.cart;
+        assert_eq!(cart.dump_ram().unwrap()[0x10], 0x24);
+        let ram = &session.emu.runtime.cpu.clock.bus.io.ram;
+        assert_eq!(ram.read_wram_in(1, 0xD000), 0x42);
+    }
+}
@@ -5,40 +5,24 @@
 //! `requestAnimationFrame`, so — unlike the SDL desktop frontend — we call
 //! [`EmuRuntime::run_frame`] directly and never let the emulator sleep/spin itself.
 //!
-//! Scope: video + input + audio. The APU's samples are buffered by [`AudioSink`]
+//! Scope: video + input + audio. The APU's samples are buffered by a [`SampleSink`]
 //! each frame and drained by JS via [`OxGbc::take_audio`] into a WebAudio scheduler.
 
-use core::apu::apu::ApuConfig;
-use core::apu::{Apu, SAMPLING_FREQUENCY};
-use core::auxiliary::io::Io;
+use core::apu::SAMPLING_FREQUENCY;
 use core::auxiliary::joypad::JoypadButton;
-use core::bus::Bus;
 use core::cart::Cart;
-use core::emu::config::{EmuConfig, GbModel};
+use core::emu::config::EmuConfig;
 use core::emu::runtime::EmuRuntime;
-use core::emu::{Emu, EmuAudioCallback};
-use core::ppu::lcd::Lcd;
-use core::ppu::tile::PixelColor;
-use core::ppu::{Ppu, LCD_X_RES, LCD_Y_RES};
+use core::emu::{Emu, SampleSink};
+use core::ppu::{LCD_X_RES, LCD_Y_RES};
 use wasm_bindgen::prelude::*;
 
-/// Accumulates the APU's interleaved stereo samples produced during a frame.
-/// Drained once per frame by [`OxGbc::take_audio`].
-#[derive(Default)]
-struct AudioSink {
-    samples: Vec<f32>,
-}
-
-impl EmuAudioCallback for AudioSink {
-    fn update(&mut self, output: &[f32], _runtime: &EmuRuntime) {
-        self.samples.extend_from_slice(output);
-    }
-}
-
 #[wasm_bindgen(js_name = oxGBC)]
 pub struct OxGbc {
     emu: Emu,
-    audio: AudioSink,
+    /// The APU's interleaved stereo samples produced during a frame.
+    /// Drained once per frame by [`OxGbc::take_audio`].
+    audio: Vec<f32>,
 }
 
 #[wasm_bindgen(js_class = oxGBC)]
@@ -48,23 +32,11 @@ impl OxGbc {
         // Surface Rust panics in the browser console instead of an opaque trap.
         console_error_panic_hook::set_once();
 
-        // Classic DMG green palette (RGBA hex).
-        let colors = [
-            PixelColor::from_hex_rgba("E0F8D0FF"),
-            PixelColor::from_hex_rgba("88C070FF"),
-            PixelColor::from_hex_rgba("346856FF"),
-            PixelColor::from_hex_rgba("081820FF"),
-        ];
-        let lcd = Lcd::new(colors, GbModel::default());
-        let ppu = Ppu::new(lcd);
-        let apu = Apu::new(ApuConfig::default());
-        let config = EmuConfig::default();
-        let bus = Bus::new(Cart::empty(), Io::new(ppu, apu), config.model);
-        let emu = Emu::new(config, EmuRuntime::new(bus)).expect("failed to build emu");
+        let emu = Emu::with_config(EmuConfig::default()).expect("failed to build emu");
 
         OxGbc {
             emu,
-            audio: AudioSink::default(),
+            audio: Vec::new(),
         }
     }
 
@@ -93,7 +65,7 @@ impl OxGbc {
     /// Advances emulation by exactly one frame. No internal sleep — the browser
     /// paces us through `requestAnimationFrame`.
     pub fn run_frame(&mut self) {
-        self.emu.runtime.run_frame(&mut self.audio);
+        self.emu.runtime.run_frame(&mut SampleSink(&mut self.audio));
     }
 
     /// Returns the current frame as RGBA bytes (`width * height * 4`), ready to be
@@ -111,7 +83,7 @@ impl OxGbc {
     /// Drains and returns the interleaved stereo samples (L, R, L, R, …) the APU
     /// produced since the last call. Feed these to a WebAudio scheduler.
     pub fn take_audio(&mut self) -> Vec<f32> {
-        std::mem::take(&mut self.audio.samples)
+        std::mem::take(&mut self.audio)
     }
 
     /// Sets a Game Boy button state. `name` is one of: